@@ -0,0 +1,9 @@
+ALTER TABLE trc20_pending_deposits
+    DROP COLUMN IF EXISTS detected_confirmations,
+    DROP COLUMN IF EXISTS detected_transfer_id;
+ALTER TABLE erc20_pending_deposits
+    DROP COLUMN IF EXISTS detected_confirmations,
+    DROP COLUMN IF EXISTS detected_transfer_id;
+
+ALTER TABLE trc20_token_transfers DROP COLUMN IF EXISTS confirmations;
+ALTER TABLE erc20_token_transfers DROP COLUMN IF EXISTS confirmations;
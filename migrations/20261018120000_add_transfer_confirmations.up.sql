@@ -0,0 +1,11 @@
+-- Confirmation depth of each transfer, updated by the sync runners from the chain head
+ALTER TABLE erc20_token_transfers ADD COLUMN confirmations INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE trc20_token_transfers ADD COLUMN confirmations INTEGER NOT NULL DEFAULT 0;
+
+-- Unconfirmed transfer seen for a pending deposit ("payment detected")
+ALTER TABLE erc20_pending_deposits
+    ADD COLUMN detected_transfer_id BIGINT REFERENCES erc20_token_transfers (id) ON DELETE SET NULL,
+    ADD COLUMN detected_confirmations INTEGER;
+ALTER TABLE trc20_pending_deposits
+    ADD COLUMN detected_transfer_id BIGINT REFERENCES trc20_token_transfers (id) ON DELETE SET NULL,
+    ADD COLUMN detected_confirmations INTEGER;
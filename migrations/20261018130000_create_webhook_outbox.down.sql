@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS webhook_outbox;
+DROP TYPE IF EXISTS webhook_event_kind;
@@ -0,0 +1,53 @@
+CREATE TYPE webhook_event_kind AS ENUM (
+    'order_status_changed',
+    'unknown_transfer_received',
+    'payment_detected'
+);
+
+-- Webhook events waiting for (or done with) delivery. Rows are written in the
+-- same transaction as the state change that caused them and drained by the
+-- WebhookSender with exponential back-off.
+CREATE TABLE webhook_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    kind webhook_event_kind NOT NULL,
+    -- order_status_changed / payment_detected
+    order_id UUID REFERENCES order_records (order_id) ON DELETE CASCADE,
+    order_status order_status,
+    -- unknown_transfer_received (erc20 or trc20 transfer id)
+    transfer_id BIGINT,
+    -- unknown_transfer_received / payment_detected; NULL means Tron
+    chain etherscan_chain,
+    -- payment_detected
+    txn_hash TEXT,
+    confirmations INTEGER,
+    required_confirmations INTEGER,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    retry_count INTEGER NOT NULL DEFAULT 0 CHECK (retry_count >= 0),
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_tried_at TIMESTAMP,
+    last_error TEXT,
+    delivered_at TIMESTAMP,
+    CHECK (
+        (kind = 'order_status_changed' AND order_id IS NOT NULL AND order_status IS NOT NULL)
+        OR (kind = 'unknown_transfer_received' AND transfer_id IS NOT NULL)
+        OR (kind = 'payment_detected' AND order_id IS NOT NULL AND txn_hash IS NOT NULL
+            AND confirmations IS NOT NULL AND required_confirmations IS NOT NULL)
+    )
+);
+
+CREATE INDEX idx_webhook_outbox_due ON webhook_outbox (next_attempt_at) WHERE delivered_at IS NULL;
+CREATE INDEX idx_webhook_outbox_order_id ON webhook_outbox (order_id);
+
+-- Carry over order status webhooks that were still being retried
+INSERT INTO webhook_outbox (kind, order_id, order_status, retry_count, last_tried_at, next_attempt_at)
+SELECT
+    'order_status_changed',
+    order_id,
+    status,
+    webhook_retry_count,
+    webhook_last_tried_at,
+    COALESCE(webhook_last_tried_at + (POWER(2, webhook_retry_count) || ' seconds')::interval, CURRENT_TIMESTAMP)
+FROM order_records
+WHERE webhook_success_at IS NULL
+  AND status != 'pending'
+  AND webhook_retry_count < 11;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_webhook_outbox_transfer_id;
+DROP TABLE IF EXISTS webhook_delivery_attempts;
+DROP TYPE IF EXISTS webhook_delivery_error_kind;
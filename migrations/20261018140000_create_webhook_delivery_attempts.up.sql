@@ -0,0 +1,28 @@
+CREATE TYPE webhook_delivery_error_kind AS ENUM (
+    'timeout',
+    'connect',
+    'http_status',
+    'request'
+);
+
+-- One row per HTTP request made for a webhook outbox row, kept so admins can
+-- see exactly what happened to each delivery.
+CREATE TABLE webhook_delivery_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    outbox_id BIGINT NOT NULL REFERENCES webhook_outbox (id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    -- hex-encoded SHA-256 of the request body
+    body_sha256 TEXT NOT NULL,
+    -- NULL if no response was received
+    response_status INTEGER,
+    -- first 1 KiB of the response body
+    response_body TEXT,
+    latency_ms INTEGER NOT NULL CHECK (latency_ms >= 0),
+    -- NULL if the attempt succeeded
+    error_kind webhook_delivery_error_kind,
+    error TEXT,
+    attempted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_webhook_delivery_attempts_outbox_id ON webhook_delivery_attempts (outbox_id);
+CREATE INDEX idx_webhook_outbox_transfer_id ON webhook_outbox (transfer_id) WHERE transfer_id IS NOT NULL;
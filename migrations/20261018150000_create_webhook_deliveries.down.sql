@@ -0,0 +1,29 @@
+DROP INDEX IF EXISTS idx_webhook_outbox_pending_fan_out;
+
+ALTER TABLE webhook_outbox
+    ADD COLUMN retry_count INTEGER NOT NULL DEFAULT 0 CHECK (retry_count >= 0),
+    ADD COLUMN next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN last_tried_at TIMESTAMP,
+    ADD COLUMN last_error TEXT,
+    ADD COLUMN delivered_at TIMESTAMP;
+
+-- Keep the retry state of each event's delivery to its own URL
+UPDATE webhook_outbox o
+SET
+    retry_count = d.retry_count,
+    next_attempt_at = d.next_attempt_at,
+    last_tried_at = d.last_tried_at,
+    last_error = d.last_error,
+    delivered_at = d.delivered_at
+FROM webhook_deliveries d
+WHERE d.outbox_id = o.id AND d.subscription IS NULL;
+
+CREATE INDEX idx_webhook_outbox_due ON webhook_outbox (next_attempt_at) WHERE delivered_at IS NULL;
+
+DROP INDEX IF EXISTS idx_webhook_delivery_attempts_delivery_id;
+ALTER TABLE webhook_delivery_attempts DROP COLUMN delivery_id;
+ALTER TABLE webhook_outbox DROP COLUMN fanned_out_at;
+DROP TABLE IF EXISTS webhook_deliveries;
+
+UPDATE order_records SET webhook_url = '' WHERE webhook_url IS NULL;
+ALTER TABLE order_records ALTER COLUMN webhook_url SET NOT NULL;
@@ -0,0 +1,51 @@
+-- Orders without a webhook URL use merchant.default_webhook_url
+ALTER TABLE order_records ALTER COLUMN webhook_url DROP NOT NULL;
+
+-- One row per (outbox event, destination), each with its own retry state.
+-- The WebhookSender fans every outbox event out to the matching destinations
+-- once and then drains due deliveries.
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    outbox_id BIGINT NOT NULL REFERENCES webhook_outbox (id) ON DELETE CASCADE,
+    -- name of a [[webhooks]] subscription; NULL means the event's own URL
+    -- (the order webhook URL or merchant.unknown_transfer_webhook_url)
+    subscription TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    retry_count INTEGER NOT NULL DEFAULT 0 CHECK (retry_count >= 0),
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_tried_at TIMESTAMP,
+    last_error TEXT,
+    delivered_at TIMESTAMP
+);
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries (next_attempt_at) WHERE delivered_at IS NULL;
+CREATE INDEX idx_webhook_deliveries_outbox_id ON webhook_deliveries (outbox_id);
+
+ALTER TABLE webhook_outbox ADD COLUMN fanned_out_at TIMESTAMP;
+
+-- Existing events keep their retry state as a delivery to their own URL
+INSERT INTO webhook_deliveries
+    (outbox_id, created_at, retry_count, next_attempt_at, last_tried_at, last_error, delivered_at)
+SELECT id, created_at, retry_count, next_attempt_at, last_tried_at, last_error, delivered_at
+FROM webhook_outbox;
+
+UPDATE webhook_outbox SET fanned_out_at = created_at;
+
+ALTER TABLE webhook_delivery_attempts
+    ADD COLUMN delivery_id BIGINT REFERENCES webhook_deliveries (id) ON DELETE CASCADE;
+UPDATE webhook_delivery_attempts a
+SET delivery_id = d.id
+FROM webhook_deliveries d
+WHERE d.outbox_id = a.outbox_id;
+ALTER TABLE webhook_delivery_attempts ALTER COLUMN delivery_id SET NOT NULL;
+CREATE INDEX idx_webhook_delivery_attempts_delivery_id ON webhook_delivery_attempts (delivery_id);
+
+DROP INDEX idx_webhook_outbox_due;
+ALTER TABLE webhook_outbox
+    DROP COLUMN retry_count,
+    DROP COLUMN next_attempt_at,
+    DROP COLUMN last_tried_at,
+    DROP COLUMN last_error,
+    DROP COLUMN delivered_at;
+
+CREATE INDEX idx_webhook_outbox_pending_fan_out ON webhook_outbox (id) WHERE fanned_out_at IS NULL;
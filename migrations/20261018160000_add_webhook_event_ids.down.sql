@@ -0,0 +1,5 @@
+ALTER TABLE webhook_outbox DROP CONSTRAINT IF EXISTS webhook_outbox_order_sequence;
+ALTER TABLE webhook_outbox DROP COLUMN sequence;
+ALTER TABLE order_records DROP COLUMN webhook_event_seq;
+DROP INDEX IF EXISTS idx_webhook_outbox_event_id;
+ALTER TABLE webhook_outbox DROP COLUMN event_id;
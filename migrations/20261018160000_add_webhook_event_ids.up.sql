@@ -0,0 +1,33 @@
+-- Stable identity of every logical webhook event: retries, deliveries to
+-- other subscriptions and manual resends all carry the same event_id.
+ALTER TABLE webhook_outbox ADD COLUMN event_id UUID NOT NULL DEFAULT gen_random_uuid();
+CREATE UNIQUE INDEX idx_webhook_outbox_event_id ON webhook_outbox (event_id);
+
+-- Per-order event counter; the row lock taken by incrementing it orders the
+-- events of an order by commit.
+ALTER TABLE order_records ADD COLUMN webhook_event_seq BIGINT NOT NULL DEFAULT 0;
+
+-- Position of the event among its order's events; NULL for unknown transfers
+ALTER TABLE webhook_outbox ADD COLUMN sequence BIGINT;
+
+UPDATE webhook_outbox o
+SET sequence = s.sequence
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY order_id ORDER BY id) AS sequence
+    FROM webhook_outbox
+    WHERE order_id IS NOT NULL
+) s
+WHERE s.id = o.id;
+
+UPDATE order_records r
+SET webhook_event_seq = s.last
+FROM (
+    SELECT order_id, MAX(sequence) AS last
+    FROM webhook_outbox
+    WHERE order_id IS NOT NULL
+    GROUP BY order_id
+) s
+WHERE s.order_id = r.order_id;
+
+ALTER TABLE webhook_outbox
+    ADD CONSTRAINT webhook_outbox_order_sequence CHECK ((order_id IS NULL) = (sequence IS NULL));
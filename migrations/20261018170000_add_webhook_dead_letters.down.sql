@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_webhook_deliveries_dead_lettered;
+DROP INDEX IF EXISTS idx_webhook_deliveries_due;
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries (next_attempt_at) WHERE delivered_at IS NULL;
+ALTER TABLE webhook_deliveries DROP COLUMN dead_lettered_at;
@@ -0,0 +1,46 @@
+-- Deliveries that used up every retry are dead-lettered: they are no longer
+-- attempted until an admin replays them.
+ALTER TABLE webhook_deliveries ADD COLUMN dead_lettered_at TIMESTAMP;
+
+UPDATE webhook_deliveries
+SET dead_lettered_at = COALESCE(last_tried_at, CURRENT_TIMESTAMP)
+WHERE delivered_at IS NULL AND retry_count >= 11;
+
+-- Order status webhooks that had already used up their retries when the
+-- outbox was introduced were not carried over. Add them as dead letters so an
+-- admin can list and replay them, unless the order has had newer events.
+WITH exhausted AS (
+    SELECT order_id, status, webhook_retry_count, webhook_last_tried_at
+    FROM order_records
+    WHERE webhook_success_at IS NULL
+      AND status != 'pending'
+      AND webhook_retry_count >= 11
+      AND webhook_event_seq = 0
+),
+events AS (
+    INSERT INTO webhook_outbox (kind, order_id, order_status, sequence, fanned_out_at)
+    SELECT 'order_status_changed', order_id, status, 1, CURRENT_TIMESTAMP
+    FROM exhausted
+    RETURNING id, order_id
+),
+sequenced AS (
+    UPDATE order_records r
+    SET webhook_event_seq = 1
+    FROM exhausted e
+    WHERE r.order_id = e.order_id
+)
+INSERT INTO webhook_deliveries (outbox_id, retry_count, last_tried_at, last_error, dead_lettered_at)
+SELECT
+    ev.id,
+    e.webhook_retry_count,
+    e.webhook_last_tried_at,
+    'retries exhausted before the webhook outbox was introduced',
+    COALESCE(e.webhook_last_tried_at, CURRENT_TIMESTAMP)
+FROM events ev
+JOIN exhausted e ON e.order_id = ev.order_id;
+
+DROP INDEX idx_webhook_deliveries_due;
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries (next_attempt_at)
+    WHERE delivered_at IS NULL AND dead_lettered_at IS NULL;
+CREATE INDEX idx_webhook_deliveries_dead_lettered ON webhook_deliveries (dead_lettered_at)
+    WHERE dead_lettered_at IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Deliveries of an order event wait for the earlier events of the order;
+-- this index serves that lookup.
+CREATE INDEX idx_webhook_outbox_order_sequence ON webhook_outbox (order_id, sequence)
+    WHERE order_id IS NOT NULL;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_order_records_merchant_created_at;
+ALTER TABLE order_records DROP COLUMN IF EXISTS merchant_id;
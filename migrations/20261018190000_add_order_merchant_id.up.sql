@@ -0,0 +1,7 @@
+-- Merchant that created the order. Orders from before multi-merchant
+-- support belong to the merchant configured in the `[merchant]` section,
+-- whose ID is 'default'.
+ALTER TABLE order_records ADD COLUMN merchant_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE order_records ALTER COLUMN merchant_id DROP DEFAULT;
+
+CREATE INDEX idx_order_records_merchant_created_at ON order_records (merchant_id, created_at DESC);
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS managed_wallets;
+DROP TABLE IF EXISTS managed_merchant_secrets;
+DROP TABLE IF EXISTS managed_merchants;
+DROP TYPE IF EXISTS blockchain_name;
@@ -0,0 +1,51 @@
+CREATE TYPE blockchain_name AS ENUM (
+    'ethereum',
+    'polygon',
+    'base',
+    'arbitrum_one',
+    'linea',
+    'optimism',
+    'avalanche_c',
+    'tron'
+);
+
+-- Merchants managed through the Admin API. They are layered over the
+-- merchants of the config file: a row replaces the file merchant with the
+-- same ID, other rows add merchants.
+CREATE TABLE managed_merchants (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    allowed_origins TEXT[] NOT NULL,
+    unknown_transfer_webhook_url TEXT,
+    default_webhook_url TEXT,
+    webhook_payload_version SMALLINT NOT NULL DEFAULT 1,
+    webhook_allowed_hosts TEXT[] NOT NULL DEFAULT '{}',
+    webhook_allow_private_networks BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Active secrets of a managed merchant, in header order. A NULL key_id is
+-- the merchant's single unnamed secret.
+CREATE TABLE managed_merchant_secrets (
+    id BIGSERIAL PRIMARY KEY,
+    merchant_id TEXT NOT NULL REFERENCES managed_merchants (id) ON DELETE CASCADE,
+    key_id TEXT,
+    secret TEXT NOT NULL,
+    UNIQUE NULLS NOT DISTINCT (merchant_id, key_id)
+);
+
+-- Wallets managed through the Admin API, layered over the [[wallets]] of
+-- the config file the same way. A disabled row hides the file wallet with
+-- the same blockchain and address.
+CREATE TABLE managed_wallets (
+    blockchain blockchain_name NOT NULL,
+    address TEXT NOT NULL,
+    enabled_coins stablecoin_name[] NOT NULL,
+    starting_tx TEXT,
+    merchant_id TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (blockchain, address)
+);
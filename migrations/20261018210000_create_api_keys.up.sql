@@ -0,0 +1,23 @@
+CREATE TYPE api_key_scope AS ENUM (
+    'create_orders',
+    'read_orders',
+    'cancel_orders',
+    'sign_urls'
+);
+
+-- Scoped Service API keys. A request names its key in the Ocrch-Key-Id
+-- header and is signed with the key's secret instead of the merchant
+-- secret. Revoked keys are kept so their usage stays visible.
+CREATE TABLE api_keys (
+    id TEXT PRIMARY KEY,
+    merchant_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    scopes api_key_scope[] NOT NULL,
+    expires_at TIMESTAMP,
+    last_used_at TIMESTAMP,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_api_keys_merchant_id ON api_keys (merchant_id, created_at);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_order_records_merchant_order_id;
+CREATE INDEX idx_order_records_merchant_order_id ON order_records (merchant_order_id);
@@ -0,0 +1,6 @@
+-- A merchant order ID identifies one order per merchant, so retried
+-- creation requests return the existing order instead of inserting a
+-- second one. Fails if duplicates already exist; resolve them first.
+DROP INDEX IF EXISTS idx_order_records_merchant_order_id;
+CREATE UNIQUE INDEX idx_order_records_merchant_order_id
+    ON order_records (merchant_id, merchant_order_id);
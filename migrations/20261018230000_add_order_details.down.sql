@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_order_records_customer_reference;
+DROP INDEX IF EXISTS idx_order_records_metadata;
+ALTER TABLE order_records DROP COLUMN IF EXISTS customer_reference;
+ALTER TABLE order_records DROP COLUMN IF EXISTS description;
+ALTER TABLE order_records DROP COLUMN IF EXISTS metadata;
@@ -0,0 +1,9 @@
+-- Merchant-supplied order details, returned in API responses and webhooks
+-- and searchable from the Admin API. Size limits are enforced by the
+-- Service API.
+ALTER TABLE order_records ADD COLUMN metadata JSONB;
+ALTER TABLE order_records ADD COLUMN description TEXT;
+ALTER TABLE order_records ADD COLUMN customer_reference TEXT;
+
+CREATE INDEX idx_order_records_metadata ON order_records USING GIN (metadata jsonb_path_ops);
+CREATE INDEX idx_order_records_customer_reference ON order_records (lower(customer_reference));
@@ -0,0 +1,2 @@
+ALTER TABLE order_records DROP COLUMN IF EXISTS cancel_url;
+ALTER TABLE order_records DROP COLUMN IF EXISTS success_url;
@@ -0,0 +1,5 @@
+-- Where the checkout frontend sends the user after the order completes.
+-- Their origins are checked against the merchant's allowed origins by the
+-- Service API.
+ALTER TABLE order_records ADD COLUMN success_url TEXT;
+ALTER TABLE order_records ADD COLUMN cancel_url TEXT;
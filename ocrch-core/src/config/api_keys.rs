@@ -1,7 +1,7 @@
 //! API keys configuration for blockchain explorer services.
 
 /// API keys for blockchain explorer services.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ApiKeysConfig {
     /// EtherScan API key (used for all EVM-compatible chains).
     pub etherscan_api_key: String,
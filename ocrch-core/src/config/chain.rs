@@ -0,0 +1,48 @@
+//! Per-chain configuration.
+
+use crate::utils::pooling_interval::PoolingPolicy;
+use ocrch_sdk::objects::blockchains::Blockchain;
+
+/// Per-chain settings that are not tied to a specific wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainConfig {
+    /// The blockchain these settings apply to.
+    pub blockchain: Blockchain,
+    /// Optional WebSocket JSON-RPC endpoint (EVM chains only).
+    ///
+    /// When set, a long-lived `eth_subscribe("logs")` connection is kept open
+    /// for this chain so incoming transfers are picked up without waiting for
+    /// the next explorer poll.
+    pub ws_rpc_url: Option<String>,
+    /// Adaptive pooling curve used by the `PoolingManager` for this chain.
+    pub pooling: PoolingPolicy,
+    /// Confirmations a transfer needs before it can settle an order.
+    ///
+    /// Transfers with fewer confirmations are reported as detected only.
+    pub required_confirmations: u32,
+}
+
+/// Default confirmation depth for a chain without a `[[chains]]` entry.
+pub fn default_required_confirmations(blockchain: Blockchain) -> u32 {
+    match blockchain {
+        Blockchain::Polygon => 32,
+        Blockchain::Ethereum
+        | Blockchain::Base
+        | Blockchain::ArbitrumOne
+        | Blockchain::Linea
+        | Blockchain::Optimism => 12,
+        Blockchain::AvalancheC => 1,
+        Blockchain::Tron => 19,
+    }
+}
+
+/// Confirmation depth for `blockchain`, falling back to the chain default.
+pub fn required_confirmations_for(chains: &[ChainConfig], blockchain: Blockchain) -> u32 {
+    chains
+        .iter()
+        .find(|c| c.blockchain == blockchain)
+        .map_or_else(
+            || default_required_confirmations(blockchain),
+            |c| c.required_confirmations,
+        )
+}
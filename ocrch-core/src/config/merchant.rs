@@ -1,36 +1,107 @@
 //! Merchant configuration.
 
+use crate::config::{WalletConfig, WebhookPayloadVersion};
+use crate::utils::webhook_url::WebhookUrlPolicy;
+use ocrch_sdk::signature::SigningKey;
+
+/// ID of the merchant configured in the single `[merchant]` section.
+pub const DEFAULT_MERCHANT_ID: &str = "default";
+
+/// An active merchant HMAC secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerchantSecret {
+    /// Key ID sent in signature headers, or `None` for the single
+    /// `merchant.secret`.
+    pub id: Option<String>,
+    /// Secret key bytes.
+    pub secret: Box<[u8]>,
+}
+
 /// Merchant configuration for API access.
 #[derive(Debug, Clone)]
 pub struct MerchantConfig {
+    /// Unique merchant ID, stored on the merchant's orders.
+    pub id: String,
     /// Human-readable merchant name.
     pub name: String,
-    /// Secret key bytes for HMAC signing.
-    pub secret: Box<[u8]>,
+    /// Active HMAC secrets. Incoming signatures may use any of them;
+    /// outgoing webhooks carry a signature per secret.
+    pub secrets: Vec<MerchantSecret>,
     /// List of allowed origins for CORS (frontend URLs).
     pub allowed_origins: Vec<String>,
     /// Optional webhook URL for unknown transfer notifications.
     pub unknown_transfer_webhook_url: Option<String>,
+    /// Webhook URL for order events of orders created without one.
+    pub default_webhook_url: Option<String>,
+    /// Payload schema version sent to the order webhook URLs and the
+    /// unknown-transfer webhook URL.
+    pub webhook_payload_version: WebhookPayloadVersion,
+    /// Restrictions on webhook URLs supplied when creating orders.
+    pub webhook_url_policy: WebhookUrlPolicy,
 }
 
 impl MerchantConfig {
     /// Create a new MerchantConfig.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
+        id: String,
         name: String,
-        secret: impl Into<Box<[u8]>>,
+        secrets: Vec<MerchantSecret>,
         allowed_origins: Vec<String>,
         unknown_transfer_webhook_url: Option<String>,
+        default_webhook_url: Option<String>,
+        webhook_payload_version: WebhookPayloadVersion,
+        webhook_url_policy: WebhookUrlPolicy,
     ) -> Self {
         Self {
+            id,
             name,
-            secret: secret.into(),
+            secrets,
             allowed_origins,
             unknown_transfer_webhook_url,
+            default_webhook_url,
+            webhook_payload_version,
+            webhook_url_policy,
         }
     }
 
-    /// Get the secret key bytes for HMAC signing.
-    pub fn secret_bytes(&self) -> &[u8] {
-        &self.secret
+    /// The active secrets as signing keys.
+    pub fn signing_keys(&self) -> Vec<SigningKey<'_>> {
+        self.secrets
+            .iter()
+            .map(|s| SigningKey {
+                id: s.id.as_deref(),
+                secret: &s.secret,
+            })
+            .collect()
+    }
+}
+
+/// The merchant with the given ID.
+pub fn find_merchant<'a>(merchants: &'a [MerchantConfig], id: &str) -> Option<&'a MerchantConfig> {
+    merchants.iter().find(|m| m.id == id)
+}
+
+/// The default merchant: the first configured one. It serves requests
+/// that do not name a merchant and owns the shared wallets' unknown
+/// transfers.
+pub fn default_merchant(merchants: &[MerchantConfig]) -> Option<&MerchantConfig> {
+    merchants.first()
+}
+
+/// The merchant notified about transfers to `address`: the wallet's owner,
+/// or the default merchant for shared and unknown wallets.
+pub fn wallet_merchant<'a>(
+    merchants: &'a [MerchantConfig],
+    wallets: &[WalletConfig],
+    address: &str,
+) -> Option<&'a MerchantConfig> {
+    let owner = wallets
+        .iter()
+        .find(|w| w.address.eq_ignore_ascii_case(address))
+        .and_then(|w| w.merchant.as_deref());
+    match owner {
+        Some(id) => find_merchant(merchants, id),
+        None => default_merchant(merchants),
     }
 }
@@ -6,17 +6,26 @@
 
 mod admin;
 mod api_keys;
+mod chain;
 mod config_store;
 mod merchant;
 mod server;
+mod sync_health;
 mod wallet;
+mod webhooks;
 
 pub use admin::AdminConfig;
 pub use api_keys::ApiKeysConfig;
+pub use chain::{ChainConfig, default_required_confirmations, required_confirmations_for};
 pub use config_store::{ConfigStore, ConfigWatcher};
-pub use merchant::MerchantConfig;
+pub use merchant::{
+    DEFAULT_MERCHANT_ID, MerchantConfig, MerchantSecret, default_merchant, find_merchant,
+    wallet_merchant,
+};
 pub use server::ServerConfig;
+pub use sync_health::SyncHealthConfig;
 pub use wallet::WalletConfig;
+pub use webhooks::{WebhookPayloadVersion, WebhookSubscriptionConfig};
 
 /// Owns the config stores for each configuration section, keeping them alive
 /// for the duration of the application. Clone it cheaply to share handles.
@@ -26,10 +35,16 @@ pub struct SharedConfig {
     pub server: ConfigStore<ServerConfig>,
     /// Admin configuration (authentication).
     pub admin: ConfigStore<AdminConfig>,
-    /// Merchant configurations indexed by ID.
-    pub merchant: ConfigStore<MerchantConfig>,
+    /// Merchant configurations; the first one is the default merchant.
+    pub merchants: ConfigStore<Vec<MerchantConfig>>,
     /// Wallet configurations for receiving payments.
     pub wallets: ConfigStore<Vec<WalletConfig>>,
     /// API keys for blockchain explorer services.
     pub api_keys: ConfigStore<ApiKeysConfig>,
+    /// Per-chain settings (RPC endpoints, etc.).
+    pub chains: ConfigStore<Vec<ChainConfig>>,
+    /// Thresholds for sync health monitoring.
+    pub sync_health: ConfigStore<SyncHealthConfig>,
+    /// Additional webhook endpoints.
+    pub webhooks: ConfigStore<Vec<WebhookSubscriptionConfig>>,
 }
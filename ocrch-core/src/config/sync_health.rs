@@ -0,0 +1,21 @@
+//! Sync health configuration.
+
+/// Thresholds for deciding when a blockchain sync is unhealthy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncHealthConfig {
+    /// Number of consecutive failed syncs after which a (blockchain, token)
+    /// pair is considered unhealthy and hidden from the User API.
+    pub unhealthy_after_failures: u32,
+    /// Number of blocks the sync may fall behind the chain head before the
+    /// pair is considered unhealthy. `None` disables the check.
+    pub max_lag_blocks: Option<u32>,
+}
+
+impl Default for SyncHealthConfig {
+    fn default() -> Self {
+        Self {
+            unhealthy_after_failures: 5,
+            max_lag_blocks: None,
+        }
+    }
+}
@@ -15,4 +15,14 @@ pub struct WalletConfig {
     /// When no transfers exist in the database, sync will start from this
     /// transaction's block (ERC-20) or timestamp (TRC-20) instead of from the beginning.
     pub starting_tx: Option<String>,
+    /// ID of the merchant this wallet belongs to, or `None` for a wallet
+    /// shared by all merchants.
+    pub merchant: Option<String>,
+}
+
+impl WalletConfig {
+    /// Whether the merchant can receive payments on this wallet.
+    pub fn is_available_to(&self, merchant_id: &str) -> bool {
+        self.merchant.as_deref().is_none_or(|m| m == merchant_id)
+    }
 }
@@ -0,0 +1,96 @@
+//! Webhook subscription configuration.
+
+use crate::config::MerchantConfig;
+use ocrch_sdk::objects::WebhookEventType;
+use ocrch_sdk::signature::SigningKey;
+
+/// Webhook payload schema version sent to an endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WebhookPayloadVersion {
+    /// The original payloads (`OrderStatusChangedPayload`, ...).
+    #[default]
+    V1,
+    /// Payloads with full transfer details (`OrderStatusChangedPayloadV2`, ...).
+    V2,
+}
+
+/// An additional webhook endpoint that receives a copy of selected events.
+///
+/// Every subscription gets its own delivery and retry state, independent of
+/// the order's webhook URL and of other subscriptions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookSubscriptionConfig {
+    /// Unique name, recorded on every delivery to this endpoint.
+    pub name: String,
+    /// URL the events are POSTed to.
+    pub url: String,
+    /// Event types sent to this endpoint.
+    pub events: Vec<WebhookEventType>,
+    /// Merchant whose events are sent to this endpoint, or `None` for the
+    /// events of every merchant.
+    pub merchant: Option<String>,
+    /// Signing secret, if different from the merchant secrets.
+    pub secret: Option<Box<[u8]>>,
+    /// Payload schema version sent to this endpoint.
+    pub payload_version: WebhookPayloadVersion,
+}
+
+impl WebhookSubscriptionConfig {
+    /// Whether this subscription receives events of the given type.
+    pub fn subscribes_to(&self, event_type: WebhookEventType) -> bool {
+        self.events.contains(&event_type)
+    }
+
+    /// Whether this subscription receives events of the given merchant.
+    /// `merchant_id` is `None` if the event's merchant is unknown, which
+    /// only subscriptions for every merchant receive.
+    pub fn covers_merchant(&self, merchant_id: Option<&str>) -> bool {
+        match &self.merchant {
+            Some(merchant) => merchant_id == Some(merchant.as_str()),
+            None => true,
+        }
+    }
+
+    /// The keys used to sign requests to this endpoint: its own secret, or
+    /// the secrets of the merchant the event belongs to. Empty if neither
+    /// is available.
+    pub fn signing_keys<'a>(&'a self, merchant: Option<&'a MerchantConfig>) -> Vec<SigningKey<'a>> {
+        match (&self.secret, merchant) {
+            (Some(secret), _) => vec![SigningKey::unnamed(secret)],
+            (None, Some(merchant)) => merchant.signing_keys(),
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(name: &str, merchant: Option<&str>) -> WebhookSubscriptionConfig {
+        WebhookSubscriptionConfig {
+            name: name.to_string(),
+            url: format!("https://{name}.example.com/hook"),
+            events: vec![WebhookEventType::OrderStatusChanged],
+            merchant: merchant.map(str::to_string),
+            secret: None,
+            payload_version: WebhookPayloadVersion::V1,
+        }
+    }
+
+    #[test]
+    fn test_covers_merchant() {
+        let shop = subscription("shop", Some("shop"));
+        let games = subscription("games", Some("games"));
+        let audit = subscription("audit", None);
+
+        assert!(shop.covers_merchant(Some("shop")));
+        assert!(!shop.covers_merchant(Some("games")));
+        assert!(games.covers_merchant(Some("games")));
+        assert!(!games.covers_merchant(Some("shop")));
+        assert!(!shop.covers_merchant(None));
+        assert!(audit.covers_merchant(Some("shop")));
+        assert!(audit.covers_merchant(Some("games")));
+        assert!(audit.covers_merchant(None));
+    }
+}
@@ -0,0 +1,255 @@
+//! Scoped API keys.
+//!
+//! A key acts for one merchant and signs Service API bodies or User API
+//! URLs with its own secret instead of the merchant secret. Requests name
+//! the key in the `Ocrch-Key-Id` header and may only perform the operations
+//! in its scopes. Revoking a key keeps the row, so its usage stays visible.
+
+use crate::framework::DatabaseProcessor;
+use kanau::processor::Processor;
+use ocrch_sdk::objects::admin::ApiKeyScope as SdkApiKeyScope;
+use time::PrimitiveDateTime;
+
+/// API key scope for database operations.
+///
+/// This is the sqlx::Type version. For API/DTO use, see
+/// `ocrch_sdk::objects::admin::ApiKeyScope`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(rename_all = "snake_case", type_name = "api_key_scope")]
+pub enum ApiKeyScope {
+    CreateOrders,
+    ReadOrders,
+    CancelOrders,
+    SignUrls,
+}
+
+impl ApiKeyScope {
+    /// The scope's name in the API, e.g. `create_orders`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiKeyScope::CreateOrders => "create_orders",
+            ApiKeyScope::ReadOrders => "read_orders",
+            ApiKeyScope::CancelOrders => "cancel_orders",
+            ApiKeyScope::SignUrls => "sign_urls",
+        }
+    }
+}
+
+impl From<ApiKeyScope> for SdkApiKeyScope {
+    fn from(value: ApiKeyScope) -> Self {
+        match value {
+            ApiKeyScope::CreateOrders => SdkApiKeyScope::CreateOrders,
+            ApiKeyScope::ReadOrders => SdkApiKeyScope::ReadOrders,
+            ApiKeyScope::CancelOrders => SdkApiKeyScope::CancelOrders,
+            ApiKeyScope::SignUrls => SdkApiKeyScope::SignUrls,
+        }
+    }
+}
+
+impl From<SdkApiKeyScope> for ApiKeyScope {
+    fn from(value: SdkApiKeyScope) -> Self {
+        match value {
+            SdkApiKeyScope::CreateOrders => ApiKeyScope::CreateOrders,
+            SdkApiKeyScope::ReadOrders => ApiKeyScope::ReadOrders,
+            SdkApiKeyScope::CancelOrders => ApiKeyScope::CancelOrders,
+            SdkApiKeyScope::SignUrls => ApiKeyScope::SignUrls,
+        }
+    }
+}
+
+/// A stored API key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    pub id: String,
+    pub merchant_id: String,
+    pub name: String,
+    pub secret: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<PrimitiveDateTime>,
+    pub last_used_at: Option<PrimitiveDateTime>,
+    pub revoked_at: Option<PrimitiveDateTime>,
+    pub created_at: PrimitiveDateTime,
+}
+
+impl ApiKey {
+    /// Whether the key is neither revoked nor expired at `now`.
+    pub fn is_active(&self, now: PrimitiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Store a new API key.
+pub struct CreateApiKey {
+    pub id: String,
+    pub merchant_id: String,
+    pub name: String,
+    pub secret: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<PrimitiveDateTime>,
+}
+
+impl Processor<CreateApiKey> for DatabaseProcessor {
+    type Output = ApiKey;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:CreateApiKey")]
+    async fn process(&self, cmd: CreateApiKey) -> Result<ApiKey, sqlx::Error> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO api_keys (id, merchant_id, name, secret, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING
+                id,
+                merchant_id,
+                name,
+                secret,
+                scopes as "scopes: Vec<ApiKeyScope>",
+                expires_at,
+                last_used_at,
+                revoked_at,
+                created_at
+            "#,
+            cmd.id,
+            cmd.merchant_id,
+            cmd.name,
+            cmd.secret,
+            &cmd.scopes as &[ApiKeyScope],
+            cmd.expires_at,
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Get an API key by its ID, including revoked keys.
+pub struct GetApiKey {
+    pub id: String,
+}
+
+impl Processor<GetApiKey> for DatabaseProcessor {
+    type Output = Option<ApiKey>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetApiKey")]
+    async fn process(&self, query: GetApiKey) -> Result<Option<ApiKey>, sqlx::Error> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT
+                id,
+                merchant_id,
+                name,
+                secret,
+                scopes as "scopes: Vec<ApiKeyScope>",
+                expires_at,
+                last_used_at,
+                revoked_at,
+                created_at
+            FROM api_keys
+            WHERE id = $1
+            "#,
+            query.id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// List API keys, including revoked ones, oldest first.
+pub struct ListApiKeys {
+    pub merchant_id: Option<String>,
+}
+
+impl Processor<ListApiKeys> for DatabaseProcessor {
+    type Output = Vec<ApiKey>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ListApiKeys")]
+    async fn process(&self, query: ListApiKeys) -> Result<Vec<ApiKey>, sqlx::Error> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT
+                id,
+                merchant_id,
+                name,
+                secret,
+                scopes as "scopes: Vec<ApiKeyScope>",
+                expires_at,
+                last_used_at,
+                revoked_at,
+                created_at
+            FROM api_keys
+            WHERE ($1::TEXT IS NULL OR merchant_id = $1)
+            ORDER BY created_at, id
+            "#,
+            query.merchant_id,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Revoke an API key. Revoking a revoked key keeps its original revocation
+/// time. Returns the key, or `None` if it does not exist.
+pub struct RevokeApiKey {
+    pub id: String,
+}
+
+impl Processor<RevokeApiKey> for DatabaseProcessor {
+    type Output = Option<ApiKey>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:RevokeApiKey")]
+    async fn process(&self, cmd: RevokeApiKey) -> Result<Option<ApiKey>, sqlx::Error> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            UPDATE api_keys
+            SET revoked_at = COALESCE(revoked_at, CURRENT_TIMESTAMP)
+            WHERE id = $1
+            RETURNING
+                id,
+                merchant_id,
+                name,
+                secret,
+                scopes as "scopes: Vec<ApiKeyScope>",
+                expires_at,
+                last_used_at,
+                revoked_at,
+                created_at
+            "#,
+            cmd.id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Record that an API key was used. The timestamp is only written when it
+/// is older than a minute, so busy keys do not cause a write per request.
+pub struct TouchApiKey {
+    pub id: String,
+}
+
+impl Processor<TouchApiKey> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:TouchApiKey")]
+    async fn process(&self, cmd: TouchApiKey) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE api_keys
+            SET last_used_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+              AND (last_used_at IS NULL OR last_used_at < CURRENT_TIMESTAMP - INTERVAL '1 minute')
+            "#,
+            cmd.id,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
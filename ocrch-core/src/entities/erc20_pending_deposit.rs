@@ -169,6 +169,122 @@ impl Processor<GetErc20DepositsForMatching> for DatabaseProcessor {
     }
 }
 
+/// A pending deposit whose detection state changed.
+#[derive(Debug, Clone)]
+pub struct Erc20DetectedDeposit {
+    pub id: i64,
+    /// `false` if only the confirmation count changed.
+    pub newly_detected: bool,
+}
+
+/// Record unconfirmed transfers seen for pending deposits.
+///
+/// `deposit_ids[i]`, `transfer_ids[i]` and `confirmations[i]` are paired.
+/// Only deposits whose detected transfer or confirmation count changed are
+/// updated and returned. A `payment_detected` webhook is queued in the same
+/// statement for every deposit whose detected transfer is new.
+#[derive(Debug, Clone)]
+pub struct MarkErc20DepositsDetected {
+    pub deposit_ids: Vec<i64>,
+    pub transfer_ids: Vec<i64>,
+    pub confirmations: Vec<i32>,
+    /// Confirmations the chain requires, recorded in the queued webhooks.
+    pub required_confirmations: i32,
+}
+
+impl Processor<MarkErc20DepositsDetected> for DatabaseProcessor {
+    type Output = Vec<Erc20DetectedDeposit>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:MarkErc20DepositsDetected")]
+    async fn process(
+        &self,
+        cmd: MarkErc20DepositsDetected,
+    ) -> Result<Vec<Erc20DetectedDeposit>, sqlx::Error> {
+        if cmd.deposit_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        sqlx::query_as!(
+            Erc20DetectedDeposit,
+            r#"
+            WITH changed AS (
+                UPDATE erc20_pending_deposits AS d
+                SET detected_transfer_id = u.transfer_id,
+                    detected_confirmations = u.confirmations
+                FROM UNNEST($1::bigint[], $2::bigint[], $3::int[]) AS u(deposit_id, transfer_id, confirmations),
+                     erc20_pending_deposits AS prev
+                WHERE d.id = u.deposit_id
+                  AND prev.id = d.id
+                  AND (d.detected_transfer_id IS DISTINCT FROM u.transfer_id
+                       OR d.detected_confirmations IS DISTINCT FROM u.confirmations)
+                RETURNING
+                    d.id,
+                    d."order" AS order_id,
+                    d.chain,
+                    u.transfer_id,
+                    u.confirmations,
+                    prev.detected_transfer_id IS DISTINCT FROM u.transfer_id AS newly_detected
+            ),
+            queued AS (
+                INSERT INTO webhook_outbox
+                    (kind, order_id, chain, txn_hash, confirmations, required_confirmations)
+                SELECT 'payment_detected', c.order_id, c.chain, t.txn_hash, c.confirmations, $4
+                FROM changed c
+                JOIN erc20_token_transfers t ON t.id = c.transfer_id
+                WHERE c.newly_detected
+            )
+            SELECT id as "id!", newly_detected as "newly_detected!" FROM changed
+            "#,
+            &cmd.deposit_ids,
+            &cmd.transfer_ids,
+            &cmd.confirmations,
+            cmd.required_confirmations,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+/// Number of live pending deposits for one (chain, token) pair.
+#[derive(Debug, Clone)]
+pub struct Erc20LivePendingDeposits {
+    pub chain: EtherScanChain,
+    pub token_name: StablecoinName,
+    pub count: i64,
+    pub latest_started_at: time::PrimitiveDateTime,
+}
+
+/// Count pending deposits whose order is still pending, grouped by chain and token.
+#[derive(Debug, Clone)]
+pub struct CountErc20LivePendingDeposits;
+
+impl Processor<CountErc20LivePendingDeposits> for DatabaseProcessor {
+    type Output = Vec<Erc20LivePendingDeposits>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:CountErc20LivePendingDeposits")]
+    async fn process(
+        &self,
+        _query: CountErc20LivePendingDeposits,
+    ) -> Result<Vec<Erc20LivePendingDeposits>, sqlx::Error> {
+        sqlx::query_as!(
+            Erc20LivePendingDeposits,
+            r#"
+            SELECT
+                d.chain as "chain: EtherScanChain",
+                d.token_name as "token_name: StablecoinName",
+                COUNT(*) as "count!",
+                MAX(d.started_at) as "latest_started_at!"
+            FROM erc20_pending_deposits d
+            JOIN order_records o ON d."order" = o.order_id
+            WHERE o.status = 'pending'
+            GROUP BY d.chain, d.token_name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
 /// List ERC-20 pending deposits with pagination and optional filters.
 #[derive(Debug, Clone)]
 pub struct ListErc20PendingDeposits {
@@ -177,6 +293,8 @@ pub struct ListErc20PendingDeposits {
     pub order_id: Option<uuid::Uuid>,
     pub chain: Option<EtherScanChain>,
     pub token: Option<StablecoinName>,
+    /// Only deposits of orders created by this merchant.
+    pub merchant_id: Option<String>,
 }
 
 impl Processor<ListErc20PendingDeposits> for DatabaseProcessor {
@@ -204,13 +322,17 @@ impl Processor<ListErc20PendingDeposits> for DatabaseProcessor {
             WHERE ($1::uuid IS NULL OR "order" = $1)
               AND ($2::etherscan_chain IS NULL OR chain = $2)
               AND ($3::stablecoin_name IS NULL OR token_name = $3)
+              AND ($4::text IS NULL OR "order" IN (
+                  SELECT order_id FROM order_records WHERE merchant_id = $4
+              ))
             ORDER BY started_at DESC
-            LIMIT $4
-            OFFSET $5
+            LIMIT $5
+            OFFSET $6
             "#,
             query.order_id as Option<uuid::Uuid>,
             query.chain as Option<EtherScanChain>,
             query.token as Option<StablecoinName>,
+            query.merchant_id as Option<String>,
             query.limit,
             query.offset,
         )
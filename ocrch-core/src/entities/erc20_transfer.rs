@@ -1,4 +1,6 @@
 use crate::entities::erc20_pending_deposit::EtherScanChain;
+use crate::entities::order_records::OrderStatus;
+use crate::entities::webhook_outbox::WebhookOutbox;
 use crate::entities::{StablecoinName, TransferStatus};
 use crate::framework::DatabaseProcessor;
 use kanau::processor::Processor;
@@ -44,6 +46,16 @@ pub struct Erc20UnmatchedTransfer {
     pub block_timestamp: i64,
 }
 
+/// A transfer that has been seen but is not yet deep enough to be matched.
+#[derive(Debug, Clone)]
+pub struct Erc20UnconfirmedTransfer {
+    pub id: i64,
+    pub to_address: String,
+    pub value: Decimal,
+    pub txn_hash: String,
+    pub confirmations: i32,
+}
+
 /// Sync cursor from the erc20_sync_cursor materialized view.
 /// Contains the block number to start syncing from.
 #[derive(Debug, Clone)]
@@ -180,6 +192,86 @@ impl Processor<GetErc20TokenTransfersUnmatched> for DatabaseProcessor {
     }
 }
 
+#[derive(Debug, Clone)]
+/// Get transfers that are still waiting for enough confirmations.
+pub struct GetErc20TokenTransfersUnconfirmed {
+    pub chain: EtherScanChain,
+    pub token: StablecoinName,
+}
+
+impl Processor<GetErc20TokenTransfersUnconfirmed> for DatabaseProcessor {
+    type Output = Vec<Erc20UnconfirmedTransfer>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetErc20TokenTransfersUnconfirmed")]
+    async fn process(
+        &self,
+        query: GetErc20TokenTransfersUnconfirmed,
+    ) -> Result<Vec<Erc20UnconfirmedTransfer>, sqlx::Error> {
+        sqlx::query_as!(
+            Erc20UnconfirmedTransfer,
+            r#"
+            SELECT
+                id,
+                to_address,
+                value,
+                txn_hash,
+                confirmations
+            FROM erc20_token_transfers
+            WHERE chain = $1
+              AND token_name = $2
+              AND status = 'waiting_for_confirmation'
+            ORDER BY block_timestamp ASC
+            "#,
+            query.chain as EtherScanChain,
+            query.token as StablecoinName,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Update the confirmation depth of unconfirmed transfers from the chain head.
+///
+/// Transfers that reach `required_confirmations` are marked as confirmed and
+/// move on to `waiting_for_match`. Returns the number of newly confirmed rows.
+pub struct UpdateErc20TransferConfirmations {
+    pub chain: EtherScanChain,
+    pub token: StablecoinName,
+    pub chain_head: i64,
+    pub required_confirmations: i32,
+}
+
+impl Processor<UpdateErc20TransferConfirmations> for DatabaseProcessor {
+    type Output = u64;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:UpdateErc20TransferConfirmations")]
+    async fn process(&self, cmd: UpdateErc20TransferConfirmations) -> Result<u64, sqlx::Error> {
+        let confirmed = sqlx::query_scalar!(
+            r#"
+            UPDATE erc20_token_transfers
+            SET confirmations = LEAST(GREATEST($3 - block_number + 1, 0), 2147483647)::int,
+                blockchain_confirmed = ($3 - block_number + 1) >= $4,
+                status = CASE
+                    WHEN ($3 - block_number + 1) >= $4 THEN 'waiting_for_match'::transfer_status
+                    ELSE status
+                END
+            WHERE chain = $1
+              AND token_name = $2
+              AND status = 'waiting_for_confirmation'
+            RETURNING blockchain_confirmed
+            "#,
+            cmd.chain as EtherScanChain,
+            cmd.token as StablecoinName,
+            cmd.chain_head,
+            i64::from(cmd.required_confirmations),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(confirmed.into_iter().filter(|c| *c).count() as u64)
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Get IDs of old unmatched transfers (older than 1 hour) for marking as unknown.
 pub struct GetOldUnmatchedErc20TransferIds {
@@ -231,11 +323,17 @@ impl Processor<MarkErc20TransfersNoMatchedDeposit> for DatabaseProcessor {
             return Ok(0);
         }
 
+        // The unknown-transfer webhooks are queued in the same statement
         let result = sqlx::query!(
             r#"
-            UPDATE erc20_token_transfers
-            SET status = 'no_matched_deposit'
-            WHERE id = ANY($1)
+            WITH marked AS (
+                UPDATE erc20_token_transfers
+                SET status = 'no_matched_deposit'
+                WHERE id = ANY($1) AND status != 'no_matched_deposit'
+                RETURNING id, chain
+            )
+            INSERT INTO webhook_outbox (kind, transfer_id, chain)
+            SELECT 'unknown_transfer_received', id, chain FROM marked
             "#,
             &cmd.transfer_ids,
         )
@@ -248,11 +346,12 @@ impl Processor<MarkErc20TransfersNoMatchedDeposit> for DatabaseProcessor {
 #[derive(Debug, Clone)]
 /// Handle matched ERC-20 transfers in a single transaction.
 ///
-/// Executes 4 SQL statements atomically:
+/// Executes 5 SQL statements atomically:
 /// 1. Mark ERC-20 transfers as matched with their deposit (fulfillment) IDs
 /// 2. Update order statuses to `Paid`
 /// 3. Delete ERC-20 pending deposits for matched orders (keep the matched deposit)
 /// 4. Delete TRC-20 pending deposits for matched orders (cross-chain cleanup)
+/// 5. Queue an order status webhook for each paid order
 pub struct HandleErc20MatchedTrans {
     pub transfer_ids: Vec<i64>,
     pub deposit_ids: Vec<i64>,
@@ -316,6 +415,10 @@ impl Processor<HandleErc20MatchedTrans> for DatabaseProcessor {
         .execute(&mut *tx)
         .await?;
 
+        // 5. Queue order status webhooks in the same transaction
+        WebhookOutbox::enqueue_order_status_many_tx(&mut tx, &cmd.order_ids, OrderStatus::Paid)
+            .await?;
+
         tx.commit().await?;
         Ok(())
     }
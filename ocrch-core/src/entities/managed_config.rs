@@ -0,0 +1,320 @@
+//! Merchants and wallets managed through the Admin API.
+//!
+//! Rows are layered over the merchants and `[[wallets]]` of the config
+//! file: a row replaces the file entry with the same key (merchant ID, or
+//! blockchain and address), other rows add entries, and a disabled wallet
+//! row hides the file wallet. Every change is announced on
+//! [`CONFIG_CHANGED_CHANNEL`] so that all server instances reload.
+
+use crate::entities::{BlockchainName, StablecoinName};
+use crate::framework::DatabaseProcessor;
+use kanau::processor::Processor;
+use uuid::Uuid;
+
+/// Postgres `NOTIFY` channel announcing changes to the managed merchants
+/// and wallets. The payload is the ID of the instance that made the change.
+pub const CONFIG_CHANGED_CHANNEL: &str = "ocrch_config_changed";
+
+/// A merchant stored in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagedMerchant {
+    pub id: String,
+    pub name: String,
+    /// Active secrets in header order.
+    pub secrets: Vec<ManagedMerchantSecret>,
+    pub allowed_origins: Vec<String>,
+    pub unknown_transfer_webhook_url: Option<String>,
+    pub default_webhook_url: Option<String>,
+    pub webhook_payload_version: i16,
+    pub webhook_allowed_hosts: Vec<String>,
+    pub webhook_allow_private_networks: bool,
+}
+
+/// An active secret of a [`ManagedMerchant`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagedMerchantSecret {
+    /// Key ID, or `None` for the merchant's single unnamed secret.
+    pub key_id: Option<String>,
+    pub secret: String,
+}
+
+/// A wallet stored in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagedWallet {
+    pub blockchain: BlockchainName,
+    pub address: String,
+    pub enabled_coins: Vec<StablecoinName>,
+    pub starting_tx: Option<String>,
+    /// Merchant owning the wallet, or `None` for a shared wallet.
+    pub merchant_id: Option<String>,
+    /// A disabled wallet is not synced or offered to customers.
+    pub enabled: bool,
+}
+
+struct ManagedMerchantRow {
+    id: String,
+    name: String,
+    allowed_origins: Vec<String>,
+    unknown_transfer_webhook_url: Option<String>,
+    default_webhook_url: Option<String>,
+    webhook_payload_version: i16,
+    webhook_allowed_hosts: Vec<String>,
+    webhook_allow_private_networks: bool,
+}
+
+#[derive(Debug, Clone)]
+/// List all managed merchants with their secrets, ordered by creation.
+pub struct ListManagedMerchants;
+
+impl Processor<ListManagedMerchants> for DatabaseProcessor {
+    type Output = Vec<ManagedMerchant>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ListManagedMerchants")]
+    async fn process(&self, _: ListManagedMerchants) -> Result<Vec<ManagedMerchant>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            ManagedMerchantRow,
+            r#"
+            SELECT
+                id,
+                name,
+                allowed_origins,
+                unknown_transfer_webhook_url,
+                default_webhook_url,
+                webhook_payload_version,
+                webhook_allowed_hosts,
+                webhook_allow_private_networks
+            FROM managed_merchants
+            ORDER BY created_at, id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let secrets = sqlx::query!(
+            r#"
+            SELECT merchant_id, key_id, secret
+            FROM managed_merchant_secrets
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ManagedMerchant {
+                secrets: secrets
+                    .iter()
+                    .filter(|s| s.merchant_id == row.id)
+                    .map(|s| ManagedMerchantSecret {
+                        key_id: s.key_id.clone(),
+                        secret: s.secret.clone(),
+                    })
+                    .collect(),
+                id: row.id,
+                name: row.name,
+                allowed_origins: row.allowed_origins,
+                unknown_transfer_webhook_url: row.unknown_transfer_webhook_url,
+                default_webhook_url: row.default_webhook_url,
+                webhook_payload_version: row.webhook_payload_version,
+                webhook_allowed_hosts: row.webhook_allowed_hosts,
+                webhook_allow_private_networks: row.webhook_allow_private_networks,
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Create a managed merchant or replace its settings and secrets.
+pub struct UpsertManagedMerchant {
+    pub merchant: ManagedMerchant,
+}
+
+impl Processor<UpsertManagedMerchant> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:UpsertManagedMerchant")]
+    async fn process(&self, cmd: UpsertManagedMerchant) -> Result<(), sqlx::Error> {
+        let m = cmd.merchant;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO managed_merchants (
+                id, name, allowed_origins, unknown_transfer_webhook_url, default_webhook_url,
+                webhook_payload_version, webhook_allowed_hosts, webhook_allow_private_networks
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (id) DO UPDATE SET
+                name = EXCLUDED.name,
+                allowed_origins = EXCLUDED.allowed_origins,
+                unknown_transfer_webhook_url = EXCLUDED.unknown_transfer_webhook_url,
+                default_webhook_url = EXCLUDED.default_webhook_url,
+                webhook_payload_version = EXCLUDED.webhook_payload_version,
+                webhook_allowed_hosts = EXCLUDED.webhook_allowed_hosts,
+                webhook_allow_private_networks = EXCLUDED.webhook_allow_private_networks,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+            m.id,
+            m.name,
+            &m.allowed_origins,
+            m.unknown_transfer_webhook_url,
+            m.default_webhook_url,
+            m.webhook_payload_version,
+            &m.webhook_allowed_hosts,
+            m.webhook_allow_private_networks,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM managed_merchant_secrets WHERE merchant_id = $1",
+            m.id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        for s in &m.secrets {
+            sqlx::query!(
+                r#"
+                INSERT INTO managed_merchant_secrets (merchant_id, key_id, secret)
+                VALUES ($1, $2, $3)
+                "#,
+                m.id,
+                s.key_id,
+                s.secret,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Delete a managed merchant. Returns whether it existed.
+pub struct DeleteManagedMerchant {
+    pub id: String,
+}
+
+impl Processor<DeleteManagedMerchant> for DatabaseProcessor {
+    type Output = bool;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:DeleteManagedMerchant")]
+    async fn process(&self, cmd: DeleteManagedMerchant) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM managed_merchants WHERE id = $1", cmd.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// List all managed wallets, including disabled ones, ordered by creation.
+pub struct ListManagedWallets;
+
+impl Processor<ListManagedWallets> for DatabaseProcessor {
+    type Output = Vec<ManagedWallet>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ListManagedWallets")]
+    async fn process(&self, _: ListManagedWallets) -> Result<Vec<ManagedWallet>, sqlx::Error> {
+        sqlx::query_as!(
+            ManagedWallet,
+            r#"
+            SELECT
+                blockchain as "blockchain: BlockchainName",
+                address,
+                enabled_coins as "enabled_coins: Vec<StablecoinName>",
+                starting_tx,
+                merchant_id,
+                enabled
+            FROM managed_wallets
+            ORDER BY created_at, blockchain, address
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Create a managed wallet or replace its settings.
+pub struct UpsertManagedWallet {
+    pub wallet: ManagedWallet,
+}
+
+impl Processor<UpsertManagedWallet> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:UpsertManagedWallet")]
+    async fn process(&self, cmd: UpsertManagedWallet) -> Result<(), sqlx::Error> {
+        let w = cmd.wallet;
+        sqlx::query!(
+            r#"
+            INSERT INTO managed_wallets (
+                blockchain, address, enabled_coins, starting_tx, merchant_id, enabled
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (blockchain, address) DO UPDATE SET
+                enabled_coins = EXCLUDED.enabled_coins,
+                starting_tx = EXCLUDED.starting_tx,
+                merchant_id = EXCLUDED.merchant_id,
+                enabled = EXCLUDED.enabled,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+            w.blockchain as BlockchainName,
+            w.address,
+            &w.enabled_coins as &[StablecoinName],
+            w.starting_tx,
+            w.merchant_id,
+            w.enabled,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Delete a managed wallet. Returns whether it existed.
+pub struct DeleteManagedWallet {
+    pub blockchain: BlockchainName,
+    pub address: String,
+}
+
+impl Processor<DeleteManagedWallet> for DatabaseProcessor {
+    type Output = bool;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:DeleteManagedWallet")]
+    async fn process(&self, cmd: DeleteManagedWallet) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM managed_wallets WHERE blockchain = $1 AND address = $2",
+            cmd.blockchain as BlockchainName,
+            cmd.address,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Announce a change to the managed merchants or wallets on
+/// [`CONFIG_CHANGED_CHANNEL`].
+pub struct NotifyManagedConfigChanged {
+    /// ID of the instance that made the change, so it can skip its own
+    /// notification.
+    pub instance_id: Uuid,
+}
+
+impl Processor<NotifyManagedConfigChanged> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:NotifyManagedConfigChanged")]
+    async fn process(&self, cmd: NotifyManagedConfigChanged) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "SELECT pg_notify($1, $2)",
+            CONFIG_CHANGED_CHANNEL,
+            cmd.instance_id.to_string(),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
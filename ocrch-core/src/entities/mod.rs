@@ -1,10 +1,18 @@
+pub mod api_key;
 pub mod erc20_pending_deposit;
 pub mod erc20_transfer;
+pub mod managed_config;
 pub mod order_records;
 pub mod trc20_pending_deposit;
 pub mod trc20_transfer;
+pub mod webhook_dead_letter;
+pub mod webhook_delivery_attempt;
+pub mod webhook_outbox;
+pub mod webhook_transfer;
 
-use ocrch_sdk::objects::{Stablecoin as SdkStablecoin, TransferStatus as SdkTransferStatus};
+use ocrch_sdk::objects::{
+    Blockchain as SdkBlockchain, Stablecoin as SdkStablecoin, TransferStatus as SdkTransferStatus,
+};
 
 /// Stablecoin name for database operations.
 ///
@@ -73,3 +81,49 @@ impl From<SdkTransferStatus> for TransferStatus {
         }
     }
 }
+
+/// Blockchain name for database operations.
+///
+/// This is the sqlx::Type version. For API/DTO use, see `ocrch_sdk::objects::Blockchain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(rename_all = "snake_case", type_name = "blockchain_name")]
+pub enum BlockchainName {
+    Ethereum,
+    Polygon,
+    Base,
+    ArbitrumOne,
+    Linea,
+    Optimism,
+    AvalancheC,
+    Tron,
+}
+
+impl From<BlockchainName> for SdkBlockchain {
+    fn from(value: BlockchainName) -> Self {
+        match value {
+            BlockchainName::Ethereum => SdkBlockchain::Ethereum,
+            BlockchainName::Polygon => SdkBlockchain::Polygon,
+            BlockchainName::Base => SdkBlockchain::Base,
+            BlockchainName::ArbitrumOne => SdkBlockchain::ArbitrumOne,
+            BlockchainName::Linea => SdkBlockchain::Linea,
+            BlockchainName::Optimism => SdkBlockchain::Optimism,
+            BlockchainName::AvalancheC => SdkBlockchain::AvalancheC,
+            BlockchainName::Tron => SdkBlockchain::Tron,
+        }
+    }
+}
+
+impl From<SdkBlockchain> for BlockchainName {
+    fn from(value: SdkBlockchain) -> Self {
+        match value {
+            SdkBlockchain::Ethereum => BlockchainName::Ethereum,
+            SdkBlockchain::Polygon => BlockchainName::Polygon,
+            SdkBlockchain::Base => BlockchainName::Base,
+            SdkBlockchain::ArbitrumOne => BlockchainName::ArbitrumOne,
+            SdkBlockchain::Linea => BlockchainName::Linea,
+            SdkBlockchain::Optimism => BlockchainName::Optimism,
+            SdkBlockchain::AvalancheC => BlockchainName::AvalancheC,
+            SdkBlockchain::Tron => BlockchainName::Tron,
+        }
+    }
+}
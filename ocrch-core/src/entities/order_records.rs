@@ -1,3 +1,4 @@
+use crate::entities::erc20_pending_deposit::EtherScanChain;
 use crate::framework::DatabaseProcessor;
 use kanau::processor::Processor;
 use ocrch_sdk::objects::OrderStatus as SdkOrderStatus;
@@ -6,14 +7,27 @@ use uuid::Uuid;
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
 pub struct OrderRecord {
     pub order_id: Uuid,
+    /// ID of the merchant that created the order.
+    pub merchant_id: String,
     pub merchant_order_id: String,
     pub amount: rust_decimal::Decimal,
     pub created_at: time::PrimitiveDateTime,
     pub status: OrderStatus,
     pub webhook_success_at: Option<time::PrimitiveDateTime>,
-    pub webhook_url: String,
+    /// `None` means `merchant.default_webhook_url` is used.
+    pub webhook_url: Option<String>,
     pub webhook_retry_count: i32,
     pub webhook_last_tried_at: Option<time::PrimitiveDateTime>,
+    /// Merchant-supplied JSON object.
+    pub metadata: Option<serde_json::Value>,
+    pub description: Option<String>,
+    /// Customer reference or email supplied by the merchant.
+    pub customer_reference: Option<String>,
+    /// Where the checkout frontend sends the user after payment.
+    pub success_url: Option<String>,
+    /// Where the checkout frontend sends the user after cancellation or
+    /// expiry.
+    pub cancel_url: Option<String>,
 }
 
 /// Order status for database operations.
@@ -50,17 +64,6 @@ impl From<SdkOrderStatus> for OrderStatus {
     }
 }
 
-/// Data returned when fetching orders for webhook retry.
-#[derive(Debug, Clone)]
-pub struct OrderForWebhookRetry {
-    pub order_id: Uuid,
-    pub merchant_order_id: String,
-    pub amount: rust_decimal::Decimal,
-    pub status: OrderStatus,
-    pub webhook_url: String,
-    pub webhook_retry_count: i32,
-}
-
 #[derive(Debug, Clone)]
 /// Get an order by its ID.
 pub struct GetOrderRecordById {
@@ -77,6 +80,7 @@ impl Processor<GetOrderRecordById> for DatabaseProcessor {
             r#"
             SELECT 
                 order_id,
+                merchant_id,
                 merchant_order_id,
                 amount,
                 created_at,
@@ -84,7 +88,12 @@ impl Processor<GetOrderRecordById> for DatabaseProcessor {
                 webhook_success_at,
                 webhook_url,
                 webhook_retry_count,
-                webhook_last_tried_at
+                webhook_last_tried_at,
+                metadata,
+                description,
+                customer_reference,
+                success_url,
+                cancel_url
             FROM order_records
             WHERE order_id = $1
             "#,
@@ -96,6 +105,51 @@ impl Processor<GetOrderRecordById> for DatabaseProcessor {
     }
 }
 
+/// Get a merchant's order by its merchant order ID.
+#[derive(Debug, Clone)]
+pub struct GetOrderRecordByMerchantOrderId {
+    pub merchant_id: String,
+    pub merchant_order_id: String,
+}
+
+impl Processor<GetOrderRecordByMerchantOrderId> for DatabaseProcessor {
+    type Output = Option<OrderRecord>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetOrderRecordByMerchantOrderId")]
+    async fn process(
+        &self,
+        query: GetOrderRecordByMerchantOrderId,
+    ) -> Result<Option<OrderRecord>, sqlx::Error> {
+        sqlx::query_as!(
+            OrderRecord,
+            r#"
+            SELECT
+                order_id,
+                merchant_id,
+                merchant_order_id,
+                amount,
+                created_at,
+                status as "status: OrderStatus",
+                webhook_success_at,
+                webhook_url,
+                webhook_retry_count,
+                webhook_last_tried_at,
+                metadata,
+                description,
+                customer_reference,
+                success_url,
+                cancel_url
+            FROM order_records
+            WHERE merchant_id = $1 AND merchant_order_id = $2
+            "#,
+            query.merchant_id,
+            query.merchant_order_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Mark a webhook as successfully delivered.
 pub struct MarkOrderWebhookSuccess {
@@ -148,56 +202,6 @@ impl Processor<IncrementOrderWebhookRetryCount> for DatabaseProcessor {
     }
 }
 
-#[derive(Debug, Clone)]
-/// Get orders that need webhook retry based on exponential backoff.
-///
-/// Returns orders that:
-/// - Have a status change (not pending)
-/// - Haven't been successfully delivered
-/// - Haven't exceeded max retries
-/// - Are due for retry based on exponential backoff (2^retry_count seconds)
-pub struct GetOrdersForWebhookRetry {
-    pub max_retry_count: i32,
-    pub limit: i64,
-}
-
-impl Processor<GetOrdersForWebhookRetry> for DatabaseProcessor {
-    type Output = Vec<OrderForWebhookRetry>;
-    type Error = sqlx::Error;
-    #[tracing::instrument(skip_all, err, name = "SQL:GetOrdersForWebhookRetry")]
-    async fn process(
-        &self,
-        query: GetOrdersForWebhookRetry,
-    ) -> Result<Vec<OrderForWebhookRetry>, sqlx::Error> {
-        let orders = sqlx::query_as!(
-            OrderForWebhookRetry,
-            r#"
-            SELECT 
-                order_id,
-                merchant_order_id,
-                amount,
-                status as "status: OrderStatus",
-                webhook_url,
-                webhook_retry_count
-            FROM order_records
-            WHERE webhook_success_at IS NULL
-              AND status != 'pending'
-              AND webhook_retry_count < $1
-              AND (
-                webhook_last_tried_at IS NULL
-                OR webhook_last_tried_at + (POWER(2, webhook_retry_count) || ' seconds')::interval < NOW()
-              )
-            LIMIT $2
-            "#,
-            query.max_retry_count,
-            query.limit,
-        )
-        .fetch_all(&self.pool)
-        .await?;
-        Ok(orders)
-    }
-}
-
 #[derive(Debug, Clone)]
 /// Update the status of an order.
 pub struct UpdateOrderStatus {
@@ -225,30 +229,104 @@ impl Processor<UpdateOrderStatus> for DatabaseProcessor {
     }
 }
 
+/// An unconfirmed transfer detected for one of an order's pending deposits.
+#[derive(Debug, Clone)]
+pub struct OrderPaymentDetection {
+    /// The EVM chain of the deposit, or `None` for Tron.
+    pub chain: Option<EtherScanChain>,
+    pub txn_hash: String,
+    pub confirmations: i32,
+}
+
+#[derive(Debug, Clone)]
+/// Get the detected-but-unconfirmed payment for an order, if any.
+pub struct GetOrderPaymentDetection {
+    pub order_id: Uuid,
+}
+
+impl Processor<GetOrderPaymentDetection> for DatabaseProcessor {
+    type Output = Option<OrderPaymentDetection>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetOrderPaymentDetection")]
+    async fn process(
+        &self,
+        query: GetOrderPaymentDetection,
+    ) -> Result<Option<OrderPaymentDetection>, sqlx::Error> {
+        sqlx::query_as!(
+            OrderPaymentDetection,
+            r#"
+            SELECT
+                d.chain as "chain?: EtherScanChain",
+                t.txn_hash as "txn_hash!",
+                d.detected_confirmations as "confirmations!"
+            FROM erc20_pending_deposits d
+            JOIN erc20_token_transfers t ON t.id = d.detected_transfer_id
+            WHERE d."order" = $1
+              AND d.detected_confirmations IS NOT NULL
+            UNION ALL
+            SELECT
+                NULL::etherscan_chain,
+                t.txn_hash,
+                d.detected_confirmations
+            FROM trc20_pending_deposits d
+            JOIN trc20_token_transfers t ON t.id = d.detected_transfer_id
+            WHERE d."order" = $1
+              AND d.detected_confirmations IS NOT NULL
+            LIMIT 1
+            "#,
+            query.order_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
 /// Create a new order record.
 ///
 /// Generates a new UUID for `order_id` and inserts the row with `status = 'pending'`.
-/// Returns the complete newly-created record.
+/// Returns the complete newly-created record, or the merchant's existing
+/// order if one has the same `merchant_order_id`.
 #[derive(Debug, Clone)]
 pub struct CreateOrderRecord {
+    pub merchant_id: String,
     pub merchant_order_id: String,
     pub amount: rust_decimal::Decimal,
-    pub webhook_url: String,
+    pub webhook_url: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+    pub description: Option<String>,
+    pub customer_reference: Option<String>,
+    pub success_url: Option<String>,
+    pub cancel_url: Option<String>,
+}
+
+/// Result of [`CreateOrderRecord`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CreatedOrderRecord {
+    /// A new order was inserted.
+    Created(OrderRecord),
+    /// The merchant already has an order with this merchant order ID; it is
+    /// returned unchanged.
+    Existing(OrderRecord),
 }
 
 impl Processor<CreateOrderRecord> for DatabaseProcessor {
-    type Output = OrderRecord;
+    type Output = CreatedOrderRecord;
     type Error = sqlx::Error;
     #[tracing::instrument(skip_all, err, name = "SQL:CreateOrderRecord")]
-    async fn process(&self, cmd: CreateOrderRecord) -> Result<OrderRecord, sqlx::Error> {
+    async fn process(&self, cmd: CreateOrderRecord) -> Result<CreatedOrderRecord, sqlx::Error> {
         let order_id = Uuid::now_v7();
-        let order = sqlx::query_as!(
+        let created = sqlx::query_as!(
             OrderRecord,
             r#"
-            INSERT INTO order_records (order_id, merchant_order_id, amount, webhook_url)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO order_records (
+                order_id, merchant_id, merchant_order_id, amount, webhook_url,
+                metadata, description, customer_reference, success_url, cancel_url
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (merchant_id, merchant_order_id) DO NOTHING
             RETURNING
                 order_id,
+                merchant_id,
                 merchant_order_id,
                 amount,
                 created_at,
@@ -256,16 +334,39 @@ impl Processor<CreateOrderRecord> for DatabaseProcessor {
                 webhook_success_at,
                 webhook_url,
                 webhook_retry_count,
-                webhook_last_tried_at
+                webhook_last_tried_at,
+                metadata,
+                description,
+                customer_reference,
+                success_url,
+                cancel_url
             "#,
             order_id,
+            cmd.merchant_id,
             cmd.merchant_order_id,
             cmd.amount,
             cmd.webhook_url,
+            cmd.metadata,
+            cmd.description,
+            cmd.customer_reference,
+            cmd.success_url,
+            cmd.cancel_url,
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
-        Ok(order)
+        if let Some(order) = created {
+            return Ok(CreatedOrderRecord::Created(order));
+        }
+
+        // The conflicting row is committed once the insert returns
+        let existing = self
+            .process(GetOrderRecordByMerchantOrderId {
+                merchant_id: cmd.merchant_id,
+                merchant_order_id: cmd.merchant_order_id,
+            })
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        Ok(CreatedOrderRecord::Existing(existing))
     }
 }
 
@@ -275,7 +376,14 @@ pub struct ListOrderRecords {
     pub limit: i64,
     pub offset: i64,
     pub status: Option<OrderStatus>,
+    pub merchant_id: Option<String>,
     pub merchant_order_id: Option<String>,
+    /// Orders whose metadata contains this JSON value.
+    pub metadata: Option<serde_json::Value>,
+    /// Orders whose description contains this text (case-insensitive).
+    pub description: Option<String>,
+    /// Orders with this customer reference (case-insensitive).
+    pub customer_reference: Option<String>,
 }
 
 impl Processor<ListOrderRecords> for DatabaseProcessor {
@@ -288,6 +396,7 @@ impl Processor<ListOrderRecords> for DatabaseProcessor {
             r#"
             SELECT
                 order_id,
+                merchant_id,
                 merchant_order_id,
                 amount,
                 created_at,
@@ -295,18 +404,99 @@ impl Processor<ListOrderRecords> for DatabaseProcessor {
                 webhook_success_at,
                 webhook_url,
                 webhook_retry_count,
-                webhook_last_tried_at
+                webhook_last_tried_at,
+                metadata,
+                description,
+                customer_reference,
+                success_url,
+                cancel_url
             FROM order_records
             WHERE ($1::order_status IS NULL OR status = $1)
-              AND ($2::text IS NULL OR merchant_order_id = $2)
+              AND ($2::text IS NULL OR merchant_id = $2)
+              AND ($3::text IS NULL OR merchant_order_id = $3)
+              AND ($6::jsonb IS NULL OR metadata @> $6)
+              AND ($7::text IS NULL OR strpos(lower(description), lower($7)) > 0)
+              AND ($8::text IS NULL OR lower(customer_reference) = lower($8))
             ORDER BY created_at DESC
-            LIMIT $3
-            OFFSET $4
+            LIMIT $4
+            OFFSET $5
             "#,
             query.status as Option<OrderStatus>,
+            query.merchant_id as Option<String>,
             query.merchant_order_id as Option<String>,
             query.limit,
             query.offset,
+            query.metadata,
+            query.description,
+            query.customer_reference,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+/// List a merchant's orders, newest first, with keyset pagination.
+#[derive(Debug, Clone)]
+pub struct ListMerchantOrderRecords {
+    pub merchant_id: String,
+    pub status: Option<OrderStatus>,
+    /// Orders created at or after this time.
+    pub since: Option<time::PrimitiveDateTime>,
+    /// Orders created at or before this time.
+    pub until: Option<time::PrimitiveDateTime>,
+    /// Orders that come after this order in the listing.
+    pub after: Option<Uuid>,
+    pub limit: i64,
+}
+
+impl Processor<ListMerchantOrderRecords> for DatabaseProcessor {
+    type Output = Vec<OrderRecord>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ListMerchantOrderRecords")]
+    async fn process(
+        &self,
+        query: ListMerchantOrderRecords,
+    ) -> Result<Vec<OrderRecord>, sqlx::Error> {
+        sqlx::query_as!(
+            OrderRecord,
+            r#"
+            SELECT
+                order_id,
+                merchant_id,
+                merchant_order_id,
+                amount,
+                created_at,
+                status as "status: OrderStatus",
+                webhook_success_at,
+                webhook_url,
+                webhook_retry_count,
+                webhook_last_tried_at,
+                metadata,
+                description,
+                customer_reference,
+                success_url,
+                cancel_url
+            FROM order_records
+            WHERE merchant_id = $1
+              AND ($2::order_status IS NULL OR status = $2)
+              AND ($3::timestamp IS NULL OR created_at >= $3)
+              AND ($4::timestamp IS NULL OR created_at <= $4)
+              AND (
+                $5::uuid IS NULL
+                OR (created_at, order_id) < (
+                    SELECT created_at, order_id FROM order_records
+                    WHERE order_id = $5 AND merchant_id = $1
+                )
+              )
+            ORDER BY created_at DESC, order_id DESC
+            LIMIT $6
+            "#,
+            query.merchant_id,
+            query.status as Option<OrderStatus>,
+            query.since,
+            query.until,
+            query.after,
+            query.limit,
         )
         .fetch_all(&self.pool)
         .await
@@ -334,6 +524,27 @@ impl OrderRecord {
         Ok(())
     }
 
+    /// Update the status of an order within a transaction if it is still
+    /// pending. Returns `false`, changing nothing, if it is not.
+    pub async fn update_pending_status_tx(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        order_id: Uuid,
+        status: OrderStatus,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE order_records
+            SET status = $1
+            WHERE order_id = $2 AND status = 'pending'
+            "#,
+            status as OrderStatus,
+            order_id,
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Update the status of multiple orders in a single query within a transaction.
     ///
     /// Uses `ANY` to batch-update all matching rows in one SQL statement.
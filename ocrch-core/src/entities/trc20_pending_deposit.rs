@@ -60,6 +60,120 @@ impl Processor<GetTrc20DepositsForMatching> for DatabaseProcessor {
     }
 }
 
+/// A pending deposit whose detection state changed.
+#[derive(Debug, Clone)]
+pub struct Trc20DetectedDeposit {
+    pub id: i64,
+    /// `false` if only the confirmation count changed.
+    pub newly_detected: bool,
+}
+
+/// Record unconfirmed transfers seen for pending deposits.
+///
+/// `deposit_ids[i]`, `transfer_ids[i]` and `confirmations[i]` are paired.
+/// Only deposits whose detected transfer or confirmation count changed are
+/// updated and returned. A `payment_detected` webhook is queued in the same
+/// statement for every deposit whose detected transfer is new.
+#[derive(Debug, Clone)]
+pub struct MarkTrc20DepositsDetected {
+    pub deposit_ids: Vec<i64>,
+    pub transfer_ids: Vec<i64>,
+    pub confirmations: Vec<i32>,
+    /// Confirmations the chain requires, recorded in the queued webhooks.
+    pub required_confirmations: i32,
+}
+
+impl Processor<MarkTrc20DepositsDetected> for DatabaseProcessor {
+    type Output = Vec<Trc20DetectedDeposit>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:MarkTrc20DepositsDetected")]
+    async fn process(
+        &self,
+        cmd: MarkTrc20DepositsDetected,
+    ) -> Result<Vec<Trc20DetectedDeposit>, sqlx::Error> {
+        if cmd.deposit_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        sqlx::query_as!(
+            Trc20DetectedDeposit,
+            r#"
+            WITH changed AS (
+                UPDATE trc20_pending_deposits AS d
+                SET detected_transfer_id = u.transfer_id,
+                    detected_confirmations = u.confirmations
+                FROM UNNEST($1::bigint[], $2::bigint[], $3::int[]) AS u(deposit_id, transfer_id, confirmations),
+                     trc20_pending_deposits AS prev
+                WHERE d.id = u.deposit_id
+                  AND prev.id = d.id
+                  AND (d.detected_transfer_id IS DISTINCT FROM u.transfer_id
+                       OR d.detected_confirmations IS DISTINCT FROM u.confirmations)
+                RETURNING
+                    d.id,
+                    d."order" AS order_id,
+                    NULL::etherscan_chain AS chain,
+                    u.transfer_id,
+                    u.confirmations,
+                    prev.detected_transfer_id IS DISTINCT FROM u.transfer_id AS newly_detected
+            ),
+            queued AS (
+                INSERT INTO webhook_outbox
+                    (kind, order_id, chain, txn_hash, confirmations, required_confirmations)
+                SELECT 'payment_detected', c.order_id, c.chain, t.txn_hash, c.confirmations, $4
+                FROM changed c
+                JOIN trc20_token_transfers t ON t.id = c.transfer_id
+                WHERE c.newly_detected
+            )
+            SELECT id as "id!", newly_detected as "newly_detected!" FROM changed
+            "#,
+            &cmd.deposit_ids,
+            &cmd.transfer_ids,
+            &cmd.confirmations,
+            cmd.required_confirmations,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+/// Number of live pending deposits for one token.
+#[derive(Debug, Clone)]
+pub struct Trc20LivePendingDeposits {
+    pub token_name: StablecoinName,
+    pub count: i64,
+    pub latest_started_at: time::PrimitiveDateTime,
+}
+
+/// Count pending deposits whose order is still pending, grouped by token.
+#[derive(Debug, Clone)]
+pub struct CountTrc20LivePendingDeposits;
+
+impl Processor<CountTrc20LivePendingDeposits> for DatabaseProcessor {
+    type Output = Vec<Trc20LivePendingDeposits>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:CountTrc20LivePendingDeposits")]
+    async fn process(
+        &self,
+        _query: CountTrc20LivePendingDeposits,
+    ) -> Result<Vec<Trc20LivePendingDeposits>, sqlx::Error> {
+        sqlx::query_as!(
+            Trc20LivePendingDeposits,
+            r#"
+            SELECT
+                d.token_name as "token_name: StablecoinName",
+                COUNT(*) as "count!",
+                MAX(d.started_at) as "latest_started_at!"
+            FROM trc20_pending_deposits d
+            JOIN order_records o ON d."order" = o.order_id
+            WHERE o.status = 'pending'
+            GROUP BY d.token_name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Trc20PendingDepositInsert {
     pub order: uuid::Uuid,
@@ -111,6 +225,8 @@ pub struct ListTrc20PendingDeposits {
     pub offset: i64,
     pub order_id: Option<uuid::Uuid>,
     pub token: Option<StablecoinName>,
+    /// Only deposits of orders created by this merchant.
+    pub merchant_id: Option<String>,
 }
 
 impl Processor<ListTrc20PendingDeposits> for DatabaseProcessor {
@@ -136,12 +252,16 @@ impl Processor<ListTrc20PendingDeposits> for DatabaseProcessor {
             FROM trc20_pending_deposits
             WHERE ($1::uuid IS NULL OR "order" = $1)
               AND ($2::stablecoin_name IS NULL OR token_name = $2)
+              AND ($3::text IS NULL OR "order" IN (
+                  SELECT order_id FROM order_records WHERE merchant_id = $3
+              ))
             ORDER BY started_at DESC
-            LIMIT $3
-            OFFSET $4
+            LIMIT $4
+            OFFSET $5
             "#,
             query.order_id as Option<uuid::Uuid>,
             query.token as Option<StablecoinName>,
+            query.merchant_id as Option<String>,
             query.limit,
             query.offset,
         )
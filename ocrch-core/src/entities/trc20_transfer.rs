@@ -1,3 +1,5 @@
+use crate::entities::order_records::OrderStatus;
+use crate::entities::webhook_outbox::WebhookOutbox;
 use crate::entities::{StablecoinName, TransferStatus};
 use crate::framework::DatabaseProcessor;
 use kanau::processor::Processor;
@@ -41,6 +43,16 @@ pub struct Trc20UnmatchedTransfer {
     pub block_timestamp: i64,
 }
 
+/// A transfer that has been seen but is not yet deep enough to be matched.
+#[derive(Debug, Clone)]
+pub struct Trc20UnconfirmedTransfer {
+    pub id: i64,
+    pub to_address: String,
+    pub value: Decimal,
+    pub txn_hash: String,
+    pub confirmations: i32,
+}
+
 /// Sync cursor from the trc20_sync_cursor materialized view.
 /// Contains the timestamp to start syncing from.
 #[derive(Debug, Clone)]
@@ -168,6 +180,80 @@ impl Processor<GetTrc20TokenTransfersUnmatched> for DatabaseProcessor {
     }
 }
 
+#[derive(Debug, Clone)]
+/// Get transfers that are still waiting for enough confirmations.
+pub struct GetTrc20TokenTransfersUnconfirmed {
+    pub token: StablecoinName,
+}
+
+impl Processor<GetTrc20TokenTransfersUnconfirmed> for DatabaseProcessor {
+    type Output = Vec<Trc20UnconfirmedTransfer>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetTrc20TokenTransfersUnconfirmed")]
+    async fn process(
+        &self,
+        query: GetTrc20TokenTransfersUnconfirmed,
+    ) -> Result<Vec<Trc20UnconfirmedTransfer>, sqlx::Error> {
+        sqlx::query_as!(
+            Trc20UnconfirmedTransfer,
+            r#"
+            SELECT
+                id,
+                to_address,
+                value,
+                txn_hash,
+                confirmations
+            FROM trc20_token_transfers
+            WHERE token_name = $1
+              AND status = 'waiting_for_confirmation'
+            ORDER BY block_timestamp ASC
+            "#,
+            query.token as StablecoinName,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Update the confirmation depth of unconfirmed transfers from the chain head.
+///
+/// Transfers that reach `required_confirmations` are marked as confirmed and
+/// move on to `waiting_for_match`. Returns the number of newly confirmed rows.
+pub struct UpdateTrc20TransferConfirmations {
+    pub token: StablecoinName,
+    pub chain_head: i64,
+    pub required_confirmations: i32,
+}
+
+impl Processor<UpdateTrc20TransferConfirmations> for DatabaseProcessor {
+    type Output = u64;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:UpdateTrc20TransferConfirmations")]
+    async fn process(&self, cmd: UpdateTrc20TransferConfirmations) -> Result<u64, sqlx::Error> {
+        let confirmed = sqlx::query_scalar!(
+            r#"
+            UPDATE trc20_token_transfers
+            SET confirmations = LEAST(GREATEST($2 - block_number + 1, 0), 2147483647)::int,
+                blockchain_confirmed = ($2 - block_number + 1) >= $3,
+                status = CASE
+                    WHEN ($2 - block_number + 1) >= $3 THEN 'waiting_for_match'::transfer_status
+                    ELSE status
+                END
+            WHERE token_name = $1
+              AND status = 'waiting_for_confirmation'
+            RETURNING blockchain_confirmed
+            "#,
+            cmd.token as StablecoinName,
+            cmd.chain_head,
+            i64::from(cmd.required_confirmations),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(confirmed.into_iter().filter(|c| *c).count() as u64)
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Get IDs of old unmatched transfers (older than 1 hour) for marking as unknown.
 pub struct GetOldUnmatchedTrc20TransferIds {
@@ -216,11 +302,17 @@ impl Processor<MarkTrc20TransfersNoMatchedDeposit> for DatabaseProcessor {
             return Ok(0);
         }
 
+        // The unknown-transfer webhooks are queued in the same statement
         let result = sqlx::query!(
             r#"
-            UPDATE trc20_token_transfers
-            SET status = 'no_matched_deposit'
-            WHERE id = ANY($1)
+            WITH marked AS (
+                UPDATE trc20_token_transfers
+                SET status = 'no_matched_deposit'
+                WHERE id = ANY($1) AND status != 'no_matched_deposit'
+                RETURNING id, NULL::etherscan_chain AS chain
+            )
+            INSERT INTO webhook_outbox (kind, transfer_id, chain)
+            SELECT 'unknown_transfer_received', id, chain FROM marked
             "#,
             &cmd.transfer_ids,
         )
@@ -255,11 +347,12 @@ impl Trc20TokenTransfer {
 #[derive(Debug, Clone)]
 /// Handle matched TRC-20 transfers in a single transaction.
 ///
-/// Executes 4 SQL statements atomically:
+/// Executes 5 SQL statements atomically:
 /// 1. Mark TRC-20 transfers as matched with their deposit (fulfillment) IDs
 /// 2. Update order statuses to `Paid`
 /// 3. Delete TRC-20 pending deposits for matched orders (keep the matched deposit)
 /// 4. Delete ERC-20 pending deposits for matched orders (cross-chain cleanup)
+/// 5. Queue an order status webhook for each paid order
 pub struct HandleTrc20MatchedTrans {
     pub transfer_ids: Vec<i64>,
     pub deposit_ids: Vec<i64>,
@@ -324,6 +417,10 @@ impl Processor<HandleTrc20MatchedTrans> for DatabaseProcessor {
         .execute(&mut *tx)
         .await?;
 
+        // 5. Queue order status webhooks in the same transaction
+        WebhookOutbox::enqueue_order_status_many_tx(&mut tx, &cmd.order_ids, OrderStatus::Paid)
+            .await?;
+
         tx.commit().await?;
         Ok(())
     }
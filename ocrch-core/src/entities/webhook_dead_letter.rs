@@ -0,0 +1,280 @@
+//! Dead-lettered webhook deliveries.
+//!
+//! A delivery that failed on every retry is dead-lettered: the
+//! `WebhookSender` stops attempting it and sets `dead_lettered_at`. Admins
+//! list dead letters and replay them in bulk once the receiving endpoint is
+//! fixed. The host filter matches the URL of the delivery's last attempt.
+
+use crate::entities::webhook_outbox::WebhookEventKind;
+use crate::framework::DatabaseProcessor;
+use kanau::processor::Processor;
+use uuid::Uuid;
+
+/// Selects dead-lettered deliveries. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct WebhookDeadLetterFilter {
+    /// Host of the URL the last attempt was sent to (case-insensitive). IPv6
+    /// hosts match with or without their brackets.
+    pub host: Option<String>,
+    pub kind: Option<WebhookEventKind>,
+    /// Dead-lettered at or after this time.
+    pub since: Option<time::PrimitiveDateTime>,
+    /// Dead-lettered at or before this time.
+    pub until: Option<time::PrimitiveDateTime>,
+}
+
+/// A dead-lettered delivery, joined with its event and last attempt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetteredWebhookDelivery {
+    pub id: i64,
+    pub outbox_id: i64,
+    pub event_id: Uuid,
+    pub kind: WebhookEventKind,
+    pub subscription: Option<String>,
+    pub order_id: Option<Uuid>,
+    pub transfer_id: Option<i64>,
+    pub retry_count: i32,
+    /// URL of the last attempt, if any request was made.
+    pub url: Option<String>,
+    pub last_error: Option<String>,
+    pub last_tried_at: Option<time::PrimitiveDateTime>,
+    pub dead_lettered_at: time::PrimitiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+/// List dead-lettered deliveries, most recently dead-lettered first.
+pub struct ListDeadLetteredWebhookDeliveries {
+    pub filter: WebhookDeadLetterFilter,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Processor<ListDeadLetteredWebhookDeliveries> for DatabaseProcessor {
+    type Output = Vec<DeadLetteredWebhookDelivery>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ListDeadLetteredWebhookDeliveries")]
+    async fn process(
+        &self,
+        query: ListDeadLetteredWebhookDeliveries,
+    ) -> Result<Vec<DeadLetteredWebhookDelivery>, sqlx::Error> {
+        let f = query.filter;
+        sqlx::query_as!(
+            DeadLetteredWebhookDelivery,
+            r#"
+            SELECT
+                d.id,
+                d.outbox_id,
+                o.event_id,
+                o.kind as "kind: WebhookEventKind",
+                d.subscription,
+                o.order_id,
+                o.transfer_id,
+                d.retry_count,
+                la.url as "url?",
+                d.last_error,
+                d.last_tried_at,
+                d.dead_lettered_at as "dead_lettered_at!"
+            FROM webhook_deliveries d
+            JOIN webhook_outbox o ON o.id = d.outbox_id
+            LEFT JOIN LATERAL (
+                SELECT a.url FROM webhook_delivery_attempts a
+                WHERE a.delivery_id = d.id
+                ORDER BY a.id DESC
+                LIMIT 1
+            ) la ON TRUE
+            WHERE d.dead_lettered_at IS NOT NULL
+              AND d.delivered_at IS NULL
+              AND ($1::text IS NULL
+                   OR lower(btrim(substring(la.url from '^[^:]+://(?:[^@/?#]*@)?(\[[^\]]+\]|[^:/?#]+)'), '[]'))
+                      = lower(btrim($1, '[]')))
+              AND ($2::webhook_event_kind IS NULL OR o.kind = $2)
+              AND ($3::timestamp IS NULL OR d.dead_lettered_at >= $3)
+              AND ($4::timestamp IS NULL OR d.dead_lettered_at <= $4)
+            ORDER BY d.dead_lettered_at DESC, d.id DESC
+            LIMIT $5 OFFSET $6
+            "#,
+            f.host,
+            f.kind as Option<WebhookEventKind>,
+            f.since,
+            f.until,
+            query.limit,
+            query.offset,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Make every matching dead-lettered delivery due again with a fresh retry
+/// schedule. Returns the number of deliveries replayed.
+pub struct ReplayDeadLetteredWebhookDeliveries {
+    pub filter: WebhookDeadLetterFilter,
+}
+
+impl Processor<ReplayDeadLetteredWebhookDeliveries> for DatabaseProcessor {
+    type Output = u64;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ReplayDeadLetteredWebhookDeliveries")]
+    async fn process(&self, cmd: ReplayDeadLetteredWebhookDeliveries) -> Result<u64, sqlx::Error> {
+        let f = cmd.filter;
+        let result = sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET dead_lettered_at = NULL, retry_count = 0, next_attempt_at = NOW()
+            WHERE id IN (
+                SELECT d.id
+                FROM webhook_deliveries d
+                JOIN webhook_outbox o ON o.id = d.outbox_id
+                LEFT JOIN LATERAL (
+                    SELECT a.url FROM webhook_delivery_attempts a
+                    WHERE a.delivery_id = d.id
+                    ORDER BY a.id DESC
+                    LIMIT 1
+                ) la ON TRUE
+                WHERE d.dead_lettered_at IS NOT NULL
+                  AND d.delivered_at IS NULL
+                  AND ($1::text IS NULL
+                       OR lower(btrim(substring(la.url from '^[^:]+://(?:[^@/?#]*@)?(\[[^\]]+\]|[^:/?#]+)'), '[]'))
+                          = lower(btrim($1, '[]')))
+                  AND ($2::webhook_event_kind IS NULL OR o.kind = $2)
+                  AND ($3::timestamp IS NULL OR d.dead_lettered_at >= $3)
+                  AND ($4::timestamp IS NULL OR d.dead_lettered_at <= $4)
+            )
+            "#,
+            f.host,
+            f.kind as Option<WebhookEventKind>,
+            f.since,
+            f.until,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order_records::{CreateOrderRecord, CreatedOrderRecord, OrderStatus};
+    use crate::entities::webhook_delivery_attempt::RecordWebhookDeliveryAttempt;
+    use crate::entities::webhook_outbox::{
+        ClaimDueWebhookDeliveries, EnqueueWebhookEvent, FanOutWebhookOutboxEntry,
+        GetWebhookOutboxEntriesToFanOut, MarkWebhookDeliveryFailed,
+    };
+    use crate::events::WebhookEvent;
+    use std::time::Duration;
+
+    /// Dead-letter a delivery whose last attempt went to `url`.
+    async fn dead_letter_to(processor: &DatabaseProcessor, url: &str) {
+        let CreatedOrderRecord::Created(order) = processor
+            .process(CreateOrderRecord {
+                merchant_id: "default".to_string(),
+                merchant_order_id: url.to_string(),
+                amount: rust_decimal::Decimal::ONE,
+                webhook_url: Some(url.to_string()),
+                metadata: None,
+                description: None,
+                customer_reference: None,
+                success_url: None,
+                cancel_url: None,
+            })
+            .await
+            .unwrap()
+        else {
+            unreachable!("merchant order IDs are unique");
+        };
+        processor
+            .process(EnqueueWebhookEvent {
+                event: WebhookEvent::OrderStatusChanged {
+                    order_id: order.order_id,
+                    new_status: OrderStatus::Cancelled,
+                },
+            })
+            .await
+            .unwrap();
+        let entries = processor
+            .process(GetWebhookOutboxEntriesToFanOut { limit: 10 })
+            .await
+            .unwrap();
+        processor
+            .process(FanOutWebhookOutboxEntry {
+                id: entries[0].id,
+                subscriptions: vec![],
+            })
+            .await
+            .unwrap();
+        let delivery = processor
+            .process(ClaimDueWebhookDeliveries {
+                limit: 1,
+                lease: Duration::from_secs(60),
+            })
+            .await
+            .unwrap()
+            .remove(0);
+        processor
+            .process(RecordWebhookDeliveryAttempt {
+                delivery_id: delivery.id,
+                outbox_id: delivery.entry.id,
+                url: url.to_string(),
+                body_sha256: String::new(),
+                response_status: Some(500),
+                response_body: None,
+                latency_ms: 0,
+                error_kind: None,
+                error: Some("HTTP 500".to_string()),
+            })
+            .await
+            .unwrap();
+        processor
+            .process(MarkWebhookDeliveryFailed {
+                id: delivery.id,
+                error: "HTTP 500".to_string(),
+                retry_delay: Duration::ZERO,
+                dead_letter: true,
+            })
+            .await
+            .unwrap();
+    }
+
+    async fn count_for_host(processor: &DatabaseProcessor, host: &str) -> usize {
+        processor
+            .process(ListDeadLetteredWebhookDeliveries {
+                filter: WebhookDeadLetterFilter {
+                    host: Some(host.to_string()),
+                    ..Default::default()
+                },
+                limit: 10,
+                offset: 0,
+            })
+            .await
+            .unwrap()
+            .len()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_host_filter(pool: sqlx::PgPool) {
+        let processor = DatabaseProcessor { pool };
+        dead_letter_to(&processor, "http://[::1]:8080/hook").await;
+        dead_letter_to(&processor, "https://user@Shop.example.com:8443/hook").await;
+
+        assert_eq!(count_for_host(&processor, "::1").await, 1);
+        assert_eq!(count_for_host(&processor, "[::1]").await, 1);
+        assert_eq!(count_for_host(&processor, "[").await, 0);
+        assert_eq!(count_for_host(&processor, "shop.example.com").await, 1);
+        assert_eq!(count_for_host(&processor, "example.com").await, 0);
+
+        let replayed = processor
+            .process(ReplayDeadLetteredWebhookDeliveries {
+                filter: WebhookDeadLetterFilter {
+                    host: Some("::1".to_string()),
+                    ..Default::default()
+                },
+            })
+            .await
+            .unwrap();
+        assert_eq!(replayed, 1);
+        assert_eq!(count_for_host(&processor, "::1").await, 0);
+        assert_eq!(count_for_host(&processor, "shop.example.com").await, 1);
+    }
+}
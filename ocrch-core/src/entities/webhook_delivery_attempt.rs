@@ -0,0 +1,193 @@
+//! Per-attempt webhook delivery log.
+//!
+//! The `WebhookSender` writes one row for every HTTP request it makes for a
+//! webhook delivery, successful or not. Admins read them back per order
+//! or per transfer to find out why a webhook did not arrive.
+
+use crate::entities::erc20_pending_deposit::EtherScanChain;
+use crate::entities::webhook_outbox::WebhookEventKind;
+use crate::framework::DatabaseProcessor;
+use kanau::processor::Processor;
+use ocrch_sdk::objects::admin::WebhookDeliveryErrorKind as SdkWebhookDeliveryErrorKind;
+use uuid::Uuid;
+
+/// Why a delivery attempt failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(rename_all = "snake_case", type_name = "webhook_delivery_error_kind")]
+pub enum WebhookDeliveryErrorKind {
+    /// The request timed out.
+    Timeout,
+    /// The connection could not be established.
+    Connect,
+    /// The endpoint answered with a non-success status.
+    HttpStatus,
+    /// Any other request error.
+    Request,
+}
+
+impl From<WebhookDeliveryErrorKind> for SdkWebhookDeliveryErrorKind {
+    fn from(value: WebhookDeliveryErrorKind) -> Self {
+        match value {
+            WebhookDeliveryErrorKind::Timeout => SdkWebhookDeliveryErrorKind::Timeout,
+            WebhookDeliveryErrorKind::Connect => SdkWebhookDeliveryErrorKind::Connect,
+            WebhookDeliveryErrorKind::HttpStatus => SdkWebhookDeliveryErrorKind::HttpStatus,
+            WebhookDeliveryErrorKind::Request => SdkWebhookDeliveryErrorKind::Request,
+        }
+    }
+}
+
+/// A recorded delivery attempt, joined with the event it was made for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookDeliveryAttempt {
+    pub id: i64,
+    pub outbox_id: i64,
+    pub event_id: Uuid,
+    pub kind: WebhookEventKind,
+    pub subscription: Option<String>,
+    pub url: String,
+    pub body_sha256: String,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub latency_ms: i32,
+    pub error_kind: Option<WebhookDeliveryErrorKind>,
+    pub error: Option<String>,
+    pub attempted_at: time::PrimitiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+/// Record a single attempt of a delivery.
+pub struct RecordWebhookDeliveryAttempt {
+    pub delivery_id: i64,
+    pub outbox_id: i64,
+    pub url: String,
+    /// Hex-encoded SHA-256 of the request body.
+    pub body_sha256: String,
+    pub response_status: Option<i32>,
+    /// Response body, already truncated by the caller.
+    pub response_body: Option<String>,
+    pub latency_ms: i32,
+    pub error_kind: Option<WebhookDeliveryErrorKind>,
+    pub error: Option<String>,
+}
+
+impl Processor<RecordWebhookDeliveryAttempt> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:RecordWebhookDeliveryAttempt")]
+    async fn process(&self, cmd: RecordWebhookDeliveryAttempt) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_delivery_attempts
+                (delivery_id, outbox_id, url, body_sha256, response_status,
+                 response_body, latency_ms, error_kind, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            cmd.delivery_id,
+            cmd.outbox_id,
+            cmd.url,
+            cmd.body_sha256,
+            cmd.response_status,
+            cmd.response_body,
+            cmd.latency_ms,
+            cmd.error_kind as Option<WebhookDeliveryErrorKind>,
+            cmd.error,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// List delivery attempts for every webhook of an order, newest first.
+pub struct ListOrderWebhookDeliveryAttempts {
+    pub order_id: Uuid,
+}
+
+impl Processor<ListOrderWebhookDeliveryAttempts> for DatabaseProcessor {
+    type Output = Vec<WebhookDeliveryAttempt>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ListOrderWebhookDeliveryAttempts")]
+    async fn process(
+        &self,
+        query: ListOrderWebhookDeliveryAttempts,
+    ) -> Result<Vec<WebhookDeliveryAttempt>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookDeliveryAttempt,
+            r#"
+            SELECT
+                a.id,
+                a.outbox_id,
+                o.event_id,
+                o.kind as "kind: WebhookEventKind",
+                d.subscription,
+                a.url,
+                a.body_sha256,
+                a.response_status,
+                a.response_body,
+                a.latency_ms,
+                a.error_kind as "error_kind: WebhookDeliveryErrorKind",
+                a.error,
+                a.attempted_at
+            FROM webhook_delivery_attempts a
+            JOIN webhook_deliveries d ON d.id = a.delivery_id
+            JOIN webhook_outbox o ON o.id = a.outbox_id
+            WHERE o.order_id = $1
+            ORDER BY a.id DESC
+            "#,
+            query.order_id,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// List delivery attempts for the unknown-transfer webhooks of a transfer,
+/// newest first.
+pub struct ListTransferWebhookDeliveryAttempts {
+    pub transfer_id: i64,
+    /// Chain of an ERC-20 transfer, `None` for a TRC-20 transfer.
+    pub chain: Option<EtherScanChain>,
+}
+
+impl Processor<ListTransferWebhookDeliveryAttempts> for DatabaseProcessor {
+    type Output = Vec<WebhookDeliveryAttempt>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ListTransferWebhookDeliveryAttempts")]
+    async fn process(
+        &self,
+        query: ListTransferWebhookDeliveryAttempts,
+    ) -> Result<Vec<WebhookDeliveryAttempt>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookDeliveryAttempt,
+            r#"
+            SELECT
+                a.id,
+                a.outbox_id,
+                o.event_id,
+                o.kind as "kind: WebhookEventKind",
+                d.subscription,
+                a.url,
+                a.body_sha256,
+                a.response_status,
+                a.response_body,
+                a.latency_ms,
+                a.error_kind as "error_kind: WebhookDeliveryErrorKind",
+                a.error,
+                a.attempted_at
+            FROM webhook_delivery_attempts a
+            JOIN webhook_deliveries d ON d.id = a.delivery_id
+            JOIN webhook_outbox o ON o.id = a.outbox_id
+            WHERE o.kind = 'unknown_transfer_received'
+              AND o.transfer_id = $1
+              AND o.chain IS NOT DISTINCT FROM $2
+            ORDER BY a.id DESC
+            "#,
+            query.transfer_id,
+            query.chain as Option<EtherScanChain>,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
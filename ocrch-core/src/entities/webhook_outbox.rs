@@ -0,0 +1,925 @@
+//! Durable webhook outbox.
+//!
+//! Every webhook event is written to `webhook_outbox` in the same transaction
+//! as the state change that caused it. The `WebhookSender` fans each event
+//! out once into `webhook_deliveries`, one row for the event's own URL and
+//! one per matching `[[webhooks]]` subscription, then drains due deliveries
+//! and reschedules failed ones with exponential back-off. No event is lost to
+//! a failed delivery or a restart, and every destination retries on its own.
+//!
+//! Each row is one logical event with a stable `event_id`. Events of an order
+//! are numbered by `sequence`, taken from a counter on the order record so
+//! that the numbers follow commit order.
+
+use crate::entities::erc20_pending_deposit::EtherScanChain;
+use crate::entities::order_records::OrderStatus;
+use crate::events::{BlockchainTarget, WebhookEvent};
+use crate::framework::DatabaseProcessor;
+use kanau::processor::Processor;
+use ocrch_sdk::objects::WebhookEventType;
+use uuid::Uuid;
+
+/// Kind of event stored in the outbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(rename_all = "snake_case", type_name = "webhook_event_kind")]
+pub enum WebhookEventKind {
+    OrderStatusChanged,
+    UnknownTransferReceived,
+    PaymentDetected,
+}
+
+impl From<WebhookEventKind> for WebhookEventType {
+    fn from(value: WebhookEventKind) -> Self {
+        match value {
+            WebhookEventKind::OrderStatusChanged => WebhookEventType::OrderStatusChanged,
+            WebhookEventKind::UnknownTransferReceived => WebhookEventType::UnknownTransferReceived,
+            WebhookEventKind::PaymentDetected => WebhookEventType::PaymentDetected,
+        }
+    }
+}
+
+impl From<WebhookEventType> for WebhookEventKind {
+    fn from(value: WebhookEventType) -> Self {
+        match value {
+            WebhookEventType::OrderStatusChanged => WebhookEventKind::OrderStatusChanged,
+            WebhookEventType::UnknownTransferReceived => WebhookEventKind::UnknownTransferReceived,
+            WebhookEventType::PaymentDetected => WebhookEventKind::PaymentDetected,
+        }
+    }
+}
+
+/// A row of the webhook outbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookOutboxEntry {
+    pub id: i64,
+    pub event_id: Uuid,
+    /// Position among the order's events, starting at 1; `None` for
+    /// unknown transfers.
+    pub sequence: Option<i64>,
+    pub kind: WebhookEventKind,
+    pub order_id: Option<Uuid>,
+    pub order_status: Option<OrderStatus>,
+    pub transfer_id: Option<i64>,
+    pub chain: Option<EtherScanChain>,
+    pub txn_hash: Option<String>,
+    pub confirmations: Option<i32>,
+    pub required_confirmations: Option<i32>,
+    pub created_at: time::PrimitiveDateTime,
+}
+
+impl WebhookOutboxEntry {
+    /// Rebuild the event this row was written for.
+    ///
+    /// Returns `None` if the row is missing a column its kind requires.
+    pub fn event(&self) -> Option<WebhookEvent> {
+        let blockchain = self
+            .chain
+            .map_or(BlockchainTarget::Trc20, BlockchainTarget::Erc20);
+        match self.kind {
+            WebhookEventKind::OrderStatusChanged => Some(WebhookEvent::OrderStatusChanged {
+                order_id: self.order_id?,
+                new_status: self.order_status?,
+            }),
+            WebhookEventKind::UnknownTransferReceived => {
+                Some(WebhookEvent::UnknownTransferReceived {
+                    transfer_id: self.transfer_id?,
+                    blockchain,
+                })
+            }
+            WebhookEventKind::PaymentDetected => Some(WebhookEvent::PaymentDetected {
+                order_id: self.order_id?,
+                blockchain,
+                txn_hash: self.txn_hash.clone()?,
+                confirmations: self.confirmations?.max(0) as u32,
+                required_confirmations: self.required_confirmations?.max(0) as u32,
+                newly_detected: true,
+            }),
+        }
+    }
+}
+
+/// Outbox column values for an event.
+struct OutboxColumns {
+    kind: WebhookEventKind,
+    order_id: Option<Uuid>,
+    order_status: Option<OrderStatus>,
+    transfer_id: Option<i64>,
+    chain: Option<EtherScanChain>,
+    txn_hash: Option<String>,
+    confirmations: Option<i32>,
+    required_confirmations: Option<i32>,
+}
+
+impl OutboxColumns {
+    fn from_event(event: &WebhookEvent) -> Self {
+        let chain = |blockchain: &BlockchainTarget| match blockchain {
+            BlockchainTarget::Erc20(chain) => Some(*chain),
+            BlockchainTarget::Trc20 => None,
+        };
+        match event {
+            WebhookEvent::OrderStatusChanged {
+                order_id,
+                new_status,
+            } => Self {
+                kind: WebhookEventKind::OrderStatusChanged,
+                order_id: Some(*order_id),
+                order_status: Some(*new_status),
+                transfer_id: None,
+                chain: None,
+                txn_hash: None,
+                confirmations: None,
+                required_confirmations: None,
+            },
+            WebhookEvent::UnknownTransferReceived {
+                transfer_id,
+                blockchain,
+            } => Self {
+                kind: WebhookEventKind::UnknownTransferReceived,
+                order_id: None,
+                order_status: None,
+                transfer_id: Some(*transfer_id),
+                chain: chain(blockchain),
+                txn_hash: None,
+                confirmations: None,
+                required_confirmations: None,
+            },
+            WebhookEvent::PaymentDetected {
+                order_id,
+                blockchain,
+                txn_hash,
+                confirmations,
+                required_confirmations,
+                ..
+            } => Self {
+                kind: WebhookEventKind::PaymentDetected,
+                order_id: Some(*order_id),
+                order_status: None,
+                transfer_id: None,
+                chain: chain(blockchain),
+                txn_hash: Some(txn_hash.clone()),
+                confirmations: Some(i32::try_from(*confirmations).unwrap_or(i32::MAX)),
+                required_confirmations: Some(
+                    i32::try_from(*required_confirmations).unwrap_or(i32::MAX),
+                ),
+            },
+        }
+    }
+}
+
+/// Webhook outbox helpers for use inside a caller's transaction.
+pub struct WebhookOutbox;
+
+impl WebhookOutbox {
+    /// Queue a webhook event within a transaction.
+    pub async fn enqueue_tx(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        event: &WebhookEvent,
+    ) -> Result<(), sqlx::Error> {
+        let c = OutboxColumns::from_event(event);
+        sqlx::query!(
+            r#"
+            WITH seq AS (
+                UPDATE order_records
+                SET webhook_event_seq = webhook_event_seq + 1
+                WHERE order_id = $2
+                RETURNING webhook_event_seq
+            )
+            INSERT INTO webhook_outbox
+                (kind, order_id, order_status, transfer_id, chain, txn_hash,
+                 confirmations, required_confirmations, sequence)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, (SELECT webhook_event_seq FROM seq))
+            "#,
+            c.kind as WebhookEventKind,
+            c.order_id,
+            c.order_status as Option<OrderStatus>,
+            c.transfer_id,
+            c.chain as Option<EtherScanChain>,
+            c.txn_hash,
+            c.confirmations,
+            c.required_confirmations,
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Queue an order status webhook for each order within a transaction.
+    pub async fn enqueue_order_status_many_tx(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        order_ids: &[Uuid],
+        status: OrderStatus,
+    ) -> Result<(), sqlx::Error> {
+        if order_ids.is_empty() {
+            return Ok(());
+        }
+        sqlx::query!(
+            r#"
+            WITH seq AS (
+                UPDATE order_records
+                SET webhook_event_seq = webhook_event_seq + 1
+                WHERE order_id = ANY($1)
+                RETURNING order_id, webhook_event_seq
+            )
+            INSERT INTO webhook_outbox (kind, order_id, order_status, sequence)
+            SELECT 'order_status_changed', order_id, $2, webhook_event_seq
+            FROM seq
+            "#,
+            order_ids,
+            status as OrderStatus,
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Queue a webhook event outside of any other state change (e.g. a resend).
+pub struct EnqueueWebhookEvent {
+    pub event: WebhookEvent,
+}
+
+impl Processor<EnqueueWebhookEvent> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:EnqueueWebhookEvent")]
+    async fn process(&self, cmd: EnqueueWebhookEvent) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        WebhookOutbox::enqueue_tx(&mut tx, &cmd.event).await?;
+        tx.commit().await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Deliver the latest order status event of an order again, to every
+/// destination it was fanned out to, keeping its event ID and sequence.
+///
+/// Returns the outbox ID of the event, or `None` if the order has none.
+pub struct RequeueOrderStatusWebhook {
+    pub order_id: Uuid,
+}
+
+impl Processor<RequeueOrderStatusWebhook> for DatabaseProcessor {
+    type Output = Option<i64>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:RequeueOrderStatusWebhook")]
+    async fn process(&self, cmd: RequeueOrderStatusWebhook) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            WITH latest AS (
+                SELECT id FROM webhook_outbox
+                WHERE kind = 'order_status_changed' AND order_id = $1
+                ORDER BY id DESC
+                LIMIT 1
+            ),
+            requeued AS (
+                UPDATE webhook_deliveries
+                SET
+                    retry_count = 0,
+                    next_attempt_at = NOW(),
+                    delivered_at = NULL,
+                    dead_lettered_at = NULL,
+                    last_error = NULL
+                WHERE outbox_id IN (SELECT id FROM latest)
+                RETURNING id
+            )
+            SELECT id as "id!" FROM latest
+            "#,
+            cmd.order_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Deliver the latest unknown transfer event of a transfer again, to every
+/// destination it was fanned out to, keeping its event ID.
+///
+/// Returns the outbox ID of the event, or `None` if the transfer has none.
+pub struct RequeueTransferWebhook {
+    pub transfer_id: i64,
+    /// Chain of an ERC-20 transfer, `None` for a TRC-20 transfer.
+    pub chain: Option<EtherScanChain>,
+}
+
+impl Processor<RequeueTransferWebhook> for DatabaseProcessor {
+    type Output = Option<i64>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:RequeueTransferWebhook")]
+    async fn process(&self, cmd: RequeueTransferWebhook) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            WITH latest AS (
+                SELECT id FROM webhook_outbox
+                WHERE kind = 'unknown_transfer_received'
+                  AND transfer_id = $1
+                  AND chain IS NOT DISTINCT FROM $2
+                ORDER BY id DESC
+                LIMIT 1
+            ),
+            requeued AS (
+                UPDATE webhook_deliveries
+                SET
+                    retry_count = 0,
+                    next_attempt_at = NOW(),
+                    delivered_at = NULL,
+                    dead_lettered_at = NULL,
+                    last_error = NULL
+                WHERE outbox_id IN (SELECT id FROM latest)
+                RETURNING id
+            )
+            SELECT id as "id!" FROM latest
+            "#,
+            cmd.transfer_id,
+            cmd.chain as Option<EtherScanChain>,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Get outbox rows that have not been fanned out into deliveries yet.
+pub struct GetWebhookOutboxEntriesToFanOut {
+    pub limit: i64,
+}
+
+impl Processor<GetWebhookOutboxEntriesToFanOut> for DatabaseProcessor {
+    type Output = Vec<WebhookOutboxEntry>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetWebhookOutboxEntriesToFanOut")]
+    async fn process(
+        &self,
+        query: GetWebhookOutboxEntriesToFanOut,
+    ) -> Result<Vec<WebhookOutboxEntry>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookOutboxEntry,
+            r#"
+            SELECT
+                id,
+                event_id,
+                sequence,
+                kind as "kind: WebhookEventKind",
+                order_id,
+                order_status as "order_status: OrderStatus",
+                transfer_id,
+                chain as "chain: EtherScanChain",
+                txn_hash,
+                confirmations,
+                required_confirmations,
+                created_at
+            FROM webhook_outbox
+            WHERE fanned_out_at IS NULL
+            ORDER BY id
+            LIMIT $1
+            "#,
+            query.limit,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Create the deliveries of an outbox row: one to the event's own URL and
+/// one per named subscription.
+///
+/// Does nothing if the row was already fanned out.
+pub struct FanOutWebhookOutboxEntry {
+    pub id: i64,
+    /// Names of the `[[webhooks]]` subscriptions that receive the event.
+    pub subscriptions: Vec<String>,
+}
+
+impl Processor<FanOutWebhookOutboxEntry> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:FanOutWebhookOutboxEntry")]
+    async fn process(&self, cmd: FanOutWebhookOutboxEntry) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            WITH marked AS (
+                UPDATE webhook_outbox
+                SET fanned_out_at = NOW()
+                WHERE id = $1 AND fanned_out_at IS NULL
+                RETURNING id
+            )
+            INSERT INTO webhook_deliveries (outbox_id, subscription)
+            SELECT marked.id, NULL FROM marked
+            UNION ALL
+            SELECT marked.id, s.name FROM marked, UNNEST($2::text[]) AS s(name)
+            "#,
+            cmd.id,
+            &cmd.subscriptions,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// A delivery of an outbox event to one destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    /// Name of the `[[webhooks]]` subscription, or `None` for the event's
+    /// own URL.
+    pub subscription: Option<String>,
+    pub retry_count: i32,
+    pub entry: WebhookOutboxEntry,
+}
+
+/// Flat row of a delivery joined with its outbox event.
+struct WebhookDeliveryRow {
+    delivery_id: i64,
+    subscription: Option<String>,
+    retry_count: i32,
+    id: i64,
+    event_id: Uuid,
+    sequence: Option<i64>,
+    kind: WebhookEventKind,
+    order_id: Option<Uuid>,
+    order_status: Option<OrderStatus>,
+    transfer_id: Option<i64>,
+    chain: Option<EtherScanChain>,
+    txn_hash: Option<String>,
+    confirmations: Option<i32>,
+    required_confirmations: Option<i32>,
+    created_at: time::PrimitiveDateTime,
+}
+
+impl From<WebhookDeliveryRow> for WebhookDelivery {
+    fn from(row: WebhookDeliveryRow) -> Self {
+        Self {
+            id: row.delivery_id,
+            subscription: row.subscription,
+            retry_count: row.retry_count,
+            entry: WebhookOutboxEntry {
+                id: row.id,
+                event_id: row.event_id,
+                sequence: row.sequence,
+                kind: row.kind,
+                order_id: row.order_id,
+                order_status: row.order_status,
+                transfer_id: row.transfer_id,
+                chain: row.chain,
+                txn_hash: row.txn_hash,
+                confirmations: row.confirmations,
+                required_confirmations: row.required_confirmations,
+                created_at: row.created_at,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Claim undelivered, not dead-lettered deliveries that are due for an
+/// attempt.
+///
+/// Claimed deliveries are not due again for `lease`, so they are attempted
+/// by one worker only; recording the outcome replaces the lease. A delivery
+/// of an order event is only due once every earlier event of the order has
+/// been delivered (or dead-lettered) to the same destination, which keeps
+/// per-order ordering.
+pub struct ClaimDueWebhookDeliveries {
+    pub limit: i64,
+    pub lease: std::time::Duration,
+}
+
+impl Processor<ClaimDueWebhookDeliveries> for DatabaseProcessor {
+    type Output = Vec<WebhookDelivery>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:ClaimDueWebhookDeliveries")]
+    async fn process(
+        &self,
+        query: ClaimDueWebhookDeliveries,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            WebhookDeliveryRow,
+            r#"
+            WITH due AS (
+                SELECT d.id
+                FROM webhook_deliveries d
+                JOIN webhook_outbox o ON o.id = d.outbox_id
+                WHERE d.delivered_at IS NULL
+                  AND d.dead_lettered_at IS NULL
+                  AND d.next_attempt_at <= NOW()
+                  AND NOT EXISTS (
+                      SELECT 1
+                      FROM webhook_outbox po
+                      JOIN webhook_deliveries pd ON pd.outbox_id = po.id
+                      WHERE po.order_id = o.order_id
+                        AND po.sequence < o.sequence
+                        AND pd.subscription IS NOT DISTINCT FROM d.subscription
+                        AND pd.delivered_at IS NULL
+                        AND pd.dead_lettered_at IS NULL
+                  )
+                ORDER BY d.id
+                LIMIT $1
+                FOR UPDATE OF d SKIP LOCKED
+            ),
+            claimed AS (
+                UPDATE webhook_deliveries d
+                SET next_attempt_at = NOW() + make_interval(secs => $2)
+                FROM due
+                WHERE d.id = due.id
+                RETURNING d.id, d.outbox_id, d.subscription, d.retry_count
+            )
+            SELECT
+                c.id as "delivery_id!",
+                c.subscription,
+                c.retry_count as "retry_count!",
+                o.id,
+                o.event_id,
+                o.sequence,
+                o.kind as "kind: WebhookEventKind",
+                o.order_id,
+                o.order_status as "order_status: OrderStatus",
+                o.transfer_id,
+                o.chain as "chain: EtherScanChain",
+                o.txn_hash,
+                o.confirmations,
+                o.required_confirmations,
+                o.created_at
+            FROM claimed c
+            JOIN webhook_outbox o ON o.id = c.outbox_id
+            ORDER BY c.id
+            "#,
+            query.limit,
+            query.lease.as_secs_f64(),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Postpone a delivery that could not be attempted, without counting a
+/// retry.
+pub struct DeferWebhookDelivery {
+    pub id: i64,
+    pub delay: std::time::Duration,
+}
+
+impl Processor<DeferWebhookDelivery> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:DeferWebhookDelivery")]
+    async fn process(&self, cmd: DeferWebhookDelivery) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            WHERE id = $1
+            "#,
+            cmd.id,
+            cmd.delay.as_secs_f64(),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Mark a delivery as done.
+pub struct MarkWebhookDeliveryDelivered {
+    pub id: i64,
+}
+
+impl Processor<MarkWebhookDeliveryDelivered> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:MarkWebhookDeliveryDelivered")]
+    async fn process(&self, cmd: MarkWebhookDeliveryDelivered) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET delivered_at = NOW(), last_tried_at = NOW(), last_error = NULL
+            WHERE id = $1
+            "#,
+            cmd.id,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Record a failed delivery and schedule the next attempt, or dead-letter
+/// it if it has no retries left.
+pub struct MarkWebhookDeliveryFailed {
+    pub id: i64,
+    pub error: String,
+    /// Delay until the next attempt.
+    pub retry_delay: std::time::Duration,
+    /// Stop retrying the delivery until it is replayed.
+    pub dead_letter: bool,
+}
+
+impl Processor<MarkWebhookDeliveryFailed> for DatabaseProcessor {
+    type Output = ();
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:MarkWebhookDeliveryFailed")]
+    async fn process(&self, cmd: MarkWebhookDeliveryFailed) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET
+                retry_count = retry_count + 1,
+                last_tried_at = NOW(),
+                last_error = $2,
+                next_attempt_at = NOW() + make_interval(secs => $3),
+                dead_lettered_at = CASE WHEN $4 THEN NOW() END
+            WHERE id = $1
+            "#,
+            cmd.id,
+            cmd.error,
+            cmd.retry_delay.as_secs_f64(),
+            cmd.dead_letter,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order_records::{
+        CreateOrderRecord, CreatedOrderRecord, GetOrderRecordById, OrderRecord,
+    };
+    use crate::entities::webhook_dead_letter::{
+        ReplayDeadLetteredWebhookDeliveries, WebhookDeadLetterFilter,
+    };
+    use std::time::Duration;
+
+    async fn create_order(processor: &DatabaseProcessor, merchant_order_id: &str) -> OrderRecord {
+        let created = processor
+            .process(CreateOrderRecord {
+                merchant_id: "default".to_string(),
+                merchant_order_id: merchant_order_id.to_string(),
+                amount: rust_decimal::Decimal::ONE,
+                webhook_url: None,
+                metadata: None,
+                description: None,
+                customer_reference: None,
+                success_url: None,
+                cancel_url: None,
+            })
+            .await
+            .unwrap();
+        let CreatedOrderRecord::Created(order) = created else {
+            unreachable!("merchant order IDs are unique per test");
+        };
+        order
+    }
+
+    async fn order_status(processor: &DatabaseProcessor, order_id: Uuid) -> OrderStatus {
+        processor
+            .process(GetOrderRecordById { order_id })
+            .await
+            .unwrap()
+            .unwrap()
+            .status
+    }
+
+    async fn outbox_entries(processor: &DatabaseProcessor) -> Vec<WebhookOutboxEntry> {
+        processor
+            .process(GetWebhookOutboxEntriesToFanOut { limit: 10 })
+            .await
+            .unwrap()
+    }
+
+    async fn claim(processor: &DatabaseProcessor) -> Vec<WebhookDelivery> {
+        processor
+            .process(ClaimDueWebhookDeliveries {
+                limit: 10,
+                lease: Duration::from_secs(60),
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn dead_letter(processor: &DatabaseProcessor, id: i64) {
+        processor
+            .process(MarkWebhookDeliveryFailed {
+                id,
+                error: "HTTP 500".to_string(),
+                retry_delay: Duration::ZERO,
+                dead_letter: true,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_resend_and_replay_revive_dead_letters(pool: sqlx::PgPool) {
+        let processor = DatabaseProcessor { pool };
+        let order = create_order(&processor, "dead-letter").await;
+        processor
+            .process(EnqueueWebhookEvent {
+                event: WebhookEvent::OrderStatusChanged {
+                    order_id: order.order_id,
+                    new_status: OrderStatus::Cancelled,
+                },
+            })
+            .await
+            .unwrap();
+        let entries = processor
+            .process(GetWebhookOutboxEntriesToFanOut { limit: 10 })
+            .await
+            .unwrap();
+        processor
+            .process(FanOutWebhookOutboxEntry {
+                id: entries[0].id,
+                subscriptions: vec![],
+            })
+            .await
+            .unwrap();
+
+        let delivery_id = claim(&processor).await[0].id;
+        dead_letter(&processor, delivery_id).await;
+        assert!(claim(&processor).await.is_empty());
+
+        // Resending the order webhook makes the delivery due again
+        processor
+            .process(RequeueOrderStatusWebhook {
+                order_id: order.order_id,
+            })
+            .await
+            .unwrap();
+        let claimed = claim(&processor).await;
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].id, delivery_id);
+        assert_eq!(claimed[0].retry_count, 0);
+
+        // So does replaying the dead letters
+        dead_letter(&processor, delivery_id).await;
+        let replayed = processor
+            .process(ReplayDeadLetteredWebhookDeliveries {
+                filter: WebhookDeadLetterFilter::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(replayed, 1);
+        let claimed = claim(&processor).await;
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].id, delivery_id);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_status_change_and_outbox_roll_back_together(pool: sqlx::PgPool) {
+        let processor = DatabaseProcessor { pool };
+        let order = create_order(&processor, "rolled-back").await;
+        let event = WebhookEvent::OrderStatusChanged {
+            order_id: order.order_id,
+            new_status: OrderStatus::Paid,
+        };
+
+        let mut tx = processor.pool.begin().await.unwrap();
+        OrderRecord::update_status_tx(&mut tx, order.order_id, OrderStatus::Paid)
+            .await
+            .unwrap();
+        WebhookOutbox::enqueue_tx(&mut tx, &event).await.unwrap();
+        tx.rollback().await.unwrap();
+
+        assert_eq!(
+            order_status(&processor, order.order_id).await,
+            OrderStatus::Pending
+        );
+        assert!(outbox_entries(&processor).await.is_empty());
+
+        // A transaction dropped without committing is rolled back as well
+        {
+            let mut tx = processor.pool.begin().await.unwrap();
+            OrderRecord::update_status_many_tx(&mut tx, &[order.order_id], OrderStatus::Paid)
+                .await
+                .unwrap();
+            WebhookOutbox::enqueue_order_status_many_tx(
+                &mut tx,
+                &[order.order_id],
+                OrderStatus::Paid,
+            )
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(
+            order_status(&processor, order.order_id).await,
+            OrderStatus::Pending
+        );
+        assert!(outbox_entries(&processor).await.is_empty());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_status_change_and_outbox_commit_together(pool: sqlx::PgPool) {
+        let processor = DatabaseProcessor { pool };
+        let first = create_order(&processor, "committed-1").await;
+        let second = create_order(&processor, "committed-2").await;
+
+        let mut tx = processor.pool.begin().await.unwrap();
+        OrderRecord::update_status_tx(&mut tx, first.order_id, OrderStatus::Paid)
+            .await
+            .unwrap();
+        WebhookOutbox::enqueue_tx(
+            &mut tx,
+            &WebhookEvent::OrderStatusChanged {
+                order_id: first.order_id,
+                new_status: OrderStatus::Paid,
+            },
+        )
+        .await
+        .unwrap();
+        OrderRecord::update_status_many_tx(&mut tx, &[second.order_id], OrderStatus::Expired)
+            .await
+            .unwrap();
+        WebhookOutbox::enqueue_order_status_many_tx(
+            &mut tx,
+            &[second.order_id],
+            OrderStatus::Expired,
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        assert_eq!(
+            order_status(&processor, first.order_id).await,
+            OrderStatus::Paid
+        );
+        assert_eq!(
+            order_status(&processor, second.order_id).await,
+            OrderStatus::Expired
+        );
+        let events: Vec<_> = outbox_entries(&processor)
+            .await
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.kind,
+                    entry.order_id,
+                    entry.order_status,
+                    entry.sequence,
+                )
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    WebhookEventKind::OrderStatusChanged,
+                    Some(first.order_id),
+                    Some(OrderStatus::Paid),
+                    Some(1)
+                ),
+                (
+                    WebhookEventKind::OrderStatusChanged,
+                    Some(second.order_id),
+                    Some(OrderStatus::Expired),
+                    Some(1)
+                ),
+            ]
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_fan_out_creates_delivery_per_subscription(pool: sqlx::PgPool) {
+        let processor = DatabaseProcessor { pool };
+        let order = create_order(&processor, "fan-out").await;
+        processor
+            .process(EnqueueWebhookEvent {
+                event: WebhookEvent::OrderStatusChanged {
+                    order_id: order.order_id,
+                    new_status: OrderStatus::Paid,
+                },
+            })
+            .await
+            .unwrap();
+        let entry_id = outbox_entries(&processor).await[0].id;
+
+        let fan_out = FanOutWebhookOutboxEntry {
+            id: entry_id,
+            subscriptions: vec!["crm".to_string(), "analytics".to_string()],
+        };
+        processor.process(fan_out.clone()).await.unwrap();
+        assert!(outbox_entries(&processor).await.is_empty());
+        // Fanning out again changes nothing
+        processor.process(fan_out).await.unwrap();
+
+        let claimed = claim(&processor).await;
+        assert!(claimed.iter().all(|delivery| delivery.entry.id == entry_id));
+        let mut subscriptions: Vec<_> = claimed
+            .into_iter()
+            .map(|delivery| delivery.subscription)
+            .collect();
+        subscriptions.sort();
+        assert_eq!(
+            subscriptions,
+            vec![None, Some("analytics".to_string()), Some("crm".to_string())]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Transfer lookups for v2 webhook payloads.
+//!
+//! ERC-20 and TRC-20 transfers live in separate tables; these queries search
+//! both and return a single shape. `chain` is `None` for TRC-20 transfers,
+//! the same convention as `webhook_outbox`.
+
+use crate::entities::StablecoinName;
+use crate::entities::erc20_pending_deposit::EtherScanChain;
+use crate::events::BlockchainTarget;
+use crate::framework::DatabaseProcessor;
+use kanau::processor::Processor;
+use uuid::Uuid;
+
+/// A transfer from either transfer table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookTransferRecord {
+    pub id: i64,
+    pub chain: Option<EtherScanChain>,
+    pub token_name: StablecoinName,
+    pub from_address: String,
+    pub to_address: String,
+    pub txn_hash: String,
+    pub value: rust_decimal::Decimal,
+    pub block_number: i64,
+    /// Seconds on EVM chains, milliseconds on Tron.
+    pub block_timestamp: i64,
+    pub confirmations: i32,
+}
+
+impl WebhookTransferRecord {
+    /// The blockchain the transfer was observed on.
+    pub fn blockchain(&self) -> BlockchainTarget {
+        self.chain
+            .map_or(BlockchainTarget::Trc20, BlockchainTarget::Erc20)
+    }
+
+    /// Block timestamp in Unix seconds on every chain.
+    pub fn block_timestamp_secs(&self) -> i64 {
+        match self.chain {
+            Some(_) => self.block_timestamp,
+            None => self.block_timestamp / 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Get the transfer that paid an order, if it was paid on-chain.
+pub struct GetOrderPayingTransfer {
+    pub order_id: Uuid,
+}
+
+impl Processor<GetOrderPayingTransfer> for DatabaseProcessor {
+    type Output = Option<WebhookTransferRecord>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetOrderPayingTransfer")]
+    async fn process(
+        &self,
+        query: GetOrderPayingTransfer,
+    ) -> Result<Option<WebhookTransferRecord>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookTransferRecord,
+            r#"
+            SELECT
+                id as "id!",
+                chain as "chain?: EtherScanChain",
+                token_name as "token_name!: StablecoinName",
+                from_address as "from_address!",
+                to_address as "to_address!",
+                txn_hash as "txn_hash!",
+                value as "value!",
+                block_number as "block_number!",
+                block_timestamp as "block_timestamp!",
+                confirmations as "confirmations!"
+            FROM (
+                SELECT t.id, t.chain, t.token_name, t.from_address, t.to_address, t.txn_hash,
+                       t.value, t.block_number, t.block_timestamp, t.confirmations
+                FROM erc20_token_transfers t
+                JOIN erc20_pending_deposits d ON d.id = t.fulfillment_id
+                WHERE d."order" = $1 AND t.status = 'matched'
+                UNION ALL
+                SELECT t.id, NULL::etherscan_chain, t.token_name, t.from_address, t.to_address,
+                       t.txn_hash, t.value, t.block_number, t.block_timestamp, t.confirmations
+                FROM trc20_token_transfers t
+                JOIN trc20_pending_deposits d ON d.id = t.fulfillment_id
+                WHERE d."order" = $1 AND t.status = 'matched'
+            ) AS paying
+            LIMIT 1
+            "#,
+            query.order_id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Get a transfer by transaction hash.
+pub struct GetTransferByTxnHash {
+    /// Chain of an ERC-20 transfer, `None` for a TRC-20 transfer.
+    pub chain: Option<EtherScanChain>,
+    pub txn_hash: String,
+}
+
+impl Processor<GetTransferByTxnHash> for DatabaseProcessor {
+    type Output = Option<WebhookTransferRecord>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetTransferByTxnHash")]
+    async fn process(
+        &self,
+        query: GetTransferByTxnHash,
+    ) -> Result<Option<WebhookTransferRecord>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookTransferRecord,
+            r#"
+            SELECT
+                id as "id!",
+                chain as "chain?: EtherScanChain",
+                token_name as "token_name!: StablecoinName",
+                from_address as "from_address!",
+                to_address as "to_address!",
+                txn_hash as "txn_hash!",
+                value as "value!",
+                block_number as "block_number!",
+                block_timestamp as "block_timestamp!",
+                confirmations as "confirmations!"
+            FROM (
+                SELECT id, chain, token_name, from_address, to_address, txn_hash,
+                       value, block_number, block_timestamp, confirmations
+                FROM erc20_token_transfers
+                WHERE $1::etherscan_chain IS NOT NULL AND chain = $1 AND txn_hash = $2
+                UNION ALL
+                SELECT id, NULL::etherscan_chain, token_name, from_address, to_address,
+                       txn_hash, value, block_number, block_timestamp, confirmations
+                FROM trc20_token_transfers
+                WHERE $1::etherscan_chain IS NULL AND txn_hash = $2
+            ) AS transfer
+            LIMIT 1
+            "#,
+            query.chain as Option<EtherScanChain>,
+            query.txn_hash,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Get a transfer by ID.
+pub struct GetTransferById {
+    /// Chain of an ERC-20 transfer, `None` for a TRC-20 transfer.
+    pub chain: Option<EtherScanChain>,
+    pub id: i64,
+}
+
+impl Processor<GetTransferById> for DatabaseProcessor {
+    type Output = Option<WebhookTransferRecord>;
+    type Error = sqlx::Error;
+    #[tracing::instrument(skip_all, err, name = "SQL:GetTransferById")]
+    async fn process(
+        &self,
+        query: GetTransferById,
+    ) -> Result<Option<WebhookTransferRecord>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookTransferRecord,
+            r#"
+            SELECT
+                id as "id!",
+                chain as "chain?: EtherScanChain",
+                token_name as "token_name!: StablecoinName",
+                from_address as "from_address!",
+                to_address as "to_address!",
+                txn_hash as "txn_hash!",
+                value as "value!",
+                block_number as "block_number!",
+                block_timestamp as "block_timestamp!",
+                confirmations as "confirmations!"
+            FROM (
+                SELECT id, chain, token_name, from_address, to_address, txn_hash,
+                       value, block_number, block_timestamp, confirmations
+                FROM erc20_token_transfers
+                WHERE $1::etherscan_chain IS NOT NULL AND chain = $1 AND id = $2
+                UNION ALL
+                SELECT id, NULL::etherscan_chain, token_name, from_address, to_address,
+                       txn_hash, value, block_number, block_timestamp, confirmations
+                FROM trc20_token_transfers
+                WHERE $1::etherscan_chain IS NULL AND id = $2
+            ) AS transfer
+            LIMIT 1
+            "#,
+            query.chain as Option<EtherScanChain>,
+            query.id,
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
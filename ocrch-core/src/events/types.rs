@@ -7,6 +7,7 @@
 use crate::entities::StablecoinName;
 use crate::entities::erc20_pending_deposit::EtherScanChain;
 use crate::entities::order_records::OrderStatus;
+use ocrch_sdk::objects::blockchains::Blockchain;
 use uuid::Uuid;
 
 /// Unified blockchain target for event routing.
@@ -29,6 +30,30 @@ impl std::fmt::Display for BlockchainTarget {
     }
 }
 
+impl From<Blockchain> for BlockchainTarget {
+    fn from(value: Blockchain) -> Self {
+        match value {
+            Blockchain::Ethereum => BlockchainTarget::Erc20(EtherScanChain::Ethereum),
+            Blockchain::Polygon => BlockchainTarget::Erc20(EtherScanChain::Polygon),
+            Blockchain::Base => BlockchainTarget::Erc20(EtherScanChain::Base),
+            Blockchain::ArbitrumOne => BlockchainTarget::Erc20(EtherScanChain::ArbitrumOne),
+            Blockchain::Linea => BlockchainTarget::Erc20(EtherScanChain::Linea),
+            Blockchain::Optimism => BlockchainTarget::Erc20(EtherScanChain::Optimism),
+            Blockchain::AvalancheC => BlockchainTarget::Erc20(EtherScanChain::AvalancheC),
+            Blockchain::Tron => BlockchainTarget::Trc20,
+        }
+    }
+}
+
+impl From<BlockchainTarget> for Blockchain {
+    fn from(value: BlockchainTarget) -> Self {
+        match value {
+            BlockchainTarget::Erc20(chain) => chain.into(),
+            BlockchainTarget::Trc20 => Blockchain::Tron,
+        }
+    }
+}
+
 /// Event emitted when a pending deposit changes status.
 ///
 /// This is the entry point for the event flow. It triggers the PoolingManager
@@ -107,4 +132,14 @@ pub enum WebhookEvent {
         transfer_id: i64,
         blockchain: BlockchainTarget,
     },
+    /// A transfer matching a pending deposit was seen but is not yet confirmed
+    PaymentDetected {
+        order_id: Uuid,
+        blockchain: BlockchainTarget,
+        txn_hash: String,
+        confirmations: u32,
+        required_confirmations: u32,
+        /// `false` if only the confirmation count changed since the last event
+        newly_detected: bool,
+    },
 }
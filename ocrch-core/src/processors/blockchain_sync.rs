@@ -4,22 +4,29 @@
 //! - Receiving `PoolingTick` events
 //! - Fetching new transfers from blockchain explorer APIs
 //! - Writing transfers to the database (with conflict handling for idempotency)
+//! - Updating confirmation depth from the chain head, promoting transfers that
+//!   reach the chain's required confirmations to `waiting_for_match`
 //! - Emitting `MatchTick` events after syncing
 //!
 //! Each enabled token on each blockchain has its own BlockchainSync instance.
 
+use crate::config::{ChainConfig, ConfigStore, required_confirmations_for};
 use crate::entities::StablecoinName;
 use crate::entities::erc20_pending_deposit::EtherScanChain;
 use crate::entities::erc20_transfer::{
     Erc20TransferInsert, GetErc20TokenTransSyncCursor, InsertManyErc20TokenTransfers,
+    UpdateErc20TransferConfirmations,
 };
 use crate::entities::trc20_transfer::{
     GetTrc20TokenTransSyncCursor, InsertManyTrc20TokenTransfers, Trc20TransferInsert,
+    UpdateTrc20TransferConfirmations,
 };
 use crate::events::{
     BlockchainTarget, MatchTick, MatchTickSender, PoolingTick, PoolingTickReceiver,
 };
 use crate::framework::DatabaseProcessor;
+use crate::processors::PoolingKey;
+use crate::processors::sync_health::SyncHealthRegistry;
 use async_trait::async_trait;
 use kanau::processor::Processor;
 use rust_decimal::Decimal;
@@ -39,6 +46,10 @@ pub enum SyncError {
     #[error("API request error: {0}")]
     Request(#[from] reqwest::Error),
 
+    /// WebSocket RPC connection error
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+
     /// API response parsing error
     #[error("API response parsing error: {0}")]
     Parse(String),
@@ -56,6 +67,43 @@ pub enum SyncError {
     UnsupportedToken,
 }
 
+/// An inclusive range for a historical resync.
+///
+/// Interpreted in the same unit as each chain's sync cursor: block numbers for
+/// ERC-20 chains and millisecond timestamps for TRC-20.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResyncRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+/// Outcome of a single [`BlockchainSync::sync`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncOutcome {
+    /// Number of new transfers synced.
+    pub transfers_synced: u32,
+    /// Highest block among the fetched transfers, if any.
+    pub last_block: Option<i64>,
+    /// Whether every transfer the explorer had was fetched, rather than a
+    /// page cut off at its result limit.
+    pub complete: bool,
+}
+
+impl SyncOutcome {
+    /// Highest block up to which every transfer has been synced.
+    ///
+    /// `chain_head` is the head fetched before the sync started: a complete
+    /// sync has seen everything up to it. An incomplete one may have stopped
+    /// partway through its last block.
+    pub fn synced_block(&self, chain_head: Option<i64>) -> Option<i64> {
+        if self.complete {
+            chain_head.max(self.last_block)
+        } else {
+            self.last_block.map(|block| block - 1)
+        }
+    }
+}
+
 /// Trait for blockchain sync implementations.
 ///
 /// Each blockchain type (ERC-20 chains, TRC-20) implements this trait
@@ -63,9 +111,16 @@ pub enum SyncError {
 #[async_trait]
 pub trait BlockchainSync: Send + Sync {
     /// Sync new transfers from the blockchain explorer API.
+    async fn sync(&self, pool: &PgPool) -> Result<SyncOutcome, SyncError>;
+
+    /// Re-fetch all transfers within `range`, independently of the sync cursor.
     ///
-    /// Returns the number of new transfers synced.
-    async fn sync(&self, pool: &PgPool) -> Result<u32, SyncError>;
+    /// Uses the same idempotent insert path as [`sync`](Self::sync), so
+    /// already-stored transfers are skipped. Returns the number of new transfers.
+    async fn resync(&self, pool: &PgPool, range: ResyncRange) -> Result<u32, SyncError>;
+
+    /// Fetch the current chain head block number.
+    async fn chain_head(&self) -> Result<i64, SyncError>;
 
     /// Get the blockchain target for this sync.
     fn blockchain_target(&self) -> BlockchainTarget;
@@ -90,6 +145,10 @@ pub struct Erc20BlockchainSync {
 impl Erc20BlockchainSync {
     const ETHERSCAN_API_URL: &str = "https://api.etherscan.io/v2/api";
 
+    /// Most results EtherScan returns for one query across all its pages
+    /// (`page` × `offset`).
+    const ETHERSCAN_RESULT_WINDOW: u32 = 10_000;
+
     /// Create a new Erc20BlockchainSync.
     ///
     /// # Arguments
@@ -116,10 +175,14 @@ impl Erc20BlockchainSync {
         }
     }
 
-    /// Fetch transfers from the EtherScan API.
-    async fn fetch_transfers(
+    /// Fetch page `page` (starting at 1) of the transfers from `start_block`
+    /// to `end_block` (inclusive), `limit` per page, in ascending block order.
+    async fn fetch_transfers_in_range(
         &self,
         start_block: i64,
+        end_block: Option<i64>,
+        page: u32,
+        limit: u32,
     ) -> Result<Vec<Erc20TokenTransferResponseItem>, SyncError> {
         let sdk_token: ocrch_sdk::objects::Stablecoin = self.token.into();
         let Some(contract_address) = sdk_token.get_data().get_contract_address(self.chain.into())
@@ -138,14 +201,25 @@ impl Erc20BlockchainSync {
                 ("contractaddress", contract_address),
                 ("address", self.wallet_address.as_str()),
                 ("startblock", start_block.to_string().as_str()),
-                ("page", "1"),
-                ("offset", "100"),
+                (
+                    "endblock",
+                    end_block
+                        .unwrap_or(i64::from(i32::MAX))
+                        .to_string()
+                        .as_str(),
+                ),
+                ("page", page.to_string().as_str()),
+                ("offset", limit.to_string().as_str()),
                 ("sort", "asc"),
             ])
             .send()
             .await?;
         let response: EtherScanResponse<Vec<Erc20TokenTransferResponseItem>> =
             response.json().await?;
+        // An empty range is reported as status "0" rather than an empty result
+        if response.status != "1" && response.message == "No transactions found" {
+            return Ok(Vec::new());
+        }
         if response.status != "1" {
             return Err(SyncError::ApiError {
                 message: response.message,
@@ -284,11 +358,36 @@ impl Erc20BlockchainSync {
             .await?;
         Ok(inserted as u32)
     }
+
+    /// Resync the transfers of a single block whose first page of `limit`
+    /// transfers has already been inserted, fetching the remaining pages.
+    async fn resync_block(&self, pool: &PgPool, block: i64, limit: u32) -> Result<u32, SyncError> {
+        let mut inserted = 0;
+        for page in 2..=Self::ETHERSCAN_RESULT_WINDOW / limit {
+            let transfers = self
+                .fetch_transfers_in_range(block, Some(block), page, limit)
+                .await?;
+            let page_count = transfers.len();
+            inserted += self.insert_transfers(pool, transfers).await?;
+            if page_count < limit as usize {
+                return Ok(inserted);
+            }
+        }
+        warn!(
+            chain = ?self.chain,
+            token = ?self.token,
+            block,
+            "Block holds more transfers than EtherScan returns, skipping the rest"
+        );
+        Ok(inserted)
+    }
 }
 
 #[async_trait]
 impl BlockchainSync for Erc20BlockchainSync {
-    async fn sync(&self, pool: &PgPool) -> Result<u32, SyncError> {
+    async fn sync(&self, pool: &PgPool) -> Result<SyncOutcome, SyncError> {
+        const PAGE_LIMIT: u32 = 100;
+
         let start_block = self.get_start_block(pool).await?;
 
         debug!(
@@ -298,7 +397,15 @@ impl BlockchainSync for Erc20BlockchainSync {
             "Fetching ERC-20 transfers"
         );
 
-        let transfers = self.fetch_transfers(start_block).await?;
+        let transfers = self
+            .fetch_transfers_in_range(start_block, None, 1, PAGE_LIMIT)
+            .await?;
+        let complete = transfers.len() < PAGE_LIMIT as usize;
+        let last_block = transfers
+            .last()
+            .map(|t| t.block_number.parse::<i64>())
+            .transpose()
+            .map_err(|e| SyncError::Parse(format!("Invalid block number: {}", e)))?;
 
         let inserted = self.insert_transfers(pool, transfers).await?;
 
@@ -309,9 +416,80 @@ impl BlockchainSync for Erc20BlockchainSync {
             "Synced ERC-20 transfers"
         );
 
+        Ok(SyncOutcome {
+            transfers_synced: inserted,
+            last_block,
+            complete,
+        })
+    }
+
+    async fn resync(&self, pool: &PgPool, range: ResyncRange) -> Result<u32, SyncError> {
+        const PAGE_LIMIT: u32 = 1000;
+
+        let mut start_block = range.start;
+        let mut inserted = 0;
+
+        while start_block <= range.end {
+            let transfers = self
+                .fetch_transfers_in_range(start_block, Some(range.end), 1, PAGE_LIMIT)
+                .await?;
+            let page_count = transfers.len();
+            let last_block = transfers
+                .last()
+                .map(|t| t.block_number.parse::<i64>())
+                .transpose()
+                .map_err(|e| SyncError::Parse(format!("Invalid block number: {}", e)))?;
+
+            inserted += self.insert_transfers(pool, transfers).await?;
+
+            // Continue from the last block seen; it may hold more transfers
+            // than fit in the page, which the insert deduplicates. A block
+            // that fills the whole page is paged through on its own.
+            match last_block {
+                Some(last_block) if page_count == PAGE_LIMIT as usize => {
+                    if last_block <= start_block {
+                        inserted += self.resync_block(pool, last_block, PAGE_LIMIT).await?;
+                        start_block = last_block + 1;
+                    } else {
+                        start_block = last_block;
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        info!(
+            chain = ?self.chain,
+            token = ?self.token,
+            start_block = range.start,
+            end_block = range.end,
+            inserted = inserted,
+            "Resynced ERC-20 transfers"
+        );
+
         Ok(inserted)
     }
 
+    async fn chain_head(&self) -> Result<i64, SyncError> {
+        let chain_id = self.chain as i32;
+        let response = self
+            .http_client
+            .get(Self::ETHERSCAN_API_URL)
+            .query(&[
+                ("apiKey", self.api_key.as_str()),
+                ("chainid", chain_id.to_string().as_str()),
+                ("module", "proxy"),
+                ("action", "eth_blockNumber"),
+            ])
+            .send()
+            .await?;
+
+        let response: EtherScanProxyResponse<String> = response.json().await?;
+
+        i64::from_str_radix(response.result.trim_start_matches("0x"), 16)
+            .map_err(|e| SyncError::Parse(format!("Invalid block number: {}", e)))
+    }
+
     fn blockchain_target(&self) -> BlockchainTarget {
         BlockchainTarget::Erc20(self.chain)
     }
@@ -340,6 +518,8 @@ impl Trc20BlockchainSync {
 
     const TRON_SCAN_TX_INFO_URL: &str = "https://apilist.tronscanapi.com/api/transaction-info";
 
+    const TRON_SCAN_BLOCK_URL: &str = "https://apilist.tronscanapi.com/api/block";
+
     const TRON_SCAN_AUTHORIZATION_HEADER: &str = "TRON-PRO-API-KEY";
 
     /// Create a new Trc20BlockchainSync.
@@ -371,19 +551,25 @@ impl Trc20BlockchainSync {
     async fn fetch_transfers(
         &self,
         start_timestamp: i64,
+        end_timestamp: Option<i64>,
         offset: i64,
         limit: i64,
     ) -> Result<TronScanTransfersResponse<Vec<Trc20TransferData>>, SyncError> {
+        let mut query = vec![
+            ("contract_address", self.contract_address.clone()),
+            ("toAddress", self.wallet_address.clone()),
+            ("start_timestamp", start_timestamp.to_string()),
+            ("start", offset.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(end_timestamp) = end_timestamp {
+            query.push(("end_timestamp", end_timestamp.to_string()));
+        }
+
         let response = self
             .http_client
             .get(Self::TRON_SCAN_TRC20_TRANSFERS_URL)
-            .query(&[
-                ("contract_address", self.contract_address.as_str()),
-                ("toAddress", self.wallet_address.as_str()),
-                ("start_timestamp", start_timestamp.to_string().as_str()),
-                ("start", offset.to_string().as_str()),
-                ("limit", limit.to_string().as_str()),
-            ])
+            .query(&query)
             .header(Self::TRON_SCAN_AUTHORIZATION_HEADER, self.api_key.as_str())
             .send()
             .await?;
@@ -519,27 +705,22 @@ impl Trc20BlockchainSync {
             .await?;
         Ok(inserted as u32)
     }
-}
 
-#[async_trait]
-impl BlockchainSync for Trc20BlockchainSync {
-    async fn sync(&self, pool: &PgPool) -> Result<u32, SyncError> {
+    /// Fetch every transfer from `start_timestamp` (up to `end_timestamp`),
+    /// following TronScan's pagination.
+    async fn fetch_all_transfers(
+        &self,
+        start_timestamp: i64,
+        end_timestamp: Option<i64>,
+    ) -> Result<Vec<Trc20TransferData>, SyncError> {
         const PAGE_LIMIT: i64 = 200;
 
-        let start_timestamp = self.get_start_timestamp(pool).await?;
-
-        debug!(
-            token = ?self.token,
-            start_timestamp = start_timestamp,
-            "Fetching TRC-20 transfers"
-        );
-
         let mut all_transfers = Vec::new();
         let mut offset: i64 = 0;
 
         loop {
             let response = self
-                .fetch_transfers(start_timestamp, offset, PAGE_LIMIT)
+                .fetch_transfers(start_timestamp, end_timestamp, offset, PAGE_LIMIT)
                 .await?;
 
             let page_count = response.token_transfers.len() as i64;
@@ -553,6 +734,24 @@ impl BlockchainSync for Trc20BlockchainSync {
             offset += PAGE_LIMIT;
         }
 
+        Ok(all_transfers)
+    }
+}
+
+#[async_trait]
+impl BlockchainSync for Trc20BlockchainSync {
+    async fn sync(&self, pool: &PgPool) -> Result<SyncOutcome, SyncError> {
+        let start_timestamp = self.get_start_timestamp(pool).await?;
+
+        debug!(
+            token = ?self.token,
+            start_timestamp = start_timestamp,
+            "Fetching TRC-20 transfers"
+        );
+
+        let all_transfers = self.fetch_all_transfers(start_timestamp, None).await?;
+        let last_block = all_transfers.iter().map(|t| t.block).max();
+
         let inserted = self.insert_transfers(pool, all_transfers).await?;
 
         debug!(
@@ -561,9 +760,61 @@ impl BlockchainSync for Trc20BlockchainSync {
             "Synced TRC-20 transfers"
         );
 
+        // Every page is followed, so the sync always reaches the end
+        Ok(SyncOutcome {
+            transfers_synced: inserted,
+            last_block,
+            complete: true,
+        })
+    }
+
+    async fn resync(&self, pool: &PgPool, range: ResyncRange) -> Result<u32, SyncError> {
+        let transfers = self
+            .fetch_all_transfers(range.start, Some(range.end))
+            .await?;
+        let inserted = self.insert_transfers(pool, transfers).await?;
+
+        info!(
+            token = ?self.token,
+            start_timestamp = range.start,
+            end_timestamp = range.end,
+            inserted = inserted,
+            "Resynced TRC-20 transfers"
+        );
+
         Ok(inserted)
     }
 
+    async fn chain_head(&self) -> Result<i64, SyncError> {
+        #[derive(Debug, serde::Deserialize)]
+        struct TronScanBlock {
+            number: i64,
+        }
+
+        let response = self
+            .http_client
+            .get(Self::TRON_SCAN_BLOCK_URL)
+            .query(&[("sort", "-number"), ("start", "0"), ("limit", "1")])
+            .header(Self::TRON_SCAN_AUTHORIZATION_HEADER, self.api_key.as_str())
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(SyncError::RateLimited {
+                retry_after_secs: 5,
+            });
+        }
+
+        let response: TronScanBlocksResponse<Vec<TronScanBlock>> = response.json().await?;
+        response
+            .data
+            .first()
+            .map(|block| block.number)
+            .ok_or_else(|| SyncError::ApiError {
+                message: "TronScan returned no blocks".to_string(),
+            })
+    }
+
     fn blockchain_target(&self) -> BlockchainTarget {
         BlockchainTarget::Trc20
     }
@@ -578,16 +829,61 @@ impl BlockchainSync for Trc20BlockchainSync {
 /// This wraps a BlockchainSync implementation and handles:
 /// - Receiving PoolingTick events
 /// - Calling sync()
+/// - Recording the outcome in the [`SyncHealthRegistry`]
 /// - Emitting MatchTick events
 pub struct BlockchainSyncRunner<S: BlockchainSync> {
     sync: S,
     pool: PgPool,
+    health: SyncHealthRegistry,
+    chains: ConfigStore<Vec<ChainConfig>>,
 }
 
 impl<S: BlockchainSync + 'static> BlockchainSyncRunner<S> {
     /// Create a new BlockchainSyncRunner.
-    pub fn new(sync: S, pool: PgPool) -> Self {
-        Self { sync, pool }
+    pub fn new(
+        sync: S,
+        pool: PgPool,
+        health: SyncHealthRegistry,
+        chains: ConfigStore<Vec<ChainConfig>>,
+    ) -> Self {
+        Self {
+            sync,
+            pool,
+            health,
+            chains,
+        }
+    }
+
+    /// Recompute confirmations of unconfirmed transfers against `chain_head`.
+    async fn update_confirmations(&self, chain_head: i64) -> Result<u64, sqlx::Error> {
+        let blockchain = self.sync.blockchain_target();
+        let token = self.sync.token();
+        let required = required_confirmations_for(&self.chains.read().await, blockchain.into());
+        let required_confirmations = i32::try_from(required).unwrap_or(i32::MAX);
+        let processor = DatabaseProcessor {
+            pool: self.pool.clone(),
+        };
+        match blockchain {
+            BlockchainTarget::Erc20(chain) => {
+                processor
+                    .process(UpdateErc20TransferConfirmations {
+                        chain,
+                        token,
+                        chain_head,
+                        required_confirmations,
+                    })
+                    .await
+            }
+            BlockchainTarget::Trc20 => {
+                processor
+                    .process(UpdateTrc20TransferConfirmations {
+                        token,
+                        chain_head,
+                        required_confirmations,
+                    })
+                    .await
+            }
+        }
     }
 
     /// Run the BlockchainSyncRunner.
@@ -694,8 +990,51 @@ impl<S: BlockchainSync + Send + Sync + 'static> Processor<PoolingTick> for Block
     async fn process(&self, _tick: PoolingTick) -> Result<MatchTick, SyncError> {
         let blockchain = self.sync.blockchain_target();
         let token = self.sync.token();
+        let key = PoolingKey::new(blockchain, token);
+
+        // The head is fetched before syncing so that a complete sync is known
+        // to have seen everything up to it.
+        let chain_head = self.sync.chain_head().await;
+        let result = self.sync.sync(&self.pool).await;
+
+        if let Ok(head) = &chain_head {
+            match self.update_confirmations(*head).await {
+                Ok(0) => {}
+                Ok(confirmed) => {
+                    debug!(%blockchain, ?token, confirmed, "Transfers reached required confirmations");
+                }
+                Err(e) => {
+                    warn!(%blockchain, ?token, error = %e, "Failed to update transfer confirmations");
+                }
+            }
+        }
 
-        let transfers_synced = self.sync.sync(&self.pool).await?;
+        let transfers_synced = match (result, chain_head) {
+            (Ok(outcome), Ok(chain_head)) => {
+                self.health
+                    .record_success(
+                        key,
+                        outcome.synced_block(Some(chain_head)),
+                        Some(chain_head),
+                    )
+                    .await;
+                outcome.transfers_synced
+            }
+            (Ok(outcome), Err(e)) => {
+                // The sync itself succeeded; only the lag is unknown
+                warn!(%blockchain, ?token, error = %e, "Failed to fetch chain head");
+                self.health
+                    .record_success(key, outcome.synced_block(None), None)
+                    .await;
+                outcome.transfers_synced
+            }
+            (Err(e), chain_head) => {
+                self.health
+                    .record_failure(key, e.to_string(), chain_head.ok())
+                    .await;
+                return Err(e);
+            }
+        };
 
         debug!(
             blockchain = %blockchain,
@@ -750,6 +1089,11 @@ struct TronScanTransfersResponse<T> {
     pub token_transfers: T,
 }
 
+#[derive(Debug, serde::Deserialize)]
+struct TronScanBlocksResponse<T> {
+    pub data: T,
+}
+
 #[derive(Debug, serde::Deserialize)]
 struct Trc20TransferData {
     pub transaction_id: String,
@@ -766,3 +1110,35 @@ struct Trc20TransferData {
 struct Trc20TokenInfo {
     pub decimals: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synced_block() {
+        let complete = SyncOutcome {
+            transfers_synced: 1,
+            last_block: Some(95),
+            complete: true,
+        };
+        assert_eq!(complete.synced_block(Some(100)), Some(100));
+        assert_eq!(complete.synced_block(Some(90)), Some(95));
+        assert_eq!(complete.synced_block(None), Some(95));
+
+        let empty = SyncOutcome {
+            complete: true,
+            ..Default::default()
+        };
+        assert_eq!(empty.synced_block(Some(100)), Some(100));
+        assert_eq!(empty.synced_block(None), None);
+
+        // A truncated page may have stopped inside its last block
+        let truncated = SyncOutcome {
+            transfers_synced: 100,
+            last_block: Some(50),
+            complete: false,
+        };
+        assert_eq!(truncated.synced_block(Some(100)), Some(49));
+    }
+}
@@ -0,0 +1,472 @@
+//! Real-time ERC-20 log subscriptions over WebSocket JSON-RPC.
+//!
+//! The LogSubscriber is responsible for:
+//! - Keeping a long-lived `eth_subscribe("logs")` connection open per EVM chain,
+//!   filtered on the `Transfer` topic for our token contracts and wallets
+//! - Writing observed transfers through the same idempotent insert path as
+//!   [`Erc20BlockchainSync`](super::Erc20BlockchainSync)
+//! - Emitting `MatchTick` events as soon as a new transfer is stored
+//! - Reconnecting with exponential back-off, and requesting a `PoolingTick`
+//!   from every runner on the chain after a reconnect so the polling path
+//!   back-fills anything missed while disconnected
+//!
+//! The subscription is purely an accelerator: the explorer polling path stays
+//! authoritative, and duplicates are discarded by the insert's conflict clause.
+
+use crate::entities::StablecoinName;
+use crate::entities::erc20_pending_deposit::EtherScanChain;
+use crate::entities::erc20_transfer::{Erc20TransferInsert, InsertManyErc20TokenTransfers};
+use crate::events::{BlockchainTarget, MatchTick, MatchTickSender, PoolingTick, PoolingTickSender};
+use crate::framework::DatabaseProcessor;
+use crate::processors::blockchain_sync::SyncError;
+use futures_util::{SinkExt, StreamExt};
+use itertools::Itertools;
+use kanau::processor::Processor;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, info, warn};
+
+/// `keccak256("Transfer(address,address,uint256)")`.
+const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// JSON-RPC request ID used for the `eth_subscribe` call.
+const SUBSCRIBE_REQUEST_ID: u64 = 1;
+
+/// Initial delay before reconnecting after a dropped connection.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound for the reconnect back-off.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// A (token, wallet) pair watched by a [`LogSubscriber`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogWatch {
+    pub token: StablecoinName,
+    pub wallet_address: String,
+}
+
+/// Keeps a WebSocket log subscription open for a single EVM chain.
+pub struct LogSubscriber {
+    chain: EtherScanChain,
+    ws_url: String,
+    watches: Vec<LogWatch>,
+    processor: DatabaseProcessor,
+}
+
+impl LogSubscriber {
+    /// Create a new LogSubscriber.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain` - The EVM chain to subscribe to
+    /// * `ws_url` - The WebSocket JSON-RPC endpoint for that chain
+    /// * `watches` - The (token, wallet) pairs to watch for incoming transfers
+    /// * `processor` - Database processor used to store transfers
+    pub fn new(
+        chain: EtherScanChain,
+        ws_url: String,
+        watches: Vec<LogWatch>,
+        processor: DatabaseProcessor,
+    ) -> Self {
+        Self {
+            chain,
+            ws_url,
+            watches,
+            processor,
+        }
+    }
+
+    /// Run the subscriber until shutdown is signaled.
+    ///
+    /// `backfill_txs` are the tick senders of the polling runners on this
+    /// chain; each receives a `PoolingTick` after a reconnect.
+    pub async fn run(
+        self,
+        mut shutdown_rx: watch::Receiver<bool>,
+        match_tx: MatchTickSender,
+        backfill_txs: Vec<(StablecoinName, PoolingTickSender)>,
+    ) {
+        let chain = self.chain;
+        info!(
+            ?chain,
+            watches = self.watches.len(),
+            "LogSubscriber started"
+        );
+
+        let mut reconnect_delay = INITIAL_RECONNECT_DELAY;
+        let mut needs_backfill = false;
+
+        loop {
+            let mut subscribed = false;
+
+            tokio::select! {
+                biased;
+
+                _ = shutdown_rx.changed() => {
+                    if *shutdown_rx.borrow() {
+                        break;
+                    }
+                    continue;
+                }
+
+                result = self.session(&match_tx, &backfill_txs, needs_backfill, &mut subscribed) => {
+                    if let Err(e) = result {
+                        warn!(?chain, error = %e, "Log subscription disconnected");
+                    }
+                }
+            }
+
+            // A session that managed to subscribe resets the back-off; either
+            // way, anything we may have missed is back-filled on reconnect.
+            if subscribed {
+                reconnect_delay = INITIAL_RECONNECT_DELAY;
+            }
+            needs_backfill = true;
+
+            debug!(?chain, delay = ?reconnect_delay, "Reconnecting log subscription");
+            tokio::select! {
+                biased;
+
+                _ = shutdown_rx.changed() => {
+                    if *shutdown_rx.borrow() {
+                        break;
+                    }
+                }
+
+                _ = tokio::time::sleep(reconnect_delay) => {}
+            }
+            reconnect_delay = (reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+
+        info!(?chain, "LogSubscriber shutdown complete");
+    }
+
+    /// Open one connection, subscribe, and stream logs until it drops.
+    async fn session(
+        &self,
+        match_tx: &MatchTickSender,
+        backfill_txs: &[(StablecoinName, PoolingTickSender)],
+        needs_backfill: bool,
+        subscribed: &mut bool,
+    ) -> Result<(), SyncError> {
+        let contracts = self.contract_addresses();
+        if contracts.is_empty() {
+            return Err(SyncError::UnsupportedToken);
+        }
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(self.ws_url.as_str())
+            .await
+            .map_err(|e| SyncError::WebSocket(e.to_string()))?;
+
+        let wallet_topics: Vec<String> = self
+            .watches
+            .iter()
+            .map(|w| address_to_topic(&w.wallet_address))
+            .unique()
+            .collect();
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": SUBSCRIBE_REQUEST_ID,
+            "method": "eth_subscribe",
+            "params": [
+                "logs",
+                {
+                    "address": contracts.keys().collect::<Vec<_>>(),
+                    "topics": [TRANSFER_TOPIC, null, wallet_topics],
+                }
+            ],
+        });
+        ws.send(Message::Text(request.to_string()))
+            .await
+            .map_err(|e| SyncError::WebSocket(e.to_string()))?;
+
+        // Logs waiting for their block timestamp, keyed by request ID.
+        let mut pending_blocks: HashMap<u64, Vec<Erc20TransferInsert>> = HashMap::new();
+        let mut next_request_id = SUBSCRIBE_REQUEST_ID + 1;
+
+        while let Some(message) = ws.next().await {
+            let text = match message.map_err(|e| SyncError::WebSocket(e.to_string()))? {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let message: RpcMessage = serde_json::from_str(&text)
+                .map_err(|e| SyncError::Parse(format!("Invalid JSON-RPC message: {}", e)))?;
+
+            if let Some(error) = message.error {
+                return Err(SyncError::ApiError {
+                    message: error.message,
+                });
+            }
+
+            match message.id {
+                Some(SUBSCRIBE_REQUEST_ID) => {
+                    *subscribed = true;
+                    info!(chain = ?self.chain, "Log subscription established");
+                    if needs_backfill {
+                        request_backfill(self.chain, backfill_txs).await;
+                    }
+                }
+                Some(id) => {
+                    let Some(transfers) = pending_blocks.remove(&id) else {
+                        continue;
+                    };
+                    let block: RpcBlock = serde_json::from_value(message.result)
+                        .map_err(|e| SyncError::Parse(format!("Invalid block: {}", e)))?;
+                    let block_timestamp = parse_hex_i64(&block.timestamp)?;
+                    let transfers = transfers
+                        .into_iter()
+                        .map(|t| Erc20TransferInsert {
+                            block_timestamp,
+                            ..t
+                        })
+                        .collect();
+                    self.store(transfers, match_tx).await;
+                }
+                None => {
+                    let Some(params) = message.params else {
+                        continue;
+                    };
+                    let Some(transfer) = self.log_to_transfer(&contracts, params.result)? else {
+                        continue;
+                    };
+
+                    // Some nodes include the block timestamp in the log itself;
+                    // otherwise fetch it before storing the transfer.
+                    if transfer.block_timestamp != 0 {
+                        self.store(vec![transfer], match_tx).await;
+                        continue;
+                    }
+                    let request = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": next_request_id,
+                        "method": "eth_getBlockByNumber",
+                        "params": [format!("{:#x}", transfer.block_number), false],
+                    });
+                    ws.send(Message::Text(request.to_string()))
+                        .await
+                        .map_err(|e| SyncError::WebSocket(e.to_string()))?;
+                    pending_blocks.insert(next_request_id, vec![transfer]);
+                    next_request_id += 1;
+                }
+            }
+        }
+
+        Err(SyncError::WebSocket("connection closed".to_string()))
+    }
+
+    /// Lowercased token contract addresses on this chain, mapped to their token.
+    fn contract_addresses(&self) -> HashMap<String, StablecoinName> {
+        self.watches
+            .iter()
+            .filter_map(|w| {
+                let sdk_token: ocrch_sdk::objects::Stablecoin = w.token.into();
+                sdk_token
+                    .get_data()
+                    .get_contract_address(self.chain.into())
+                    .map(|address| (address.to_lowercase(), w.token))
+            })
+            .collect()
+    }
+
+    /// Convert a log notification into a transfer insert.
+    ///
+    /// Returns `None` for logs we are not watching or that were removed by a
+    /// reorg; the polling path reconciles those.
+    fn log_to_transfer(
+        &self,
+        contracts: &HashMap<String, StablecoinName>,
+        log: RpcLog,
+    ) -> Result<Option<Erc20TransferInsert>, SyncError> {
+        if log.removed {
+            debug!(chain = ?self.chain, txn_hash = %log.transaction_hash, "Ignoring removed log");
+            return Ok(None);
+        }
+        let Some(&token) = contracts.get(&log.address.to_lowercase()) else {
+            return Ok(None);
+        };
+        let (Some(from_address), Some(to_address)) = (
+            log.topics.get(1).and_then(|t| topic_to_address(t)),
+            log.topics.get(2).and_then(|t| topic_to_address(t)),
+        ) else {
+            return Ok(None);
+        };
+        if !self
+            .watches
+            .iter()
+            .any(|w| w.token == token && w.wallet_address.to_lowercase() == to_address)
+        {
+            return Ok(None);
+        }
+
+        let block_timestamp = match log.block_timestamp.as_deref() {
+            Some(ts) => parse_hex_i64(ts)?,
+            None => 0,
+        };
+
+        Ok(Some(Erc20TransferInsert {
+            token_name: token,
+            chain: self.chain,
+            from_address,
+            to_address,
+            txn_hash: log.transaction_hash,
+            value: parse_token_amount(&log.data, token_decimals(token))?,
+            block_number: parse_hex_i64(&log.block_number)?,
+            block_timestamp,
+        }))
+    }
+
+    /// Store transfers and emit a `MatchTick` for each token with new rows.
+    async fn store(&self, transfers: Vec<Erc20TransferInsert>, match_tx: &MatchTickSender) {
+        let tokens: Vec<StablecoinName> = transfers.iter().map(|t| t.token_name).unique().collect();
+
+        let inserted = match self
+            .processor
+            .process(InsertManyErc20TokenTransfers { transfers })
+            .await
+        {
+            Ok(inserted) => inserted,
+            Err(e) => {
+                error!(chain = ?self.chain, error = %e, "Failed to store subscribed transfers");
+                return;
+            }
+        };
+        if inserted == 0 {
+            return;
+        }
+
+        debug!(chain = ?self.chain, inserted, "Stored transfers from log subscription");
+        for token in tokens {
+            let tick = MatchTick {
+                blockchain: BlockchainTarget::Erc20(self.chain),
+                token,
+                transfers_synced: inserted as u32,
+            };
+            if let Err(e) = match_tx.send(tick).await {
+                error!(chain = ?self.chain, error = %e, "Failed to send MatchTick");
+            }
+        }
+    }
+}
+
+/// Ask every polling runner on the chain to sync now.
+async fn request_backfill(
+    chain: EtherScanChain,
+    backfill_txs: &[(StablecoinName, PoolingTickSender)],
+) {
+    for (token, tick_tx) in backfill_txs {
+        let tick = PoolingTick {
+            blockchain: BlockchainTarget::Erc20(chain),
+            token: *token,
+        };
+        if let Err(e) = tick_tx.send(tick).await {
+            warn!(?chain, ?token, error = %e, "Failed to request back-fill");
+        }
+    }
+}
+
+/// Decimals of the supported ERC-20 stablecoins (identical on every chain we support).
+fn token_decimals(token: StablecoinName) -> u32 {
+    match token {
+        StablecoinName::Usdt | StablecoinName::Usdc => 6,
+        StablecoinName::Dai => 18,
+    }
+}
+
+/// Left-pad an address to a 32-byte topic.
+fn address_to_topic(address: &str) -> String {
+    format!("0x{:0>64}", address.trim_start_matches("0x").to_lowercase())
+}
+
+/// Extract the lowercased address from a 32-byte topic.
+fn topic_to_address(topic: &str) -> Option<String> {
+    let hex = topic.trim_start_matches("0x");
+    (hex.len() == 64).then(|| format!("0x{}", hex[24..].to_lowercase()))
+}
+
+fn parse_hex_i64(value: &str) -> Result<i64, SyncError> {
+    i64::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map_err(|e| SyncError::Parse(format!("Invalid hex quantity {}: {}", value, e)))
+}
+
+/// Parse a `uint256` log data word into a token amount.
+fn parse_token_amount(data: &str, decimals: u32) -> Result<Decimal, SyncError> {
+    let hex = data.trim_start_matches("0x").trim_start_matches('0');
+    let raw = if hex.is_empty() {
+        0
+    } else {
+        i128::from_str_radix(hex, 16)
+            .map_err(|e| SyncError::Parse(format!("Invalid transfer value: {}", e)))?
+    };
+    Decimal::try_from_i128_with_scale(raw, decimals)
+        .map_err(|e| SyncError::Parse(format!("Invalid transfer value: {}", e)))
+}
+
+/// A JSON-RPC response or subscription notification.
+#[derive(Debug, Deserialize)]
+struct RpcMessage {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    result: serde_json::Value,
+    #[serde(default)]
+    error: Option<RpcError>,
+    #[serde(default)]
+    params: Option<RpcSubscriptionParams>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcSubscriptionParams {
+    result: RpcLog,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcLog {
+    address: String,
+    topics: Vec<String>,
+    data: String,
+    block_number: String,
+    transaction_hash: String,
+    #[serde(default)]
+    block_timestamp: Option<String>,
+    #[serde(default)]
+    removed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcBlock {
+    timestamp: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_address_round_trip() {
+        let address = "0xAbCdEf0123456789abcdef0123456789ABCDEF01";
+        let topic = address_to_topic(address);
+        assert_eq!(topic.len(), 66);
+        assert_eq!(
+            topic_to_address(&topic).as_deref(),
+            Some("0xabcdef0123456789abcdef0123456789abcdef01")
+        );
+    }
+
+    #[test]
+    fn test_parse_token_amount() {
+        let data = "0x00000000000000000000000000000000000000000000000000000000004c4b40";
+        assert_eq!(parse_token_amount(data, 6).unwrap(), Decimal::new(5, 0));
+        assert!(parse_token_amount(&format!("0x{}", "f".repeat(64)), 6).is_err());
+    }
+}
@@ -4,15 +4,23 @@
 //!
 //! - `PoolingManager`: Receives `PendingDepositChanged`, emits `PoolingTick`
 //! - `BlockchainSync`: Receives `PoolingTick`, emits `MatchTick`
+//! - `LogSubscriber`: Streams EVM `Transfer` logs over WebSocket, emits `MatchTick`
 //! - `OrderBookWatcher`: Receives `MatchTick`, emits `WebhookEvent`
 //! - `WebhookSender`: Receives `WebhookEvent`, delivers webhooks
 
 pub mod blockchain_sync;
+pub mod log_subscriber;
 pub mod order_watcher;
 pub mod pooling_manager;
+pub mod sync_health;
+pub mod webhook_hosts;
 pub mod webhook_sender;
 
-pub use blockchain_sync::{BlockchainSync, Erc20BlockchainSync, SyncError, Trc20BlockchainSync};
+pub use blockchain_sync::{
+    BlockchainSync, Erc20BlockchainSync, ResyncRange, SyncError, SyncOutcome, Trc20BlockchainSync,
+};
+pub use log_subscriber::{LogSubscriber, LogWatch};
 pub use order_watcher::OrderBookWatcher;
 pub use pooling_manager::{PoolingKey, PoolingManager, PoolingManagerConfig};
+pub use sync_health::{SyncHealth, SyncHealthRegistry};
 pub use webhook_sender::WebhookSender;
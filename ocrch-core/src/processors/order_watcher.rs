@@ -7,22 +7,28 @@
 //! - Matching transfers to deposits by wallet address and amount
 //! - Updating transfer status to `Matched` and linking `fulfillment_id`
 //! - Emitting `WebhookEvent::OrderStatusChanged` for successful matches
+//! - Pairing not-yet-confirmed transfers with the remaining deposits and
+//!   emitting `WebhookEvent::PaymentDetected` when that state changes
 
+use crate::config::{ChainConfig, ConfigStore, required_confirmations_for};
 use crate::entities::StablecoinName;
 use crate::entities::erc20_pending_deposit::{
     Erc20PendingDepositMatch, EtherScanChain, GetErc20DepositsForMatching,
+    MarkErc20DepositsDetected,
 };
 use crate::entities::erc20_transfer::{
-    Erc20UnmatchedTransfer, GetErc20TokenTransfersUnmatched, GetOldUnmatchedErc20TransferIds,
-    HandleErc20MatchedTrans, MarkErc20TransfersNoMatchedDeposit,
+    Erc20UnconfirmedTransfer, Erc20UnmatchedTransfer, GetErc20TokenTransfersUnconfirmed,
+    GetErc20TokenTransfersUnmatched, GetOldUnmatchedErc20TransferIds, HandleErc20MatchedTrans,
+    MarkErc20TransfersNoMatchedDeposit,
 };
 use crate::entities::order_records::OrderStatus;
 use crate::entities::trc20_pending_deposit::{
-    GetTrc20DepositsForMatching, Trc20PendingDepositMatch,
+    GetTrc20DepositsForMatching, MarkTrc20DepositsDetected, Trc20PendingDepositMatch,
 };
 use crate::entities::trc20_transfer::{
-    GetOldUnmatchedTrc20TransferIds, GetTrc20TokenTransfersUnmatched, HandleTrc20MatchedTrans,
-    MarkTrc20TransfersNoMatchedDeposit, Trc20UnmatchedTransfer,
+    GetOldUnmatchedTrc20TransferIds, GetTrc20TokenTransfersUnconfirmed,
+    GetTrc20TokenTransfersUnmatched, HandleTrc20MatchedTrans, MarkTrc20TransfersNoMatchedDeposit,
+    Trc20UnconfirmedTransfer, Trc20UnmatchedTransfer,
 };
 use crate::events::{
     BlockchainTarget, MatchTick, MatchTickReceiver, WebhookEvent, WebhookEventSender,
@@ -104,6 +110,50 @@ impl From<Trc20UnmatchedTransfer> for UnmatchedTransfer {
     }
 }
 
+/// A seen-but-unconfirmed transfer, with the details needed for detection events.
+#[derive(Debug)]
+struct UnconfirmedTransfer {
+    id: i64,
+    to_address: String,
+    value: Decimal,
+    txn_hash: String,
+    confirmations: i32,
+}
+
+impl From<Erc20UnconfirmedTransfer> for UnconfirmedTransfer {
+    fn from(t: Erc20UnconfirmedTransfer) -> Self {
+        Self {
+            id: t.id,
+            to_address: t.to_address,
+            value: t.value,
+            txn_hash: t.txn_hash,
+            confirmations: t.confirmations,
+        }
+    }
+}
+
+impl From<Trc20UnconfirmedTransfer> for UnconfirmedTransfer {
+    fn from(t: Trc20UnconfirmedTransfer) -> Self {
+        Self {
+            id: t.id,
+            to_address: t.to_address,
+            value: t.value,
+            txn_hash: t.txn_hash,
+            confirmations: t.confirmations,
+        }
+    }
+}
+
+/// Transfers paired with deposits for detection, split into parallel columns.
+#[derive(Debug, Default)]
+struct Detections {
+    deposit_ids: Vec<i64>,
+    transfer_ids: Vec<i64>,
+    confirmations: Vec<i32>,
+    order_ids: Vec<Uuid>,
+    txn_hashes: Vec<String>,
+}
+
 #[derive(Debug)]
 struct MatchResult {
     transfer_id: i64,
@@ -114,6 +164,8 @@ struct MatchResult {
 /// OrderBookWatcher handles matching pending deposits to blockchain transfers.
 pub struct OrderBookWatcher {
     pub processor: DatabaseProcessor,
+    /// Per-chain settings, used for the required confirmation depth.
+    pub chains: ConfigStore<Vec<ChainConfig>>,
 }
 
 impl OrderBookWatcher {
@@ -242,6 +294,164 @@ impl OrderBookWatcher {
         }
         (matched, left_only, right_only)
     }
+
+    /// Pair unconfirmed transfers with the deposits left over after matching.
+    fn pair_detections(
+        unconfirmed: Vec<UnconfirmedTransfer>,
+        deposits: Vec<PendingDepositMatch>,
+    ) -> Detections {
+        let mut details: Vec<(i64, String, i32)> = Vec::with_capacity(unconfirmed.len());
+        let transfers = unconfirmed
+            .into_iter()
+            .map(|t| {
+                details.push((t.id, t.txn_hash, t.confirmations));
+                UnmatchedTransfer {
+                    id: t.id,
+                    to_address: t.to_address,
+                    value: t.value,
+                }
+            })
+            .collect();
+        let (matches, _, _) = Self::compute_matches(transfers, deposits);
+
+        let mut detections = Detections::default();
+        for m in matches {
+            let Some((_, txn_hash, confirmations)) =
+                details.iter().find(|(id, _, _)| *id == m.transfer_id)
+            else {
+                continue;
+            };
+            detections.deposit_ids.push(m.deposit_id);
+            detections.transfer_ids.push(m.transfer_id);
+            detections.confirmations.push(*confirmations);
+            detections.order_ids.push(m.order_id);
+            detections.txn_hashes.push(txn_hash.clone());
+        }
+        detections
+    }
+
+    /// Turn the deposits whose detection state changed into events.
+    fn detection_events(
+        blockchain: BlockchainTarget,
+        required_confirmations: u32,
+        detections: Detections,
+        changed: Vec<(i64, bool)>,
+    ) -> Vec<WebhookEvent> {
+        changed
+            .into_iter()
+            .filter_map(|(deposit_id, newly_detected)| {
+                let i = detections
+                    .deposit_ids
+                    .iter()
+                    .position(|id| *id == deposit_id)?;
+                info!(
+                    blockchain = %blockchain,
+                    order_id = %detections.order_ids[i],
+                    txn_hash = %detections.txn_hashes[i],
+                    confirmations = detections.confirmations[i],
+                    required_confirmations,
+                    "Detected unconfirmed payment"
+                );
+                Some(WebhookEvent::PaymentDetected {
+                    order_id: detections.order_ids[i],
+                    blockchain,
+                    txn_hash: detections.txn_hashes[i].clone(),
+                    confirmations: detections.confirmations[i].max(0) as u32,
+                    required_confirmations,
+                    newly_detected,
+                })
+            })
+            .collect()
+    }
+
+    /// Record unconfirmed ERC-20 transfers seen for the given deposits.
+    async fn detect_erc20(
+        &self,
+        chain: EtherScanChain,
+        token: StablecoinName,
+        deposits: Vec<PendingDepositMatch>,
+    ) -> Result<Vec<WebhookEvent>, MatchError> {
+        if deposits.is_empty() {
+            return Ok(Vec::new());
+        }
+        let unconfirmed: Vec<UnconfirmedTransfer> = self
+            .processor
+            .process(GetErc20TokenTransfersUnconfirmed { chain, token })
+            .await?
+            .into_iter()
+            .map(UnconfirmedTransfer::from)
+            .collect();
+        if unconfirmed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let blockchain = BlockchainTarget::Erc20(chain);
+        let required_confirmations =
+            required_confirmations_for(&self.chains.read().await, blockchain.into());
+        let detections = Self::pair_detections(unconfirmed, deposits);
+        let changed = self
+            .processor
+            .process(MarkErc20DepositsDetected {
+                deposit_ids: detections.deposit_ids.clone(),
+                transfer_ids: detections.transfer_ids.clone(),
+                confirmations: detections.confirmations.clone(),
+                required_confirmations: i32::try_from(required_confirmations).unwrap_or(i32::MAX),
+            })
+            .await?
+            .into_iter()
+            .map(|d| (d.id, d.newly_detected))
+            .collect();
+        Ok(Self::detection_events(
+            blockchain,
+            required_confirmations,
+            detections,
+            changed,
+        ))
+    }
+
+    /// Record unconfirmed TRC-20 transfers seen for the given deposits.
+    async fn detect_trc20(
+        &self,
+        token: StablecoinName,
+        deposits: Vec<PendingDepositMatch>,
+    ) -> Result<Vec<WebhookEvent>, MatchError> {
+        if deposits.is_empty() {
+            return Ok(Vec::new());
+        }
+        let unconfirmed: Vec<UnconfirmedTransfer> = self
+            .processor
+            .process(GetTrc20TokenTransfersUnconfirmed { token })
+            .await?
+            .into_iter()
+            .map(UnconfirmedTransfer::from)
+            .collect();
+        if unconfirmed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let blockchain = BlockchainTarget::Trc20;
+        let required_confirmations =
+            required_confirmations_for(&self.chains.read().await, blockchain.into());
+        let detections = Self::pair_detections(unconfirmed, deposits);
+        let changed = self
+            .processor
+            .process(MarkTrc20DepositsDetected {
+                deposit_ids: detections.deposit_ids.clone(),
+                transfer_ids: detections.transfer_ids.clone(),
+                confirmations: detections.confirmations.clone(),
+                required_confirmations: i32::try_from(required_confirmations).unwrap_or(i32::MAX),
+            })
+            .await?
+            .into_iter()
+            .map(|d| (d.id, d.newly_detected))
+            .collect();
+        Ok(Self::detection_events(
+            blockchain,
+            required_confirmations,
+            detections,
+            changed,
+        ))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -290,7 +500,7 @@ impl Processor<Erc20Matching> for OrderBookWatcher {
                 token = ?token,
                 "No unmatched ERC-20 transfers"
             );
-            return Ok(Vec::new());
+            return self.detect_erc20(chain, token, deposits).await;
         }
 
         info!(
@@ -300,7 +510,7 @@ impl Processor<Erc20Matching> for OrderBookWatcher {
             transfers = transfers.len(),
             "Attempting to match ERC-20 transfers"
         );
-        let (matches, _, _) = Self::compute_matches(transfers, deposits);
+        let (matches, _, remaining_deposits) = Self::compute_matches(transfers, deposits);
 
         // Check for unknown transfers (transfers older than 1 hour with no matched deposit)
         let old_transfers = self
@@ -343,7 +553,7 @@ impl Processor<Erc20Matching> for OrderBookWatcher {
                 .multiunzip();
 
             // Execute all matches in a single transaction — O(1) DB operations
-            // Exactly 4 SQL statements regardless of the number of matches.
+            // Exactly 5 SQL statements regardless of the number of matches.
             self.processor
                 .process(HandleErc20MatchedTrans {
                     transfer_ids,
@@ -359,6 +569,8 @@ impl Processor<Erc20Matching> for OrderBookWatcher {
                 });
             events.extend(mapped);
         }
+
+        events.extend(self.detect_erc20(chain, token, remaining_deposits).await?);
         Ok(events)
     }
 }
@@ -400,7 +612,7 @@ impl Processor<Trc20Matching> for OrderBookWatcher {
 
         if transfers.is_empty() {
             debug!(token = ?token, "No unmatched TRC-20 transfers");
-            return Ok(Vec::new());
+            return self.detect_trc20(token, deposits).await;
         }
 
         debug!(
@@ -409,7 +621,7 @@ impl Processor<Trc20Matching> for OrderBookWatcher {
             transfers = transfers.len(),
             "Attempting to match TRC-20 transfers"
         );
-        let (matches, _, _) = Self::compute_matches(transfers, deposits);
+        let (matches, _, remaining_deposits) = Self::compute_matches(transfers, deposits);
 
         // Check for unknown transfers (transfers older than 1 hour with no matched deposit)
         let old_transfers = self
@@ -449,7 +661,7 @@ impl Processor<Trc20Matching> for OrderBookWatcher {
                 .multiunzip();
 
             // Execute all matches in a single transaction — O(1) DB operations
-            // Exactly 4 SQL statements regardless of the number of matches.
+            // Exactly 5 SQL statements regardless of the number of matches.
             self.processor
                 .process(HandleTrc20MatchedTrans {
                     transfer_ids,
@@ -466,6 +678,8 @@ impl Processor<Trc20Matching> for OrderBookWatcher {
                 });
             events.extend(mapped);
         }
+
+        events.extend(self.detect_trc20(token, remaining_deposits).await?);
         Ok(events)
     }
 }
@@ -3,22 +3,30 @@
 //! The PoolingManager is responsible for:
 //! - Receiving `PendingDepositChanged` events via the `Processor` trait
 //! - Tracking the latest pending deposit timestamp per (blockchain, token) pair
+//! - Tracking how many live pending deposits each pair has, seeded from the
+//!   database on startup and refreshed periodically
 //! - Emitting `PoolingTick` events on a calculated schedule
 //! - Reacting to config changes by diffing active tasks (spawning/aborting
 //!   only the tasks that actually changed)
 //!
 //! The pooling frequency is calculated based on how recently there was activity,
-//! using the `pooling_freq` function from the utils module.
+//! using the `pooling_freq` function from the utils module and the
+//! [`PoolingPolicy`] configured for the chain. Pairs with no live pending
+//! deposits and no recent activity drop to the policy's heartbeat interval.
 
 use crate::config::{ConfigStore, ConfigWatcher};
 use crate::entities::StablecoinName;
+use crate::entities::erc20_pending_deposit::CountErc20LivePendingDeposits;
+use crate::entities::trc20_pending_deposit::CountTrc20LivePendingDeposits;
 use crate::events::{
     BlockchainTarget, PendingDepositChanged, PendingDepositChangedReceiver, PoolingTick,
     PoolingTickSender,
 };
-use crate::utils::pooling_interval::pooling_freq;
+use crate::framework::DatabaseProcessor;
+use crate::utils::pooling_interval::{PoolingPolicy, pooling_freq};
 use kanau::processor::Processor;
 use std::convert::Infallible;
+use std::time::Duration;
 use tokio::sync::{broadcast, watch};
 use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
@@ -54,6 +62,42 @@ pub struct PoolingManagerConfig {
     /// Stored as a `Vec` because the number of pairs is very small,
     /// making linear scans faster than hash lookups.
     pub tick_senders: Vec<(PoolingKey, PoolingTickSender)>,
+    /// Per-chain pooling curves. Chains without an entry use
+    /// [`PoolingPolicy::default()`].
+    pub policies: Vec<(BlockchainTarget, PoolingPolicy)>,
+}
+
+impl PoolingManagerConfig {
+    /// Pooling curve for the given chain.
+    pub fn policy_for(&self, blockchain: BlockchainTarget) -> PoolingPolicy {
+        self.policies
+            .iter()
+            .find(|(target, _)| *target == blockchain)
+            .map(|(_, policy)| policy.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Live pending deposits for a single blockchain-token pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LivePending {
+    count: i64,
+    latest_started_at: time::PrimitiveDateTime,
+}
+
+/// How often live pending deposit counts are re-read from the database.
+///
+/// Deposits leave the pending set when their order is paid, which the
+/// PoolingManager is not told about, so counts are reconciled on a timer.
+const LIVE_PENDING_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A running tick loop together with what it was spawned with, so config
+/// diffs can tell whether it needs to be restarted.
+struct ActiveTickLoop {
+    key: PoolingKey,
+    sender: PoolingTickSender,
+    policy: PoolingPolicy,
+    handle: JoinHandle<()>,
 }
 
 // ---------------------------------------------------------------------------
@@ -71,24 +115,35 @@ pub struct PoolingManagerConfig {
 pub struct PoolingManager {
     /// Broadcast channel for notifying tick loops of timestamp updates.
     update_tx: broadcast::Sender<(PoolingKey, time::PrimitiveDateTime)>,
+    /// Live pending deposits per pair, watched by the tick loops.
+    live_tx: watch::Sender<Vec<(PoolingKey, LivePending)>>,
+    /// Database access for counting live pending deposits.
+    processor: DatabaseProcessor,
 }
 
 impl PoolingManager {
     /// Create a new PoolingManager.
-    pub fn new() -> Self {
+    pub fn new(processor: DatabaseProcessor) -> Self {
         let (update_tx, _) = broadcast::channel(64);
-        Self { update_tx }
+        let (live_tx, _) = watch::channel(Vec::new());
+        Self {
+            update_tx,
+            live_tx,
+            processor,
+        }
     }
 
     /// Run the PoolingManager until shutdown is signaled.
     ///
     /// This method:
-    /// 1. Reads the initial config and spawns tick-loop tasks
+    /// 1. Seeds live pending deposit counts from the database, reads the
+    ///    initial config and spawns tick-loop tasks
     /// 2. Listens for `PendingDepositChanged` events and broadcasts
     ///    timestamp updates to the tick loops
     /// 3. Reacts to config changes by diffing and spawning/aborting
     ///    only the tasks that actually changed
-    /// 4. Shuts down gracefully when the shutdown signal fires
+    /// 4. Periodically re-reads live pending deposit counts
+    /// 5. Shuts down gracefully when the shutdown signal fires
     pub async fn run(
         self,
         mut shutdown_rx: watch::Receiver<bool>,
@@ -96,13 +151,25 @@ impl PoolingManager {
         config_store: ConfigStore<PoolingManagerConfig>,
         mut config_watcher: ConfigWatcher,
     ) {
-        // -- Bootstrap from initial config ----------------------------------
-        let mut active_tasks: Vec<(PoolingKey, JoinHandle<()>)> = Vec::new();
+        // -- Bootstrap from the database and initial config -----------------
+        self.refresh_live_pending().await;
+        let mut refresh = tokio::time::interval_at(
+            tokio::time::Instant::now() + LIVE_PENDING_REFRESH_INTERVAL,
+            LIVE_PENDING_REFRESH_INTERVAL,
+        );
+
+        let mut active_tasks: Vec<ActiveTickLoop> = Vec::new();
         {
             let config = config_store.read().await;
             for (key, sender) in &config.tick_senders {
-                let handle = self.spawn_tick_loop(*key, sender.clone());
-                active_tasks.push((*key, handle));
+                let policy = config.policy_for(key.blockchain);
+                let handle = self.spawn_tick_loop(*key, sender.clone(), policy.clone());
+                active_tasks.push(ActiveTickLoop {
+                    key: *key,
+                    sender: sender.clone(),
+                    policy,
+                    handle,
+                });
             }
             info!(
                 "PoolingManager started with {} blockchain-token pairs",
@@ -138,6 +205,11 @@ impl PoolingManager {
                     let _ = self.process(event).await;
                 }
 
+                // Reconcile live pending deposit counts with the database.
+                _ = refresh.tick() => {
+                    self.refresh_live_pending().await;
+                }
+
                 // All senders dropped.
                 else => {
                     info!("PendingDepositChanged channel closed");
@@ -147,8 +219,8 @@ impl PoolingManager {
         }
 
         // -- Cleanup --------------------------------------------------------
-        for (_, handle) in active_tasks {
-            handle.abort();
+        for task in active_tasks {
+            task.handle.abort();
         }
 
         info!("PoolingManager shutdown complete");
@@ -156,30 +228,105 @@ impl PoolingManager {
 
     // -- Private helpers ----------------------------------------------------
 
+    /// Re-read live pending deposit counts from the database.
+    ///
+    /// Tick loops are only woken if the counts actually changed. On failure
+    /// the previous counts are kept.
+    async fn refresh_live_pending(&self) {
+        let erc20 = match self.processor.process(CountErc20LivePendingDeposits).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                warn!(error = %e, "Failed to count live ERC-20 pending deposits");
+                return;
+            }
+        };
+        let trc20 = match self.processor.process(CountTrc20LivePendingDeposits).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                warn!(error = %e, "Failed to count live TRC-20 pending deposits");
+                return;
+            }
+        };
+
+        let live: Vec<(PoolingKey, LivePending)> = erc20
+            .into_iter()
+            .map(|row| {
+                (
+                    PoolingKey::new(BlockchainTarget::Erc20(row.chain), row.token_name),
+                    LivePending {
+                        count: row.count,
+                        latest_started_at: row.latest_started_at,
+                    },
+                )
+            })
+            .chain(trc20.into_iter().map(|row| {
+                (
+                    PoolingKey::new(BlockchainTarget::Trc20, row.token_name),
+                    LivePending {
+                        count: row.count,
+                        latest_started_at: row.latest_started_at,
+                    },
+                )
+            }))
+            .collect();
+
+        self.live_tx.send_if_modified(|current| {
+            let unchanged =
+                current.len() == live.len() && live.iter().all(|entry| current.contains(entry));
+            if !unchanged {
+                debug!(pairs = live.len(), "Live pending deposits changed");
+                *current = live;
+            }
+            !unchanged
+        });
+    }
+
     /// Diff `active` tasks against `new_config` and reconcile:
-    /// - Abort tasks whose keys are absent from the new config.
-    /// - Spawn tasks for keys present in the new config but not yet active.
-    fn apply_diff(
-        &self,
-        active: &mut Vec<(PoolingKey, JoinHandle<()>)>,
-        new_config: &PoolingManagerConfig,
-    ) {
-        // 1. Remove tasks whose keys are absent from new config.
-        active.retain(|(key, handle)| {
-            let keep = new_config.tick_senders.iter().any(|(k, _)| k == key);
+    /// - Abort tasks whose (key, sender) pair is absent from the new config.
+    ///   A sync runner that was respawned gets a new channel, so its old
+    ///   tick loop is replaced rather than left sending into a dead sender.
+    /// - Restart tasks whose pooling policy changed, keeping their sender.
+    /// - Spawn tasks for (key, sender) pairs not yet active.
+    fn apply_diff(&self, active: &mut Vec<ActiveTickLoop>, new_config: &PoolingManagerConfig) {
+        // 1. Remove tasks whose (key, sender) pairs are absent from new config.
+        active.retain(|task| {
+            let keep = new_config
+                .tick_senders
+                .iter()
+                .any(|(k, sender)| *k == task.key && sender.same_channel(&task.sender));
             if !keep {
-                info!(blockchain = %key.blockchain, token = ?key.token, "Aborting removed tick loop");
-                handle.abort();
+                info!(blockchain = %task.key.blockchain, token = ?task.key.token, "Aborting removed tick loop");
+                task.handle.abort();
             }
             keep
         });
 
-        // 2. Spawn tasks for newly added keys.
+        // 2. Restart tasks whose pooling policy changed.
+        for task in active.iter_mut() {
+            let policy = new_config.policy_for(task.key.blockchain);
+            if policy != task.policy {
+                info!(blockchain = %task.key.blockchain, token = ?task.key.token, "Pooling policy changed, restarting tick loop");
+                task.handle.abort();
+                task.handle = self.spawn_tick_loop(task.key, task.sender.clone(), policy.clone());
+                task.policy = policy;
+            }
+        }
+
+        // 3. Spawn tasks for newly added (key, sender) pairs.
         for (key, sender) in &new_config.tick_senders {
-            if !active.iter().any(|(k, _)| k == key) {
+            if !active
+                .iter()
+                .any(|task| task.key == *key && task.sender.same_channel(sender))
+            {
                 info!(blockchain = %key.blockchain, token = ?key.token, "Spawning new tick loop");
-                let handle = self.spawn_tick_loop(*key, sender.clone());
-                active.push((*key, handle));
+                let policy = new_config.policy_for(key.blockchain);
+                let handle = self.spawn_tick_loop(*key, sender.clone(), policy.clone());
+                active.push(ActiveTickLoop {
+                    key: *key,
+                    sender: sender.clone(),
+                    policy,
+                    handle,
+                });
             }
         }
     }
@@ -187,23 +334,47 @@ impl PoolingManager {
     /// Spawn a tick-loop task for a single blockchain-token pair.
     ///
     /// The spawned task runs an adaptive-interval loop using [`pooling_freq`]:
-    /// it sleeps for the calculated interval, emits a `PoolingTick`, then
-    /// recalculates. If a timestamp update arrives via the broadcast channel,
-    /// the interval is recalculated immediately.
-    fn spawn_tick_loop(&self, key: PoolingKey, tick_sender: PoolingTickSender) -> JoinHandle<()> {
+    /// it waits for the calculated interval since the last tick, emits a
+    /// `PoolingTick`, then recalculates. While the pair has no live pending
+    /// deposits and no recent activity, the policy's heartbeat interval is
+    /// used instead. Timestamp updates and live-count changes cause the
+    /// interval to be recalculated immediately.
+    fn spawn_tick_loop(
+        &self,
+        key: PoolingKey,
+        tick_sender: PoolingTickSender,
+        policy: PoolingPolicy,
+    ) -> JoinHandle<()> {
         let mut update_rx = self.update_tx.subscribe();
+        let mut live_rx = self.live_tx.subscribe();
         let blockchain = key.blockchain;
         let token = key.token;
 
         tokio::spawn(async move {
             let mut last_pending_at = time::PrimitiveDateTime::MIN;
+            let mut last_tick_at = tokio::time::Instant::now();
 
             loop {
+                let live = live_rx
+                    .borrow_and_update()
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, live)| *live);
+                if let Some(live) = live {
+                    last_pending_at = last_pending_at.max(live.latest_started_at);
+                }
+
                 let now = time::OffsetDateTime::now_utc();
                 let now = time::PrimitiveDateTime::new(now.date(), now.time());
-                let next_interval = pooling_freq(last_pending_at, now);
-                let sleep_duration =
-                    std::time::Duration::from_secs(next_interval.whole_seconds() as u64);
+                let has_pending = live.is_some_and(|live| live.count > 0)
+                    || now - last_pending_at < policy.idle_after;
+                let next_interval = if has_pending {
+                    pooling_freq(&policy, last_pending_at, now)
+                } else {
+                    policy.heartbeat_interval
+                };
+                let deadline =
+                    last_tick_at + Duration::from_secs(next_interval.whole_seconds().max(0) as u64);
 
                 debug!(
                     %blockchain,
@@ -211,6 +382,7 @@ impl PoolingManager {
                     %last_pending_at,
                     %now,
                     %next_interval,
+                    has_pending,
                     "Calculating next pooling interval"
                 );
 
@@ -226,13 +398,15 @@ impl PoolingManager {
                                 ?token,
                                 "Updated last_pending_at, recalculating interval"
                             );
-                            // Skip sleep and immediately recalculate.
-                            continue;
                         }
                     }
 
+                    // Live pending deposit counts changed — recalculate.
+                    Ok(()) = live_rx.changed() => {}
+
                     // Interval elapsed — emit a tick.
-                    _ = tokio::time::sleep(sleep_duration) => {
+                    _ = tokio::time::sleep_until(deadline) => {
+                        last_tick_at = tokio::time::Instant::now();
                         let tick = PoolingTick { blockchain, token };
                         if let Err(e) = tick_sender.send(tick).await {
                             warn!(
@@ -251,12 +425,6 @@ impl PoolingManager {
     }
 }
 
-impl Default for PoolingManager {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 // ---------------------------------------------------------------------------
 // Processor trait implementation
 // ---------------------------------------------------------------------------
@@ -276,6 +444,23 @@ impl Processor<PendingDepositChanged> for PoolingManager {
             "Received PendingDepositChanged, broadcasting timestamp update"
         );
 
+        // A new pending deposit was just created, so the pair is live until
+        // the next database refresh says otherwise.
+        self.live_tx
+            .send_modify(|live| match live.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, entry)) => {
+                    entry.count += 1;
+                    entry.latest_started_at = entry.latest_started_at.max(now);
+                }
+                None => live.push((
+                    key,
+                    LivePending {
+                        count: 1,
+                        latest_started_at: now,
+                    },
+                )),
+            });
+
         // Broadcast to tick loops; if nobody is listening yet that is fine.
         let _ = self.update_tx.send((key, now));
         Ok(())
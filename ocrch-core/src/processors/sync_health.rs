@@ -0,0 +1,248 @@
+//! Sync health tracking.
+//!
+//! Each `BlockchainSyncRunner` records the outcome of every sync in a shared
+//! [`SyncHealthRegistry`], keyed by (blockchain, token). The registry is read
+//! by the Admin API to report sync status and by the User API to hide chains
+//! that cannot currently be watched.
+
+use crate::config::SyncHealthConfig;
+use crate::processors::PoolingKey;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::sync::RwLock;
+
+/// Health snapshot of the sync for a single (blockchain, token) pair.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncHealth {
+    /// When the last sync attempt finished.
+    pub last_attempt_at: Option<OffsetDateTime>,
+    /// When the last successful sync finished.
+    pub last_success_at: Option<OffsetDateTime>,
+    /// Number of failed syncs since the last success.
+    pub consecutive_failures: u32,
+    /// Error message of the most recent failure, cleared on success.
+    pub last_error: Option<String>,
+    /// Highest block up to which every transfer has been synced.
+    pub latest_block: Option<i64>,
+    /// Most recently observed chain head.
+    pub chain_head: Option<i64>,
+}
+
+impl SyncHealth {
+    /// Number of blocks between the chain head and the latest block synced.
+    pub fn lag(&self) -> Option<i64> {
+        Some((self.chain_head? - self.latest_block?).max(0))
+    }
+
+    /// Whether the pair is healthy under the given thresholds.
+    ///
+    /// An unknown lag never makes a pair unhealthy.
+    pub fn is_healthy(&self, config: &SyncHealthConfig) -> bool {
+        let lagging = match (config.max_lag_blocks, self.lag()) {
+            (Some(max), Some(lag)) => lag > i64::from(max),
+            _ => false,
+        };
+        self.consecutive_failures < config.unhealthy_after_failures && !lagging
+    }
+}
+
+/// Shared, cheaply cloneable registry of [`SyncHealth`] per (blockchain, token).
+///
+/// Stored as a `Vec` because the number of pairs is very small, matching
+/// [`PoolingKey`] usage elsewhere.
+#[derive(Debug, Clone, Default)]
+pub struct SyncHealthRegistry {
+    inner: Arc<RwLock<Vec<(PoolingKey, SyncHealth)>>>,
+}
+
+impl SyncHealthRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a successful sync.
+    ///
+    /// `synced_block` is the highest block up to which the sync has seen
+    /// every transfer, and `chain_head` the head observed alongside it. Either
+    /// is left unchanged when unknown.
+    pub async fn record_success(
+        &self,
+        key: PoolingKey,
+        synced_block: Option<i64>,
+        chain_head: Option<i64>,
+    ) {
+        let now = OffsetDateTime::now_utc();
+        self.update(key, |health| {
+            health.last_attempt_at = Some(now);
+            health.last_success_at = Some(now);
+            health.consecutive_failures = 0;
+            health.last_error = None;
+            if synced_block.is_some() {
+                health.latest_block = health.latest_block.max(synced_block);
+            }
+            if chain_head.is_some() {
+                health.chain_head = chain_head;
+            }
+        })
+        .await;
+    }
+
+    /// Record a failed sync.
+    ///
+    /// `chain_head` is recorded if it could still be fetched, so the lag keeps
+    /// growing while the sync itself is failing.
+    pub async fn record_failure(&self, key: PoolingKey, error: String, chain_head: Option<i64>) {
+        let now = OffsetDateTime::now_utc();
+        self.update(key, |health| {
+            health.last_attempt_at = Some(now);
+            health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+            health.last_error = Some(error);
+            if chain_head.is_some() {
+                health.chain_head = chain_head;
+            }
+        })
+        .await;
+    }
+
+    /// Get the health of a single pair, if it has been synced at least once.
+    pub async fn get(&self, key: PoolingKey) -> Option<SyncHealth> {
+        let inner = self.inner.read().await;
+        inner
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, health)| health.clone())
+    }
+
+    /// Snapshot the health of every pair.
+    pub async fn snapshot(&self) -> Vec<(PoolingKey, SyncHealth)> {
+        self.inner.read().await.clone()
+    }
+
+    /// Forget a pair, e.g. after its last wallet was removed from the config.
+    pub async fn remove(&self, key: PoolingKey) {
+        self.inner.write().await.retain(|(k, _)| *k != key);
+    }
+
+    async fn update(&self, key: PoolingKey, f: impl FnOnce(&mut SyncHealth)) {
+        let mut inner = self.inner.write().await;
+        match inner.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, health)) => f(health),
+            None => {
+                let mut health = SyncHealth::default();
+                f(&mut health);
+                inner.push((key, health));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::StablecoinName;
+    use crate::entities::erc20_pending_deposit::EtherScanChain;
+    use crate::events::BlockchainTarget;
+
+    fn key() -> PoolingKey {
+        PoolingKey::new(
+            BlockchainTarget::Erc20(EtherScanChain::Ethereum),
+            StablecoinName::Usdt,
+        )
+    }
+
+    fn config(max_lag_blocks: Option<u32>) -> SyncHealthConfig {
+        SyncHealthConfig {
+            unhealthy_after_failures: 2,
+            max_lag_blocks,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_success() {
+        let registry = SyncHealthRegistry::new();
+        assert_eq!(registry.get(key()).await, None);
+
+        registry.record_success(key(), Some(90), Some(100)).await;
+        let health = registry.get(key()).await.unwrap();
+        assert!(health.last_attempt_at.is_some());
+        assert_eq!(health.last_success_at, health.last_attempt_at);
+        assert_eq!(health.latest_block, Some(90));
+        assert_eq!(health.chain_head, Some(100));
+        assert_eq!(health.lag(), Some(10));
+
+        // Unknown values keep the previous ones, and the synced block never
+        // moves backwards
+        registry.record_success(key(), None, None).await;
+        registry.record_success(key(), Some(80), Some(110)).await;
+        let health = registry.get(key()).await.unwrap();
+        assert_eq!(health.latest_block, Some(90));
+        assert_eq!(health.chain_head, Some(110));
+        assert_eq!(health.lag(), Some(20));
+    }
+
+    #[tokio::test]
+    async fn test_record_failure() {
+        let registry = SyncHealthRegistry::new();
+        registry.record_success(key(), Some(100), Some(100)).await;
+        registry
+            .record_failure(key(), "rate limited".to_string(), Some(105))
+            .await;
+        registry
+            .record_failure(key(), "timed out".to_string(), None)
+            .await;
+
+        let health = registry.get(key()).await.unwrap();
+        assert_eq!(health.consecutive_failures, 2);
+        assert_eq!(health.last_error.as_deref(), Some("timed out"));
+        assert!(health.last_success_at < health.last_attempt_at);
+        assert_eq!(health.latest_block, Some(100));
+        assert_eq!(health.chain_head, Some(105));
+        assert_eq!(health.lag(), Some(5));
+
+        registry.record_success(key(), Some(105), Some(105)).await;
+        let health = registry.get(key()).await.unwrap();
+        assert_eq!(health.consecutive_failures, 0);
+        assert_eq!(health.last_error, None);
+    }
+
+    #[test]
+    fn test_lag() {
+        let mut health = SyncHealth::default();
+        assert_eq!(health.lag(), None);
+        health.chain_head = Some(100);
+        assert_eq!(health.lag(), None);
+        health.latest_block = Some(97);
+        assert_eq!(health.lag(), Some(3));
+        // Transfers found past the head fetched before the sync
+        health.latest_block = Some(101);
+        assert_eq!(health.lag(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_health_transitions() {
+        let registry = SyncHealthRegistry::new();
+        let healthy = |health: Option<SyncHealth>, config: &SyncHealthConfig| {
+            health.unwrap().is_healthy(config)
+        };
+
+        registry.record_success(key(), Some(100), Some(100)).await;
+        assert!(healthy(registry.get(key()).await, &config(None)));
+
+        // Failures up to the threshold
+        registry.record_failure(key(), "e".to_string(), None).await;
+        assert!(healthy(registry.get(key()).await, &config(None)));
+        registry.record_failure(key(), "e".to_string(), None).await;
+        assert!(!healthy(registry.get(key()).await, &config(None)));
+
+        // A success recovers, but a large lag is unhealthy when configured
+        registry.record_success(key(), Some(100), Some(150)).await;
+        assert!(healthy(registry.get(key()).await, &config(None)));
+        assert!(healthy(registry.get(key()).await, &config(Some(50))));
+        assert!(!healthy(registry.get(key()).await, &config(Some(49))));
+
+        // Catching up recovers
+        registry.record_success(key(), Some(150), Some(150)).await;
+        assert!(healthy(registry.get(key()).await, &config(Some(49))));
+    }
+}
@@ -0,0 +1,292 @@
+//! Per-host admission control for webhook delivery.
+//!
+//! Every destination host gets a small number of concurrent requests and a
+//! circuit breaker. After [`FAILURE_THRESHOLD`] consecutive failures the
+//! circuit opens and deliveries to the host are deferred for [`COOLDOWN`];
+//! then a single probe request is let through, which closes the circuit on
+//! success and reopens it on failure. A probe whose permit is dropped without
+//! an outcome, because the request was cancelled or panicked, lets the next
+//! request probe instead. A slow or failing endpoint thus only holds its own
+//! share of the `WebhookSender`'s workers.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Maximum number of concurrent requests to a single host.
+pub const MAX_CONCURRENT_PER_HOST: usize = 4;
+
+/// Consecutive failures that open a host's circuit.
+pub const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open circuit defers deliveries before probing the host again.
+pub const COOLDOWN: Duration = Duration::from_secs(60);
+
+/// How long a delivery is deferred when its host has no free slot.
+pub const BUSY_RETRY_AFTER: Duration = Duration::from_secs(2);
+
+/// Number of tracked hosts above which idle hosts are forgotten.
+const MAX_IDLE_HOSTS: usize = 1024;
+
+/// Why a request to a host cannot be made now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostUnavailable {
+    /// Every request slot of the host is in use.
+    Busy,
+    /// The host's circuit is open.
+    CircuitOpen,
+}
+
+impl HostUnavailable {
+    /// How long to defer the delivery.
+    pub fn retry_after(self) -> Duration {
+        match self {
+            HostUnavailable::Busy => BUSY_RETRY_AFTER,
+            HostUnavailable::CircuitOpen => COOLDOWN,
+        }
+    }
+}
+
+impl std::fmt::Display for HostUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostUnavailable::Busy => f.write_str("host is busy"),
+            HostUnavailable::CircuitOpen => f.write_str("circuit open"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HostState {
+    slots: Arc<Semaphore>,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// A probe request is in flight after the cooldown.
+    probing: bool,
+}
+
+impl HostState {
+    fn new() -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT_PER_HOST)),
+            consecutive_failures: 0,
+            open_until: None,
+            probing: false,
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.consecutive_failures == 0
+            && self.open_until.is_none()
+            && self.slots.available_permits() == MAX_CONCURRENT_PER_HOST
+    }
+}
+
+/// A request slot of a host, held for the duration of the request.
+///
+/// Report the outcome of the request with [`record`](Self::record).
+#[derive(Debug)]
+pub struct HostPermit<'a> {
+    hosts: &'a WebhookHosts,
+    host: String,
+    /// This is the probe request of an open circuit.
+    probe: bool,
+    _slot: OwnedSemaphorePermit,
+}
+
+impl HostPermit<'_> {
+    /// Record the outcome of the request and release the slot.
+    pub fn record(self, success: bool) {
+        self.record_at(success, Instant::now());
+    }
+
+    fn record_at(mut self, success: bool, now: Instant) {
+        self.hosts.record_at(&self.host, success, self.probe, now);
+        self.probe = false;
+    }
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            // The probe never finished, so let another request probe
+            let mut hosts = self
+                .hosts
+                .hosts
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(state) = hosts.get_mut(&self.host) {
+                state.probing = false;
+            }
+        }
+    }
+}
+
+/// Concurrency limits and circuit breakers of every destination host.
+#[derive(Debug, Default)]
+pub struct WebhookHosts {
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl WebhookHosts {
+    /// Take a request slot of `host`, unless it is busy or its circuit is
+    /// open.
+    pub fn admit(&self, host: &str) -> Result<HostPermit<'_>, HostUnavailable> {
+        self.admit_at(host, Instant::now())
+    }
+
+    fn admit_at(&self, host: &str, now: Instant) -> Result<HostPermit<'_>, HostUnavailable> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+        if hosts.len() > MAX_IDLE_HOSTS {
+            hosts.retain(|_, state| !state.is_idle());
+        }
+        let state = hosts.entry(host.to_string()).or_insert_with(HostState::new);
+
+        let probe = match state.open_until {
+            Some(until) if now < until || state.probing => {
+                return Err(HostUnavailable::CircuitOpen);
+            }
+            Some(_) => true,
+            None => false,
+        };
+        let slot = Arc::clone(&state.slots)
+            .try_acquire_owned()
+            .map_err(|_| HostUnavailable::Busy)?;
+        state.probing = probe;
+        Ok(HostPermit {
+            hosts: self,
+            host: host.to_string(),
+            probe,
+            _slot: slot,
+        })
+    }
+
+    fn record_at(&self, host: &str, success: bool, probe: bool, now: Instant) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(state) = hosts.get_mut(host) else {
+            return;
+        };
+        if success {
+            state.consecutive_failures = 0;
+            state.open_until = None;
+        } else {
+            state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+            if probe || state.consecutive_failures >= FAILURE_THRESHOLD {
+                if state.open_until.is_none_or(|until| until <= now) {
+                    tracing::warn!(
+                        host,
+                        consecutive_failures = state.consecutive_failures,
+                        "Webhook host circuit opened"
+                    );
+                }
+                state.open_until = Some(now + COOLDOWN);
+            }
+        }
+        if probe {
+            state.probing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_concurrency_limit() {
+        let hosts = WebhookHosts::default();
+        let permits: Vec<_> = (0..MAX_CONCURRENT_PER_HOST)
+            .map(|_| hosts.admit("a.example").ok())
+            .collect();
+        assert!(permits.iter().all(Option::is_some));
+        assert_eq!(hosts.admit("a.example").err(), Some(HostUnavailable::Busy));
+        // Other hosts are unaffected
+        assert!(hosts.admit("b.example").is_ok());
+
+        drop(permits);
+        assert!(hosts.admit("a.example").is_ok());
+    }
+
+    /// Fail `host` until its circuit opens.
+    fn open_circuit(hosts: &WebhookHosts, host: &str, now: Instant) {
+        for _ in 0..FAILURE_THRESHOLD {
+            hosts.admit_at(host, now).unwrap().record_at(false, now);
+        }
+        assert_eq!(
+            hosts.admit_at(host, now).err(),
+            Some(HostUnavailable::CircuitOpen)
+        );
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let hosts = WebhookHosts::default();
+        let start = Instant::now();
+        open_circuit(&hosts, "a.example", start);
+
+        // After the cooldown a single probe is let through
+        let later = start + COOLDOWN;
+        let probe = hosts.admit_at("a.example", later).unwrap();
+        assert_eq!(
+            hosts.admit_at("a.example", later).err(),
+            Some(HostUnavailable::CircuitOpen)
+        );
+
+        // A failed probe reopens the circuit
+        probe.record_at(false, later);
+        assert_eq!(
+            hosts.admit_at("a.example", later).err(),
+            Some(HostUnavailable::CircuitOpen)
+        );
+
+        // A successful probe closes it
+        let much_later = later + COOLDOWN;
+        let probe = hosts.admit_at("a.example", much_later).unwrap();
+        probe.record_at(true, much_later);
+        assert!(hosts.admit_at("a.example", much_later).is_ok());
+        assert!(hosts.admit_at("a.example", much_later).is_ok());
+    }
+
+    #[test]
+    fn test_abandoned_probe() {
+        let hosts = WebhookHosts::default();
+        let start = Instant::now();
+        open_circuit(&hosts, "a.example", start);
+
+        // A probe dropped without an outcome lets the next request probe
+        let later = start + COOLDOWN;
+        let probe = hosts.admit_at("a.example", later).unwrap();
+        drop(probe);
+        let probe = hosts.admit_at("a.example", later).unwrap();
+        assert_eq!(
+            hosts.admit_at("a.example", later).err(),
+            Some(HostUnavailable::CircuitOpen)
+        );
+        probe.record_at(true, later);
+        assert!(hosts.admit_at("a.example", later).is_ok());
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let hosts = WebhookHosts::default();
+        let now = Instant::now();
+        let fail = |n: u32| {
+            for _ in 0..n {
+                hosts
+                    .admit_at("a.example", now)
+                    .unwrap()
+                    .record_at(false, now);
+            }
+        };
+
+        fail(FAILURE_THRESHOLD - 1);
+        hosts
+            .admit_at("a.example", now)
+            .unwrap()
+            .record_at(true, now);
+        fail(FAILURE_THRESHOLD - 1);
+        assert!(hosts.admit_at("a.example", now).is_ok());
+    }
+}
@@ -1,33 +1,103 @@
 //! WebhookSender processor.
 //!
 //! The WebhookSender is responsible for:
-//! - Receiving `WebhookEvent` from the queue
-//! - Looking up the webhook URL from the order record
-//! - Sending HTTP POST requests with signed body
-//! - Handling retries with exponential backoff (2^0 to 2^11 seconds)
-//! - Updating `webhook_retry_count` and `webhook_last_tried_at` in the database
+//! - Fanning each `webhook_outbox` event out into `webhook_deliveries`: one
+//!   delivery to the event's own URL and one per `[[webhooks]]` subscription
+//!   that selects the event type
+//! - Looking up the merchant of each event (the order's merchant, or the
+//!   owner of the wallet an unknown transfer was sent to) and the URL of
+//!   each delivery (the order's webhook URL, the merchant's default or
+//!   unknown-transfer URL, or the subscription URL)
+//! - Sending HTTP POST requests with signed body and the event's stable ID
+//!   in the `Ocrch-Event-Id` header; order webhook URLs supplied through the
+//!   Service API are checked against the merchant's
+//!   [`WebhookUrlPolicy`](crate::utils::webhook_url::WebhookUrlPolicy) on
+//!   every request, including redirects, and only connect to public addresses
+//!   unless private networks are allowed
+//! - Attempting due deliveries concurrently on a bounded pool of workers,
+//!   with per-host concurrency limits and circuit breakers (see
+//!   [`webhook_hosts`](super::webhook_hosts)); deliveries of an order are
+//!   still made in event order for every destination
+//! - Rescheduling failed deliveries with exponential backoff (2^1 to 2^11
+//!   seconds), independently for every destination, and dead-lettering them
+//!   once the retries are used up
+//! - Recording every HTTP request in `webhook_delivery_attempts`
+//! - Mirroring order status deliveries to the order's own URL into
+//!   `webhook_retry_count`, `webhook_last_tried_at` and `webhook_success_at`
+//!   on the order record
+//!
+//! Events are written to the outbox in the same transaction as the state
+//! change that caused them. `WebhookEvent`s received on the queue only wake
+//! the drain loop early, so nothing is lost if the process stops while
+//! events are still in flight.
 
-use crate::config::SharedConfig;
+use crate::config::{
+    MerchantConfig, SharedConfig, WebhookPayloadVersion, WebhookSubscriptionConfig,
+    default_merchant, find_merchant, wallet_merchant,
+};
 use crate::entities::order_records::{
-    GetOrderRecordById, GetOrdersForWebhookRetry, IncrementOrderWebhookRetryCount,
-    MarkOrderWebhookSuccess, OrderStatus,
+    GetOrderRecordById, IncrementOrderWebhookRetryCount, MarkOrderWebhookSuccess, OrderStatus,
+};
+use crate::entities::webhook_delivery_attempt::{
+    RecordWebhookDeliveryAttempt, WebhookDeliveryErrorKind,
+};
+use crate::entities::webhook_outbox::{
+    ClaimDueWebhookDeliveries, DeferWebhookDelivery, FanOutWebhookOutboxEntry,
+    GetWebhookOutboxEntriesToFanOut, MarkWebhookDeliveryDelivered, MarkWebhookDeliveryFailed,
+    WebhookDelivery, WebhookOutboxEntry,
+};
+use crate::entities::webhook_transfer::{
+    GetOrderPayingTransfer, GetTransferById, GetTransferByTxnHash, WebhookTransferRecord,
 };
-use crate::events::{BlockchainTarget, WebhookEvent, WebhookEventReceiver};
+use crate::events::{WebhookEvent, WebhookEventReceiver};
 use crate::framework::DatabaseProcessor;
+use crate::processors::webhook_hosts::{HostUnavailable, WebhookHosts};
+use crate::utils::hex;
+use crate::utils::webhook_url::{PublicAddressResolver, WebhookUrlError, WebhookUrlPolicy};
 use kanau::processor::Processor;
 use ocrch_sdk::objects::{
-    OrderStatus as SdkOrderStatus, OrderStatusChangedPayload, UnknownTransferPayload,
+    OrderStatus as SdkOrderStatus, OrderStatusChangedPayload, OrderStatusChangedPayloadV2,
+    PaymentDetectedPayload, PaymentDetectedPayloadV2, PingPayload, UnknownTransferPayload,
+    UnknownTransferPayloadV2, WebhookEventType, WebhookTransfer, admin::TestWebhookEvent,
 };
-use ocrch_sdk::signature::SignedObject;
-use sqlx::PgPool;
+use ocrch_sdk::signature::{EVENT_ID_HEADER, SIGNATURE_HEADER, Signature, SignedObject};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::watch;
+use tokio::task::JoinSet;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-/// Maximum retry attempts (2^11 = 2048 seconds max backoff)
+/// Maximum delivery attempts, including the first, before a delivery is
+/// dead-lettered
 const MAX_RETRY_COUNT: u32 = 11;
 
+/// How often the outbox is checked for due retries when no event arrives.
+const OUTBOX_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Maximum number of outbox rows fanned out per query.
+const OUTBOX_BATCH_SIZE: i64 = 10;
+
+/// Maximum number of deliveries attempted concurrently.
+const MAX_CONCURRENT_DELIVERIES: usize = 32;
+
+/// How long a claimed delivery is reserved for its worker. Must exceed the
+/// HTTP timeout; a delivery left by a stopped process is retried after it.
+const DELIVERY_LEASE: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Maximum number of response body bytes kept for errors and the attempt log.
+const RESPONSE_BODY_LIMIT: usize = 1024;
+
+/// Maximum number of redirects followed per attempt.
+const MAX_REDIRECTS: usize = 5;
+
+/// Timeout of a single webhook request.
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Merchant order ID of the fake order in test webhooks.
+const TEST_MERCHANT_ORDER_ID: &str = "ocrch-test";
+
 /// Errors that can occur during webhook delivery.
 #[derive(Debug, Error)]
 pub enum WebhookError {
@@ -50,13 +120,120 @@ pub enum WebhookError {
     /// Payload serialization error
     #[error("payload serialization error: {0}")]
     SerializationError(String),
+
+    /// Transfer referenced by an event not found
+    #[error("transfer not found: {0}")]
+    TransferNotFound(String),
+
+    /// Outbox row is missing a column its event kind requires
+    #[error("malformed webhook outbox row: {0}")]
+    MalformedOutboxEntry(i64),
+
+    /// The webhook URL or a redirect target was refused
+    #[error("webhook URL not allowed: {0}")]
+    UrlNotAllowed(#[from] WebhookUrlError),
+
+    /// The endpoint redirected more than `MAX_REDIRECTS` times
+    #[error("too many redirects")]
+    TooManyRedirects,
+
+    /// Test webhook names a subscription that is not configured
+    #[error("webhook subscription not found: {0}")]
+    SubscriptionNotFound(String),
+
+    /// Test webhook names a merchant that is not configured
+    #[error("merchant not found: {0}")]
+    MerchantNotFound(String),
+
+    /// The destination host cannot take a request now
+    #[error("webhook host {host} unavailable: {reason}")]
+    HostUnavailable {
+        host: String,
+        reason: HostUnavailable,
+    },
+}
+
+/// Body of an outgoing webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum WebhookPayload {
+    OrderStatusChanged(OrderStatusChangedPayload),
+    UnknownTransfer(UnknownTransferPayload),
+    PaymentDetected(PaymentDetectedPayload),
+    OrderStatusChangedV2(OrderStatusChangedPayloadV2),
+    UnknownTransferV2(UnknownTransferPayloadV2),
+    PaymentDetectedV2(PaymentDetectedPayloadV2),
+    Ping(PingPayload),
+}
+
+impl Signature for WebhookPayload {}
+
+/// The classified result of a single webhook request.
+struct AttemptOutcome {
+    result: Result<(), WebhookError>,
+    response_status: Option<u16>,
+    response_body: Option<String>,
+    error_kind: Option<WebhookDeliveryErrorKind>,
+    error: Option<String>,
+}
+
+impl AttemptOutcome {
+    fn from_response(response: Result<(reqwest::StatusCode, String), WebhookError>) -> Self {
+        match response {
+            Ok((status, response_body)) => {
+                let error = (!status.is_success()).then(|| WebhookError::DeliveryFailed {
+                    status: status.as_u16(),
+                    body: response_body.clone(),
+                });
+                Self {
+                    response_status: Some(status.as_u16()),
+                    response_body: Some(response_body),
+                    error_kind: error.as_ref().map(|_| WebhookDeliveryErrorKind::HttpStatus),
+                    error: error.as_ref().map(ToString::to_string),
+                    result: error.map_or(Ok(()), Err),
+                }
+            }
+            Err(e) => {
+                let (error_kind, message) = match &e {
+                    WebhookError::Request(e) if e.is_timeout() => {
+                        (WebhookDeliveryErrorKind::Timeout, e.to_string())
+                    }
+                    WebhookError::Request(e) if e.is_connect() => {
+                        (WebhookDeliveryErrorKind::Connect, e.to_string())
+                    }
+                    WebhookError::Request(e) => (WebhookDeliveryErrorKind::Request, e.to_string()),
+                    e => (WebhookDeliveryErrorKind::Request, e.to_string()),
+                };
+                Self {
+                    response_status: None,
+                    response_body: None,
+                    error_kind: Some(error_kind),
+                    error: Some(message),
+                    result: Err(e),
+                }
+            }
+        }
+    }
+}
+
+/// Where a delivery is sent.
+#[derive(Debug, Clone)]
+struct WebhookTarget {
+    url: String,
+    /// Policy the URL must satisfy, for URLs supplied through the Service
+    /// API. Configured URLs are trusted.
+    policy: Option<WebhookUrlPolicy>,
 }
 
 /// WebhookSender handles delivering webhook events to merchant endpoints.
 pub struct WebhookSender {
     processor: DatabaseProcessor,
+    /// Client for trusted URLs and for policies allowing private networks.
     http_client: reqwest::Client,
+    /// Client that only connects to public addresses.
+    public_client: reqwest::Client,
     config: SharedConfig,
+    hosts: WebhookHosts,
 }
 
 impl WebhookSender {
@@ -64,15 +241,18 @@ impl WebhookSender {
     pub fn new(processor: DatabaseProcessor, config: SharedConfig) -> Self {
         Self {
             processor,
-            http_client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .unwrap_or_else(|_| reqwest::Client::new()),
+            http_client: webhook_client(false),
+            public_client: webhook_client(true),
             config,
+            hosts: WebhookHosts::default(),
         }
     }
 
     /// Run the WebhookSender.
+    ///
+    /// Drains the outbox on startup, whenever a `WebhookEvent` arrives or a
+    /// delivery finishes, and every [`OUTBOX_POLL_INTERVAL`] to pick up due
+    /// retries. In-flight deliveries are awaited on shutdown.
     pub async fn run(
         self,
         mut shutdown_rx: watch::Receiver<bool>,
@@ -80,21 +260,9 @@ impl WebhookSender {
     ) {
         info!("WebhookSender started");
 
-        // Also spawn a background task to retry failed webhooks
-        let pool = self.processor.pool.clone();
-        let http_client = self.http_client.clone();
-        let retry_config = self.config.clone();
-        let mut retry_shutdown_rx = shutdown_rx.clone();
-
-        let retry_handle = tokio::spawn(async move {
-            Self::retry_failed_webhooks_loop(
-                pool,
-                http_client,
-                retry_config,
-                &mut retry_shutdown_rx,
-            )
-            .await;
-        });
+        let this = Arc::new(self);
+        let mut workers = JoinSet::new();
+        let mut poll = tokio::time::interval(OUTBOX_POLL_INTERVAL);
 
         loop {
             tokio::select! {
@@ -108,169 +276,618 @@ impl WebhookSender {
                     }
                 }
 
-                // Receive WebhookEvent events
+                // A new event was queued; coalesce any burst into one drain
                 Some(event) = webhook_rx.recv() => {
                     debug!(event = ?event, "Received WebhookEvent");
+                    while webhook_rx.try_recv().is_ok() {}
+                    this.drain_outbox(&mut workers).await;
+                }
 
-                    if let Err(e) = self.process(event).await {
-                        error!(error = %e, "Failed to process WebhookEvent");
+                // A worker is free, and the next event of its order may be due
+                Some(joined) = workers.join_next(), if !workers.is_empty() => {
+                    log_worker_exit(joined);
+                    while let Some(joined) = workers.try_join_next() {
+                        log_worker_exit(joined);
                     }
+                    this.drain_outbox(&mut workers).await;
                 }
 
-                else => {
-                    info!("WebhookEvent channel closed");
-                    break;
+                _ = poll.tick() => {
+                    this.drain_outbox(&mut workers).await;
                 }
             }
         }
 
-        // Wait for retry task to complete
-        let _ = retry_handle.await;
+        if !workers.is_empty() {
+            info!(
+                in_flight = workers.len(),
+                "Waiting for in-flight webhook deliveries"
+            );
+            while let Some(joined) = workers.join_next().await {
+                log_worker_exit(joined);
+            }
+        }
 
         info!("WebhookSender shutdown complete");
     }
 
-    /// Send an order status change webhook.
-    async fn send_order_status_webhook(
-        &self,
-        order_id: Uuid,
-        new_status: OrderStatus,
-    ) -> Result<(), WebhookError> {
-        let Some(order_info) = self
-            .processor
-            .process(GetOrderRecordById { order_id })
-            .await?
-        else {
-            return Err(WebhookError::OrderNotFound(order_id));
-        };
+    /// Fan out new outbox events, then claim due deliveries for every free
+    /// worker and start attempting them.
+    ///
+    /// Stops early if the database could not be reached, so an outage does
+    /// not spin on the same rows.
+    async fn drain_outbox(self: &Arc<Self>, workers: &mut JoinSet<()>) {
+        if !self.fan_out_outbox().await {
+            return;
+        }
 
-        let sdk_status: SdkOrderStatus = new_status.into();
-        let payload = OrderStatusChangedPayload {
-            event_type: "order_status_changed".to_string(),
-            order_id: order_info.order_id,
-            merchant_order_id: order_info.merchant_order_id.clone(),
-            status: sdk_status,
-            amount: order_info.amount.to_string(),
-            timestamp: time::OffsetDateTime::now_utc().unix_timestamp(),
-        };
+        loop {
+            let free = MAX_CONCURRENT_DELIVERIES.saturating_sub(workers.len());
+            if free == 0 {
+                return;
+            }
+            let deliveries = match self
+                .processor
+                .process(ClaimDueWebhookDeliveries {
+                    limit: free as i64,
+                    lease: DELIVERY_LEASE,
+                })
+                .await
+            {
+                Ok(deliveries) => deliveries,
+                Err(e) => {
+                    error!(error = %e, "Failed to claim webhook deliveries");
+                    return;
+                }
+            };
+            let fetched = deliveries.len();
 
-        let merchant = self.config.merchant.read().await;
-        let signed = SignedObject::new(payload, merchant.secret_bytes())
-            .map_err(|e| WebhookError::SerializationError(e.to_string()))?;
-        let signature_header = signed.to_header();
-        let body = signed.json.clone();
-        drop(merchant);
+            for delivery in deliveries {
+                let sender = Arc::clone(self);
+                workers.spawn(async move {
+                    let id = delivery.id;
+                    if let Err(e) = sender.process(delivery).await {
+                        error!(delivery_id = id, error = %e, "Failed to process webhook delivery");
+                    }
+                });
+            }
 
-        let result = self
-            .send_webhook(&order_info.webhook_url, &body, Some(&signature_header))
-            .await;
+            if fetched < free {
+                return;
+            }
+        }
+    }
 
-        match &result {
-            Ok(()) => {
-                self.mark_webhook_success(order_id).await?;
-                info!(order_id = %order_id, "Webhook delivered successfully");
+    /// Create the deliveries of every outbox event that has none yet.
+    ///
+    /// Subscriptions are matched against the configuration at the time of
+    /// fan-out; those restricted to a merchant only get that merchant's
+    /// events. Returns `false` if the database could not be reached.
+    async fn fan_out_outbox(&self) -> bool {
+        loop {
+            let entries = match self
+                .processor
+                .process(GetWebhookOutboxEntriesToFanOut {
+                    limit: OUTBOX_BATCH_SIZE,
+                })
+                .await
+            {
+                Ok(entries) => entries,
+                Err(e) => {
+                    error!(error = %e, "Failed to read webhook outbox");
+                    return false;
+                }
+            };
+            let fetched = entries.len();
+
+            for entry in entries {
+                let matching: Vec<WebhookSubscriptionConfig> = self
+                    .config
+                    .webhooks
+                    .read()
+                    .await
+                    .iter()
+                    .filter(|w| w.subscribes_to(entry.kind.into()))
+                    .cloned()
+                    .collect();
+                // Only look the merchant up if a subscription depends on it
+                let merchant_id = if matching.iter().any(|w| w.merchant.is_some()) {
+                    match self.event_merchant(&entry).await {
+                        Ok(merchant) => merchant.map(|m| m.id),
+                        Err(WebhookError::Database(e)) => {
+                            error!(outbox_id = entry.id, error = %e, "Failed to resolve webhook event merchant");
+                            return false;
+                        }
+                        Err(e) => {
+                            warn!(outbox_id = entry.id, error = %e, "Webhook event has no merchant");
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+                let subscriptions: Vec<String> = matching
+                    .into_iter()
+                    .filter(|w| w.covers_merchant(merchant_id.as_deref()))
+                    .map(|w| w.name)
+                    .collect();
+                if let Err(e) = self
+                    .processor
+                    .process(FanOutWebhookOutboxEntry {
+                        id: entry.id,
+                        subscriptions,
+                    })
+                    .await
+                {
+                    error!(outbox_id = entry.id, error = %e, "Failed to fan out webhook event");
+                    return false;
+                }
             }
-            Err(e) => {
-                warn!(
-                    order_id = %order_id,
-                    error = %e,
-                    retry_count = order_info.webhook_retry_count,
-                    "Webhook delivery failed"
-                );
-                self.increment_retry_count(order_id).await?;
+
+            if (fetched as i64) < OUTBOX_BATCH_SIZE {
+                return true;
             }
         }
-
-        result
     }
 
-    /// Send an unknown transfer webhook.
-    async fn send_unknown_transfer_webhook(
+    /// The merchant an outbox event belongs to: the order's merchant, or
+    /// the merchant of the wallet an unknown transfer was sent to. `None`
+    /// if that merchant is no longer configured.
+    async fn event_merchant(
         &self,
-        transfer_id: i64,
-        blockchain: BlockchainTarget,
-    ) -> Result<(), WebhookError> {
-        let merchant = self.config.merchant.read().await;
-        let webhook_url = match &merchant.unknown_transfer_webhook_url {
-            Some(url) => url.clone(),
-            None => {
-                debug!(
-                    transfer_id = transfer_id,
-                    blockchain = %blockchain,
-                    "Unknown transfer detected, no webhook URL configured"
-                );
-                return Ok(());
+        entry: &WebhookOutboxEntry,
+    ) -> Result<Option<MerchantConfig>, WebhookError> {
+        let event = entry
+            .event()
+            .ok_or(WebhookError::MalformedOutboxEntry(entry.id))?;
+        match event {
+            WebhookEvent::OrderStatusChanged { order_id, .. }
+            | WebhookEvent::PaymentDetected { order_id, .. } => {
+                let order = self
+                    .processor
+                    .process(GetOrderRecordById { order_id })
+                    .await?
+                    .ok_or(WebhookError::OrderNotFound(order_id))?;
+                let merchants = self.config.merchants.read().await;
+                Ok(find_merchant(&merchants, &order.merchant_id).cloned())
+            }
+            WebhookEvent::UnknownTransferReceived { transfer_id, .. } => {
+                let transfer = self
+                    .processor
+                    .process(GetTransferById {
+                        chain: entry.chain,
+                        id: transfer_id,
+                    })
+                    .await?
+                    .ok_or(WebhookError::TransferNotFound(transfer_id.to_string()))?;
+                let merchants = self.config.merchants.read().await;
+                let wallets = self.config.wallets.read().await;
+                Ok(wallet_merchant(&merchants, &wallets, &transfer.to_address).cloned())
             }
+        }
+    }
+
+    /// Build the payload of an outbox event in the given schema version,
+    /// together with the event's own target if its merchant has a URL
+    /// configured.
+    async fn build_payload(
+        &self,
+        entry: &WebhookOutboxEntry,
+        version: WebhookPayloadVersion,
+        merchant: Option<&MerchantConfig>,
+    ) -> Result<(Option<WebhookTarget>, WebhookPayload), WebhookError> {
+        let emitted_at = entry.created_at.assume_utc().unix_timestamp();
+        let event_id = entry.event_id;
+        let event = entry
+            .event()
+            .ok_or(WebhookError::MalformedOutboxEntry(entry.id))?;
+        // Only order events are numbered
+        let sequence = || {
+            entry
+                .sequence
+                .map(|s| s.max(0) as u64)
+                .ok_or(WebhookError::MalformedOutboxEntry(entry.id))
         };
 
-        let payload = UnknownTransferPayload {
-            event_type: "unknown_transfer_received".to_string(),
-            transfer_id,
-            blockchain: blockchain.to_string(),
-            timestamp: time::OffsetDateTime::now_utc().unix_timestamp(),
+        match event {
+            WebhookEvent::OrderStatusChanged {
+                order_id,
+                new_status,
+            } => {
+                let order = self
+                    .processor
+                    .process(GetOrderRecordById { order_id })
+                    .await?
+                    .ok_or(WebhookError::OrderNotFound(order_id))?;
+                let sdk_status: SdkOrderStatus = new_status.into();
+                let sequence = sequence()?;
+                let payload = match version {
+                    WebhookPayloadVersion::V1 => {
+                        WebhookPayload::OrderStatusChanged(OrderStatusChangedPayload {
+                            event_type: "order_status_changed".to_string(),
+                            event_id,
+                            sequence,
+                            order_id: order.order_id,
+                            merchant_order_id: order.merchant_order_id,
+                            metadata: order.metadata,
+                            description: order.description,
+                            customer_reference: order.customer_reference,
+                            status: sdk_status,
+                            amount: order.amount.to_string(),
+                            timestamp: emitted_at,
+                        })
+                    }
+                    WebhookPayloadVersion::V2 => {
+                        let transfer = match new_status {
+                            OrderStatus::Paid => {
+                                self.processor
+                                    .process(GetOrderPayingTransfer { order_id })
+                                    .await?
+                            }
+                            _ => None,
+                        };
+                        WebhookPayload::OrderStatusChangedV2(OrderStatusChangedPayloadV2 {
+                            version: 2,
+                            event_type: WebhookEventType::OrderStatusChanged,
+                            event_id,
+                            sequence,
+                            order_id: order.order_id,
+                            merchant_order_id: order.merchant_order_id,
+                            metadata: order.metadata,
+                            description: order.description,
+                            customer_reference: order.customer_reference,
+                            status: sdk_status,
+                            expected_amount: order.amount,
+                            received_amount: transfer.as_ref().map(|t| t.value),
+                            transfer: transfer.map(webhook_transfer),
+                            timestamp: emitted_at,
+                        })
+                    }
+                };
+                let target = Self::order_webhook_target(merchant, order.webhook_url);
+                Ok((target, payload))
+            }
+            WebhookEvent::UnknownTransferReceived {
+                transfer_id,
+                blockchain,
+            } => {
+                let payload = match version {
+                    WebhookPayloadVersion::V1 => {
+                        WebhookPayload::UnknownTransfer(UnknownTransferPayload {
+                            event_type: "unknown_transfer_received".to_string(),
+                            event_id,
+                            transfer_id,
+                            blockchain: blockchain.to_string(),
+                            timestamp: emitted_at,
+                        })
+                    }
+                    WebhookPayloadVersion::V2 => {
+                        let transfer = self
+                            .processor
+                            .process(GetTransferById {
+                                chain: entry.chain,
+                                id: transfer_id,
+                            })
+                            .await?
+                            .ok_or(WebhookError::TransferNotFound(transfer_id.to_string()))?;
+                        WebhookPayload::UnknownTransferV2(UnknownTransferPayloadV2 {
+                            version: 2,
+                            event_type: WebhookEventType::UnknownTransferReceived,
+                            event_id,
+                            received_amount: transfer.value,
+                            transfer: webhook_transfer(transfer),
+                            timestamp: emitted_at,
+                        })
+                    }
+                };
+                let target = merchant
+                    .and_then(|m| m.unknown_transfer_webhook_url.clone())
+                    .map(|url| WebhookTarget { url, policy: None });
+                Ok((target, payload))
+            }
+            WebhookEvent::PaymentDetected {
+                order_id,
+                blockchain,
+                txn_hash,
+                confirmations,
+                required_confirmations,
+                ..
+            } => {
+                let order = self
+                    .processor
+                    .process(GetOrderRecordById { order_id })
+                    .await?
+                    .ok_or(WebhookError::OrderNotFound(order_id))?;
+                let sequence = sequence()?;
+                let payload = match version {
+                    WebhookPayloadVersion::V1 => {
+                        WebhookPayload::PaymentDetected(PaymentDetectedPayload {
+                            event_type: "payment_detected".to_string(),
+                            event_id,
+                            sequence,
+                            order_id: order.order_id,
+                            merchant_order_id: order.merchant_order_id,
+                            metadata: order.metadata,
+                            description: order.description,
+                            customer_reference: order.customer_reference,
+                            blockchain: blockchain.into(),
+                            txn_hash,
+                            confirmations,
+                            required_confirmations,
+                            amount: order.amount.to_string(),
+                            timestamp: emitted_at,
+                        })
+                    }
+                    WebhookPayloadVersion::V2 => {
+                        let transfer = self
+                            .processor
+                            .process(GetTransferByTxnHash {
+                                chain: entry.chain,
+                                txn_hash: txn_hash.clone(),
+                            })
+                            .await?
+                            .ok_or(WebhookError::TransferNotFound(txn_hash))?;
+                        let received_amount = transfer.value;
+                        // Report the confirmations at detection time, so every
+                        // delivery of the event carries the same payload
+                        let mut transfer = webhook_transfer(transfer);
+                        transfer.confirmations = confirmations;
+                        WebhookPayload::PaymentDetectedV2(PaymentDetectedPayloadV2 {
+                            version: 2,
+                            event_type: WebhookEventType::PaymentDetected,
+                            event_id,
+                            sequence,
+                            order_id: order.order_id,
+                            merchant_order_id: order.merchant_order_id,
+                            metadata: order.metadata,
+                            description: order.description,
+                            customer_reference: order.customer_reference,
+                            expected_amount: order.amount,
+                            received_amount,
+                            required_confirmations,
+                            transfer,
+                            timestamp: emitted_at,
+                        })
+                    }
+                };
+                let target = Self::order_webhook_target(merchant, order.webhook_url);
+                Ok((target, payload))
+            }
+        }
+    }
+
+    /// The order's webhook URL, restricted by the merchant's webhook URL
+    /// policy, falling back to the merchant default.
+    fn order_webhook_target(
+        merchant: Option<&MerchantConfig>,
+        order_url: Option<String>,
+    ) -> Option<WebhookTarget> {
+        let merchant = merchant?;
+        match order_url {
+            Some(url) => Some(WebhookTarget {
+                url,
+                policy: Some(merchant.webhook_url_policy.clone()),
+            }),
+            None => merchant
+                .default_webhook_url
+                .clone()
+                .map(|url| WebhookTarget { url, policy: None }),
+        }
+    }
+
+    /// Attempt a single delivery.
+    ///
+    /// Succeeds without sending anything if the destination no longer has a
+    /// URL or signing key: the event's own URL is not configured, its
+    /// merchant or the subscription was removed from the config.
+    async fn deliver(&self, delivery: &WebhookDelivery) -> Result<(), WebhookError> {
+        let entry = &delivery.entry;
+        let merchant = self.event_merchant(entry).await?;
+        let version = match &delivery.subscription {
+            None => merchant
+                .as_ref()
+                .map(|m| m.webhook_payload_version)
+                .unwrap_or_default(),
+            Some(name) => self
+                .config
+                .webhooks
+                .read()
+                .await
+                .iter()
+                .find(|w| &w.name == name)
+                .map(|w| w.payload_version)
+                .unwrap_or_default(),
         };
+        let (own_target, payload) = self
+            .build_payload(entry, version, merchant.as_ref())
+            .await?;
 
-        let signed = SignedObject::new(payload, merchant.secret_bytes())
+        let webhooks = self.config.webhooks.read().await;
+        let target = match &delivery.subscription {
+            None => own_target
+                .zip(merchant.as_ref())
+                .map(|(target, m)| (target, m.signing_keys())),
+            Some(name) => webhooks
+                .iter()
+                .find(|w| &w.name == name)
+                .map(|w| {
+                    let target = WebhookTarget {
+                        url: w.url.clone(),
+                        policy: None,
+                    };
+                    (target, w.signing_keys(merchant.as_ref()))
+                })
+                .filter(|(_, keys)| !keys.is_empty()),
+        };
+        let Some((target, keys)) = target else {
+            debug!(
+                outbox_id = entry.id,
+                kind = ?entry.kind,
+                subscription = ?delivery.subscription,
+                "No webhook URL configured, discarding delivery"
+            );
+            return Ok(());
+        };
+        // One signature per active key, so receivers keep verifying while a
+        // secret is being rotated
+        let signed = SignedObject::with_keys(payload, &keys)
             .map_err(|e| WebhookError::SerializationError(e.to_string()))?;
         let signature_header = signed.to_header();
-        let body = signed.json.clone();
-        drop(merchant);
+        drop(webhooks);
+
+        let host = webhook_host(&target.url);
+        let permit = self
+            .hosts
+            .admit(&host)
+            .map_err(|reason| WebhookError::HostUnavailable {
+                host: host.clone(),
+                reason,
+            })?;
 
-        match self
-            .send_webhook(&webhook_url, &body, Some(&signature_header))
-            .await
+        let result = self
+            .send_webhook(
+                delivery.id,
+                entry,
+                &target,
+                &signed.json,
+                Some(&signature_header),
+            )
+            .await;
+        permit.record(result.is_ok());
+
+        // Order records track the delivery to the order's own URL
+        if delivery.subscription.is_none()
+            && let Some(WebhookEvent::OrderStatusChanged { order_id, .. }) = entry.event()
         {
-            Ok(()) => {
-                info!(
-                    transfer_id = transfer_id,
-                    blockchain = %blockchain,
-                    "Unknown transfer webhook delivered successfully"
-                );
-                Ok(())
-            }
-            Err(e) => {
-                warn!(
-                    transfer_id = transfer_id,
-                    blockchain = %blockchain,
-                    error = %e,
-                    "Unknown transfer webhook delivery failed"
-                );
-                Err(e)
+            match &result {
+                Ok(()) => self.mark_webhook_success(order_id).await?,
+                Err(_) => self.increment_retry_count(order_id).await?,
             }
         }
+
+        match &result {
+            Ok(()) => info!(
+                outbox_id = entry.id,
+                kind = ?entry.kind,
+                subscription = ?delivery.subscription,
+                "Webhook delivered successfully"
+            ),
+            Err(e) => warn!(
+                outbox_id = entry.id,
+                kind = ?entry.kind,
+                subscription = ?delivery.subscription,
+                retry_count = delivery.retry_count,
+                error = %e,
+                "Webhook delivery failed"
+            ),
+        }
+
+        result
     }
 
-    /// Send the webhook HTTP request.
+    /// Send the webhook HTTP request and record the attempt.
+    ///
+    /// Failing to record the attempt is logged but does not change the
+    /// delivery result.
     async fn send_webhook(
         &self,
-        url: &str,
+        delivery_id: i64,
+        entry: &WebhookOutboxEntry,
+        target: &WebhookTarget,
         body: &str,
         signature: Option<&str>,
     ) -> Result<(), WebhookError> {
-        let mut request = self
-            .http_client
-            .post(url)
-            .header("Content-Type", "application/json");
+        let outbox_id = entry.id;
+        let started = std::time::Instant::now();
+        let response = self
+            .post_webhook(target, entry.event_id, body, signature)
+            .await;
+        let latency_ms = i32::try_from(started.elapsed().as_millis()).unwrap_or(i32::MAX);
 
-        if let Some(sig) = signature {
-            request = request.header("Ocrch-Signature", sig);
+        let outcome = AttemptOutcome::from_response(response);
+        let attempt = RecordWebhookDeliveryAttempt {
+            delivery_id,
+            outbox_id,
+            url: target.url.clone(),
+            body_sha256: sha256_hex(body),
+            response_status: outcome.response_status.map(i32::from),
+            response_body: outcome.response_body,
+            latency_ms,
+            error_kind: outcome.error_kind,
+            error: outcome.error,
+        };
+
+        if let Err(e) = self.processor.process(attempt).await {
+            warn!(delivery_id = delivery_id, error = %e, "Failed to record webhook delivery attempt");
         }
 
-        let response = request.body(body.to_string()).send().await?;
+        outcome.result
+    }
+
+    /// POST the webhook and return the response status and the start of the
+    /// response body.
+    ///
+    /// Redirects are followed here rather than by the HTTP client, so that
+    /// every hop of a restricted target is checked against its policy.
+    async fn post_webhook(
+        &self,
+        target: &WebhookTarget,
+        event_id: Uuid,
+        body: &str,
+        signature: Option<&str>,
+    ) -> Result<(reqwest::StatusCode, String), WebhookError> {
+        let mut url = target.url.clone();
 
-        let status = response.status();
+        for _ in 0..=MAX_REDIRECTS {
+            let client = match &target.policy {
+                Some(policy) => {
+                    policy.check(&url)?;
+                    if policy.allow_private_networks {
+                        &self.http_client
+                    } else {
+                        &self.public_client
+                    }
+                }
+                None => &self.http_client,
+            };
 
-        // Per spec: 500 OK means success (this seems like a typo in the spec,
-        // but we'll interpret it as 200 OK for success)
-        if status.is_success() {
-            Ok(())
-        } else {
-            let body = response.text().await.unwrap_or_default();
-            Err(WebhookError::DeliveryFailed {
-                status: status.as_u16(),
-                body,
-            })
+            let mut request = client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .header(EVENT_ID_HEADER, event_id.to_string());
+
+            if let Some(sig) = signature {
+                request = request.header(SIGNATURE_HEADER, sig);
+            }
+
+            let mut response = request.body(body.to_string()).send().await?;
+            let status = response.status();
+
+            if status.is_redirection()
+                && let Some(location) = response
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|l| l.to_str().ok())
+                    .and_then(|l| response.url().join(l).ok())
+            {
+                url = location.to_string();
+                continue;
+            }
+
+            // Only keep the start of the body; a failure to read the rest does
+            // not change the outcome, which is decided by the status alone.
+            let mut bytes = Vec::new();
+            while bytes.len() < RESPONSE_BODY_LIMIT {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => bytes.extend_from_slice(&chunk),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            return Ok((status, truncate_body(&bytes)));
         }
+
+        Err(WebhookError::TooManyRedirects)
     }
 
     /// Mark a webhook as successfully delivered.
@@ -288,152 +905,301 @@ impl WebhookSender {
             .await?;
         Ok(())
     }
+}
 
-    /// Background loop to retry failed webhooks.
-    async fn retry_failed_webhooks_loop(
-        pool: PgPool,
-        http_client: reqwest::Client,
-        config: SharedConfig,
-        shutdown_rx: &mut watch::Receiver<bool>,
-    ) {
-        info!("Webhook retry loop started");
-
-        loop {
-            tokio::select! {
-                biased;
-
-                _ = shutdown_rx.changed() => {
-                    if *shutdown_rx.borrow() {
-                        info!("Webhook retry loop shutting down");
-                        break;
-                    }
-                }
+impl Processor<WebhookDelivery> for WebhookSender {
+    type Output = ();
+    type Error = WebhookError;
 
-                _ = tokio::time::sleep(std::time::Duration::from_secs(10)) => {
-                    if let Err(e) = Self::retry_pending_webhooks(&pool, &http_client, &config).await {
-                        error!(error = %e, "Failed to retry webhooks");
-                    }
+    /// Attempt a single delivery and record the outcome.
+    ///
+    /// Delivery failures are recorded on the delivery and are not returned
+    /// as errors; only database failures are.
+    async fn process(&self, delivery: WebhookDelivery) -> Result<(), WebhookError> {
+        match self.deliver(&delivery).await {
+            Ok(()) => {
+                self.processor
+                    .process(MarkWebhookDeliveryDelivered { id: delivery.id })
+                    .await?;
+            }
+            Err(WebhookError::Database(e)) => return Err(WebhookError::Database(e)),
+            // Not attempted; try again later without spending a retry
+            Err(WebhookError::HostUnavailable { host, reason }) => {
+                debug!(
+                    delivery_id = delivery.id,
+                    host,
+                    reason = %reason,
+                    "Deferring webhook delivery"
+                );
+                self.processor
+                    .process(DeferWebhookDelivery {
+                        id: delivery.id,
+                        delay: reason.retry_after(),
+                    })
+                    .await?;
+            }
+            Err(e) => {
+                let retry_count = delivery.retry_count.max(0) as u32 + 1;
+                let dead_letter = retry_count >= MAX_RETRY_COUNT;
+                self.processor
+                    .process(MarkWebhookDeliveryFailed {
+                        id: delivery.id,
+                        error: e.to_string(),
+                        retry_delay: calculate_retry_delay(retry_count),
+                        dead_letter,
+                    })
+                    .await?;
+                if dead_letter {
+                    // Alert: nothing retries this delivery until an admin
+                    // replays it
+                    error!(
+                        alert = "webhook_dead_lettered",
+                        delivery_id = delivery.id,
+                        outbox_id = delivery.entry.id,
+                        event_id = %delivery.entry.event_id,
+                        kind = ?delivery.entry.kind,
+                        subscription = ?delivery.subscription,
+                        error = %e,
+                        "Webhook delivery dead-lettered after exhausting its retries"
+                    );
                 }
             }
         }
+        Ok(())
     }
+}
 
-    /// Retry pending webhooks that are due for retry.
-    async fn retry_pending_webhooks(
-        pool: &PgPool,
-        http_client: &reqwest::Client,
-        config: &SharedConfig,
-    ) -> Result<(), WebhookError> {
-        let processor = DatabaseProcessor { pool: pool.clone() };
-        let orders_to_retry = processor
-            .process(GetOrdersForWebhookRetry {
-                max_retry_count: MAX_RETRY_COUNT as i32,
-                limit: 10,
-            })
-            .await?;
+/// Build a webhook HTTP client. Redirects are followed by `post_webhook`.
+fn webhook_client(public_only: bool) -> reqwest::Client {
+    let builder = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+    let builder = if public_only {
+        builder.dns_resolver(Arc::new(PublicAddressResolver))
+    } else {
+        builder
+    };
+    builder.build().unwrap_or_else(|e| {
+        error!(error = %e, "Failed to build webhook HTTP client, using defaults");
+        reqwest::Client::new()
+    })
+}
 
-        for order in orders_to_retry {
-            let sdk_status: SdkOrderStatus = order.status.into();
-            let payload = OrderStatusChangedPayload {
-                event_type: "order_status_changed".to_string(),
-                order_id: order.order_id,
-                merchant_order_id: order.merchant_order_id.clone(),
-                status: sdk_status,
-                amount: order.amount.to_string(),
-                timestamp: time::OffsetDateTime::now_utc().unix_timestamp(),
-            };
+/// Log a delivery worker that did not finish normally.
+fn log_worker_exit(joined: Result<(), tokio::task::JoinError>) {
+    if let Err(e) = joined {
+        error!(error = %e, "Webhook delivery worker failed");
+    }
+}
 
-            let merchant = config.merchant.read().await;
-            let signed = match SignedObject::new(payload, merchant.secret_bytes()) {
-                Ok(s) => s,
-                Err(e) => {
-                    error!(
-                        order_id = %order.order_id,
-                        error = %e,
-                        "Failed to sign webhook payload"
-                    );
-                    continue;
-                }
-            };
-            let signature_header = signed.to_header();
-            let body = signed.json.clone();
-            drop(merchant);
+/// The lowercased host of a webhook URL, used to group deliveries per
+/// destination.
+fn webhook_host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_ascii_lowercase))
+        .unwrap_or_else(|| url.to_string())
+}
 
-            let request = http_client
-                .post(&order.webhook_url)
-                .header("Content-Type", "application/json")
-                .header("Ocrch-Signature", &signature_header)
-                .body(body);
+/// A synthetic webhook, sent once on request and not stored in the outbox.
+///
+/// Signed and sent like a delivery, but not logged as a delivery attempt
+/// and not counted by the per-host circuit breakers.
+#[derive(Debug, Clone)]
+pub struct TestWebhook {
+    pub event: TestWebhookEvent,
+    pub target: TestWebhookTarget,
+    /// Merchant whose secrets sign the event; defaults to the default
+    /// merchant.
+    pub merchant_id: Option<String>,
+    /// Payload version of an order event; defaults to the target's version.
+    pub payload_version: Option<WebhookPayloadVersion>,
+}
 
-            match request.send().await {
-                Ok(response) if response.status().is_success() => {
-                    processor
-                        .process(MarkOrderWebhookSuccess {
-                            order_id: order.order_id,
-                        })
-                        .await?;
+/// Destination of a [`TestWebhook`].
+#[derive(Debug, Clone)]
+pub enum TestWebhookTarget {
+    /// A URL, signed with the merchant's secrets.
+    Url(String),
+    /// A `[[webhooks]]` subscription by name.
+    Subscription(String),
+}
 
-                    info!(
-                        order_id = %order.order_id,
-                        retry_count = order.webhook_retry_count,
-                        "Webhook retry successful"
-                    );
-                }
-                Ok(response) => {
-                    let status = response.status();
-                    processor
-                        .process(IncrementOrderWebhookRetryCount {
-                            order_id: order.order_id,
-                        })
-                        .await?;
+/// What happened to a [`TestWebhook`].
+#[derive(Debug, Clone)]
+pub struct TestWebhookResult {
+    pub url: String,
+    pub event_id: Uuid,
+    pub request_body: String,
+    pub signature: String,
+    pub response_status: Option<u16>,
+    pub response_body: Option<String>,
+    pub latency_ms: u32,
+    pub error_kind: Option<WebhookDeliveryErrorKind>,
+    pub error: Option<String>,
+}
 
-                    warn!(
-                        order_id = %order.order_id,
-                        status = %status,
-                        retry_count = order.webhook_retry_count + 1,
-                        "Webhook retry failed"
-                    );
-                }
-                Err(e) => {
-                    processor
-                        .process(IncrementOrderWebhookRetryCount {
-                            order_id: order.order_id,
-                        })
-                        .await?;
+impl Processor<TestWebhook> for WebhookSender {
+    type Output = TestWebhookResult;
+    type Error = WebhookError;
 
-                    warn!(
-                        order_id = %order.order_id,
-                        error = %e,
-                        retry_count = order.webhook_retry_count + 1,
-                        "Webhook retry request failed"
-                    );
-                }
+    /// Send a test webhook and report the endpoint's response.
+    ///
+    /// Only an unknown merchant or subscription or a signing failure is
+    /// returned as an error; a failed request is part of the result.
+    async fn process(&self, test: TestWebhook) -> Result<TestWebhookResult, WebhookError> {
+        let merchants = self.config.merchants.read().await;
+        let merchant = match &test.merchant_id {
+            Some(id) => find_merchant(&merchants, id),
+            None => default_merchant(&merchants),
+        }
+        .ok_or_else(|| {
+            WebhookError::MerchantNotFound(test.merchant_id.clone().unwrap_or_default())
+        })?;
+        let webhooks = self.config.webhooks.read().await;
+        let (url, keys, version) = match &test.target {
+            TestWebhookTarget::Url(url) => (
+                url.clone(),
+                merchant.signing_keys(),
+                merchant.webhook_payload_version,
+            ),
+            TestWebhookTarget::Subscription(name) => {
+                let w = webhooks
+                    .iter()
+                    .find(|w| &w.name == name)
+                    .ok_or_else(|| WebhookError::SubscriptionNotFound(name.clone()))?;
+                (
+                    w.url.clone(),
+                    w.signing_keys(Some(merchant)),
+                    w.payload_version,
+                )
             }
+        };
+
+        let event_id = Uuid::new_v4();
+        let payload = test_payload(
+            test.event,
+            test.payload_version.unwrap_or(version),
+            event_id,
+        );
+        let signed = SignedObject::with_keys(payload, &keys)
+            .map_err(|e| WebhookError::SerializationError(e.to_string()))?;
+        let signature = signed.to_header();
+        drop(webhooks);
+        drop(merchants);
+
+        let target = WebhookTarget { url, policy: None };
+        let started = std::time::Instant::now();
+        let response = self
+            .post_webhook(&target, event_id, &signed.json, Some(&signature))
+            .await;
+        let latency_ms = u32::try_from(started.elapsed().as_millis()).unwrap_or(u32::MAX);
+        let outcome = AttemptOutcome::from_response(response);
+
+        info!(
+            url = %target.url,
+            event = ?test.event,
+            status = ?outcome.response_status,
+            error = ?outcome.error,
+            "Test webhook sent"
+        );
+
+        Ok(TestWebhookResult {
+            url: target.url,
+            event_id,
+            request_body: signed.json,
+            signature,
+            response_status: outcome.response_status,
+            response_body: outcome.response_body,
+            latency_ms,
+            error_kind: outcome.error_kind,
+            error: outcome.error,
+        })
+    }
+}
+
+/// Build the payload of a test webhook. Order events describe a fake order
+/// with the nil UUID, merchant order ID `ocrch-test` and amount 0.
+fn test_payload(
+    event: TestWebhookEvent,
+    version: WebhookPayloadVersion,
+    event_id: Uuid,
+) -> WebhookPayload {
+    let timestamp = time::OffsetDateTime::now_utc().unix_timestamp();
+    match (event, version) {
+        (TestWebhookEvent::Ping, _) => WebhookPayload::Ping(PingPayload {
+            event_type: "ping".to_string(),
+            event_id,
+            timestamp,
+        }),
+        (TestWebhookEvent::OrderStatusChanged, WebhookPayloadVersion::V1) => {
+            WebhookPayload::OrderStatusChanged(OrderStatusChangedPayload {
+                event_type: "order_status_changed".to_string(),
+                event_id,
+                sequence: 1,
+                order_id: Uuid::nil(),
+                merchant_order_id: TEST_MERCHANT_ORDER_ID.to_string(),
+                metadata: None,
+                description: None,
+                customer_reference: None,
+                status: SdkOrderStatus::Paid,
+                amount: rust_decimal::Decimal::ZERO.to_string(),
+                timestamp,
+            })
         }
+        (TestWebhookEvent::OrderStatusChanged, WebhookPayloadVersion::V2) => {
+            WebhookPayload::OrderStatusChangedV2(OrderStatusChangedPayloadV2 {
+                version: 2,
+                event_type: WebhookEventType::OrderStatusChanged,
+                event_id,
+                sequence: 1,
+                order_id: Uuid::nil(),
+                merchant_order_id: TEST_MERCHANT_ORDER_ID.to_string(),
+                metadata: None,
+                description: None,
+                customer_reference: None,
+                status: SdkOrderStatus::Paid,
+                expected_amount: rust_decimal::Decimal::ZERO,
+                received_amount: None,
+                transfer: None,
+                timestamp,
+            })
+        }
+    }
+}
 
-        Ok(())
+/// Convert a stored transfer into its v2 payload form.
+fn webhook_transfer(t: WebhookTransferRecord) -> WebhookTransfer {
+    WebhookTransfer {
+        transfer_id: t.id,
+        blockchain: t.blockchain().into(),
+        token: t.token_name.into(),
+        block_timestamp: t.block_timestamp_secs(),
+        txn_hash: t.txn_hash,
+        from_address: t.from_address,
+        to_address: t.to_address,
+        block_number: t.block_number,
+        confirmations: t.confirmations.max(0) as u32,
+        amount: t.value,
     }
 }
 
-impl Processor<WebhookEvent> for WebhookSender {
-    type Output = ();
-    type Error = WebhookError;
+/// Hex-encoded SHA-256 of a request body.
+fn sha256_hex(body: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, body.as_bytes());
+    hex::encode(digest.as_ref())
+}
 
-    async fn process(&self, event: WebhookEvent) -> Result<(), WebhookError> {
-        match event {
-            WebhookEvent::OrderStatusChanged {
-                order_id,
-                new_status,
-            } => self.send_order_status_webhook(order_id, new_status).await,
-            WebhookEvent::UnknownTransferReceived {
-                transfer_id,
-                blockchain,
-            } => {
-                self.send_unknown_transfer_webhook(transfer_id, blockchain)
-                    .await
-            }
-        }
+/// Decode up to [`RESPONSE_BODY_LIMIT`] bytes of a response body, dropping
+/// a character cut in half by the limit.
+fn truncate_body(bytes: &[u8]) -> String {
+    let mut bytes = &bytes[..bytes.len().min(RESPONSE_BODY_LIMIT)];
+    if let Err(e) = std::str::from_utf8(bytes)
+        && e.error_len().is_none()
+    {
+        bytes = &bytes[..e.valid_up_to()];
     }
+    String::from_utf8_lossy(bytes).into_owned()
 }
 
 /// Calculate the next retry delay based on retry count.
@@ -471,4 +1237,56 @@ mod tests {
             std::time::Duration::from_secs(2048)
         );
     }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_truncate_body() {
+        assert_eq!(truncate_body(b"ok"), "ok");
+        // The limit falls in the middle of a two-byte character
+        let long = format!("a{}", "é".repeat(RESPONSE_BODY_LIMIT));
+        let truncated = truncate_body(long.as_bytes());
+        assert_eq!(truncated.len(), RESPONSE_BODY_LIMIT - 1);
+        assert!(!truncated.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn test_test_payload() {
+        let event_id = Uuid::new_v4();
+        let json = |payload: WebhookPayload| {
+            serde_json::to_value(payload).unwrap_or(serde_json::Value::Null)
+        };
+
+        let ping = json(test_payload(
+            TestWebhookEvent::Ping,
+            WebhookPayloadVersion::V2,
+            event_id,
+        ));
+        assert_eq!(ping["event_type"], "ping");
+        assert_eq!(ping["event_id"], event_id.to_string());
+
+        let v1 = json(test_payload(
+            TestWebhookEvent::OrderStatusChanged,
+            WebhookPayloadVersion::V1,
+            event_id,
+        ));
+        assert_eq!(v1["event_type"], "order_status_changed");
+        assert_eq!(v1["merchant_order_id"], TEST_MERCHANT_ORDER_ID);
+        assert_eq!(v1["order_id"], Uuid::nil().to_string());
+        assert!(v1.get("version").is_none());
+
+        let v2 = json(test_payload(
+            TestWebhookEvent::OrderStatusChanged,
+            WebhookPayloadVersion::V2,
+            event_id,
+        ));
+        assert_eq!(v2["version"], 2);
+        assert_eq!(v2["status"], "paid");
+    }
 }
@@ -1 +1,3 @@
+pub mod hex;
 pub mod pooling_interval;
+pub mod webhook_url;
@@ -1,13 +1,94 @@
+/// One step of a pooling decay curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolingDecayStep {
+    /// Time since the last pending deposit activity after which this step applies.
+    pub after: time::Duration,
+    /// Pooling interval while this step applies.
+    pub interval: time::Duration,
+}
+
+/// Adaptive pooling curve for a single chain.
+///
+/// Right after pending deposit activity the chain is polled every
+/// `active_interval`. As time passes the interval backs off through the
+/// `decay` steps, and once `idle_after` has elapsed it settles on
+/// `idle_interval`. While there are no live pending deposits at all the
+/// chain is only polled every `heartbeat_interval`, which keeps
+/// unknown-transfer detection alive without burning explorer quota.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolingPolicy {
+    /// Interval used right after pending deposit activity.
+    pub active_interval: time::Duration,
+    /// Intermediate steps, sorted by ascending `after`.
+    pub decay: Vec<PoolingDecayStep>,
+    /// Time since the last activity after which the chain is considered idle.
+    pub idle_after: time::Duration,
+    /// Interval used once the chain is idle.
+    pub idle_interval: time::Duration,
+    /// Interval used while the chain has no live pending deposits.
+    pub heartbeat_interval: time::Duration,
+}
+
+impl Default for PoolingPolicy {
+    fn default() -> Self {
+        Self {
+            active_interval: time::Duration::seconds(2),
+            decay: vec![
+                PoolingDecayStep {
+                    after: time::Duration::seconds(5),
+                    interval: time::Duration::seconds(10),
+                },
+                PoolingDecayStep {
+                    after: time::Duration::seconds(10),
+                    interval: time::Duration::seconds(30),
+                },
+            ],
+            idle_after: time::Duration::seconds(30),
+            idle_interval: time::Duration::seconds(60),
+            heartbeat_interval: time::Duration::minutes(15),
+        }
+    }
+}
+
 /// Returns the next pooling frequency based on the lastest pending deposit timestamp.
 pub fn pooling_freq(
+    policy: &PoolingPolicy,
     last_pending: time::PrimitiveDateTime,
     now: time::PrimitiveDateTime,
 ) -> time::Duration {
     let last_waited = now - last_pending;
-    match last_waited {
-        d if d < time::Duration::seconds(5) => time::Duration::seconds(2),
-        d if d < time::Duration::seconds(10) => time::Duration::seconds(10),
-        d if d < time::Duration::seconds(30) => time::Duration::seconds(30),
-        _ => time::Duration::seconds(60),
+    if last_waited >= policy.idle_after {
+        return policy.idle_interval;
+    }
+    policy
+        .decay
+        .iter()
+        .rev()
+        .find(|step| last_waited >= step.after)
+        .map_or(policy.active_interval, |step| step.interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_curve() {
+        let policy = PoolingPolicy::default();
+        let epoch = time::OffsetDateTime::UNIX_EPOCH;
+        let now = time::PrimitiveDateTime::new(epoch.date(), epoch.time());
+        let freq = |ago: i64| pooling_freq(&policy, now - time::Duration::seconds(ago), now);
+
+        assert_eq!(freq(0), time::Duration::seconds(2));
+        assert_eq!(freq(4), time::Duration::seconds(2));
+        assert_eq!(freq(5), time::Duration::seconds(10));
+        assert_eq!(freq(9), time::Duration::seconds(10));
+        assert_eq!(freq(10), time::Duration::seconds(30));
+        assert_eq!(freq(29), time::Duration::seconds(30));
+        assert_eq!(freq(30), time::Duration::seconds(60));
+        assert_eq!(
+            pooling_freq(&policy, time::PrimitiveDateTime::MIN, now),
+            time::Duration::seconds(60)
+        );
     }
 }
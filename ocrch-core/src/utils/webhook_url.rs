@@ -0,0 +1,278 @@
+//! Webhook URL restrictions.
+//!
+//! Order webhook URLs come from the Service API, so a leaked API key could
+//! otherwise make the server POST into the internal network. A
+//! [`WebhookUrlPolicy`] restricts those URLs to an optional host allowlist
+//! and, unless private networks are allowed, to public addresses. It is
+//! checked when an order is created and again before every request of a
+//! delivery, including each redirect; [`PublicAddressResolver`] repeats the
+//! address check on the addresses a request actually connects to.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use thiserror::Error;
+use url::{Host, Url};
+
+/// Why a webhook URL was refused.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WebhookUrlError {
+    /// Not an absolute `http` or `https` URL with a host
+    #[error("invalid webhook URL")]
+    InvalidUrl,
+
+    /// The host is not in the merchant's allowlist
+    #[error("webhook host {0} is not allowed")]
+    HostNotAllowed(String),
+
+    /// The host is or resolves to a private, loopback or link-local address
+    #[error("webhook host {0} is not a public address")]
+    PrivateAddress(String),
+}
+
+/// Restrictions on merchant-supplied webhook URLs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebhookUrlPolicy {
+    /// Lowercase hosts webhook URLs may point to. `*.example.com` matches
+    /// every subdomain of `example.com`. Empty allows every host.
+    pub allowed_hosts: Vec<String>,
+    /// Allow private, loopback and link-local addresses.
+    pub allow_private_networks: bool,
+}
+
+impl WebhookUrlPolicy {
+    /// Check a URL without resolving its host.
+    ///
+    /// Rejects URLs that are malformed, not `http(s)`, point to a host
+    /// outside the allowlist, or to a non-public IP literal.
+    pub fn check(&self, url: &str) -> Result<Url, WebhookUrlError> {
+        let parsed = Url::parse(url).map_err(|_| WebhookUrlError::InvalidUrl)?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(WebhookUrlError::InvalidUrl);
+        }
+        let (name, ip) = match parsed.host() {
+            Some(Host::Domain(domain)) => (domain.to_ascii_lowercase(), None),
+            Some(Host::Ipv4(ip)) => (ip.to_string(), Some(IpAddr::V4(ip))),
+            Some(Host::Ipv6(ip)) => (ip.to_string(), Some(IpAddr::V6(ip))),
+            None => return Err(WebhookUrlError::InvalidUrl),
+        };
+        if !self.host_allowed(&name) {
+            return Err(WebhookUrlError::HostNotAllowed(name));
+        }
+        if let Some(ip) = ip
+            && !self.allow_private_networks
+            && !is_public_ip(ip)
+        {
+            return Err(WebhookUrlError::PrivateAddress(name));
+        }
+        Ok(parsed)
+    }
+
+    /// Check a URL and every address its host currently resolves to.
+    ///
+    /// A host that cannot be resolved is accepted; the addresses are
+    /// checked again when the webhook is sent.
+    pub async fn check_resolved(&self, url: &str) -> Result<(), WebhookUrlError> {
+        let parsed = self.check(url)?;
+        if self.allow_private_networks {
+            return Ok(());
+        }
+        let (Some(Host::Domain(domain)), Some(port)) =
+            (parsed.host(), parsed.port_or_known_default())
+        else {
+            return Ok(());
+        };
+        if let Ok(mut addrs) = tokio::net::lookup_host((domain, port)).await
+            && addrs.any(|addr| !is_public_ip(addr.ip()))
+        {
+            return Err(WebhookUrlError::PrivateAddress(domain.to_ascii_lowercase()));
+        }
+        Ok(())
+    }
+
+    fn host_allowed(&self, host: &str) -> bool {
+        self.allowed_hosts.is_empty()
+            || self
+                .allowed_hosts
+                .iter()
+                .any(|pattern| match pattern.strip_prefix("*.") {
+                    Some(domain) => host
+                        .strip_suffix(domain)
+                        .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+                    None => host == pattern,
+                })
+    }
+}
+
+/// Whether an address is publicly routable: not private, loopback,
+/// link-local, unspecified, multicast or otherwise reserved.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(mapped),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // 0.0.0.0/8, 100.64.0.0/10 (carrier-grade NAT), 192.0.0.0/24,
+        // 198.18.0.0/15 (benchmarking), 240.0.0.0/4 (reserved)
+        || a == 0
+        || (a == 100 && (b & 0xc0) == 64)
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (b & 0xfe) == 18)
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let [s0, s1, s2, s3, s4, s5, s6, s7] = ip.segments();
+    let embedded = |high: u16, low: u16| {
+        let [a, b] = high.to_be_bytes();
+        let [c, d] = low.to_be_bytes();
+        Ipv4Addr::new(a, b, c, d)
+    };
+    // ::/96 (IPv4-compatible) and 2002::/16 (6to4) reach the embedded IPv4
+    // address, so they are public only if it is
+    if [s0, s1, s2, s3, s4, s5] == [0; 6] {
+        return is_public_ipv4(embedded(s6, s7));
+    }
+    if s0 == 0x2002 {
+        return is_public_ipv4(embedded(s1, s2));
+    }
+    !(ip.is_multicast()
+        // fc00::/7 (unique local), fe80::/10 (link-local), fec0::/10
+        // (site-local), 64:ff9b::/96 (NAT64), 100::/64 (discard-only),
+        // 2001:db8::/32 (documentation)
+        || (s0 & 0xfe00) == 0xfc00
+        || (s0 & 0xffc0) == 0xfe80
+        || (s0 & 0xffc0) == 0xfec0
+        || (s0 == 0x64 && s1 == 0xff9b)
+        || [s0, s1, s2, s3] == [0x100, 0, 0, 0]
+        || (s0 == 0x2001 && s1 == 0x0db8))
+}
+
+/// DNS resolver that only returns public addresses.
+///
+/// Used by the HTTP client for merchant-supplied webhook URLs, so a host
+/// that resolves to an internal address at send time is never connected
+/// to, even if it resolved to a public one when the order was created.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublicAddressResolver;
+
+impl Resolve for PublicAddressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(WebhookUrlError::PrivateAddress(host.to_string()).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "::",
+            "::127.0.0.1",
+            "::10.0.0.1",
+            "2002:7f00:1::",
+            "2002:a9fe:a9fe::1",
+            "2002:c0a8:101:1::1",
+            "100::1",
+            "100::ffff:ffff:ffff:ffff",
+        ] {
+            let ip: IpAddr = ip.parse().expect(ip);
+            assert!(!is_public_ip(ip), "{ip} should not be public");
+        }
+        for ip in [
+            "1.1.1.1",
+            "93.184.216.34",
+            "2606:4700:4700::1111",
+            "::1.1.1.1",
+            "2002:101:101::1",
+            "100:0:0:1::1",
+        ] {
+            let ip: IpAddr = ip.parse().expect(ip);
+            assert!(is_public_ip(ip), "{ip} should be public");
+        }
+    }
+
+    #[test]
+    fn test_policy_check() {
+        let policy = WebhookUrlPolicy::default();
+        assert!(policy.check("https://shop.example.com/hook").is_ok());
+        assert_eq!(
+            policy.check("ftp://shop.example.com/hook").err(),
+            Some(WebhookUrlError::InvalidUrl)
+        );
+        assert_eq!(
+            policy.check("http://169.254.169.254/latest").err(),
+            Some(WebhookUrlError::PrivateAddress(
+                "169.254.169.254".to_string()
+            ))
+        );
+        assert_eq!(
+            policy.check("http://[::1]:8080/").err(),
+            Some(WebhookUrlError::PrivateAddress("::1".to_string()))
+        );
+
+        let private = WebhookUrlPolicy {
+            allow_private_networks: true,
+            ..WebhookUrlPolicy::default()
+        };
+        assert!(private.check("http://127.0.0.1:8080/hook").is_ok());
+    }
+
+    #[test]
+    fn test_policy_allowlist() {
+        let policy = WebhookUrlPolicy {
+            allowed_hosts: vec!["shop.example.com".to_string(), "*.example.net".to_string()],
+            allow_private_networks: false,
+        };
+        assert!(policy.check("https://shop.example.com/hook").is_ok());
+        assert!(policy.check("https://SHOP.example.com/hook").is_ok());
+        assert!(policy.check("https://a.b.example.net/hook").is_ok());
+        for url in [
+            "https://other.example.com/hook",
+            "https://example.net/hook",
+            "https://evilexample.net/hook",
+        ] {
+            assert!(
+                matches!(policy.check(url), Err(WebhookUrlError::HostNotAllowed(_))),
+                "{url} should not be allowed"
+            );
+        }
+    }
+}
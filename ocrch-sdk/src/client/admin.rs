@@ -9,9 +9,15 @@ use uuid::Uuid;
 
 use super::ClientError;
 use crate::objects::admin::{
-    AdminOrderResponse, AdminPendingDepositResponse, AdminTransferResponse, AdminWalletResponse,
-    ListDepositsQuery, ListOrdersQuery, ListTransfersQuery,
+    AdminApiKeyResponse, AdminDeadLetterResponse, AdminMerchantResponse, AdminOrderResponse,
+    AdminPendingDepositResponse, AdminSyncHealthResponse, AdminTransferResponse,
+    AdminWalletResponse, AdminWebhookAttemptResponse, CreateApiKeyRequest, CreatedApiKeyResponse,
+    ListApiKeysQuery, ListDeadLettersQuery, ListDepositsQuery, ListOrdersQuery, ListTransfersQuery,
+    PutMerchantRequest, PutWalletRequest, ReloadReport, ReplayDeadLettersRequest,
+    ReplayDeadLettersResponse, ResyncRequest, ResyncResponse, TestWebhookRequest,
+    TestWebhookResponse,
 };
+use crate::objects::blockchains::Blockchain;
 use crate::signature::ADMIN_AUTH_HEADER;
 
 /// Typed HTTP client for the Ocrch **Admin API**.
@@ -120,6 +126,121 @@ impl AdminClient {
         parse_response(resp).await
     }
 
+    /// `PUT /api/v1/admin/wallets/{blockchain}/{address}` – create or
+    /// replace a wallet stored in the database.
+    pub async fn put_wallet(
+        &self,
+        blockchain: Blockchain,
+        address: &str,
+        request: &PutWalletRequest,
+    ) -> Result<ReloadReport, ClientError> {
+        let url = self
+            .base_url
+            .join(&Self::wallet_path(blockchain, address))?;
+
+        let resp = self
+            .http
+            .put(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .json(request)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `DELETE /api/v1/admin/wallets/{blockchain}/{address}` – delete a
+    /// wallet stored in the database.
+    pub async fn delete_wallet(
+        &self,
+        blockchain: Blockchain,
+        address: &str,
+    ) -> Result<ReloadReport, ClientError> {
+        let url = self
+            .base_url
+            .join(&Self::wallet_path(blockchain, address))?;
+
+        let resp = self
+            .http
+            .delete(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `GET /api/v1/admin/merchants` – list configured merchants, without
+    /// their secrets.
+    pub async fn list_merchants(&self) -> Result<Vec<AdminMerchantResponse>, ClientError> {
+        let url = self.base_url.join("/api/v1/admin/merchants")?;
+
+        let resp = self
+            .http
+            .get(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `PUT /api/v1/admin/merchants/{merchant_id}` – create or replace a
+    /// merchant stored in the database.
+    pub async fn put_merchant(
+        &self,
+        merchant_id: &str,
+        request: &PutMerchantRequest,
+    ) -> Result<ReloadReport, ClientError> {
+        let url = self.base_url.join(&format!(
+            "/api/v1/admin/merchants/{}",
+            urlencoding::encode(merchant_id)
+        ))?;
+
+        let resp = self
+            .http
+            .put(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .json(request)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `DELETE /api/v1/admin/merchants/{merchant_id}` – delete a merchant
+    /// stored in the database.
+    pub async fn delete_merchant(&self, merchant_id: &str) -> Result<ReloadReport, ClientError> {
+        let url = self.base_url.join(&format!(
+            "/api/v1/admin/merchants/{}",
+            urlencoding::encode(merchant_id)
+        ))?;
+
+        let resp = self
+            .http
+            .delete(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `GET /api/v1/admin/sync-health` – sync health per (blockchain,
+    /// stablecoin) pair.
+    pub async fn sync_health(&self) -> Result<Vec<AdminSyncHealthResponse>, ClientError> {
+        let url = self.base_url.join("/api/v1/admin/sync-health")?;
+
+        let resp = self
+            .http
+            .get(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
     /// `POST /api/v1/admin/orders/{order_id}/mark-paid` – force-mark an
     /// order as paid.
     pub async fn mark_order_paid(&self, order_id: Uuid) -> Result<AdminOrderResponse, ClientError> {
@@ -182,6 +303,187 @@ impl AdminClient {
 
         Ok(())
     }
+
+    /// `GET /api/v1/admin/orders/{order_id}/webhook-attempts` – delivery
+    /// attempts for every webhook of an order, newest first.
+    pub async fn order_webhook_attempts(
+        &self,
+        order_id: Uuid,
+    ) -> Result<Vec<AdminWebhookAttemptResponse>, ClientError> {
+        let url = self
+            .base_url
+            .join(&format!("/api/v1/admin/orders/{order_id}/webhook-attempts"))?;
+
+        let resp = self
+            .http
+            .get(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `GET /api/v1/admin/transfers/{transfer_id}/webhook-attempts` –
+    /// delivery attempts for the unknown-transfer webhooks of a transfer,
+    /// newest first.
+    pub async fn transfer_webhook_attempts(
+        &self,
+        transfer_id: i64,
+    ) -> Result<Vec<AdminWebhookAttemptResponse>, ClientError> {
+        let url = self.base_url.join(&format!(
+            "/api/v1/admin/transfers/{transfer_id}/webhook-attempts"
+        ))?;
+
+        let resp = self
+            .http
+            .get(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `GET /api/v1/admin/webhook-dead-letters` – webhook deliveries that
+    /// exhausted their retries, most recently dead-lettered first.
+    pub async fn webhook_dead_letters(
+        &self,
+        query: &ListDeadLettersQuery,
+    ) -> Result<Vec<AdminDeadLetterResponse>, ClientError> {
+        let url = self.base_url.join("/api/v1/admin/webhook-dead-letters")?;
+
+        let resp = self
+            .http
+            .get(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .query(query)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `POST /api/v1/admin/webhook-dead-letters/replay` – schedule matching
+    /// dead-lettered deliveries again.
+    pub async fn replay_webhook_dead_letters(
+        &self,
+        request: &ReplayDeadLettersRequest,
+    ) -> Result<ReplayDeadLettersResponse, ClientError> {
+        let url = self
+            .base_url
+            .join("/api/v1/admin/webhook-dead-letters/replay")?;
+
+        let resp = self
+            .http
+            .post(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .json(request)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `POST /api/v1/admin/webhook-test` – send a signed test event and
+    /// return the endpoint's response.
+    pub async fn test_webhook(
+        &self,
+        request: &TestWebhookRequest,
+    ) -> Result<TestWebhookResponse, ClientError> {
+        let url = self.base_url.join("/api/v1/admin/webhook-test")?;
+
+        let resp = self
+            .http
+            .post(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .json(request)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `POST /api/v1/admin/resync` – re-fetch a wallet's transfers over a
+    /// historical range and report how many were new.
+    pub async fn resync(&self, request: &ResyncRequest) -> Result<ResyncResponse, ClientError> {
+        let url = self.base_url.join("/api/v1/admin/resync")?;
+
+        let resp = self
+            .http
+            .post(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .json(request)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `GET /api/v1/admin/api-keys` – list API keys, including revoked
+    /// ones, without their secrets.
+    pub async fn list_api_keys(
+        &self,
+        query: &ListApiKeysQuery,
+    ) -> Result<Vec<AdminApiKeyResponse>, ClientError> {
+        let url = self.base_url.join("/api/v1/admin/api-keys")?;
+
+        let resp = self
+            .http
+            .get(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .query(query)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `POST /api/v1/admin/api-keys` – create an API key. The response is
+    /// the only place its secret is returned.
+    pub async fn create_api_key(
+        &self,
+        request: &CreateApiKeyRequest,
+    ) -> Result<CreatedApiKeyResponse, ClientError> {
+        let url = self.base_url.join("/api/v1/admin/api-keys")?;
+
+        let resp = self
+            .http
+            .post(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .json(request)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    /// `DELETE /api/v1/admin/api-keys/{key_id}` – revoke an API key.
+    pub async fn revoke_api_key(&self, key_id: &str) -> Result<AdminApiKeyResponse, ClientError> {
+        let url = self.base_url.join(&format!(
+            "/api/v1/admin/api-keys/{}",
+            urlencoding::encode(key_id)
+        ))?;
+
+        let resp = self
+            .http
+            .delete(url)
+            .header(ADMIN_AUTH_HEADER, &self.admin_secret)
+            .send()
+            .await?;
+
+        parse_response(resp).await
+    }
+
+    fn wallet_path(blockchain: Blockchain, address: &str) -> String {
+        // The path segment is the blockchain's serialized name, e.g. `eth`
+        let blockchain = serde_json::to_string(&blockchain).unwrap_or_default();
+        format!(
+            "/api/v1/admin/wallets/{}/{}",
+            blockchain.trim_matches('"'),
+            urlencoding::encode(address)
+        )
+    }
 }
 
 async fn parse_response<T: serde::de::DeserializeOwned>(
@@ -12,7 +12,7 @@ mod ws;
 pub use admin::AdminClient;
 pub use service::ServiceClient;
 pub use user::UserClient;
-pub use webhook::verify_webhook;
+pub use webhook::{WebhookDeduplicator, verify_webhook, verify_webhook_once};
 pub use ws::OrderStatusStream;
 
 use reqwest::StatusCode;
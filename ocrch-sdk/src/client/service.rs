@@ -8,19 +8,24 @@ use url::Url;
 use uuid::Uuid;
 
 use super::ClientError;
-use crate::objects::create_payment::{GetOrderRequest, OrderResponse, PaymentCreatingEssential};
-use crate::signature::{SIGNATURE_HEADER, SignedObject};
+use crate::objects::create_payment::{
+    CancelOrderRequest, GetOrderRequest, ListOrdersRequest, LookupOrderRequest, OrderListResponse,
+    OrderResponse, PaymentCreatingEssential,
+};
+use crate::signature::{KEY_ID_HEADER, SIGNATURE_HEADER, Signature, SignedObject};
 
 /// Typed HTTP client for the Ocrch **Service API**.
 ///
-/// The service API is called by the merchant backend to create orders and
-/// query their status.  Every request body is signed with
+/// The service API is called by the merchant backend to create, query,
+/// list and cancel orders.  Every request body is signed with
 /// `HMAC-SHA256("{timestamp}.{json}", merchant_secret)`.
 #[derive(Debug, Clone)]
 pub struct ServiceClient {
     http: Client,
     base_url: Url,
     secret: Vec<u8>,
+    merchant_id: Option<String>,
+    key_id: Option<String>,
 }
 
 impl ServiceClient {
@@ -33,6 +38,41 @@ impl ServiceClient {
             http: Client::new(),
             base_url,
             secret: merchant_secret.into(),
+            merchant_id: None,
+            key_id: None,
+        }
+    }
+
+    /// Send requests for the merchant with the given ID instead of the
+    /// server's default merchant.
+    pub fn with_merchant(mut self, merchant_id: impl Into<String>) -> Self {
+        self.merchant_id = Some(merchant_id.into());
+        self
+    }
+
+    /// Sign requests with an API key instead of the merchant secret. Pass
+    /// the key's secret to [`ServiceClient::new`] and its ID here.
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    fn api_url(&self, path: &str) -> Result<Url, url::ParseError> {
+        match &self.merchant_id {
+            Some(id) => self
+                .base_url
+                .join(&format!("/api/v1/service/merchants/{id}{path}")),
+            None => self.base_url.join(&format!("/api/v1/service{path}")),
+        }
+    }
+
+    /// A POST request carrying the `Ocrch-Key-Id` header if an API key is
+    /// used.
+    fn post(&self, url: Url) -> reqwest::RequestBuilder {
+        let request = self.http.post(url);
+        match &self.key_id {
+            Some(key_id) => request.header(KEY_ID_HEADER, key_id),
+            None => request,
         }
     }
 
@@ -44,36 +84,70 @@ impl ServiceClient {
     }
 
     /// `POST /api/v1/service/orders` – create a new pending order.
+    ///
+    /// Safe to retry: if an order with the same `order_id` and parameters
+    /// exists, it is returned instead. Different parameters for an existing
+    /// `order_id` fail with `409 Conflict`.
     pub async fn create_order(
         &self,
         payload: PaymentCreatingEssential,
     ) -> Result<OrderResponse, ClientError> {
-        let signed = SignedObject::new(payload, &self.secret).map_err(ClientError::Json)?;
-
-        let url = self.base_url.join("/api/v1/service/orders")?;
-
-        let resp = self
-            .http
-            .post(url)
-            .header(SIGNATURE_HEADER, signed.to_header())
-            .body(signed.json)
-            .header(reqwest::header::CONTENT_TYPE, "application/json")
-            .send()
-            .await?;
-
-        parse_response(resp).await
+        self.post_signed("/orders", payload).await
     }
 
     /// `POST /api/v1/service/orders/status` – get the status of an existing
     /// order.
     pub async fn get_order_status(&self, order_id: Uuid) -> Result<OrderResponse, ClientError> {
-        let body = GetOrderRequest { order_id };
+        self.post_signed("/orders/status", GetOrderRequest { order_id })
+            .await
+    }
+
+    /// `POST /api/v1/service/orders/lookup` – get an order by the
+    /// merchant order ID it was created with.
+    pub async fn lookup_order(
+        &self,
+        merchant_order_id: impl Into<String>,
+    ) -> Result<OrderResponse, ClientError> {
+        let body = LookupOrderRequest {
+            merchant_order_id: merchant_order_id.into(),
+        };
+        self.post_signed("/orders/lookup", body).await
+    }
+
+    /// `POST /api/v1/service/orders/list` – list orders, newest first.
+    ///
+    /// Pass the returned `next_cursor` as `cursor` to get the next page.
+    pub async fn list_orders(
+        &self,
+        request: ListOrdersRequest,
+    ) -> Result<OrderListResponse, ClientError> {
+        self.post_signed("/orders/list", request).await
+    }
+
+    /// `POST /api/v1/service/orders/cancel` – cancel a pending order, or
+    /// mark it as expired if `expire` is set.
+    ///
+    /// Fails with `409 Conflict` if the order is no longer pending.
+    pub async fn cancel_order(
+        &self,
+        order_id: Uuid,
+        expire: bool,
+    ) -> Result<OrderResponse, ClientError> {
+        self.post_signed("/orders/cancel", CancelOrderRequest { order_id, expire })
+            .await
+    }
+
+    /// Sign `body` and POST it to `path`.
+    async fn post_signed<B: Signature, R: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        body: B,
+    ) -> Result<R, ClientError> {
         let signed = SignedObject::new(body, &self.secret).map_err(ClientError::Json)?;
 
-        let url = self.base_url.join("/api/v1/service/orders/status")?;
+        let url = self.api_url(path)?;
 
         let resp = self
-            .http
             .post(url)
             .header(SIGNATURE_HEADER, signed.to_header())
             .body(signed.json)
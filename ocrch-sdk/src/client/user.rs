@@ -12,7 +12,7 @@ use uuid::Uuid;
 use super::{ClientError, OrderStatusStream};
 use crate::objects::create_payment::OrderResponse;
 use crate::objects::user::{ChainCoinPair, PaymentDetail, SelectPaymentMethod};
-use crate::signature::{SIGNATURE_HEADER, SIGNED_URL_HEADER, sign_url};
+use crate::signature::{KEY_ID_HEADER, SIGNATURE_HEADER, SIGNED_URL_HEADER, sign_url};
 
 /// Typed HTTP client for the Ocrch **User API**.
 ///
@@ -31,6 +31,8 @@ pub struct UserClient {
     base_url: Url,
     secret: Vec<u8>,
     frontend_url: String,
+    merchant_id: Option<String>,
+    key_id: Option<String>,
 }
 
 impl UserClient {
@@ -50,6 +52,32 @@ impl UserClient {
             base_url,
             secret: merchant_secret.into(),
             frontend_url: frontend_url.into(),
+            merchant_id: None,
+            key_id: None,
+        }
+    }
+
+    /// Send requests for the merchant with the given ID instead of the
+    /// server's default merchant.
+    pub fn with_merchant(mut self, merchant_id: impl Into<String>) -> Self {
+        self.merchant_id = Some(merchant_id.into());
+        self
+    }
+
+    /// Sign URLs with an API key holding the `sign_urls` scope instead of
+    /// the merchant secret. Pass the key's secret to [`UserClient::new`] and
+    /// its ID here.
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    fn api_url(&self, path: &str) -> Result<Url, url::ParseError> {
+        match &self.merchant_id {
+            Some(id) => self
+                .base_url
+                .join(&format!("/api/v1/user/merchants/{id}{path}")),
+            None => self.base_url.join(&format!("/api/v1/user{path}")),
         }
     }
 
@@ -59,6 +87,15 @@ impl UserClient {
         self
     }
 
+    /// A request carrying the `Ocrch-Key-Id` header if an API key is used.
+    fn request(&self, method: reqwest::Method, url: Url) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, url);
+        match &self.key_id {
+            Some(key_id) => request.header(KEY_ID_HEADER, key_id),
+            None => request,
+        }
+    }
+
     fn sign_headers(&self) -> (String, &str) {
         let sig = sign_url(&self.frontend_url, &self.secret);
         (sig, &self.frontend_url)
@@ -69,11 +106,10 @@ impl UserClient {
     pub async fn list_chains(&self) -> Result<Vec<ChainCoinPair>, ClientError> {
         let (sig, signed_url) = self.sign_headers();
 
-        let url = self.base_url.join("/api/v1/user/chains")?;
+        let url = self.api_url("/chains")?;
 
         let resp = self
-            .http
-            .get(url)
+            .request(reqwest::Method::GET, url)
             .header(SIGNATURE_HEADER, sig)
             .header(SIGNED_URL_HEADER, signed_url)
             .send()
@@ -91,13 +127,10 @@ impl UserClient {
     ) -> Result<PaymentDetail, ClientError> {
         let (sig, signed_url) = self.sign_headers();
 
-        let url = self
-            .base_url
-            .join(&format!("/api/v1/user/orders/{order_id}/payment"))?;
+        let url = self.api_url(&format!("/orders/{order_id}/payment"))?;
 
         let resp = self
-            .http
-            .post(url)
+            .request(reqwest::Method::POST, url)
             .header(SIGNATURE_HEADER, sig)
             .header(SIGNED_URL_HEADER, signed_url)
             .json(&method)
@@ -111,13 +144,10 @@ impl UserClient {
     pub async fn cancel_order(&self, order_id: Uuid) -> Result<OrderResponse, ClientError> {
         let (sig, signed_url) = self.sign_headers();
 
-        let url = self
-            .base_url
-            .join(&format!("/api/v1/user/orders/{order_id}/cancel"))?;
+        let url = self.api_url(&format!("/orders/{order_id}/cancel"))?;
 
         let resp = self
-            .http
-            .post(url)
+            .request(reqwest::Method::POST, url)
             .header(SIGNATURE_HEADER, sig)
             .header(SIGNED_URL_HEADER, signed_url)
             .send()
@@ -141,9 +171,7 @@ impl UserClient {
         let (sig, signed_url) = self.sign_headers();
 
         // Derive a WebSocket URL from the HTTP base URL by swapping the scheme.
-        let http_url = self
-            .base_url
-            .join(&format!("/api/v1/user/orders/{order_id}/ws"))?;
+        let http_url = self.api_url(&format!("/orders/{order_id}/ws"))?;
 
         let ws_scheme = match http_url.scheme() {
             "https" => "wss",
@@ -158,10 +186,14 @@ impl UserClient {
             u
         };
 
-        let request = tokio_tungstenite::tungstenite::http::Request::builder()
+        let mut request = tokio_tungstenite::tungstenite::http::Request::builder()
             .uri(ws_url.as_str())
             .header(SIGNATURE_HEADER, sig.as_str())
-            .header(SIGNED_URL_HEADER, signed_url)
+            .header(SIGNED_URL_HEADER, signed_url);
+        if let Some(key_id) = &self.key_id {
+            request = request.header(KEY_ID_HEADER, key_id.as_str());
+        }
+        let request = request
             .body(())
             .map_err(tokio_tungstenite::tungstenite::Error::HttpFormat)
             .map_err(ClientError::Ws)?;
@@ -175,13 +207,10 @@ impl UserClient {
     pub async fn get_order_status(&self, order_id: Uuid) -> Result<OrderResponse, ClientError> {
         let (sig, signed_url) = self.sign_headers();
 
-        let url = self
-            .base_url
-            .join(&format!("/api/v1/user/orders/{order_id}/status"))?;
+        let url = self.api_url(&format!("/orders/{order_id}/status"))?;
 
         let resp = self
-            .http
-            .get(url)
+            .request(reqwest::Method::GET, url)
             .header(SIGNATURE_HEADER, sig)
             .header(SIGNED_URL_HEADER, signed_url)
             .send()
@@ -1,8 +1,15 @@
 //! Webhook signature verification helper.
 //!
 //! Convenience wrapper around [`SignedObject`] for verifying incoming
-//! webhook payloads sent by the Ocrch server.
+//! webhook payloads sent by the Ocrch server, and an in-memory
+//! [`WebhookDeduplicator`] for dropping repeated deliveries of an event.
 
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, PoisonError};
+
+use uuid::Uuid;
+
+use crate::objects::WebhookEventId;
 use crate::signature::{Signature, SignatureError, SignedObject};
 
 /// Verify and deserialize an incoming Ocrch webhook.
@@ -29,3 +36,124 @@ pub fn verify_webhook<T: Signature>(
 ) -> Result<T, SignatureError> {
     SignedObject::<T>::from_header_and_body(signature_header, body.to_owned())?.verify(secret)
 }
+
+/// Verify an incoming Ocrch webhook and drop it if its event was already
+/// seen by `dedup`.
+///
+/// Returns `Ok(None)` for a repeated delivery. The event is only recorded
+/// once its signature has been verified; call
+/// [`WebhookDeduplicator::forget`] if handling it fails, so that the next
+/// delivery is processed.
+///
+/// # Example
+///
+/// ```ignore
+/// use ocrch_sdk::client::{WebhookDeduplicator, verify_webhook_once};
+/// use ocrch_sdk::objects::webhook::OrderStatusChangedPayload;
+///
+/// let dedup = WebhookDeduplicator::new(10_000);
+/// if let Some(payload) = verify_webhook_once::<OrderStatusChangedPayload>(
+///     signature_header, &body, merchant_secret, &dedup,
+/// )? {
+///     handle(payload);
+/// }
+/// ```
+pub fn verify_webhook_once<T: Signature + WebhookEventId>(
+    signature_header: &str,
+    body: &str,
+    secret: &[u8],
+    dedup: &WebhookDeduplicator,
+) -> Result<Option<T>, SignatureError> {
+    let payload: T = verify_webhook(signature_header, body, secret)?;
+    Ok(dedup.first_seen(payload.event_id()).then_some(payload))
+}
+
+/// Remembers the IDs of the most recent webhook events.
+///
+/// Holds at most `capacity` IDs and forgets the oldest first. The set lives
+/// in memory, so receivers running several instances or needing to survive
+/// restarts should store event IDs in their own database instead.
+#[derive(Debug)]
+pub struct WebhookDeduplicator {
+    capacity: usize,
+    seen: Mutex<SeenEvents>,
+}
+
+#[derive(Debug, Default)]
+struct SeenEvents {
+    ids: HashSet<Uuid>,
+    order: VecDeque<Uuid>,
+}
+
+impl WebhookDeduplicator {
+    /// Create a deduplicator remembering up to `capacity` event IDs.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            seen: Mutex::new(SeenEvents::default()),
+        }
+    }
+
+    /// Record an event ID, returning `true` if it had not been seen before.
+    pub fn first_seen(&self, event_id: Uuid) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        if !seen.ids.insert(event_id) {
+            return false;
+        }
+        seen.order.push_back(event_id);
+        while seen.order.len() > self.capacity {
+            if let Some(oldest) = seen.order.pop_front() {
+                seen.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Forget an event ID, so its next delivery is treated as new.
+    pub fn forget(&self, event_id: Uuid) {
+        let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+        if seen.ids.remove(&event_id) {
+            seen.order.retain(|id| *id != event_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_is_rejected() {
+        let dedup = WebhookDeduplicator::new(10);
+        let id = Uuid::from_u128(1);
+        assert!(dedup.first_seen(id));
+        assert!(!dedup.first_seen(id));
+        assert!(dedup.first_seen(Uuid::from_u128(2)));
+        assert!(!dedup.first_seen(id));
+    }
+
+    #[test]
+    fn test_forgotten_id_is_accepted_again() {
+        let dedup = WebhookDeduplicator::new(10);
+        let id = Uuid::from_u128(1);
+        assert!(dedup.first_seen(id));
+        dedup.forget(id);
+        assert!(dedup.first_seen(id));
+        assert!(!dedup.first_seen(id));
+    }
+
+    #[test]
+    fn test_oldest_id_is_evicted_at_capacity() {
+        let dedup = WebhookDeduplicator::new(2);
+        let (a, b, c) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        assert!(dedup.first_seen(a));
+        assert!(dedup.first_seen(b));
+        assert!(dedup.first_seen(c));
+        // `a` was evicted to make room for `c`
+        assert!(!dedup.first_seen(b));
+        assert!(!dedup.first_seen(c));
+        assert!(dedup.first_seen(a));
+        // ...and accepting `a` again evicted `b`
+        assert!(dedup.first_seen(b));
+    }
+}
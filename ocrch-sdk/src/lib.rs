@@ -7,6 +7,8 @@
 //! - [`signature`] – HMAC-SHA256 signing and verification primitives.
 //! - [`client`] (feature `client`) – typed HTTP clients for the Admin, Service,
 //!   and User APIs, plus a webhook verification helper.
+//! - [`receiver`] (feature `axum`) – an axum extractor and a tower layer that
+//!   verify incoming webhooks.
 
 #![deny(clippy::unwrap_used)]
 #![deny(clippy::expect_used)]
@@ -20,3 +22,6 @@ pub mod signature;
 
 #[cfg(feature = "client")]
 pub mod client;
+
+#[cfg(feature = "axum")]
+pub mod receiver;
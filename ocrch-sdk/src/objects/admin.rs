@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::blockchains::{Blockchain, Stablecoin};
-use super::webhook::{OrderStatus, TransferStatus};
+use super::webhook::{OrderStatus, TransferStatus, WebhookEventType};
 
 // ---------------------------------------------------------------------------
 // Responses
@@ -15,6 +15,8 @@ use super::webhook::{OrderStatus, TransferStatus};
 pub struct AdminOrderResponse {
     /// Internal order ID.
     pub order_id: Uuid,
+    /// ID of the merchant the order belongs to.
+    pub merchant_id: String,
     /// Merchant-assigned order identifier.
     pub merchant_order_id: String,
     /// Payment amount in the selected stablecoin.
@@ -23,14 +25,21 @@ pub struct AdminOrderResponse {
     pub status: OrderStatus,
     /// Unix timestamp of when the order was created.
     pub created_at: i64,
-    /// Merchant webhook URL for order status change events.
-    pub webhook_url: String,
+    /// Merchant webhook URL for order events, or `None` to use the
+    /// configured default.
+    pub webhook_url: Option<String>,
     /// Number of times the webhook has been attempted.
     pub webhook_retry_count: i32,
     /// Unix timestamp of the first successful webhook delivery, if any.
     pub webhook_success_at: Option<i64>,
     /// Unix timestamp of the most recent webhook attempt, if any.
     pub webhook_last_tried_at: Option<i64>,
+    /// Merchant-supplied JSON object.
+    pub metadata: Option<serde_json::Value>,
+    /// Human-readable description.
+    pub description: Option<String>,
+    /// Customer reference or email.
+    pub customer_reference: Option<String>,
 }
 
 /// Unified pending deposit response covering both ERC-20 and TRC-20.
@@ -96,6 +105,424 @@ pub struct AdminWalletResponse {
     pub address: String,
     /// Stablecoins enabled for this wallet.
     pub enabled_coins: Vec<Stablecoin>,
+    /// ID of the merchant owning this wallet, or `None` if it is shared by
+    /// all merchants.
+    pub merchant: Option<String>,
+    /// Transaction hash the initial sync starts from, if set.
+    pub starting_tx: Option<String>,
+    /// Whether the wallet is synced and offered to customers.
+    pub enabled: bool,
+    /// Whether the wallet is stored in the database (managed through the
+    /// Admin API) rather than only in the config file.
+    pub managed: bool,
+}
+
+/// Merchant settings, without secrets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminMerchantResponse {
+    /// Unique merchant ID.
+    pub id: String,
+    /// Human-readable merchant name.
+    pub name: String,
+    /// Key IDs of the active named secrets. Empty if the merchant has a
+    /// single unnamed secret.
+    pub secret_ids: Vec<String>,
+    /// Allowed origins for signed checkout URLs.
+    pub allowed_origins: Vec<String>,
+    /// Webhook URL for unknown transfers, if any.
+    pub unknown_transfer_webhook_url: Option<String>,
+    /// Webhook URL for orders created without one, if any.
+    pub default_webhook_url: Option<String>,
+    /// Payload schema version (1 or 2) of the merchant's webhooks.
+    pub webhook_payload_version: u8,
+    /// Hosts that order webhook URLs may point to.
+    pub webhook_allowed_hosts: Vec<String>,
+    /// Whether order webhook URLs may point to private addresses.
+    pub webhook_allow_private_networks: bool,
+    /// Whether this is the default merchant, used by paths without a
+    /// merchant ID.
+    pub default: bool,
+    /// Whether the merchant is stored in the database (managed through the
+    /// Admin API) rather than only in the config file.
+    pub managed: bool,
+}
+
+/// Sync health of a single (blockchain, stablecoin) pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminSyncHealthResponse {
+    /// Blockchain being synced.
+    pub blockchain: Blockchain,
+    /// Stablecoin being synced.
+    pub token: Stablecoin,
+    /// Whether the pair is below the configured failure threshold.
+    /// Unhealthy pairs are hidden from the User API.
+    pub healthy: bool,
+    /// Unix timestamp of the last sync attempt, if any.
+    pub last_attempt_at: Option<i64>,
+    /// Unix timestamp of the last successful sync, if any.
+    pub last_success_at: Option<i64>,
+    /// Number of failed syncs since the last success.
+    pub consecutive_failures: u32,
+    /// Error message of the most recent failure, if still failing.
+    pub last_error: Option<String>,
+    /// Highest block up to which every transfer has been synced.
+    pub latest_block: Option<i64>,
+    /// Most recently observed chain head.
+    pub chain_head: Option<i64>,
+    /// Blocks between `chain_head` and `latest_block`.
+    pub lag: Option<i64>,
+}
+
+/// Result of a historical transfer resync.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResyncResponse {
+    /// Number of transfers that were not already stored.
+    pub inserted: u32,
+}
+
+/// A single sync runner, identified by the wallet and stablecoin it syncs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncRunnerRef {
+    /// Blockchain the wallet is on.
+    pub blockchain: Blockchain,
+    /// Wallet address being synced.
+    pub wallet_address: String,
+    /// Stablecoin being synced.
+    pub token: Stablecoin,
+}
+
+/// Changes applied to the running sync tasks by a configuration reload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadReport {
+    /// Unix timestamp when the reload was applied.
+    pub reloaded_at: i64,
+    /// Runners spawned for newly configured wallet-coin pairs.
+    pub added: Vec<SyncRunnerRef>,
+    /// Runners shut down because their wallet-coin pair was removed.
+    pub removed: Vec<SyncRunnerRef>,
+    /// Runners respawned because their wallet settings or API key changed.
+    pub restarted: Vec<SyncRunnerRef>,
+    /// Whether the EtherScan API key changed.
+    pub etherscan_api_key_changed: bool,
+    /// Whether the TronScan API key changed.
+    pub tronscan_api_key_changed: bool,
+    /// Chains whose WebSocket log subscriber was started, stopped or respawned.
+    pub log_subscribers_changed: Vec<Blockchain>,
+}
+
+/// An API key, without its secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminApiKeyResponse {
+    /// Key ID, sent in the `Ocrch-Key-Id` header.
+    pub id: String,
+    /// Merchant the key acts for.
+    pub merchant_id: String,
+    /// Human-readable name.
+    pub name: String,
+    /// Operations the key may perform.
+    pub scopes: Vec<ApiKeyScope>,
+    /// Unix timestamp of when the key was created.
+    pub created_at: i64,
+    /// Unix timestamp after which the key is rejected, if any.
+    pub expires_at: Option<i64>,
+    /// Unix timestamp of the key's last accepted request (updated at most
+    /// once a minute).
+    pub last_used_at: Option<i64>,
+    /// Unix timestamp of when the key was revoked, if it was.
+    pub revoked_at: Option<i64>,
+}
+
+/// A newly created API key. The secret is only returned here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedApiKeyResponse {
+    /// The stored key.
+    #[serde(flatten)]
+    pub key: AdminApiKeyResponse,
+    /// Secret to sign requests with.
+    pub secret: String,
+}
+
+/// Why a webhook delivery attempt failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDeliveryErrorKind {
+    /// The request timed out.
+    Timeout,
+    /// The connection to the endpoint could not be established.
+    Connect,
+    /// The endpoint answered with a non-success status.
+    HttpStatus,
+    /// Any other request error.
+    Request,
+}
+
+/// A single webhook delivery attempt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminWebhookAttemptResponse {
+    /// Internal attempt ID.
+    pub id: i64,
+    /// Internal ID of the webhook event this attempt delivered.
+    pub outbox_id: i64,
+    /// ID of the webhook event, as sent in the `Ocrch-Event-Id` header and
+    /// shared by retries and manual resends.
+    pub event_id: Uuid,
+    /// Type of the webhook event.
+    pub event_type: WebhookEventType,
+    /// Name of the `[[webhooks]]` subscription the attempt was made for, or
+    /// `None` for the event's own URL.
+    pub subscription: Option<String>,
+    /// URL the request was sent to.
+    pub url: String,
+    /// Hex-encoded SHA-256 of the request body.
+    pub body_sha256: String,
+    /// HTTP status of the response, if one was received.
+    pub response_status: Option<u16>,
+    /// Start of the response body (at most 1 KiB), if one was received.
+    pub response_body: Option<String>,
+    /// Time until the response headers arrived or the request failed.
+    pub latency_ms: u32,
+    /// Why the attempt failed, or `None` if it succeeded.
+    pub error_kind: Option<WebhookDeliveryErrorKind>,
+    /// Error message of a failed attempt.
+    pub error: Option<String>,
+    /// Unix timestamp of the attempt.
+    pub attempted_at: i64,
+}
+
+/// A webhook delivery that failed on every retry and is no longer attempted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminDeadLetterResponse {
+    /// Internal delivery ID.
+    pub id: i64,
+    /// Internal ID of the webhook event, as in [`AdminWebhookAttemptResponse`].
+    pub outbox_id: i64,
+    /// ID of the webhook event, as sent in the `Ocrch-Event-Id` header.
+    pub event_id: Uuid,
+    /// Type of the webhook event.
+    pub event_type: WebhookEventType,
+    /// Name of the `[[webhooks]]` subscription, or `None` for the event's
+    /// own URL.
+    pub subscription: Option<String>,
+    /// Order of an order event.
+    pub order_id: Option<Uuid>,
+    /// Transfer of an unknown transfer event.
+    pub transfer_id: Option<i64>,
+    /// Number of failed attempts.
+    pub retry_count: i32,
+    /// URL of the last attempt, if a request was made.
+    pub url: Option<String>,
+    /// Error of the last attempt.
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last attempt, if any.
+    pub last_tried_at: Option<i64>,
+    /// Unix timestamp of when the delivery was dead-lettered.
+    pub dead_lettered_at: i64,
+}
+
+/// Result of a test webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestWebhookResponse {
+    /// URL the request was sent to.
+    pub url: String,
+    /// Event ID sent in the payload and the `Ocrch-Event-Id` header.
+    pub event_id: Uuid,
+    /// JSON body that was sent.
+    pub request_body: String,
+    /// `Ocrch-Signature` header that was sent.
+    pub signature: String,
+    /// HTTP status of the response, if one was received.
+    pub response_status: Option<u16>,
+    /// Start of the response body (at most 1 KiB), if one was received.
+    pub response_body: Option<String>,
+    /// Time until the response headers arrived or the request failed.
+    pub latency_ms: u32,
+    /// Why the request failed, or `None` if the endpoint accepted it.
+    pub error_kind: Option<WebhookDeliveryErrorKind>,
+    /// Error message of a failed request.
+    pub error: Option<String>,
+}
+
+/// Result of a dead-letter replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayDeadLettersResponse {
+    /// Number of deliveries scheduled again.
+    pub replayed: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Requests
+// ---------------------------------------------------------------------------
+
+/// Operation an API key may perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    /// Create orders through the Service API.
+    CreateOrders,
+    /// Read orders through the Service API.
+    ReadOrders,
+    /// Cancel orders through the Service API.
+    CancelOrders,
+    /// Sign checkout URLs for the User API.
+    SignUrls,
+}
+
+/// Request to create an API key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApiKeyRequest {
+    /// Merchant the key acts for; defaults to the default merchant.
+    #[serde(default)]
+    pub merchant_id: Option<String>,
+    /// Human-readable name.
+    pub name: String,
+    /// Operations the key may perform; at least one.
+    pub scopes: Vec<ApiKeyScope>,
+    /// Unix timestamp after which the key is rejected.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+/// Event sent by a test webhook.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestWebhookEvent {
+    /// A [`PingPayload`](crate::objects::PingPayload).
+    #[default]
+    Ping,
+    /// An `order_status_changed` event of a fake, paid order.
+    OrderStatusChanged,
+}
+
+/// Request to send a test webhook. Exactly one of `url` and `subscription`
+/// must be set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestWebhookRequest {
+    /// Event to send.
+    #[serde(default)]
+    pub event: TestWebhookEvent,
+    /// URL to send the event to, signed with the merchant secrets.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Merchant whose settings and secrets the event is sent with.
+    /// Defaults to the default merchant.
+    #[serde(default)]
+    pub merchant_id: Option<String>,
+    /// Name of the `[[webhooks]]` subscription to send the event to, with
+    /// its URL, secret and payload version.
+    #[serde(default)]
+    pub subscription: Option<String>,
+    /// Payload schema version (1 or 2) of an `order_status_changed` event.
+    /// Defaults to the version configured for the target.
+    #[serde(default)]
+    pub payload_version: Option<u8>,
+}
+
+/// Request to replay dead-lettered webhook deliveries. Set at least one
+/// filter, or `all` to replay every dead letter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayDeadLettersRequest {
+    /// Only deliveries whose last attempt went to this host.
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Only deliveries of this event type.
+    #[serde(default)]
+    pub event_type: Option<WebhookEventType>,
+    /// Only deliveries dead-lettered at or after this Unix timestamp.
+    #[serde(default)]
+    pub since: Option<i64>,
+    /// Only deliveries dead-lettered at or before this Unix timestamp.
+    #[serde(default)]
+    pub until: Option<i64>,
+    /// Replay every dead letter when no filter is set.
+    #[serde(default)]
+    pub all: bool,
+}
+
+/// Request to re-fetch a wallet's transfers over a historical range.
+///
+/// The range is inclusive and uses block numbers on EVM chains and
+/// millisecond timestamps on Tron.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResyncRequest {
+    /// Blockchain the wallet is on.
+    pub blockchain: Blockchain,
+    /// Configured wallet address to rescan.
+    pub wallet_address: String,
+    /// Stablecoin to rescan.
+    pub token: Stablecoin,
+    /// Range start (block number or millisecond timestamp).
+    pub start: i64,
+    /// Range end (block number or millisecond timestamp).
+    pub end: i64,
+}
+
+/// Request to create or replace a managed wallet. The blockchain and
+/// address are taken from the path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PutWalletRequest {
+    /// Stablecoins to accept at this wallet.
+    pub enabled_coins: Vec<Stablecoin>,
+    /// Transaction hash the initial sync starts from.
+    #[serde(default)]
+    pub starting_tx: Option<String>,
+    /// ID of the merchant owning the wallet, or `None` to share it.
+    #[serde(default)]
+    pub merchant: Option<String>,
+    /// Set to `false` to stop syncing the wallet and offering it to
+    /// customers, including a wallet from the config file.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// Request to create or replace a managed merchant. The merchant ID is
+/// taken from the path.
+///
+/// Set exactly one of `secret` and `secrets`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PutMerchantRequest {
+    /// Human-readable merchant name.
+    pub name: String,
+    /// Single HMAC secret.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Active secrets with key IDs, used instead of `secret` to rotate keys.
+    #[serde(default)]
+    pub secrets: Vec<MerchantSecretRequest>,
+    /// Allowed origins for signed checkout URLs.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Webhook URL for unknown transfers.
+    #[serde(default)]
+    pub unknown_transfer_webhook_url: Option<String>,
+    /// Webhook URL for orders created without one.
+    #[serde(default)]
+    pub default_webhook_url: Option<String>,
+    /// Payload schema version (1 or 2) of the merchant's webhooks.
+    #[serde(default = "default_payload_version")]
+    pub webhook_payload_version: u8,
+    /// Hosts that order webhook URLs may point to.
+    #[serde(default)]
+    pub webhook_allowed_hosts: Vec<String>,
+    /// Allow order webhook URLs that point to private addresses.
+    #[serde(default)]
+    pub webhook_allow_private_networks: bool,
+}
+
+/// A merchant secret with its key ID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MerchantSecretRequest {
+    /// Key ID sent in signature headers.
+    pub id: String,
+    /// Secret key.
+    pub secret: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_payload_version() -> u8 {
+    1
 }
 
 // ---------------------------------------------------------------------------
@@ -119,6 +546,15 @@ pub struct ListOrdersQuery {
     pub status: Option<OrderStatus>,
     /// Filter by merchant-assigned order ID.
     pub merchant_order_id: Option<String>,
+    /// Filter by merchant.
+    pub merchant_id: Option<String>,
+    /// Only orders whose metadata contains this JSON object, e.g.
+    /// `{"user_id":"42"}`.
+    pub metadata: Option<String>,
+    /// Only orders whose description contains this text (case-insensitive).
+    pub description: Option<String>,
+    /// Filter by customer reference (case-insensitive).
+    pub customer_reference: Option<String>,
 }
 
 /// Query parameters for listing pending deposits.
@@ -136,6 +572,8 @@ pub struct ListDepositsQuery {
     pub blockchain: Option<Blockchain>,
     /// Filter by stablecoin.
     pub token: Option<Stablecoin>,
+    /// Filter by the merchant of the deposit's order.
+    pub merchant_id: Option<String>,
 }
 
 /// Query parameters for listing transfers by wallet.
@@ -155,7 +593,33 @@ pub struct ListTransfersQuery {
     pub token: Option<Stablecoin>,
 }
 
-fn default_limit() -> i64 {
+/// Query parameters for listing API keys.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListApiKeysQuery {
+    /// Filter by merchant.
+    pub merchant_id: Option<String>,
+}
+
+/// Query parameters for listing dead-lettered webhook deliveries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListDeadLettersQuery {
+    /// Maximum number of results to return (default 20, max 200).
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    /// Number of results to skip for pagination.
+    #[serde(default)]
+    pub offset: i64,
+    /// Filter by the host of the last attempt's URL.
+    pub host: Option<String>,
+    /// Filter by event type.
+    pub event_type: Option<WebhookEventType>,
+    /// Only deliveries dead-lettered at or after this Unix timestamp.
+    pub since: Option<i64>,
+    /// Only deliveries dead-lettered at or before this Unix timestamp.
+    pub until: Option<i64>,
+}
+
+pub(crate) fn default_limit() -> i64 {
     DEFAULT_LIMIT
 }
 
@@ -6,10 +6,19 @@ use crate::signature::Signature;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Maximum size of an order's `metadata`, in bytes of compact JSON.
+pub const MAX_METADATA_SIZE: usize = 4096;
+
+/// Maximum length of an order's `description`, in characters.
+pub const MAX_DESCRIPTION_LENGTH: usize = 1000;
+
+/// Maximum length of an order's `customer_reference`, in characters.
+pub const MAX_CUSTOMER_REFERENCE_LENGTH: usize = 255;
+
 /// Request payload for creating a new order.
 ///
 /// Sent by the application backend to the Service API.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PaymentCreatingEssential {
     /// Payment amount in the selected stablecoin.
     pub amount: rust_decimal::Decimal,
@@ -21,8 +30,31 @@ pub struct PaymentCreatingEssential {
     pub blockchain: Option<blockchains::Blockchain>,
     /// Pre-selected stablecoin, or `None` to let the user choose.
     pub stablecoin: Option<blockchains::Stablecoin>,
-    /// URL that the Ocrch server will POST webhook events to.
-    pub webhook_url: String,
+    /// URL that the Ocrch server will POST webhook events for this order to,
+    /// or `None` to use the configured `merchant.default_webhook_url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Arbitrary JSON object stored with the order, at most
+    /// [`MAX_METADATA_SIZE`] bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// Human-readable description, at most [`MAX_DESCRIPTION_LENGTH`]
+    /// characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Customer reference or email, at most
+    /// [`MAX_CUSTOMER_REFERENCE_LENGTH`] characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer_reference: Option<String>,
+    /// URL the checkout frontend redirects the user to after payment. Its
+    /// origin must be one of the merchant's allowed origins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success_url: Option<String>,
+    /// URL the checkout frontend redirects the user to after the order is
+    /// cancelled or expires. Its origin must be one of the merchant's
+    /// allowed origins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancel_url: Option<String>,
 }
 
 impl Signature for PaymentCreatingEssential {}
@@ -38,7 +70,74 @@ pub struct GetOrderRequest {
 
 impl Signature for GetOrderRequest {}
 
-/// Response returned by both the "create order" and "get order status" endpoints.
+/// Request payload for looking up an order by the merchant's own order ID.
+///
+/// Sent by the application backend to the Service API.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LookupOrderRequest {
+    /// Merchant-assigned order identifier given when the order was created.
+    pub merchant_order_id: String,
+}
+
+impl Signature for LookupOrderRequest {}
+
+/// Request payload for listing the merchant's orders, newest first.
+///
+/// Sent by the application backend to the Service API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListOrdersRequest {
+    /// Maximum number of orders to return (default 20, max 200).
+    #[serde(default = "super::admin::default_limit")]
+    pub limit: i64,
+    /// Only orders with this status.
+    pub status: Option<OrderStatus>,
+    /// Only orders created at or after this Unix timestamp.
+    pub since: Option<i64>,
+    /// Only orders created at or before this Unix timestamp.
+    pub until: Option<i64>,
+    /// [`OrderListResponse::next_cursor`] of the previous page, or `None`
+    /// for the first page.
+    pub cursor: Option<Uuid>,
+}
+
+impl Default for ListOrdersRequest {
+    fn default() -> Self {
+        Self {
+            limit: super::admin::default_limit(),
+            status: None,
+            since: None,
+            until: None,
+            cursor: None,
+        }
+    }
+}
+
+impl Signature for ListOrdersRequest {}
+
+/// A page of orders returned by the "list orders" endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderListResponse {
+    /// Orders, newest first.
+    pub orders: Vec<OrderResponse>,
+    /// Cursor of the next page, or `None` if this is the last page.
+    pub next_cursor: Option<Uuid>,
+}
+
+/// Request payload for cancelling or expiring a pending order.
+///
+/// Sent by the application backend to the Service API.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CancelOrderRequest {
+    /// Internal Ocrch order ID returned when the order was created.
+    pub order_id: Uuid,
+    /// Mark the order as `Expired` instead of `Cancelled`.
+    #[serde(default)]
+    pub expire: bool,
+}
+
+impl Signature for CancelOrderRequest {}
+
+/// Response returned by the Service API order endpoints.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderResponse {
     /// Internal order ID (UUID).
@@ -51,4 +150,14 @@ pub struct OrderResponse {
     pub status: OrderStatus,
     /// Unix timestamp of when the order was created.
     pub created_at: i64,
+    /// Merchant-supplied JSON object.
+    pub metadata: Option<serde_json::Value>,
+    /// Human-readable description.
+    pub description: Option<String>,
+    /// Customer reference or email.
+    pub customer_reference: Option<String>,
+    /// Redirect URL after payment.
+    pub success_url: Option<String>,
+    /// Redirect URL after cancellation or expiry.
+    pub cancel_url: Option<String>,
 }
@@ -20,9 +20,16 @@ pub mod webhook;
 pub mod ws;
 
 pub use blockchains::{Blockchain, Stablecoin};
-pub use create_payment::{GetOrderRequest, OrderResponse, PaymentCreatingEssential};
+pub use create_payment::{
+    CancelOrderRequest, GetOrderRequest, ListOrdersRequest, LookupOrderRequest, OrderListResponse,
+    OrderResponse, PaymentCreatingEssential,
+};
 pub use user::{ChainCoinPair, PaymentDetail, SelectPaymentMethod};
-pub use webhook::{OrderStatus, OrderStatusChangedPayload, TransferStatus, UnknownTransferPayload};
+pub use webhook::{
+    OrderStatus, OrderStatusChangedPayload, OrderStatusChangedPayloadV2, PaymentDetectedPayload,
+    PaymentDetectedPayloadV2, PingPayload, TransferStatus, UnknownTransferPayload,
+    UnknownTransferPayloadV2, WebhookEvent, WebhookEventId, WebhookEventType, WebhookTransfer,
+};
 pub use ws::{WsCloseCode, WsServerMessage};
 
 pub use crate::signature::{Signature, SignatureError, SignedObject};
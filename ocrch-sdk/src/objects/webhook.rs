@@ -1,10 +1,35 @@
 //! Webhook payload types for order and transfer events.
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
 use uuid::Uuid;
 
+use super::blockchains::{Blockchain, Stablecoin};
 use crate::signature::Signature;
 
+/// Type of a webhook event.
+///
+/// Used to select events in `[[webhooks]]` subscriptions and in the Admin
+/// API delivery log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    /// Order status changed to paid, expired or cancelled.
+    OrderStatusChanged,
+    /// A transfer could not be matched to any pending deposit.
+    UnknownTransferReceived,
+    /// A payment was detected on-chain but is not yet confirmed.
+    PaymentDetected,
+}
+
+/// Identity of a webhook event, shared by every delivery of it.
+///
+/// Retries, deliveries to other endpoints and manual resends of an event
+/// all carry the same event ID, also sent in the `Ocrch-Event-Id` header.
+pub trait WebhookEventId {
+    /// Unique ID of the event.
+    fn event_id(&self) -> Uuid;
+}
+
 /// Webhook payload for order status change events.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderStatusChangedPayload {
@@ -14,16 +39,32 @@ pub struct OrderStatusChangedPayload {
     pub order_id: Uuid,
     /// Merchant-assigned order identifier.
     pub merchant_order_id: String,
+    /// Merchant-supplied JSON object of the order.
+    pub metadata: Option<serde_json::Value>,
+    /// Description of the order.
+    pub description: Option<String>,
+    /// Customer reference or email of the order.
+    pub customer_reference: Option<String>,
     /// New order status.
     pub status: OrderStatus,
     /// Payment amount as a string.
     pub amount: String,
+    /// Unique ID of the event; the same for every delivery of it.
+    pub event_id: Uuid,
+    /// Position of the event among the order's events, starting at 1.
+    pub sequence: u64,
     /// Unix timestamp of when the event was emitted.
     pub timestamp: i64,
 }
 
 impl Signature for OrderStatusChangedPayload {}
 
+impl WebhookEventId for OrderStatusChangedPayload {
+    fn event_id(&self) -> Uuid {
+        self.event_id
+    }
+}
+
 /// Webhook payload for unknown transfer events.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnknownTransferPayload {
@@ -33,12 +74,297 @@ pub struct UnknownTransferPayload {
     pub transfer_id: i64,
     /// Blockchain the transfer was observed on (as a string).
     pub blockchain: String,
+    /// Unique ID of the event; the same for every delivery of it.
+    pub event_id: Uuid,
     /// Unix timestamp of when the event was emitted.
     pub timestamp: i64,
 }
 
 impl Signature for UnknownTransferPayload {}
 
+impl WebhookEventId for UnknownTransferPayload {
+    fn event_id(&self) -> Uuid {
+        self.event_id
+    }
+}
+
+/// Webhook payload sent when a transfer for an order has been seen on-chain
+/// but does not yet have enough confirmations to mark the order as paid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentDetectedPayload {
+    /// Event type identifier (`"payment_detected"`).
+    pub event_type: String,
+    /// Internal order ID.
+    pub order_id: Uuid,
+    /// Merchant-assigned order identifier.
+    pub merchant_order_id: String,
+    /// Merchant-supplied JSON object of the order.
+    pub metadata: Option<serde_json::Value>,
+    /// Description of the order.
+    pub description: Option<String>,
+    /// Customer reference or email of the order.
+    pub customer_reference: Option<String>,
+    /// Blockchain the transfer was observed on.
+    pub blockchain: Blockchain,
+    /// Transaction hash of the detected transfer.
+    pub txn_hash: String,
+    /// Confirmations the transfer had when the event was emitted.
+    pub confirmations: u32,
+    /// Confirmations required before the order is marked as paid.
+    pub required_confirmations: u32,
+    /// Payment amount as a string.
+    pub amount: String,
+    /// Unique ID of the event; the same for every delivery of it.
+    pub event_id: Uuid,
+    /// Position of the event among the order's events, starting at 1.
+    pub sequence: u64,
+    /// Unix timestamp of when the event was emitted.
+    pub timestamp: i64,
+}
+
+impl Signature for PaymentDetectedPayload {}
+
+impl WebhookEventId for PaymentDetectedPayload {
+    fn event_id(&self) -> Uuid {
+        self.event_id
+    }
+}
+
+/// On-chain transfer included in v2 webhook payloads.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookTransfer {
+    /// Internal transfer ID.
+    pub transfer_id: i64,
+    /// Blockchain the transfer was observed on.
+    pub blockchain: Blockchain,
+    /// Stablecoin transferred.
+    pub token: Stablecoin,
+    /// Transaction hash.
+    pub txn_hash: String,
+    /// Sender address.
+    pub from_address: String,
+    /// Recipient (merchant wallet) address.
+    pub to_address: String,
+    /// Block number containing the transaction.
+    pub block_number: i64,
+    /// Unix timestamp of the block, in seconds.
+    pub block_timestamp: i64,
+    /// Confirmations of the transfer when the payload was built.
+    pub confirmations: u32,
+    /// Amount transferred.
+    pub amount: rust_decimal::Decimal,
+}
+
+/// Version 2 webhook payload for order status change events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderStatusChangedPayloadV2 {
+    /// Payload schema version, always `2`.
+    pub version: u32,
+    /// Always [`WebhookEventType::OrderStatusChanged`].
+    pub event_type: WebhookEventType,
+    /// Internal order ID.
+    pub order_id: Uuid,
+    /// Merchant-assigned order identifier.
+    pub merchant_order_id: String,
+    /// Merchant-supplied JSON object of the order.
+    pub metadata: Option<serde_json::Value>,
+    /// Description of the order.
+    pub description: Option<String>,
+    /// Customer reference or email of the order.
+    pub customer_reference: Option<String>,
+    /// New order status.
+    pub status: OrderStatus,
+    /// Amount the order asked for.
+    pub expected_amount: rust_decimal::Decimal,
+    /// Amount received, if the order was paid by an on-chain transfer.
+    pub received_amount: Option<rust_decimal::Decimal>,
+    /// The paying transfer, if the order was paid by an on-chain transfer.
+    /// `None` for expired and cancelled orders and for orders marked paid
+    /// by an admin.
+    pub transfer: Option<WebhookTransfer>,
+    /// Unique ID of the event; the same for every delivery of it.
+    pub event_id: Uuid,
+    /// Position of the event among the order's events, starting at 1.
+    pub sequence: u64,
+    /// Unix timestamp of when the event was emitted.
+    pub timestamp: i64,
+}
+
+impl Signature for OrderStatusChangedPayloadV2 {}
+
+impl WebhookEventId for OrderStatusChangedPayloadV2 {
+    fn event_id(&self) -> Uuid {
+        self.event_id
+    }
+}
+
+/// Version 2 webhook payload for payment detected events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentDetectedPayloadV2 {
+    /// Payload schema version, always `2`.
+    pub version: u32,
+    /// Always [`WebhookEventType::PaymentDetected`].
+    pub event_type: WebhookEventType,
+    /// Internal order ID.
+    pub order_id: Uuid,
+    /// Merchant-assigned order identifier.
+    pub merchant_order_id: String,
+    /// Merchant-supplied JSON object of the order.
+    pub metadata: Option<serde_json::Value>,
+    /// Description of the order.
+    pub description: Option<String>,
+    /// Customer reference or email of the order.
+    pub customer_reference: Option<String>,
+    /// Amount the order asked for.
+    pub expected_amount: rust_decimal::Decimal,
+    /// Amount of the detected transfer.
+    pub received_amount: rust_decimal::Decimal,
+    /// Confirmations required before the order is marked as paid.
+    pub required_confirmations: u32,
+    /// The detected transfer. Its `confirmations` are the ones it had when
+    /// it was detected.
+    pub transfer: WebhookTransfer,
+    /// Unique ID of the event; the same for every delivery of it.
+    pub event_id: Uuid,
+    /// Position of the event among the order's events, starting at 1.
+    pub sequence: u64,
+    /// Unix timestamp of when the event was emitted.
+    pub timestamp: i64,
+}
+
+impl Signature for PaymentDetectedPayloadV2 {}
+
+impl WebhookEventId for PaymentDetectedPayloadV2 {
+    fn event_id(&self) -> Uuid {
+        self.event_id
+    }
+}
+
+/// Version 2 webhook payload for unknown transfer events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnknownTransferPayloadV2 {
+    /// Payload schema version, always `2`.
+    pub version: u32,
+    /// Always [`WebhookEventType::UnknownTransferReceived`].
+    pub event_type: WebhookEventType,
+    /// Amount received.
+    pub received_amount: rust_decimal::Decimal,
+    /// The unmatched transfer.
+    pub transfer: WebhookTransfer,
+    /// Unique ID of the event; the same for every delivery of it.
+    pub event_id: Uuid,
+    /// Unix timestamp of when the event was emitted.
+    pub timestamp: i64,
+}
+
+impl Signature for UnknownTransferPayloadV2 {}
+
+impl WebhookEventId for UnknownTransferPayloadV2 {
+    fn event_id(&self) -> Uuid {
+        self.event_id
+    }
+}
+
+/// Webhook payload of a test `ping` event, sent through the Admin API to
+/// check that an endpoint receives and verifies webhooks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PingPayload {
+    /// Event type identifier (`"ping"`).
+    pub event_type: String,
+    /// Unique ID of the event.
+    pub event_id: Uuid,
+    /// Unix timestamp of when the event was emitted.
+    pub timestamp: i64,
+}
+
+impl Signature for PingPayload {}
+
+impl WebhookEventId for PingPayload {
+    fn event_id(&self) -> Uuid {
+        self.event_id
+    }
+}
+
+/// Any webhook sent by Ocrch, dispatched on `event_type` and `version`.
+///
+/// Serializes to the inner payload. Deserializing fails for event types
+/// this SDK version does not know.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum WebhookEvent {
+    /// A v1 `order_status_changed` event.
+    OrderStatusChanged(OrderStatusChangedPayload),
+    /// A v2 `order_status_changed` event.
+    OrderStatusChangedV2(OrderStatusChangedPayloadV2),
+    /// A v1 `payment_detected` event.
+    PaymentDetected(PaymentDetectedPayload),
+    /// A v2 `payment_detected` event.
+    PaymentDetectedV2(PaymentDetectedPayloadV2),
+    /// A v1 `unknown_transfer_received` event.
+    UnknownTransfer(UnknownTransferPayload),
+    /// A v2 `unknown_transfer_received` event.
+    UnknownTransferV2(UnknownTransferPayloadV2),
+    /// A test `ping` event.
+    Ping(PingPayload),
+}
+
+impl<'de> Deserialize<'de> for WebhookEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const EVENT_TYPES: &[&str] = &[
+            "order_status_changed",
+            "payment_detected",
+            "unknown_transfer_received",
+            "ping",
+        ];
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let event_type = value
+            .get("event_type")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("event_type"))?
+            .to_owned();
+        let v2 = value.get("version").and_then(serde_json::Value::as_u64) == Some(2);
+
+        let event = match (event_type.as_str(), v2) {
+            ("order_status_changed", false) => {
+                serde_json::from_value(value).map(Self::OrderStatusChanged)
+            }
+            ("order_status_changed", true) => {
+                serde_json::from_value(value).map(Self::OrderStatusChangedV2)
+            }
+            ("payment_detected", false) => serde_json::from_value(value).map(Self::PaymentDetected),
+            ("payment_detected", true) => {
+                serde_json::from_value(value).map(Self::PaymentDetectedV2)
+            }
+            ("unknown_transfer_received", false) => {
+                serde_json::from_value(value).map(Self::UnknownTransfer)
+            }
+            ("unknown_transfer_received", true) => {
+                serde_json::from_value(value).map(Self::UnknownTransferV2)
+            }
+            ("ping", _) => serde_json::from_value(value).map(Self::Ping),
+            (other, _) => return Err(D::Error::unknown_variant(other, EVENT_TYPES)),
+        };
+        event.map_err(D::Error::custom)
+    }
+}
+
+impl Signature for WebhookEvent {}
+
+impl WebhookEventId for WebhookEvent {
+    fn event_id(&self) -> Uuid {
+        match self {
+            Self::OrderStatusChanged(p) => p.event_id,
+            Self::OrderStatusChangedV2(p) => p.event_id,
+            Self::PaymentDetected(p) => p.event_id,
+            Self::PaymentDetectedV2(p) => p.event_id,
+            Self::UnknownTransfer(p) => p.event_id,
+            Self::UnknownTransferV2(p) => p.event_id,
+            Self::Ping(p) => p.event_id,
+        }
+    }
+}
+
 /// Order status for API responses.
 ///
 /// This is the API/DTO version without sqlx::Type.
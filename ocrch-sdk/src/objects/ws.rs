@@ -9,14 +9,22 @@
 //!    current order status immediately after the upgrade.
 //! 2. Subsequent [`WsServerMessage::StatusUpdate`] frames are sent
 //!    whenever the order status changes.
-//! 3. After a terminal status (`Paid`, `Expired`, `Cancelled`) the
-//!    server sends a normal close frame.
-//! 4. If the order is not found or an internal error occurs *during*
+//! 3. While the order is pending, a [`WsServerMessage::PaymentDetected`]
+//!    frame is sent when a matching transfer is seen on-chain but is not
+//!    yet confirmed, and again whenever its confirmation count changes.
+//!    If a payment was already detected, one is sent right after the
+//!    first status frame.
+//! 4. After a terminal status (`Paid`, `Expired`, `Cancelled`) the
+//!    server sends a normal close frame. The terminal status frame's
+//!    [`OrderResponse::success_url`] and [`OrderResponse::cancel_url`]
+//!    tell the checkout page where to redirect the user.
+//! 5. If the order is not found or an internal error occurs *during*
 //!    the handshake phase, the server sends a close frame with an
 //!    application-defined close code (see [`WsCloseCode`]).
 
 use serde::{Deserialize, Serialize};
 
+use super::blockchains::Blockchain;
 use super::create_payment::OrderResponse;
 
 /// Server-to-client WebSocket message.
@@ -26,6 +34,7 @@ use super::create_payment::OrderResponse;
 ///
 /// ```json
 /// {"type":"status_update","order":{ ... }}
+/// {"type":"payment_detected","blockchain":"eth","txn_hash":"0x...","confirmations":3,"required_confirmations":12}
 /// {"type":"error","code":4004,"reason":"order not found"}
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +47,19 @@ pub enum WsServerMessage {
         order: OrderResponse,
     },
 
+    /// A transfer for this order was seen on-chain but is still waiting
+    /// for confirmations. The order itself remains `Pending`.
+    PaymentDetected {
+        /// Blockchain the transfer was observed on.
+        blockchain: Blockchain,
+        /// Transaction hash of the detected transfer.
+        txn_hash: String,
+        /// Current number of confirmations.
+        confirmations: u32,
+        /// Confirmations required before the order is marked as paid.
+        required_confirmations: u32,
+    },
+
     /// A server-side error that does **not** close the connection by
     /// itself.  The server may still send a close frame afterwards.
     Error {
@@ -0,0 +1,433 @@
+//! Webhook verification for axum and tower services.
+//!
+//! [`VerifiedWebhook`] is an axum extractor that reads the `Ocrch-Signature`
+//! header, verifies the body against the merchant secrets in
+//! [`WebhookSecrets`] (including the [`MAX_SIGNATURE_AGE`] freshness check)
+//! and deserializes it, by default into a [`WebhookEvent`].
+//!
+//! [`VerifyWebhookLayer`] does the same for any tower service taking an
+//! axum request: requests that fail verification are answered with an error
+//! response, and verified ones reach the inner service with the
+//! [`WebhookEvent`] stored in their extensions and the body left intact.
+//!
+//! [`MAX_SIGNATURE_AGE`]: crate::signature::MAX_SIGNATURE_AGE
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::body::Body;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use tower::{Layer, Service};
+
+use crate::objects::WebhookEvent;
+use crate::signature::{SIGNATURE_HEADER, Signature, SignatureError, SignedObject, SigningKey};
+
+/// Maximum accepted webhook body size in bytes.
+pub const MAX_WEBHOOK_BODY_SIZE: usize = 1024 * 1024;
+
+/// Merchant secrets webhooks are verified with.
+///
+/// A webhook is accepted if it was signed with any of the secrets, so both
+/// the old and the new secret can be configured while rotating. Cheap to
+/// clone.
+#[derive(Clone)]
+pub struct WebhookSecrets {
+    secrets: Arc<[Box<[u8]>]>,
+}
+
+impl WebhookSecrets {
+    /// Verify webhooks with a single secret.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self::from_secrets([secret])
+    }
+
+    /// Verify webhooks with any of several secrets.
+    pub fn from_secrets<I>(secrets: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        Self {
+            secrets: secrets
+                .into_iter()
+                .map(|secret| Box::from(secret.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// Verify a raw webhook against these secrets and deserialize it.
+    pub fn verify<T: Signature>(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<T, WebhookRejection> {
+        let header = headers
+            .get(SIGNATURE_HEADER)
+            .ok_or(WebhookRejection::MissingSignature)?
+            .to_str()
+            .map_err(|_| WebhookRejection::Signature(SignatureError::InvalidFormat))?;
+        let body = std::str::from_utf8(body).map_err(|_| WebhookRejection::InvalidBody)?;
+        let keys: Vec<SigningKey<'_>> = self
+            .secrets
+            .iter()
+            .map(|secret| SigningKey::unnamed(secret))
+            .collect();
+        Ok(SignedObject::<T>::from_header_and_body(header, body.to_owned())?.verify_any(&keys)?)
+    }
+}
+
+impl std::fmt::Debug for WebhookSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookSecrets")
+            .field("secrets", &self.secrets.len())
+            .finish()
+    }
+}
+
+/// Why an incoming webhook was rejected.
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookRejection {
+    /// The request has no `Ocrch-Signature` header.
+    #[error("missing Ocrch-Signature header")]
+    MissingSignature,
+    /// The body could not be read, is too large or is not UTF-8.
+    #[error("invalid webhook body")]
+    InvalidBody,
+    /// The header, body or signature failed verification.
+    #[error(transparent)]
+    Signature(#[from] SignatureError),
+}
+
+impl WebhookRejection {
+    /// HTTP status the rejection is answered with.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            WebhookRejection::MissingSignature
+            | WebhookRejection::Signature(SignatureError::SignatureMismatch)
+            | WebhookRejection::Signature(SignatureError::Expired) => StatusCode::UNAUTHORIZED,
+            WebhookRejection::InvalidBody | WebhookRejection::Signature(_) => {
+                StatusCode::BAD_REQUEST
+            }
+        }
+    }
+}
+
+impl IntoResponse for WebhookRejection {
+    fn into_response(self) -> Response {
+        (self.status(), self.to_string()).into_response()
+    }
+}
+
+/// Axum extractor for a verified webhook.
+///
+/// Requires [`WebhookSecrets`] to be obtainable from the router state.
+///
+/// # Example
+///
+/// ```ignore
+/// use ocrch_sdk::objects::WebhookEvent;
+/// use ocrch_sdk::receiver::{VerifiedWebhook, WebhookSecrets};
+///
+/// async fn webhook(VerifiedWebhook(event): VerifiedWebhook) {
+///     match event {
+///         WebhookEvent::OrderStatusChanged(payload) => { /* ... */ }
+///         _ => {}
+///     }
+/// }
+///
+/// let app = Router::new()
+///     .route("/ocrch/webhook", post(webhook))
+///     .with_state(WebhookSecrets::new(merchant_secret));
+/// ```
+#[derive(Debug, Clone)]
+pub struct VerifiedWebhook<T = WebhookEvent>(pub T);
+
+impl<S, T> FromRequest<S> for VerifiedWebhook<T>
+where
+    S: Send + Sync,
+    T: Signature,
+    WebhookSecrets: FromRef<S>,
+{
+    type Rejection = WebhookRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let secrets = WebhookSecrets::from_ref(state);
+        let (parts, body) = req.into_parts();
+        let body = axum::body::to_bytes(body, MAX_WEBHOOK_BODY_SIZE)
+            .await
+            .map_err(|_| WebhookRejection::InvalidBody)?;
+        secrets.verify(&parts.headers, &body).map(VerifiedWebhook)
+    }
+}
+
+/// Tower layer that verifies webhooks before they reach the inner service.
+///
+/// # Example
+///
+/// ```ignore
+/// use ocrch_sdk::objects::WebhookEvent;
+/// use ocrch_sdk::receiver::{VerifyWebhookLayer, WebhookSecrets};
+///
+/// async fn webhook(Extension(event): Extension<WebhookEvent>) { /* ... */ }
+///
+/// let app = Router::new()
+///     .route("/ocrch/webhook", post(webhook))
+///     .layer(VerifyWebhookLayer::new(WebhookSecrets::new(merchant_secret)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct VerifyWebhookLayer {
+    secrets: WebhookSecrets,
+}
+
+impl VerifyWebhookLayer {
+    /// Verify webhooks with `secrets`.
+    pub fn new(secrets: WebhookSecrets) -> Self {
+        Self { secrets }
+    }
+}
+
+impl<S> Layer<S> for VerifyWebhookLayer {
+    type Service = VerifyWebhook<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        VerifyWebhook {
+            inner,
+            secrets: self.secrets.clone(),
+        }
+    }
+}
+
+/// Service produced by [`VerifyWebhookLayer`].
+#[derive(Debug, Clone)]
+pub struct VerifyWebhook<S> {
+    inner: S,
+    secrets: WebhookSecrets,
+}
+
+impl<S> Service<Request> for VerifyWebhook<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        // Use the service that was polled ready and leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let secrets = self.secrets.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+            let body = match axum::body::to_bytes(body, MAX_WEBHOOK_BODY_SIZE).await {
+                Ok(body) => body,
+                Err(_) => return Ok(WebhookRejection::InvalidBody.into_response()),
+            };
+            let event: WebhookEvent = match secrets.verify(&parts.headers, &body) {
+                Ok(event) => event,
+                Err(rejection) => return Ok(rejection.into_response()),
+            };
+            parts.extensions.insert(event);
+            inner
+                .call(Request::from_parts(parts, Body::from(body)))
+                .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::webhook::{
+        OrderStatus, OrderStatusChangedPayload, OrderStatusChangedPayloadV2,
+    };
+    use crate::objects::{WebhookEventId, WebhookEventType};
+    use crate::signature::{KeyedSignature, MAX_SIGNATURE_AGE, format_signature_header};
+    use axum::Extension;
+    use axum::routing::post;
+    use uuid::Uuid;
+
+    const SECRET: &[u8] = b"merchant-secret";
+
+    fn v1_payload() -> OrderStatusChangedPayload {
+        OrderStatusChangedPayload {
+            event_type: "order_status_changed".to_string(),
+            order_id: Uuid::from_u128(1),
+            merchant_order_id: "order-1".to_string(),
+            metadata: None,
+            description: None,
+            customer_reference: None,
+            status: OrderStatus::Paid,
+            amount: "10.00".to_string(),
+            event_id: Uuid::from_u128(100),
+            sequence: 1,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    fn v2_payload() -> OrderStatusChangedPayloadV2 {
+        OrderStatusChangedPayloadV2 {
+            version: 2,
+            event_type: WebhookEventType::OrderStatusChanged,
+            order_id: Uuid::from_u128(2),
+            merchant_order_id: "order-2".to_string(),
+            metadata: None,
+            description: None,
+            customer_reference: None,
+            status: OrderStatus::Paid,
+            expected_amount: rust_decimal::Decimal::TEN,
+            received_amount: Some(rust_decimal::Decimal::TEN),
+            transfer: None,
+            event_id: Uuid::from_u128(200),
+            sequence: 1,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    fn request(header: Option<String>, body: String) -> Request {
+        let mut builder = Request::builder().method("POST").uri("/webhook");
+        if let Some(header) = header {
+            builder = builder.header(SIGNATURE_HEADER, header);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    fn signed_request<T: Signature>(payload: T, secret: &[u8]) -> Request {
+        let signed = SignedObject::new(payload, secret).unwrap();
+        request(Some(signed.to_header()), signed.json)
+    }
+
+    async fn extract(req: Request) -> Result<WebhookEvent, WebhookRejection> {
+        VerifiedWebhook::<WebhookEvent>::from_request(req, &WebhookSecrets::new(SECRET))
+            .await
+            .map(|VerifiedWebhook(event)| event)
+    }
+
+    #[tokio::test]
+    async fn test_valid_webhook_accepted() {
+        let event = extract(signed_request(v1_payload(), SECRET)).await.unwrap();
+        assert_eq!(event.event_id(), Uuid::from_u128(100));
+
+        // Any configured secret is accepted while rotating
+        let secrets = WebhookSecrets::from_secrets([b"old".as_slice(), SECRET]);
+        let VerifiedWebhook(payload) = VerifiedWebhook::<OrderStatusChangedPayload>::from_request(
+            signed_request(v1_payload(), SECRET),
+            &secrets,
+        )
+        .await
+        .unwrap();
+        assert_eq!(payload.merchant_order_id, "order-1");
+    }
+
+    #[tokio::test]
+    async fn test_missing_signature_rejected() {
+        let body = serde_json::to_string(&v1_payload()).unwrap();
+        let rejection = extract(request(None, body)).await.unwrap_err();
+        assert!(matches!(rejection, WebhookRejection::MissingSignature));
+        assert_eq!(rejection.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_bad_signature_rejected() {
+        let rejection = extract(signed_request(v1_payload(), b"other-secret"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            rejection,
+            WebhookRejection::Signature(SignatureError::SignatureMismatch)
+        ));
+        assert_eq!(rejection.status(), StatusCode::UNAUTHORIZED);
+
+        // A body changed after signing
+        let signed = SignedObject::new(v1_payload(), SECRET).unwrap();
+        let body = signed.json.replace("order-1", "order-9");
+        let rejection = extract(request(Some(signed.to_header()), body))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            rejection,
+            WebhookRejection::Signature(SignatureError::SignatureMismatch)
+        ));
+
+        let rejection = extract(request(Some("garbage".to_string()), signed.json))
+            .await
+            .unwrap_err();
+        assert_eq!(rejection.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_expired_timestamp_rejected() {
+        let timestamp = time::OffsetDateTime::now_utc().unix_timestamp() - MAX_SIGNATURE_AGE - 10;
+        let body = serde_json::to_string(&v1_payload()).unwrap();
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, SECRET);
+        let signature = KeyedSignature {
+            key_id: None,
+            signature: Box::from(
+                ring::hmac::sign(&key, format!("{timestamp}.{body}").as_bytes()).as_ref(),
+            ),
+        };
+        let header = format_signature_header(timestamp, &[signature]);
+
+        let rejection = extract(request(Some(header), body)).await.unwrap_err();
+        assert!(matches!(
+            rejection,
+            WebhookRejection::Signature(SignatureError::Expired)
+        ));
+        assert_eq!(rejection.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_dispatches_payload_versions() {
+        match extract(signed_request(v1_payload(), SECRET)).await.unwrap() {
+            WebhookEvent::OrderStatusChanged(payload) => {
+                assert_eq!(payload.order_id, Uuid::from_u128(1));
+            }
+            event => panic!("expected a v1 event, got {event:?}"),
+        }
+        match extract(signed_request(v2_payload(), SECRET)).await.unwrap() {
+            WebhookEvent::OrderStatusChangedV2(payload) => {
+                assert_eq!(payload.order_id, Uuid::from_u128(2));
+                assert_eq!(payload.received_amount, Some(rust_decimal::Decimal::TEN));
+            }
+            event => panic!("expected a v2 event, got {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layer() {
+        async fn handler(Extension(event): Extension<WebhookEvent>, body: String) -> String {
+            format!("{} {}", event.event_id(), body.len())
+        }
+        let mut app = axum::Router::new()
+            .route("/webhook", post(handler))
+            .layer(VerifyWebhookLayer::new(WebhookSecrets::new(SECRET)));
+
+        let signed = SignedObject::new(v2_payload(), SECRET).unwrap();
+        let expected = format!("{} {}", Uuid::from_u128(200), signed.json.len());
+        let response = app
+            .call(request(Some(signed.to_header()), signed.json))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, expected.as_bytes());
+
+        let response = app
+            .call(signed_request(v2_payload(), b"other-secret"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
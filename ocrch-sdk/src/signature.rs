@@ -1,12 +1,18 @@
 //! Signature algorithm and verification for all Ocrch APIs.
 //!
 //! Every Ocrch API endpoint uses HMAC-SHA256 signatures defined in this
-//! module.  The wire format for the header is:
+//! module.  The header has two wire formats:
 //!
 //! ```text
 //! Ocrch-Signature: {unix_timestamp}.{base64_signature}
+//! Ocrch-Signature: t={unix_timestamp},{key_id}={base64_signature},...
 //! ```
 //!
+//! The first carries a single signature made with an unnamed key. The
+//! second carries one signature per named key, so that a secret can be
+//! rotated while both the old and the new key are active. A signature is
+//! valid if any of them verifies.
+//!
 //! Two signing schemes exist:
 //!
 //! * **Body signing** (Service API, Webhook API):
@@ -21,6 +27,14 @@ pub const SIGNATURE_HEADER: &str = "Ocrch-Signature";
 /// Header name carrying the signed frontend URL (User API).
 pub const SIGNED_URL_HEADER: &str = "Ocrch-Signed-Url";
 
+/// Header name carrying the ID of a webhook event, identical for every
+/// delivery of the event.
+pub const EVENT_ID_HEADER: &str = "Ocrch-Event-Id";
+
+/// Header name carrying the ID of the API key a Service API body or a User
+/// API URL was signed with. Without it, the merchant secret is used.
+pub const KEY_ID_HEADER: &str = "Ocrch-Key-Id";
+
 /// Header name for admin API authentication (plaintext secret).
 pub const ADMIN_AUTH_HEADER: &str = "Ocrch-Admin-Authorization";
 
@@ -51,6 +65,33 @@ pub enum SignatureError {
     Expired,
 }
 
+/// An HMAC key used to sign or verify, with an optional key ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigningKey<'a> {
+    /// Key ID carried in the keyed header format, or `None` for an unnamed
+    /// key. When verifying, an unnamed key is tried against every signature.
+    pub id: Option<&'a str>,
+    /// HMAC secret.
+    pub secret: &'a [u8],
+}
+
+impl<'a> SigningKey<'a> {
+    /// An unnamed key.
+    pub fn unnamed(secret: &'a [u8]) -> Self {
+        Self { id: None, secret }
+    }
+}
+
+/// One signature of an `Ocrch-Signature` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyedSignature {
+    /// ID of the key that made the signature, or `None` for the
+    /// single-signature format.
+    pub key_id: Option<String>,
+    /// Raw HMAC-SHA256 bytes.
+    pub signature: Box<[u8]>,
+}
+
 impl From<ring::error::Unspecified> for SignatureError {
     fn from(_: ring::error::Unspecified) -> Self {
         Self::SignatureMismatch
@@ -62,7 +103,7 @@ impl From<ring::error::Unspecified> for SignatureError {
 // ---------------------------------------------------------------------------
 
 /// A signed API body carrying its typed payload, timestamp, raw JSON, and
-/// HMAC-SHA256 signature.
+/// HMAC-SHA256 signatures.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SignedObject<T: Signature> {
     /// Deserialized payload.
@@ -71,8 +112,8 @@ pub struct SignedObject<T: Signature> {
     pub timestamp: i64,
     /// Raw JSON string of the body (used for HMAC verification).
     pub json: String,
-    /// Raw HMAC-SHA256 bytes.
-    pub signature: Box<[u8]>,
+    /// Signatures carried in the header, one per signing key.
+    pub signatures: Vec<KeyedSignature>,
 }
 
 impl<T: Signature> SignedObject<T> {
@@ -82,19 +123,28 @@ impl<T: Signature> SignedObject<T> {
     /// `HMAC-SHA256("{timestamp}.{json}", key)`, and returns the assembled
     /// [`SignedObject`].
     pub fn new(body: T, key: &[u8]) -> Result<Self, serde_json::Error> {
+        Self::with_keys(body, &[SigningKey::unnamed(key)])
+    }
+
+    /// Create a new signed object with one signature per key.
+    ///
+    /// Use named keys to produce the keyed header format.
+    pub fn with_keys(body: T, keys: &[SigningKey<'_>]) -> Result<Self, serde_json::Error> {
         let now = time::OffsetDateTime::now_utc().unix_timestamp();
         let json = serde_json::to_string(&body)?;
         let data = format!("{now}.{json}");
-        let signature = ring::hmac::sign(
-            &ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key),
-            data.as_bytes(),
-        );
-        let signature = signature.as_ref().to_owned().into_boxed_slice();
+        let signatures = keys
+            .iter()
+            .map(|key| KeyedSignature {
+                key_id: key.id.map(str::to_owned),
+                signature: hmac_sha256(key.secret, &data),
+            })
+            .collect();
         Ok(Self {
             body,
             timestamp: now,
             json,
-            signature,
+            signatures,
         })
     }
 
@@ -107,37 +157,45 @@ impl<T: Signature> SignedObject<T> {
         header_value: &str,
         body_json: String,
     ) -> Result<Self, SignatureError> {
-        let (timestamp, signature) = parse_signature_header(header_value)?;
+        let (timestamp, signatures) = parse_signature_header(header_value)?;
         let body: T = serde_json::from_str(&body_json)?;
         Ok(Self {
             body,
             timestamp,
             json: body_json,
-            signature,
+            signatures,
         })
     }
 
     /// Verify the HMAC signature and timestamp freshness, consuming `self`
     /// and returning the authenticated payload.
+    ///
+    /// Succeeds if any signature of the header was made with `key`.
     pub fn verify(self, key: &[u8]) -> Result<T, SignatureError> {
+        self.verify_any(&[SigningKey::unnamed(key)])
+    }
+
+    /// Verify that any signature was made with any of `keys`, and check
+    /// timestamp freshness.
+    pub fn verify_any(self, keys: &[SigningKey<'_>]) -> Result<T, SignatureError> {
         let data = format!("{}.{}", self.timestamp, self.json);
-        ring::hmac::verify(
-            &ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key),
-            data.as_bytes(),
-            self.signature.as_ref(),
-        )?;
+        verify_signatures(&data, &self.signatures, keys)?;
         check_timestamp(self.timestamp)?;
         Ok(self.body)
     }
 
-    /// Format the full `Ocrch-Signature` header value (`{timestamp}.{b64}`).
+    /// Format the full `Ocrch-Signature` header value.
     pub fn to_header(&self) -> String {
-        format_signature_header(self.timestamp, &self.signature)
+        format_signature_header(self.timestamp, &self.signatures)
     }
 
-    /// Base64-encode the raw signature bytes (without the timestamp prefix).
+    /// Base64-encode the raw bytes of the first signature (without the
+    /// timestamp prefix).
     pub fn stringify_signature(&self) -> String {
-        fast32::base64::RFC4648_NOPAD.encode(&self.signature)
+        self.signatures
+            .first()
+            .map(|s| fast32::base64::RFC4648_NOPAD.encode(&s.signature))
+            .unwrap_or_default()
     }
 }
 
@@ -145,27 +203,117 @@ impl<T: Signature> SignedObject<T> {
 // Header parsing / formatting
 // ---------------------------------------------------------------------------
 
-/// Parse an `Ocrch-Signature` header value (`{timestamp}.{base64}`) into
-/// `(timestamp, raw_signature_bytes)`.
-pub fn parse_signature_header(value: &str) -> Result<(i64, Box<[u8]>), SignatureError> {
-    let dot_pos = value.find('.').ok_or(SignatureError::InvalidFormat)?;
-    let timestamp: i64 = value[..dot_pos]
+/// Parse an `Ocrch-Signature` header value in either format into
+/// `(timestamp, signatures)`.
+pub fn parse_signature_header(value: &str) -> Result<(i64, Vec<KeyedSignature>), SignatureError> {
+    let decode = |b64: &str| {
+        fast32::base64::RFC4648_NOPAD
+            .decode_str(b64)
+            .map(Vec::into_boxed_slice)
+            .map_err(|_| SignatureError::InvalidBase64)
+    };
+
+    if let Some(keyed) = value.strip_prefix("t=") {
+        let mut parts = keyed.split(',');
+        let timestamp: i64 = parts
+            .next()
+            .and_then(|t| t.parse().ok())
+            .ok_or(SignatureError::InvalidFormat)?;
+        let signatures = parts
+            .map(|part| {
+                let (key_id, b64) = part.split_once('=').ok_or(SignatureError::InvalidFormat)?;
+                if key_id.is_empty() {
+                    return Err(SignatureError::InvalidFormat);
+                }
+                Ok(KeyedSignature {
+                    key_id: Some(key_id.to_owned()),
+                    signature: decode(b64)?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if signatures.is_empty() {
+            return Err(SignatureError::InvalidFormat);
+        }
+        return Ok((timestamp, signatures));
+    }
+
+    let (timestamp, b64) = value.split_once('.').ok_or(SignatureError::InvalidFormat)?;
+    let timestamp: i64 = timestamp
         .parse()
         .map_err(|_| SignatureError::InvalidFormat)?;
-    let signature_bytes = fast32::base64::RFC4648_NOPAD
-        .decode_str(&value[dot_pos + 1..])
-        .map_err(|_| SignatureError::InvalidBase64)?
-        .into_boxed_slice();
-    Ok((timestamp, signature_bytes))
+    let signature = KeyedSignature {
+        key_id: None,
+        signature: decode(b64)?,
+    };
+    Ok((timestamp, vec![signature]))
+}
+
+/// Format a header value from its parts.
+///
+/// A single unnamed signature is written as `{timestamp}.{base64}`, anything
+/// else as `t={timestamp},{key_id}={base64},...`. Unnamed signatures cannot
+/// be written in the keyed format and are left out of it.
+pub fn format_signature_header(timestamp: i64, signatures: &[KeyedSignature]) -> String {
+    let encode = |signature: &[u8]| fast32::base64::RFC4648_NOPAD.encode(signature);
+    if let [
+        KeyedSignature {
+            key_id: None,
+            signature,
+        },
+    ] = signatures
+    {
+        return format!("{timestamp}.{}", encode(signature));
+    }
+    let mut header = format!("t={timestamp}");
+    for s in signatures {
+        if let Some(key_id) = &s.key_id {
+            header.push_str(&format!(",{key_id}={}", encode(&s.signature)));
+        }
+    }
+    header
 }
 
-/// Format a `{timestamp}.{base64}` header value from its parts.
-pub fn format_signature_header(timestamp: i64, signature: &[u8]) -> String {
-    format!(
-        "{}.{}",
-        timestamp,
-        fast32::base64::RFC4648_NOPAD.encode(signature)
-    )
+// ---------------------------------------------------------------------------
+// HMAC helpers
+// ---------------------------------------------------------------------------
+
+fn hmac_sha256(key: &[u8], data: &str) -> Box<[u8]> {
+    let signature = ring::hmac::sign(
+        &ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key),
+        data.as_bytes(),
+    );
+    signature.as_ref().to_owned().into_boxed_slice()
+}
+
+/// Check that any signature was made over `data` with any of `keys`.
+///
+/// A named signature is only checked against the key with the same ID; an
+/// unnamed signature or key is checked against every key or signature.
+fn verify_signatures(
+    data: &str,
+    signatures: &[KeyedSignature],
+    keys: &[SigningKey<'_>],
+) -> Result<(), SignatureError> {
+    let verified = signatures.iter().any(|s| {
+        keys.iter()
+            .filter(|key| match (&s.key_id, key.id) {
+                (Some(sig_id), Some(key_id)) => sig_id == key_id,
+                _ => true,
+            })
+            .any(|key| {
+                ring::hmac::verify(
+                    &ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key.secret),
+                    data.as_bytes(),
+                    &s.signature,
+                )
+                .is_ok()
+            })
+    });
+    if verified {
+        Ok(())
+    } else {
+        Err(SignatureError::SignatureMismatch)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -191,11 +339,11 @@ pub fn check_timestamp(timestamp: i64) -> Result<(), SignatureError> {
 pub fn sign_url(url: &str, key: &[u8]) -> String {
     let timestamp = time::OffsetDateTime::now_utc().unix_timestamp();
     let data = format!("{url}.{timestamp}");
-    let sig = ring::hmac::sign(
-        &ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key),
-        data.as_bytes(),
-    );
-    format_signature_header(timestamp, sig.as_ref())
+    let signature = KeyedSignature {
+        key_id: None,
+        signature: hmac_sha256(key, &data),
+    };
+    format_signature_header(timestamp, &[signature])
 }
 
 /// Verify a signed frontend URL.
@@ -216,3 +364,191 @@ pub fn verify_url(
     check_timestamp(timestamp)?;
     Ok(())
 }
+
+/// Verify a signed frontend URL against any of several keys.
+///
+/// Checks that any of `signatures` is `HMAC-SHA256("{url}.{timestamp}", key)`
+/// for one of `keys`, and timestamp freshness.
+pub fn verify_url_any(
+    url: &str,
+    timestamp: i64,
+    signatures: &[KeyedSignature],
+    keys: &[SigningKey<'_>],
+) -> Result<(), SignatureError> {
+    let data = format!("{url}.{timestamp}");
+    verify_signatures(&data, signatures, keys)?;
+    check_timestamp(timestamp)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct Body {
+        order_id: String,
+    }
+
+    impl Signature for Body {}
+
+    fn body() -> Body {
+        Body {
+            order_id: "order-1".to_string(),
+        }
+    }
+
+    fn key<'a>(id: &'a str, secret: &'a [u8]) -> SigningKey<'a> {
+        SigningKey {
+            id: Some(id),
+            secret,
+        }
+    }
+
+    /// Parse a header and verify it against `keys`, as a server does.
+    fn verify_header(
+        header: &str,
+        json: &str,
+        keys: &[SigningKey<'_>],
+    ) -> Result<Body, SignatureError> {
+        SignedObject::<Body>::from_header_and_body(header, json.to_string())?.verify_any(keys)
+    }
+
+    #[test]
+    fn test_unnamed_header_round_trip() {
+        let signed = SignedObject::new(body(), b"secret").unwrap();
+        let header = signed.to_header();
+        assert!(header.starts_with(&format!("{}.", signed.timestamp)));
+
+        let (timestamp, signatures) = parse_signature_header(&header).unwrap();
+        assert_eq!(timestamp, signed.timestamp);
+        assert_eq!(signatures, signed.signatures);
+        assert_eq!(format_signature_header(timestamp, &signatures), header);
+
+        let verified = SignedObject::<Body>::from_header_and_body(&header, signed.json)
+            .unwrap()
+            .verify(b"secret")
+            .unwrap();
+        assert_eq!(verified, body());
+    }
+
+    #[test]
+    fn test_keyed_header_round_trip() {
+        let keys = [key("2026-09", b"old"), key("2026-10", b"new")];
+        let signed = SignedObject::with_keys(body(), &keys).unwrap();
+        let header = signed.to_header();
+        assert!(header.starts_with(&format!("t={},2026-09=", signed.timestamp)));
+        assert!(header.contains(",2026-10="));
+
+        let (timestamp, signatures) = parse_signature_header(&header).unwrap();
+        assert_eq!(timestamp, signed.timestamp);
+        assert_eq!(signatures, signed.signatures);
+        assert_eq!(format_signature_header(timestamp, &signatures), header);
+
+        assert_eq!(verify_header(&header, &signed.json, &keys).unwrap(), body());
+        assert_eq!(
+            verify_header(&header, &signed.json, &[key("2026-10", b"new")]).unwrap(),
+            body()
+        );
+        // An unnamed key is tried against every signature
+        assert_eq!(
+            verify_header(&header, &signed.json, &[SigningKey::unnamed(b"old")]).unwrap(),
+            body()
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_id() {
+        let signed = SignedObject::with_keys(body(), &[key("2026-10", b"secret")]).unwrap();
+        // Same secret, but the signature names another key
+        assert!(matches!(
+            verify_header(
+                &signed.to_header(),
+                &signed.json,
+                &[key("2026-11", b"secret")]
+            ),
+            Err(SignatureError::SignatureMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_malformed_header() {
+        for header in [
+            "",
+            "abc",
+            "t=",
+            "t=123",
+            "t=x,k=AAAA",
+            "t=1,=AAAA",
+            "t=1,kAAAA",
+            "x.AAAA",
+        ] {
+            assert!(
+                matches!(
+                    parse_signature_header(header),
+                    Err(SignatureError::InvalidFormat)
+                ),
+                "{header:?}"
+            );
+        }
+        for header in ["123.!!!", "t=123,k=!!!"] {
+            assert!(
+                matches!(
+                    parse_signature_header(header),
+                    Err(SignatureError::InvalidBase64)
+                ),
+                "{header:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stale_timestamp() {
+        let timestamp = time::OffsetDateTime::now_utc().unix_timestamp() - MAX_SIGNATURE_AGE - 10;
+        let json = serde_json::to_string(&body()).unwrap();
+        let signature = KeyedSignature {
+            key_id: None,
+            signature: hmac_sha256(b"secret", &format!("{timestamp}.{json}")),
+        };
+        let header = format_signature_header(timestamp, &[signature]);
+        assert!(matches!(
+            verify_header(&header, &json, &[SigningKey::unnamed(b"secret")]),
+            Err(SignatureError::Expired)
+        ));
+    }
+
+    #[test]
+    fn test_only_rotated_key_matches() {
+        // The sender still signs with a retired secret under the old ID, and
+        // with the new secret; only the second signature verifies
+        let sender_keys = [key("2026-09", b"retired"), key("2026-10", b"new")];
+        let signed = SignedObject::with_keys(body(), &sender_keys).unwrap();
+        let header = signed.to_header();
+
+        let server_keys = [key("2026-09", b"old"), key("2026-10", b"new")];
+        assert_eq!(
+            verify_header(&header, &signed.json, &server_keys).unwrap(),
+            body()
+        );
+        assert!(matches!(
+            verify_header(&header, &signed.json, &server_keys[..1]),
+            Err(SignatureError::SignatureMismatch)
+        ));
+
+        let url = "https://checkout.example.com/pay?order=1";
+        let timestamp = time::OffsetDateTime::now_utc().unix_timestamp();
+        let data = format!("{url}.{timestamp}");
+        let signatures = [
+            KeyedSignature {
+                key_id: Some("2026-09".to_string()),
+                signature: hmac_sha256(b"retired", &data),
+            },
+            KeyedSignature {
+                key_id: Some("2026-10".to_string()),
+                signature: hmac_sha256(b"new", &data),
+            },
+        ];
+        assert!(verify_url_any(url, timestamp, &signatures, &server_keys).is_ok());
+        assert!(verify_url_any(url, timestamp, &signatures, &server_keys[..1]).is_err());
+    }
+}
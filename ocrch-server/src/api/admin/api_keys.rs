@@ -0,0 +1,132 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use kanau::processor::Processor;
+use ocrch_core::config::{default_merchant, find_merchant};
+use ocrch_core::entities::api_key::{ApiKey, CreateApiKey, ListApiKeys, RevokeApiKey};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_core::utils::hex;
+use ocrch_sdk::objects::admin::{
+    AdminApiKeyResponse, CreateApiKeyRequest, CreatedApiKeyResponse, ListApiKeysQuery,
+};
+
+use crate::api::extractors::AdminAuth;
+use crate::state::AppState;
+
+use super::AdminApiError;
+
+/// `POST /api-keys` — create an API key for a merchant.
+///
+/// The key ID and secret are generated here; the secret is only returned
+/// in this response.
+pub async fn create_api_key(
+    state: State<AppState>,
+    _auth: AdminAuth,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    if request.scopes.is_empty() {
+        return Err(AdminApiError::InvalidRequest(
+            "an API key needs at least one scope".to_string(),
+        ));
+    }
+    let expires_at = request
+        .expires_at
+        .map(|ts| {
+            time::OffsetDateTime::from_unix_timestamp(ts)
+                .map(|t| time::PrimitiveDateTime::new(t.date(), t.time()))
+                .map_err(|_| AdminApiError::InvalidRequest(format!("invalid expires_at {ts}")))
+        })
+        .transpose()?;
+
+    let merchants = state.config.merchants.read().await;
+    let merchant = match &request.merchant_id {
+        Some(id) => find_merchant(&merchants, id),
+        None => default_merchant(&merchants),
+    };
+    let merchant_id = merchant.ok_or(AdminApiError::NotFound)?.id.clone();
+    drop(merchants);
+
+    let mut scopes = Vec::new();
+    for scope in request.scopes.into_iter().map(Into::into) {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    let key = processor
+        .process(CreateApiKey {
+            id: format!("ak_{}", hex::encode(&rand::random::<[u8; 8]>())),
+            merchant_id,
+            name: request.name,
+            secret: hex::encode(&rand::random::<[u8; 32]>()),
+            scopes,
+            expires_at,
+        })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    let response = CreatedApiKeyResponse {
+        key: to_response(&key),
+        secret: key.secret,
+    };
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// `GET /api-keys` — list API keys, including revoked ones. Secrets are
+/// not returned.
+pub async fn list_api_keys(
+    state: State<AppState>,
+    _auth: AdminAuth,
+    Query(query): Query<ListApiKeysQuery>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    let keys = processor
+        .process(ListApiKeys {
+            merchant_id: query.merchant_id,
+        })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    let response: Vec<AdminApiKeyResponse> = keys.iter().map(to_response).collect();
+    Ok(Json(response))
+}
+
+/// `DELETE /api-keys/{key_id}` — revoke an API key. Requests signed with it
+/// are rejected from now on.
+pub async fn revoke_api_key(
+    state: State<AppState>,
+    _auth: AdminAuth,
+    Path(key_id): Path<String>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    let key = processor
+        .process(RevokeApiKey { id: key_id })
+        .await
+        .map_err(AdminApiError::Database)?
+        .ok_or(AdminApiError::NotFound)?;
+
+    Ok(Json(to_response(&key)))
+}
+
+fn to_response(key: &ApiKey) -> AdminApiKeyResponse {
+    AdminApiKeyResponse {
+        id: key.id.clone(),
+        merchant_id: key.merchant_id.clone(),
+        name: key.name.clone(),
+        scopes: key.scopes.iter().map(|&s| s.into()).collect(),
+        created_at: key.created_at.assume_utc().unix_timestamp(),
+        expires_at: key.expires_at.map(|t| t.assume_utc().unix_timestamp()),
+        last_used_at: key.last_used_at.map(|t| t.assume_utc().unix_timestamp()),
+        revoked_at: key.revoked_at.map(|t| t.assume_utc().unix_timestamp()),
+    }
+}
@@ -46,6 +46,7 @@ pub async fn list_deposits(
                 order_id: query.order_id,
                 chain: erc20_chain,
                 token: query.token.map(StablecoinName::from),
+                merchant_id: query.merchant_id.clone(),
             })
             .await
             .map_err(AdminApiError::Database)?;
@@ -72,6 +73,7 @@ pub async fn list_deposits(
                 offset: 0,
                 order_id: query.order_id,
                 token: query.token.map(StablecoinName::from),
+                merchant_id: query.merchant_id.clone(),
             })
             .await
             .map_err(AdminApiError::Database)?;
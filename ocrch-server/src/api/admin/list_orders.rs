@@ -20,6 +20,15 @@ pub async fn list_orders(
     };
 
     let (limit, offset) = clamp_pagination(query.limit, query.offset);
+    let metadata = query
+        .metadata
+        .map(|m| match serde_json::from_str::<serde_json::Value>(&m) {
+            Ok(value) if value.is_object() => Ok(value),
+            _ => Err(AdminApiError::InvalidRequest(
+                "metadata must be a JSON object".to_string(),
+            )),
+        })
+        .transpose()?;
 
     let records = processor
         .process(ListOrderRecords {
@@ -27,6 +36,10 @@ pub async fn list_orders(
             offset,
             status: query.status.map(Into::into),
             merchant_order_id: query.merchant_order_id,
+            merchant_id: query.merchant_id,
+            metadata,
+            description: query.description,
+            customer_reference: query.customer_reference,
         })
         .await
         .map_err(AdminApiError::Database)?;
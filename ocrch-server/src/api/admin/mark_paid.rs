@@ -1,6 +1,7 @@
 use axum::{Json, extract::Path, response::IntoResponse};
 use kanau::processor::Processor;
-use ocrch_core::entities::order_records::{GetOrderRecordById, OrderStatus, UpdateOrderStatus};
+use ocrch_core::entities::order_records::{GetOrderRecordById, OrderRecord, OrderStatus};
+use ocrch_core::entities::webhook_outbox::WebhookOutbox;
 use ocrch_core::events::WebhookEvent;
 use ocrch_core::framework::DatabaseProcessor;
 use uuid::Uuid;
@@ -12,8 +13,9 @@ use super::{AdminApiError, order_to_admin_response};
 
 /// `POST /orders/{order_id}/mark-paid` — force-mark an order as paid.
 ///
-/// Updates the order status to `Paid`, emits a webhook event, and broadcasts
-/// to WebSocket clients.
+/// Updates the order status to `Paid` and queues the webhook in the same
+/// transaction, then wakes the webhook sender and broadcasts to WebSocket
+/// clients.
 pub async fn mark_paid(
     state: axum::extract::State<AppState>,
     _auth: AdminAuth,
@@ -33,25 +35,31 @@ pub async fn mark_paid(
         return Ok(Json(order_to_admin_response(&order)));
     }
 
-    processor
-        .process(UpdateOrderStatus {
-            order_id,
-            status: OrderStatus::Paid,
-        })
+    let webhook_event = WebhookEvent::OrderStatusChanged {
+        order_id,
+        new_status: OrderStatus::Paid,
+    };
+
+    let mut tx = state.db.begin().await.map_err(AdminApiError::Database)?;
+    OrderRecord::update_status_tx(&mut tx, order_id, OrderStatus::Paid)
+        .await
+        .map_err(AdminApiError::Database)?;
+    WebhookOutbox::enqueue_tx(&mut tx, &webhook_event)
         .await
         .map_err(AdminApiError::Database)?;
+    tx.commit().await.map_err(AdminApiError::Database)?;
 
     state
         .event_senders
         .webhook_event
-        .send(WebhookEvent::OrderStatusChanged {
-            order_id,
-            new_status: OrderStatus::Paid,
-        })
+        .send(webhook_event)
         .await
         .map_err(|_| AdminApiError::EventChannelClosed)?;
 
-    let _ = state.order_status_tx.send(OrderStatusUpdate { order_id });
+    let _ = state.order_status_tx.send(OrderStatusUpdate {
+        order_id,
+        payment_detected: None,
+    });
 
     let updated = processor
         .process(GetOrderRecordById { order_id })
@@ -0,0 +1,129 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use kanau::processor::Processor;
+use ocrch_core::config::WebhookPayloadVersion;
+use ocrch_core::entities::managed_config::{
+    DeleteManagedMerchant, ManagedMerchant, ManagedMerchantSecret, UpsertManagedMerchant,
+};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_sdk::objects::admin::{AdminMerchantResponse, PutMerchantRequest};
+
+use crate::api::extractors::AdminAuth;
+use crate::state::AppState;
+
+use super::{AdminApiError, apply_managed_change, check_managed, load_managed};
+
+/// `GET /merchants` — show the configured merchants, default merchant
+/// first. Secrets are not returned.
+pub async fn list_merchants(
+    state: State<AppState>,
+    _auth: AdminAuth,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let managed = load_managed(&state).await?;
+    let merchants = state.config.merchants.read().await;
+
+    let response: Vec<AdminMerchantResponse> = merchants
+        .iter()
+        .enumerate()
+        .map(|(i, m)| AdminMerchantResponse {
+            id: m.id.clone(),
+            name: m.name.clone(),
+            secret_ids: m.secrets.iter().filter_map(|s| s.id.clone()).collect(),
+            allowed_origins: m.allowed_origins.clone(),
+            unknown_transfer_webhook_url: m.unknown_transfer_webhook_url.clone(),
+            default_webhook_url: m.default_webhook_url.clone(),
+            webhook_payload_version: match m.webhook_payload_version {
+                WebhookPayloadVersion::V1 => 1,
+                WebhookPayloadVersion::V2 => 2,
+            },
+            webhook_allowed_hosts: m.webhook_url_policy.allowed_hosts.clone(),
+            webhook_allow_private_networks: m.webhook_url_policy.allow_private_networks,
+            default: i == 0,
+            managed: managed.merchants.iter().any(|managed| managed.id == m.id),
+        })
+        .collect();
+    drop(merchants);
+
+    Ok(Json(response))
+}
+
+/// `PUT /merchants/{merchant_id}` — create or replace a managed merchant.
+///
+/// A merchant from the config file with the same ID is replaced. The new
+/// settings apply on every instance; returns the reload diff of this one.
+pub async fn put_merchant(
+    state: State<AppState>,
+    _auth: AdminAuth,
+    Path(merchant_id): Path<String>,
+    Json(request): Json<PutMerchantRequest>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let mut managed = load_managed(&state).await?;
+
+    let secrets = request
+        .secret
+        .map(|secret| ManagedMerchantSecret {
+            key_id: None,
+            secret,
+        })
+        .into_iter()
+        .chain(request.secrets.into_iter().map(|s| ManagedMerchantSecret {
+            key_id: Some(s.id),
+            secret: s.secret,
+        }))
+        .collect();
+    let merchant = ManagedMerchant {
+        id: merchant_id,
+        name: request.name,
+        secrets,
+        allowed_origins: request.allowed_origins,
+        unknown_transfer_webhook_url: request.unknown_transfer_webhook_url,
+        default_webhook_url: request.default_webhook_url,
+        webhook_payload_version: request.webhook_payload_version.into(),
+        webhook_allowed_hosts: request.webhook_allowed_hosts,
+        webhook_allow_private_networks: request.webhook_allow_private_networks,
+    };
+    managed.merchants.retain(|m| m.id != merchant.id);
+    managed.merchants.push(merchant.clone());
+    check_managed(&state, &managed)?;
+
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    processor
+        .process(UpsertManagedMerchant { merchant })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    apply_managed_change(&state).await
+}
+
+/// `DELETE /merchants/{merchant_id}` — delete a managed merchant.
+///
+/// A merchant from the config file with the same ID takes effect again.
+/// Returns 404 if no such merchant is stored, and 400 if wallets still
+/// belong to it.
+pub async fn delete_merchant(
+    state: State<AppState>,
+    _auth: AdminAuth,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let mut managed = load_managed(&state).await?;
+    if !managed.merchants.iter().any(|m| m.id == merchant_id) {
+        return Err(AdminApiError::NotFound);
+    }
+    managed.merchants.retain(|m| m.id != merchant_id);
+    check_managed(&state, &managed)?;
+
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    processor
+        .process(DeleteManagedMerchant { id: merchant_id })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    apply_managed_change(&state).await
+}
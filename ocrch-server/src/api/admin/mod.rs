@@ -9,26 +9,58 @@
 //! - `GET  /deposits`                         – list pending deposits (paginated, filterable)
 //! - `GET  /wallets/{address}/transfers`      – list transfers for a wallet
 //! - `GET  /wallets`                          – show wallets and enabled coins
+//! - `PUT  /wallets/{blockchain}/{address}`   – create or replace a managed wallet
+//! - `DELETE /wallets/{blockchain}/{address}` – delete a managed wallet
+//! - `GET  /merchants`                        – show merchants (without secrets)
+//! - `PUT  /merchants/{merchant_id}`          – create or replace a managed merchant
+//! - `DELETE /merchants/{merchant_id}`        – delete a managed merchant
+//! - `GET  /sync-health`                      – sync health per chain-coin pair
 //! - `POST /orders/{order_id}/mark-paid`      – force-mark an order as paid
 //! - `POST /orders/{order_id}/resend-webhook` – resend order status webhook
 //! - `POST /transfers/{transfer_id}/resend-webhook` – resend unknown transfer webhook
+//! - `GET  /orders/{order_id}/webhook-attempts` – webhook delivery attempts for an order
+//! - `GET  /transfers/{transfer_id}/webhook-attempts` – webhook delivery attempts for a transfer
+//! - `GET  /webhook-dead-letters`             – webhook deliveries that exhausted their retries
+//! - `POST /webhook-dead-letters/replay`      – replay dead-lettered deliveries by filter
+//! - `POST /webhook-test`                     – send a signed test event to a URL or subscription
+//! - `POST /resync`                           – re-fetch a wallet's transfers over a range
+//! - `GET  /api-keys`                         – list API keys (without secrets)
+//! - `POST /api-keys`                         – create an API key
+//! - `DELETE /api-keys/{key_id}`              – revoke an API key
+//! - `GET  /reload`                           – diff applied by the last config reload
+//! - `POST /reload`                           – reload the config file and respawn sync tasks
 
 use axum::{
-    Router,
+    Json, Router,
     http::StatusCode,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
 
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_core::processors::webhook_sender::WebhookError;
+use ocrch_sdk::objects::admin::ReloadReport;
+
+use crate::config::ConfigError;
+use crate::config::managed::ManagedConfig;
+use crate::resync::ResyncError;
 use crate::state::AppState;
 
+mod api_keys;
 mod list_deposits;
 mod list_orders;
 mod list_transfers;
 mod mark_paid;
+mod merchants;
+mod reload;
 mod resend_order_webhook;
 mod resend_transfer_webhook;
-mod show_wallets;
+mod resync;
+mod sync_health;
+mod wallets;
+mod webhook_attempts;
+mod webhook_dead_letters;
+mod webhook_test;
 
 /// Build the Admin API router.
 pub fn router() -> Router<AppState> {
@@ -39,7 +71,17 @@ pub fn router() -> Router<AppState> {
             "/wallets/{address}/transfers",
             get(list_transfers::list_transfers),
         )
-        .route("/wallets", get(show_wallets::show_wallets))
+        .route("/wallets", get(wallets::show_wallets))
+        .route(
+            "/wallets/{blockchain}/{address}",
+            put(wallets::put_wallet).delete(wallets::delete_wallet),
+        )
+        .route("/merchants", get(merchants::list_merchants))
+        .route(
+            "/merchants/{merchant_id}",
+            put(merchants::put_merchant).delete(merchants::delete_merchant),
+        )
+        .route("/sync-health", get(sync_health::sync_health))
         .route("/orders/{order_id}/mark-paid", post(mark_paid::mark_paid))
         .route(
             "/orders/{order_id}/resend-webhook",
@@ -49,6 +91,33 @@ pub fn router() -> Router<AppState> {
             "/transfers/{transfer_id}/resend-webhook",
             post(resend_transfer_webhook::resend_transfer_webhook),
         )
+        .route(
+            "/orders/{order_id}/webhook-attempts",
+            get(webhook_attempts::order_webhook_attempts),
+        )
+        .route(
+            "/transfers/{transfer_id}/webhook-attempts",
+            get(webhook_attempts::transfer_webhook_attempts),
+        )
+        .route(
+            "/webhook-dead-letters",
+            get(webhook_dead_letters::list_dead_letters),
+        )
+        .route(
+            "/webhook-dead-letters/replay",
+            post(webhook_dead_letters::replay_dead_letters),
+        )
+        .route("/webhook-test", post(webhook_test::test_webhook))
+        .route("/resync", post(resync::resync))
+        .route(
+            "/api-keys",
+            get(api_keys::list_api_keys).post(api_keys::create_api_key),
+        )
+        .route("/api-keys/{key_id}", delete(api_keys::revoke_api_key))
+        .route(
+            "/reload",
+            get(reload::last_reload).post(reload::reload_config),
+        )
 }
 
 // ---------------------------------------------------------------------------
@@ -61,6 +130,16 @@ pub(crate) enum AdminApiError {
     Database(sqlx::Error),
     NotFound,
     EventChannelClosed,
+    Resync(ResyncError),
+    Reload(ConfigError),
+    InvalidRequest(String),
+    Webhook(WebhookError),
+}
+
+impl From<ResyncError> for AdminApiError {
+    fn from(e: ResyncError) -> Self {
+        AdminApiError::Resync(e)
+    }
 }
 
 impl IntoResponse for AdminApiError {
@@ -77,10 +156,59 @@ impl IntoResponse for AdminApiError {
                 tracing::error!("Admin API: event channel closed");
                 (StatusCode::INTERNAL_SERVER_ERROR, "internal server error").into_response()
             }
+            AdminApiError::Resync(ResyncError::Sync(e)) => {
+                tracing::error!(error = %e, "Admin API resync failed");
+                (StatusCode::BAD_GATEWAY, format!("resync failed: {e}")).into_response()
+            }
+            AdminApiError::Resync(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            AdminApiError::Reload(e) => {
+                tracing::error!(error = %e, "Admin API config reload failed");
+                (StatusCode::BAD_REQUEST, format!("reload failed: {e}")).into_response()
+            }
+            AdminApiError::InvalidRequest(message) => {
+                (StatusCode::BAD_REQUEST, message).into_response()
+            }
+            AdminApiError::Webhook(e) => {
+                tracing::error!(error = %e, "Admin API webhook error");
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal server error").into_response()
+            }
         }
     }
 }
 
+// ---------------------------------------------------------------------------
+// Managed merchants and wallets
+// ---------------------------------------------------------------------------
+
+/// Read the merchants and wallets stored in the database.
+async fn load_managed(state: &AppState) -> Result<ManagedConfig, AdminApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    ManagedConfig::load(&processor)
+        .await
+        .map_err(AdminApiError::Database)
+}
+
+/// Check a changed set of managed entries against the config file before
+/// storing it.
+fn check_managed(state: &AppState, managed: &ManagedConfig) -> Result<(), AdminApiError> {
+    state.reloader.check(managed).map_err(|e| match e {
+        ConfigError::ValidationError(message) => AdminApiError::InvalidRequest(message),
+        e => AdminApiError::Reload(e),
+    })
+}
+
+/// Apply a stored change on every instance and return this one's diff.
+async fn apply_managed_change(state: &AppState) -> Result<Json<ReloadReport>, AdminApiError> {
+    let report = state
+        .reloader
+        .managed_changed()
+        .await
+        .map_err(AdminApiError::Reload)?;
+    Ok(Json(report))
+}
+
 // ---------------------------------------------------------------------------
 // Conversion helpers
 // ---------------------------------------------------------------------------
@@ -91,6 +219,7 @@ use ocrch_sdk::objects::admin::AdminOrderResponse;
 pub(crate) fn order_to_admin_response(r: &OrderRecord) -> AdminOrderResponse {
     AdminOrderResponse {
         order_id: r.order_id,
+        merchant_id: r.merchant_id.clone(),
         merchant_order_id: r.merchant_order_id.clone(),
         amount: r.amount,
         status: r.status.into(),
@@ -103,5 +232,8 @@ pub(crate) fn order_to_admin_response(r: &OrderRecord) -> AdminOrderResponse {
         webhook_last_tried_at: r
             .webhook_last_tried_at
             .map(|t| t.assume_utc().unix_timestamp()),
+        metadata: r.metadata.clone(),
+        description: r.description.clone(),
+        customer_reference: r.customer_reference.clone(),
     }
 }
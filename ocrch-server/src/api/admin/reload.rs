@@ -0,0 +1,37 @@
+use axum::{Json, response::IntoResponse};
+
+use crate::api::extractors::AdminAuth;
+use crate::state::AppState;
+
+use super::AdminApiError;
+
+/// `POST /reload` — reload the config file, same as sending SIGHUP.
+///
+/// Sync runners and log subscribers whose settings changed are respawned
+/// before the response is sent. Returns the applied diff.
+pub async fn reload_config(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let report = state
+        .reloader
+        .reload()
+        .await
+        .map_err(AdminApiError::Reload)?;
+    Ok(Json(report))
+}
+
+/// `GET /reload` — the diff applied by the most recent successful reload.
+///
+/// Returns 404 if the config has not been reloaded since startup.
+pub async fn last_reload(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let report = state
+        .reloader
+        .last_report()
+        .await
+        .ok_or(AdminApiError::NotFound)?;
+    Ok(Json(report))
+}
@@ -1,6 +1,7 @@
 use axum::{extract::Path, http::StatusCode, response::IntoResponse};
 use kanau::processor::Processor;
 use ocrch_core::entities::order_records::GetOrderRecordById;
+use ocrch_core::entities::webhook_outbox::{EnqueueWebhookEvent, RequeueOrderStatusWebhook};
 use ocrch_core::events::WebhookEvent;
 use ocrch_core::framework::DatabaseProcessor;
 use uuid::Uuid;
@@ -12,8 +13,10 @@ use super::AdminApiError;
 
 /// `POST /orders/{order_id}/resend-webhook` — resend the order status webhook.
 ///
-/// Emits a `WebhookEvent::OrderStatusChanged` for the order's current status,
-/// causing the webhook sender to re-deliver it.
+/// Delivers the order's latest status event again, with the same event ID
+/// and sequence, to every destination it was sent to. Orders without one
+/// get a new `WebhookEvent::OrderStatusChanged` for their current status.
+/// Either way the webhook sender is woken to deliver it.
 pub async fn resend_order_webhook(
     state: axum::extract::State<AppState>,
     _auth: AdminAuth,
@@ -29,13 +32,27 @@ pub async fn resend_order_webhook(
         .map_err(AdminApiError::Database)?
         .ok_or(AdminApiError::NotFound)?;
 
+    let event = WebhookEvent::OrderStatusChanged {
+        order_id,
+        new_status: order.status,
+    };
+    let requeued = processor
+        .process(RequeueOrderStatusWebhook { order_id })
+        .await
+        .map_err(AdminApiError::Database)?;
+    if requeued.is_none() {
+        processor
+            .process(EnqueueWebhookEvent {
+                event: event.clone(),
+            })
+            .await
+            .map_err(AdminApiError::Database)?;
+    }
+
     state
         .event_senders
         .webhook_event
-        .send(WebhookEvent::OrderStatusChanged {
-            order_id,
-            new_status: order.status,
-        })
+        .send(event)
         .await
         .map_err(|_| AdminApiError::EventChannelClosed)?;
 
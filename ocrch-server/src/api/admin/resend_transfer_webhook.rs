@@ -2,6 +2,7 @@ use axum::{extract::Path, http::StatusCode, response::IntoResponse};
 use kanau::processor::Processor;
 use ocrch_core::entities::erc20_transfer::GetErc20TransferById;
 use ocrch_core::entities::trc20_transfer::GetTrc20TransferById;
+use ocrch_core::entities::webhook_outbox::{EnqueueWebhookEvent, RequeueTransferWebhook};
 use ocrch_core::events::{BlockchainTarget, WebhookEvent};
 use ocrch_core::framework::DatabaseProcessor;
 
@@ -12,8 +13,10 @@ use super::AdminApiError;
 
 /// `POST /transfers/{transfer_id}/resend-webhook` — resend an unknown transfer webhook.
 ///
-/// Looks up the transfer by ID in both ERC-20 and TRC-20 tables, then emits a
-/// `WebhookEvent::UnknownTransferReceived` event.
+/// Looks up the transfer by ID in both ERC-20 and TRC-20 tables, then
+/// delivers its latest unknown transfer event again with the same event ID,
+/// or queues a new `WebhookEvent::UnknownTransferReceived` if it has none, and
+/// wakes the webhook sender.
 pub async fn resend_transfer_webhook(
     state: axum::extract::State<AppState>,
     _auth: AdminAuth,
@@ -23,42 +26,50 @@ pub async fn resend_transfer_webhook(
         pool: state.db.clone(),
     };
 
-    if let Some(erc20) = processor
+    let blockchain = if let Some(erc20) = processor
         .process(GetErc20TransferById { id: transfer_id })
         .await
         .map_err(AdminApiError::Database)?
     {
-        state
-            .event_senders
-            .webhook_event
-            .send(WebhookEvent::UnknownTransferReceived {
-                transfer_id,
-                blockchain: BlockchainTarget::Erc20(erc20.chain),
-            })
-            .await
-            .map_err(|_| AdminApiError::EventChannelClosed)?;
-
-        return Ok(StatusCode::ACCEPTED);
-    }
-
-    if processor
+        BlockchainTarget::Erc20(erc20.chain)
+    } else if processor
         .process(GetTrc20TransferById { id: transfer_id })
         .await
         .map_err(AdminApiError::Database)?
         .is_some()
     {
-        state
-            .event_senders
-            .webhook_event
-            .send(WebhookEvent::UnknownTransferReceived {
-                transfer_id,
-                blockchain: BlockchainTarget::Trc20,
+        BlockchainTarget::Trc20
+    } else {
+        return Err(AdminApiError::NotFound);
+    };
+
+    let chain = match blockchain {
+        BlockchainTarget::Erc20(chain) => Some(chain),
+        BlockchainTarget::Trc20 => None,
+    };
+    let event = WebhookEvent::UnknownTransferReceived {
+        transfer_id,
+        blockchain,
+    };
+    let requeued = processor
+        .process(RequeueTransferWebhook { transfer_id, chain })
+        .await
+        .map_err(AdminApiError::Database)?;
+    if requeued.is_none() {
+        processor
+            .process(EnqueueWebhookEvent {
+                event: event.clone(),
             })
             .await
-            .map_err(|_| AdminApiError::EventChannelClosed)?;
-
-        return Ok(StatusCode::ACCEPTED);
+            .map_err(AdminApiError::Database)?;
     }
 
-    Err(AdminApiError::NotFound)
+    state
+        .event_senders
+        .webhook_event
+        .send(event)
+        .await
+        .map_err(|_| AdminApiError::EventChannelClosed)?;
+
+    Ok(StatusCode::ACCEPTED)
 }
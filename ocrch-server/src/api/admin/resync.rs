@@ -0,0 +1,20 @@
+use axum::{Json, response::IntoResponse};
+use ocrch_sdk::objects::admin::{ResyncRequest, ResyncResponse};
+
+use crate::api::extractors::AdminAuth;
+use crate::state::AppState;
+
+use super::AdminApiError;
+
+/// `POST /resync` — re-fetch a wallet's transfers over a historical range.
+///
+/// Runs synchronously and reports how many transfers were new. The live sync
+/// cursor is left untouched.
+pub async fn resync(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+    Json(request): Json<ResyncRequest>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let inserted = crate::resync::resync(&state.db, &state.config, &request).await?;
+    Ok(Json(ResyncResponse { inserted }))
+}
@@ -0,0 +1,34 @@
+use axum::{Json, response::IntoResponse};
+use ocrch_sdk::objects::admin::AdminSyncHealthResponse;
+
+use crate::api::extractors::AdminAuth;
+use crate::state::AppState;
+
+/// `GET /sync-health` — sync health per (blockchain, stablecoin) pair.
+///
+/// Pairs that have not completed a sync attempt yet are not listed.
+pub async fn sync_health(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+) -> impl IntoResponse {
+    let health_config = state.config.sync_health.read().await.clone();
+    let snapshot = state.sync_health.snapshot().await;
+
+    let response: Vec<AdminSyncHealthResponse> = snapshot
+        .into_iter()
+        .map(|(key, health)| AdminSyncHealthResponse {
+            blockchain: key.blockchain.into(),
+            token: key.token.into(),
+            healthy: health.is_healthy(&health_config),
+            last_attempt_at: health.last_attempt_at.map(|t| t.unix_timestamp()),
+            last_success_at: health.last_success_at.map(|t| t.unix_timestamp()),
+            consecutive_failures: health.consecutive_failures,
+            lag: health.lag(),
+            last_error: health.last_error,
+            latest_block: health.latest_block,
+            chain_head: health.chain_head,
+        })
+        .collect();
+
+    Json(response)
+}
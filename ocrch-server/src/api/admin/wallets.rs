@@ -0,0 +1,157 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use kanau::processor::Processor;
+use ocrch_core::entities::managed_config::{
+    DeleteManagedWallet, ManagedWallet, UpsertManagedWallet,
+};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_sdk::objects::admin::{AdminWalletResponse, PutWalletRequest};
+use ocrch_sdk::objects::blockchains::Blockchain;
+
+use crate::api::extractors::AdminAuth;
+use crate::config::managed::same_wallet;
+use crate::state::AppState;
+
+use super::{AdminApiError, apply_managed_change, check_managed, load_managed};
+
+/// `GET /wallets` — show the configured wallets and enabled coins, followed
+/// by the disabled managed wallets.
+pub async fn show_wallets(
+    state: State<AppState>,
+    _auth: AdminAuth,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let managed = load_managed(&state).await?;
+    let wallets = state.config.wallets.read().await;
+
+    let mut response: Vec<AdminWalletResponse> = wallets
+        .iter()
+        .map(|w| AdminWalletResponse {
+            blockchain: w.blockchain,
+            address: w.address.clone(),
+            enabled_coins: w.enabled_coins.clone(),
+            merchant: w.merchant.clone(),
+            starting_tx: w.starting_tx.clone(),
+            enabled: true,
+            managed: managed.wallets.iter().any(|m| {
+                Blockchain::from(m.blockchain) == w.blockchain
+                    && same_wallet(w.blockchain, &m.address, &w.address)
+            }),
+        })
+        .collect();
+    drop(wallets);
+
+    response.extend(managed.wallets.into_iter().filter(|w| !w.enabled).map(|w| {
+        AdminWalletResponse {
+            blockchain: w.blockchain.into(),
+            address: w.address,
+            enabled_coins: w.enabled_coins.into_iter().map(Into::into).collect(),
+            merchant: w.merchant_id,
+            starting_tx: w.starting_tx,
+            enabled: false,
+            managed: true,
+        }
+    }));
+
+    Ok(Json(response))
+}
+
+/// `PUT /wallets/{blockchain}/{address}` — create or replace a managed
+/// wallet.
+///
+/// A wallet from the config file with the same address is replaced, or
+/// hidden if the wallet is disabled. Sync runners are respawned on every
+/// instance; returns the diff applied to this one.
+pub async fn put_wallet(
+    state: State<AppState>,
+    _auth: AdminAuth,
+    Path((blockchain, address)): Path<(Blockchain, String)>,
+    Json(request): Json<PutWalletRequest>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let mut managed = load_managed(&state).await?;
+
+    // Keep the spelling of an existing EVM address, so the same wallet is
+    // not stored twice
+    let address = managed
+        .wallets
+        .iter()
+        .filter(|w| Blockchain::from(w.blockchain) == blockchain)
+        .map(|w| &w.address)
+        .chain(
+            state
+                .config
+                .wallets
+                .read()
+                .await
+                .iter()
+                .filter(|w| w.blockchain == blockchain)
+                .map(|w| &w.address),
+        )
+        .find(|a| same_wallet(blockchain, a, &address))
+        .cloned()
+        .unwrap_or(address);
+
+    let wallet = ManagedWallet {
+        blockchain: blockchain.into(),
+        address,
+        enabled_coins: request.enabled_coins.into_iter().map(Into::into).collect(),
+        starting_tx: request.starting_tx,
+        merchant_id: request.merchant,
+        enabled: request.enabled,
+    };
+    managed
+        .wallets
+        .retain(|w| !(w.blockchain == wallet.blockchain && w.address == wallet.address));
+    managed.wallets.push(wallet.clone());
+    check_managed(&state, &managed)?;
+
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    processor
+        .process(UpsertManagedWallet { wallet })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    apply_managed_change(&state).await
+}
+
+/// `DELETE /wallets/{blockchain}/{address}` — delete a managed wallet.
+///
+/// A wallet from the config file with the same address takes effect
+/// again. Returns 404 if no such wallet is stored.
+pub async fn delete_wallet(
+    state: State<AppState>,
+    _auth: AdminAuth,
+    Path((blockchain, address)): Path<(Blockchain, String)>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let mut managed = load_managed(&state).await?;
+    let stored = managed
+        .wallets
+        .iter()
+        .find(|w| {
+            Blockchain::from(w.blockchain) == blockchain
+                && same_wallet(blockchain, &w.address, &address)
+        })
+        .map(|w| w.address.clone())
+        .ok_or(AdminApiError::NotFound)?;
+    managed
+        .wallets
+        .retain(|w| !(Blockchain::from(w.blockchain) == blockchain && w.address == stored));
+    check_managed(&state, &managed)?;
+
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    processor
+        .process(DeleteManagedWallet {
+            blockchain: blockchain.into(),
+            address: stored,
+        })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    apply_managed_change(&state).await
+}
@@ -0,0 +1,100 @@
+use axum::{Json, extract::Path, response::IntoResponse};
+use kanau::processor::Processor;
+use ocrch_core::entities::erc20_transfer::GetErc20TransferById;
+use ocrch_core::entities::order_records::GetOrderRecordById;
+use ocrch_core::entities::trc20_transfer::GetTrc20TransferById;
+use ocrch_core::entities::webhook_delivery_attempt::{
+    ListOrderWebhookDeliveryAttempts, ListTransferWebhookDeliveryAttempts, WebhookDeliveryAttempt,
+};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_sdk::objects::admin::AdminWebhookAttemptResponse;
+use uuid::Uuid;
+
+use crate::api::extractors::AdminAuth;
+use crate::state::AppState;
+
+use super::AdminApiError;
+
+/// `GET /orders/{order_id}/webhook-attempts` — delivery attempts for every
+/// webhook of an order, newest first.
+pub async fn order_webhook_attempts(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+    Path(order_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+
+    processor
+        .process(GetOrderRecordById { order_id })
+        .await
+        .map_err(AdminApiError::Database)?
+        .ok_or(AdminApiError::NotFound)?;
+
+    let attempts = processor
+        .process(ListOrderWebhookDeliveryAttempts { order_id })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    let response: Vec<_> = attempts.iter().map(attempt_to_response).collect();
+    Ok(Json(response))
+}
+
+/// `GET /transfers/{transfer_id}/webhook-attempts` — delivery attempts for
+/// the unknown-transfer webhooks of a transfer, newest first.
+///
+/// The transfer is looked up in the ERC-20 table first, then TRC-20, same as
+/// the resend endpoint.
+pub async fn transfer_webhook_attempts(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+    Path(transfer_id): Path<i64>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+
+    let chain = if let Some(erc20) = processor
+        .process(GetErc20TransferById { id: transfer_id })
+        .await
+        .map_err(AdminApiError::Database)?
+    {
+        Some(erc20.chain)
+    } else if processor
+        .process(GetTrc20TransferById { id: transfer_id })
+        .await
+        .map_err(AdminApiError::Database)?
+        .is_some()
+    {
+        None
+    } else {
+        return Err(AdminApiError::NotFound);
+    };
+
+    let attempts = processor
+        .process(ListTransferWebhookDeliveryAttempts { transfer_id, chain })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    let response: Vec<_> = attempts.iter().map(attempt_to_response).collect();
+    Ok(Json(response))
+}
+
+fn attempt_to_response(a: &WebhookDeliveryAttempt) -> AdminWebhookAttemptResponse {
+    AdminWebhookAttemptResponse {
+        id: a.id,
+        outbox_id: a.outbox_id,
+        event_id: a.event_id,
+        event_type: a.kind.into(),
+        subscription: a.subscription.clone(),
+        url: a.url.clone(),
+        body_sha256: a.body_sha256.clone(),
+        response_status: a.response_status.and_then(|s| u16::try_from(s).ok()),
+        response_body: a.response_body.clone(),
+        latency_ms: a.latency_ms.max(0) as u32,
+        error_kind: a.error_kind.map(Into::into),
+        error: a.error.clone(),
+        attempted_at: a.attempted_at.assume_utc().unix_timestamp(),
+    }
+}
@@ -0,0 +1,120 @@
+use axum::{Json, extract::Query, response::IntoResponse};
+use kanau::processor::Processor;
+use ocrch_core::entities::webhook_dead_letter::{
+    DeadLetteredWebhookDelivery, ListDeadLetteredWebhookDeliveries,
+    ReplayDeadLetteredWebhookDeliveries, WebhookDeadLetterFilter,
+};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_sdk::objects::WebhookEventType;
+use ocrch_sdk::objects::admin::{
+    AdminDeadLetterResponse, ListDeadLettersQuery, ReplayDeadLettersRequest,
+    ReplayDeadLettersResponse, clamp_pagination,
+};
+
+use crate::api::extractors::AdminAuth;
+use crate::state::AppState;
+
+use super::AdminApiError;
+
+/// `GET /webhook-dead-letters` — webhook deliveries that exhausted their
+/// retries, most recently dead-lettered first.
+pub async fn list_dead_letters(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+    Query(query): Query<ListDeadLettersQuery>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+
+    let (limit, offset) = clamp_pagination(query.limit, query.offset);
+    let filter = dead_letter_filter(query.host, query.event_type, query.since, query.until)?;
+
+    let deliveries = processor
+        .process(ListDeadLetteredWebhookDeliveries {
+            filter,
+            limit,
+            offset,
+        })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    let response: Vec<_> = deliveries.iter().map(dead_letter_to_response).collect();
+    Ok(Json(response))
+}
+
+/// `POST /webhook-dead-letters/replay` — schedule every matching dead-lettered
+/// delivery again with a fresh retry schedule.
+///
+/// The webhook sender picks the deliveries up on its next poll. A request
+/// without filters is rejected unless it sets `all`.
+pub async fn replay_dead_letters(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+    Json(request): Json<ReplayDeadLettersRequest>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+
+    let filter = dead_letter_filter(
+        request.host,
+        request.event_type,
+        request.since,
+        request.until,
+    )?;
+    if !request.all
+        && filter.host.is_none()
+        && filter.kind.is_none()
+        && filter.since.is_none()
+        && filter.until.is_none()
+    {
+        return Err(AdminApiError::InvalidRequest(
+            "set a filter, or `all` to replay every dead letter".to_string(),
+        ));
+    }
+
+    let replayed = processor
+        .process(ReplayDeadLetteredWebhookDeliveries { filter })
+        .await
+        .map_err(AdminApiError::Database)?;
+
+    tracing::info!(replayed, "Replayed dead-lettered webhook deliveries");
+    Ok(Json(ReplayDeadLettersResponse { replayed }))
+}
+
+fn dead_letter_filter(
+    host: Option<String>,
+    event_type: Option<WebhookEventType>,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> Result<WebhookDeadLetterFilter, AdminApiError> {
+    let to_datetime = |ts: i64| {
+        time::OffsetDateTime::from_unix_timestamp(ts)
+            .map(|t| time::PrimitiveDateTime::new(t.date(), t.time()))
+            .map_err(|_| AdminApiError::InvalidRequest(format!("invalid timestamp {ts}")))
+    };
+    Ok(WebhookDeadLetterFilter {
+        host: host.filter(|h| !h.is_empty()),
+        kind: event_type.map(Into::into),
+        since: since.map(to_datetime).transpose()?,
+        until: until.map(to_datetime).transpose()?,
+    })
+}
+
+fn dead_letter_to_response(d: &DeadLetteredWebhookDelivery) -> AdminDeadLetterResponse {
+    AdminDeadLetterResponse {
+        id: d.id,
+        outbox_id: d.outbox_id,
+        event_id: d.event_id,
+        event_type: d.kind.into(),
+        subscription: d.subscription.clone(),
+        order_id: d.order_id,
+        transfer_id: d.transfer_id,
+        retry_count: d.retry_count,
+        url: d.url.clone(),
+        last_error: d.last_error.clone(),
+        last_tried_at: d.last_tried_at.map(|t| t.assume_utc().unix_timestamp()),
+        dead_lettered_at: d.dead_lettered_at.assume_utc().unix_timestamp(),
+    }
+}
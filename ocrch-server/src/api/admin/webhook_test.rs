@@ -0,0 +1,93 @@
+use axum::{Json, response::IntoResponse};
+use kanau::processor::Processor;
+use ocrch_core::config::WebhookPayloadVersion;
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_core::processors::WebhookSender;
+use ocrch_core::processors::webhook_sender::{
+    TestWebhook, TestWebhookResult, TestWebhookTarget, WebhookError,
+};
+use ocrch_sdk::objects::admin::{TestWebhookRequest, TestWebhookResponse};
+
+use crate::api::extractors::AdminAuth;
+use crate::state::AppState;
+
+use super::AdminApiError;
+
+/// `POST /webhook-test` — send a signed test event to a URL or a
+/// `[[webhooks]]` subscription and return the endpoint's response.
+///
+/// The event goes through the `WebhookSender` signing and HTTP path but is
+/// not stored; a failed request is reported in the response body, not as
+/// an error status.
+pub async fn test_webhook(
+    state: axum::extract::State<AppState>,
+    _auth: AdminAuth,
+    Json(request): Json<TestWebhookRequest>,
+) -> Result<impl IntoResponse, AdminApiError> {
+    let target = match (request.url, request.subscription) {
+        (Some(url), None) => {
+            match url::Url::parse(&url) {
+                Ok(u) if matches!(u.scheme(), "http" | "https") => {}
+                _ => {
+                    return Err(AdminApiError::InvalidRequest(format!(
+                        "invalid webhook URL {url:?}"
+                    )));
+                }
+            }
+            TestWebhookTarget::Url(url)
+        }
+        (None, Some(name)) => TestWebhookTarget::Subscription(name),
+        _ => {
+            return Err(AdminApiError::InvalidRequest(
+                "set exactly one of url and subscription".to_string(),
+            ));
+        }
+    };
+    let payload_version = match request.payload_version {
+        None => None,
+        Some(1) => Some(WebhookPayloadVersion::V1),
+        Some(2) => Some(WebhookPayloadVersion::V2),
+        Some(v) => {
+            return Err(AdminApiError::InvalidRequest(format!(
+                "unsupported payload version {v}"
+            )));
+        }
+    };
+
+    let sender = WebhookSender::new(
+        DatabaseProcessor {
+            pool: state.db.clone(),
+        },
+        state.config.clone(),
+    );
+    let result = sender
+        .process(TestWebhook {
+            event: request.event,
+            target,
+            payload_version,
+            merchant_id: request.merchant_id,
+        })
+        .await
+        .map_err(|e| match e {
+            WebhookError::SubscriptionNotFound(_) | WebhookError::MerchantNotFound(_) => {
+                AdminApiError::NotFound
+            }
+            e => AdminApiError::Webhook(e),
+        })?;
+
+    Ok(Json(test_result_to_response(result)))
+}
+
+fn test_result_to_response(r: TestWebhookResult) -> TestWebhookResponse {
+    TestWebhookResponse {
+        url: r.url,
+        event_id: r.event_id,
+        request_body: r.request_body,
+        signature: r.signature,
+        response_status: r.response_status,
+        response_body: r.response_body,
+        latency_ms: r.latency_ms,
+        error_kind: r.error_kind.map(Into::into),
+        error: r.error,
+    }
+}
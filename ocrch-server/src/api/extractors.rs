@@ -8,19 +8,143 @@
 //! - `AdminAuth` — verifies the `Ocrch-Admin-Authorization` header against the
 //!   argon2-hashed admin secret (used by the Admin API).
 //!
+//! `SignedBody` and `VerifiedUrl` verify against the merchant named by the
+//! `{merchant_id}` path segment, or the default merchant on routes without
+//! one, and report that merchant's ID to the handler.
+//!
+//! With an `Ocrch-Key-Id` header, they verify against that API key of the
+//! merchant instead of the merchant secrets, and reject the request unless
+//! the key is active and holds the scope the request needs.
+//!
 //! All cryptographic operations are delegated to [`ocrch_sdk::signature`].
 
 use axum::{
-    extract::{FromRequest, FromRequestParts, Request},
-    http::{StatusCode, request::Parts},
+    extract::{FromRequest, FromRequestParts, RawPathParams, Request},
+    http::{HeaderMap, StatusCode, request::Parts},
     response::{IntoResponse, Response},
 };
+use kanau::processor::Processor;
+use ocrch_core::config::{MerchantConfig, default_merchant, find_merchant};
+use ocrch_core::entities::api_key::{ApiKey, ApiKeyScope, GetApiKey, TouchApiKey};
+use ocrch_core::framework::DatabaseProcessor;
 use ocrch_sdk::signature::{
-    self, SIGNATURE_HEADER, SIGNED_URL_HEADER, Signature, SignatureError, SignedObject,
+    self, KEY_ID_HEADER, SIGNATURE_HEADER, SIGNED_URL_HEADER, Signature, SignatureError,
+    SignedObject, SigningKey,
 };
 
 use crate::state::AppState;
 
+// ---------------------------------------------------------------------------
+// Merchant resolution
+// ---------------------------------------------------------------------------
+
+/// The `{merchant_id}` path segment, if the route has one.
+async fn path_merchant_id(parts: &mut Parts, state: &AppState) -> Option<String> {
+    let params = RawPathParams::from_request_parts(parts, state).await.ok()?;
+    params
+        .iter()
+        .find(|(key, _)| *key == "merchant_id")
+        .map(|(_, value)| value.to_string())
+}
+
+/// The merchant with the given ID, or the default merchant.
+fn resolve_merchant<'a>(
+    merchants: &'a [MerchantConfig],
+    merchant_id: Option<&str>,
+) -> Option<&'a MerchantConfig> {
+    match merchant_id {
+        Some(id) => find_merchant(merchants, id),
+        None => default_merchant(merchants),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// API keys
+// ---------------------------------------------------------------------------
+
+/// Errors from looking up the API key named by `Ocrch-Key-Id`.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiKeyError {
+    #[error("invalid Ocrch-Key-Id header")]
+    InvalidHeader,
+    /// The key does not exist, belongs to another merchant, or is revoked
+    /// or expired.
+    #[error("unknown or inactive API key")]
+    Inactive,
+    #[error("API key lacks the {} scope", .0.as_str())]
+    MissingScope(ApiKeyScope),
+    #[error("database error: {0}")]
+    Database(sqlx::Error),
+}
+
+impl IntoResponse for ApiKeyError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            ApiKeyError::InvalidHeader => StatusCode::BAD_REQUEST,
+            ApiKeyError::Inactive => StatusCode::UNAUTHORIZED,
+            ApiKeyError::MissingScope(_) => StatusCode::FORBIDDEN,
+            ApiKeyError::Database(e) => {
+                tracing::error!(error = %e, "API key lookup failed");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+                    .into_response();
+            }
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+/// The `Ocrch-Key-Id` header, if present.
+fn key_id_header(headers: &HeaderMap) -> Result<Option<String>, ApiKeyError> {
+    headers
+        .get(KEY_ID_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::to_owned)
+                .map_err(|_| ApiKeyError::InvalidHeader)
+        })
+        .transpose()
+}
+
+/// Look up an active API key of `merchant_id` that holds `scope`.
+async fn authorize_api_key(
+    state: &AppState,
+    key_id: String,
+    merchant_id: &str,
+    scope: ApiKeyScope,
+) -> Result<ApiKey, ApiKeyError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    let now = time::OffsetDateTime::now_utc();
+    let key = processor
+        .process(GetApiKey { id: key_id })
+        .await
+        .map_err(ApiKeyError::Database)?
+        .filter(|key| key.merchant_id == merchant_id)
+        .filter(|key| key.is_active(time::PrimitiveDateTime::new(now.date(), now.time())))
+        .ok_or(ApiKeyError::Inactive)?;
+    if !key.scopes.contains(&scope) {
+        return Err(ApiKeyError::MissingScope(scope));
+    }
+    Ok(key)
+}
+
+/// Record that a key signed an accepted request.
+async fn touch_api_key(state: &AppState, key_id: String) {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    if let Err(e) = processor.process(TouchApiKey { id: key_id }).await {
+        tracing::warn!(error = %e, "Failed to record API key use");
+    }
+}
+
+/// The [`ApiKeyScope`] an API key needs to send a Service API body.
+pub trait ScopedBody {
+    const SCOPE: ApiKeyScope;
+}
+
 // ---------------------------------------------------------------------------
 // SignedBody — Service API authentication via signed JSON body
 // ---------------------------------------------------------------------------
@@ -32,14 +156,25 @@ use crate::state::AppState;
 ///
 /// ```text
 /// Ocrch-Signature: {unix_timestamp}.{base64_signature}
+/// Ocrch-Signature: t={unix_timestamp},{key_id}={base64_signature},...
 /// ```
 ///
-/// The signature is computed as `HMAC-SHA256("{timestamp}.{json_body}", merchant_secret)`.
-pub struct SignedBody<T: Signature>(pub T);
+/// The signature is computed as `HMAC-SHA256("{timestamp}.{json_body}", merchant_secret)`
+/// and accepted if made with any active secret of the request's merchant.
+/// With an `Ocrch-Key-Id` header, it must be made with that API key's secret,
+/// and the key must hold the body's [`ScopedBody::SCOPE`].
+pub struct SignedBody<T: Signature> {
+    /// ID of the merchant whose secret signed the body.
+    pub merchant_id: String,
+    /// The verified body.
+    pub body: T,
+}
 
 /// Errors that can occur during signed-body verification.
 #[derive(Debug, thiserror::Error)]
 pub enum SignedBodyError {
+    #[error("merchant not found")]
+    UnknownMerchant,
     #[error("missing Ocrch-Signature header")]
     MissingHeader,
     #[error("invalid Ocrch-Signature header format")]
@@ -52,6 +187,8 @@ pub enum SignedBodyError {
     JsonError(serde_json::Error),
     #[error("signature verification failed")]
     VerificationFailed,
+    #[error(transparent)]
+    ApiKey(#[from] ApiKeyError),
 }
 
 impl From<SignatureError> for SignedBodyError {
@@ -68,6 +205,7 @@ impl From<SignatureError> for SignedBodyError {
 impl IntoResponse for SignedBodyError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
+            SignedBodyError::UnknownMerchant => (StatusCode::NOT_FOUND, "merchant not found"),
             SignedBodyError::MissingHeader => {
                 (StatusCode::UNAUTHORIZED, "missing Ocrch-Signature header")
             }
@@ -85,24 +223,29 @@ impl IntoResponse for SignedBodyError {
             SignedBodyError::VerificationFailed => {
                 (StatusCode::UNAUTHORIZED, "signature verification failed")
             }
+            SignedBodyError::ApiKey(e) => return e.into_response(),
         };
         (status, message).into_response()
     }
 }
 
-impl<T: Signature + Send> FromRequest<AppState> for SignedBody<T> {
+impl<T: Signature + ScopedBody + Send> FromRequest<AppState> for SignedBody<T> {
     type Rejection = SignedBodyError;
 
     async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
-        let header_value = req
-            .headers()
+        let (mut parts, body) = req.into_parts();
+        let path_merchant_id = path_merchant_id(&mut parts, state).await;
+        let key_id = key_id_header(&parts.headers)?;
+
+        let header_value = parts
+            .headers
             .get(SIGNATURE_HEADER)
             .ok_or(SignedBodyError::MissingHeader)?
             .to_str()
             .map_err(|_| SignedBodyError::InvalidHeader)?
             .to_owned();
 
-        let body_bytes = axum::body::to_bytes(req.into_body(), 1024 * 1024)
+        let body_bytes = axum::body::to_bytes(body, 1024 * 1024)
             .await
             .map_err(|_| SignedBodyError::BodyReadError)?;
 
@@ -111,11 +254,32 @@ impl<T: Signature + Send> FromRequest<AppState> for SignedBody<T> {
 
         let signed = SignedObject::<T>::from_header_and_body(&header_value, json)?;
 
-        let merchant = state.config.merchant.read().await;
-        let verified_body = signed.verify(merchant.secret_bytes())?;
-        drop(merchant);
+        let merchants = state.config.merchants.read().await;
+        let merchant = resolve_merchant(&merchants, path_merchant_id.as_deref())
+            .ok_or(SignedBodyError::UnknownMerchant)?;
+        let merchant_id = merchant.id.clone();
+        let verified_body = match key_id {
+            None => {
+                let verified = signed.verify_any(&merchant.signing_keys())?;
+                drop(merchants);
+                verified
+            }
+            Some(id) => {
+                drop(merchants);
+                let key = authorize_api_key(state, id, &merchant_id, T::SCOPE).await?;
+                let verified = signed.verify_any(&[SigningKey {
+                    id: Some(&key.id),
+                    secret: key.secret.as_bytes(),
+                }])?;
+                touch_api_key(state, key.id).await;
+                verified
+            }
+        };
 
-        Ok(SignedBody(verified_body))
+        Ok(SignedBody {
+            merchant_id,
+            body: verified_body,
+        })
     }
 }
 
@@ -134,15 +298,23 @@ impl<T: Signature + Send> FromRequest<AppState> for SignedBody<T> {
 /// ```
 ///
 /// The signature is computed as
-/// `HMAC-SHA256("{full_url}.{timestamp}", merchant_secret)`.
+/// `HMAC-SHA256("{full_url}.{timestamp}", merchant_secret)` with any active
+/// secret of the request's merchant; the keyed header format is accepted
+/// too. With an `Ocrch-Key-Id` header, it must be made with the secret of
+/// that API key, which needs the `sign_urls` scope. The URL's origin must be
+/// one of the merchant's allowed origins.
 ///
 /// Implements `FromRequestParts` so it can be combined with `Json<T>`,
 /// `Path<T>`, etc.
-pub struct VerifiedUrl;
+pub struct VerifiedUrl {
+    /// ID of the merchant whose secret signed the URL.
+    pub merchant_id: String,
+}
 
 /// Errors returned by the [`VerifiedUrl`] extractor.
 #[derive(Debug)]
 pub enum VerifiedUrlError {
+    UnknownMerchant,
     MissingSignature,
     MissingUrl,
     InvalidHeader,
@@ -150,6 +322,13 @@ pub enum VerifiedUrlError {
     SignatureMismatch,
     TimestampTooOld,
     OriginNotAllowed,
+    ApiKey(ApiKeyError),
+}
+
+impl From<ApiKeyError> for VerifiedUrlError {
+    fn from(err: ApiKeyError) -> Self {
+        Self::ApiKey(err)
+    }
 }
 
 impl From<SignatureError> for VerifiedUrlError {
@@ -167,6 +346,7 @@ impl From<SignatureError> for VerifiedUrlError {
 impl IntoResponse for VerifiedUrlError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
+            VerifiedUrlError::UnknownMerchant => (StatusCode::NOT_FOUND, "merchant not found"),
             VerifiedUrlError::MissingSignature => {
                 (StatusCode::UNAUTHORIZED, "missing Ocrch-Signature header")
             }
@@ -182,6 +362,7 @@ impl IntoResponse for VerifiedUrlError {
             }
             VerifiedUrlError::TimestampTooOld => (StatusCode::UNAUTHORIZED, "signature expired"),
             VerifiedUrlError::OriginNotAllowed => (StatusCode::FORBIDDEN, "origin not allowed"),
+            VerifiedUrlError::ApiKey(e) => return e.into_response(),
         };
         (status, message).into_response()
     }
@@ -194,6 +375,9 @@ impl FromRequestParts<AppState> for VerifiedUrl {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
+        let path_merchant_id = path_merchant_id(parts, state).await;
+        let key_id = key_id_header(&parts.headers)?;
+
         let sig_value = parts
             .headers
             .get(SIGNATURE_HEADER)
@@ -201,38 +385,58 @@ impl FromRequestParts<AppState> for VerifiedUrl {
             .to_str()
             .map_err(|_| VerifiedUrlError::InvalidHeader)?;
 
-        let (timestamp, signature_bytes) = signature::parse_signature_header(sig_value)?;
+        let (timestamp, signatures) = signature::parse_signature_header(sig_value)?;
 
         let signed_url = parts
             .headers
             .get(SIGNED_URL_HEADER)
             .ok_or(VerifiedUrlError::MissingUrl)?
             .to_str()
-            .map_err(|_| VerifiedUrlError::InvalidHeader)?;
+            .map_err(|_| VerifiedUrlError::InvalidHeader)?
+            .to_owned();
 
-        let merchant = state.config.merchant.read().await;
-        signature::verify_url(
-            signed_url,
-            timestamp,
-            &signature_bytes,
-            merchant.secret_bytes(),
-        )?;
+        let merchants = state.config.merchants.read().await;
+        let merchant = resolve_merchant(&merchants, path_merchant_id.as_deref())
+            .ok_or(VerifiedUrlError::UnknownMerchant)?;
+        let merchant_id = merchant.id.clone();
+        let allowed_origins = merchant.allowed_origins.clone();
+
+        match key_id {
+            None => {
+                let verified = signature::verify_url_any(
+                    &signed_url,
+                    timestamp,
+                    &signatures,
+                    &merchant.signing_keys(),
+                );
+                drop(merchants);
+                verified?;
+            }
+            Some(id) => {
+                drop(merchants);
+                let key = authorize_api_key(state, id, &merchant_id, ApiKeyScope::SignUrls).await?;
+                signature::verify_url_any(
+                    &signed_url,
+                    timestamp,
+                    &signatures,
+                    &[SigningKey {
+                        id: Some(&key.id),
+                        secret: key.secret.as_bytes(),
+                    }],
+                )?;
+                touch_api_key(state, key.id).await;
+            }
+        }
 
         let parsed_url =
-            url::Url::parse(signed_url).map_err(|_| VerifiedUrlError::InvalidHeader)?;
+            url::Url::parse(&signed_url).map_err(|_| VerifiedUrlError::InvalidHeader)?;
         let origin = parsed_url.origin().unicode_serialization();
 
-        if !merchant
-            .allowed_origins
-            .iter()
-            .any(|allowed| allowed == &origin)
-        {
-            drop(merchant);
+        if !allowed_origins.iter().any(|allowed| allowed == &origin) {
             return Err(VerifiedUrlError::OriginNotAllowed);
         }
 
-        drop(merchant);
-        Ok(VerifiedUrl)
+        Ok(VerifiedUrl { merchant_id })
     }
 }
 
@@ -294,3 +498,242 @@ impl FromRequestParts<AppState> for AdminAuth {
         Ok(AdminAuth)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::FromRequest;
+    use ocrch_core::entities::api_key::{CreateApiKey, RevokeApiKey};
+    use ocrch_sdk::objects::GetOrderRequest;
+    use sqlx::PgPool;
+    use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::test_util::{MERCHANT_SECRET, TestApp, signed_post};
+
+    const KEY_SECRET: &str = "key-secret";
+
+    fn processor(app: &TestApp) -> DatabaseProcessor {
+        DatabaseProcessor {
+            pool: app.state.db.clone(),
+        }
+    }
+
+    async fn create_key(
+        app: &TestApp,
+        id: &str,
+        merchant_id: &str,
+        scopes: Vec<ApiKeyScope>,
+        expires_at: Option<PrimitiveDateTime>,
+    ) -> ApiKey {
+        processor(app)
+            .process(CreateApiKey {
+                id: id.to_string(),
+                merchant_id: merchant_id.to_string(),
+                name: id.to_string(),
+                secret: KEY_SECRET.to_string(),
+                scopes,
+                expires_at,
+            })
+            .await
+            .unwrap()
+    }
+
+    /// A request signed by an API key, naming it in `Ocrch-Key-Id`.
+    fn keyed_request(key_id: &str, secret: &str) -> Request {
+        let body = GetOrderRequest {
+            order_id: Uuid::new_v4(),
+        };
+        let signed = SignedObject::with_keys(
+            body,
+            &[SigningKey {
+                id: Some(key_id),
+                secret: secret.as_bytes(),
+            }],
+        )
+        .unwrap();
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/service/orders/get")
+            .header(SIGNATURE_HEADER, signed.to_header())
+            .header(KEY_ID_HEADER, key_id)
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(signed.json))
+            .unwrap()
+    }
+
+    async fn extract(
+        app: &TestApp,
+        req: Request,
+    ) -> Result<SignedBody<GetOrderRequest>, SignedBodyError> {
+        SignedBody::<GetOrderRequest>::from_request(req, &app.state).await
+    }
+
+    fn status(err: SignedBodyError) -> StatusCode {
+        err.into_response().status()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_merchant_secret_accepted(pool: PgPool) {
+        let app = TestApp::new(pool);
+        let body = GetOrderRequest {
+            order_id: Uuid::new_v4(),
+        };
+        let req = signed_post("/api/v1/service/orders/get", body.clone(), MERCHANT_SECRET);
+
+        let signed = extract(&app, req).await.unwrap();
+        assert_eq!(signed.merchant_id, "default");
+        assert_eq!(signed.body.order_id, body.order_id);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_api_key_accepted(pool: PgPool) {
+        let app = TestApp::new(pool);
+        create_key(
+            &app,
+            "key_read",
+            "default",
+            vec![ApiKeyScope::ReadOrders],
+            None,
+        )
+        .await;
+
+        let signed = extract(&app, keyed_request("key_read", KEY_SECRET))
+            .await
+            .unwrap();
+        assert_eq!(signed.merchant_id, "default");
+
+        let key = processor(&app)
+            .process(GetApiKey {
+                id: "key_read".to_string(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(key.last_used_at.is_some());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_api_key_missing_scope(pool: PgPool) {
+        let app = TestApp::new(pool);
+        create_key(
+            &app,
+            "key_create",
+            "default",
+            vec![ApiKeyScope::CreateOrders, ApiKeyScope::CancelOrders],
+            None,
+        )
+        .await;
+
+        let err = extract(&app, keyed_request("key_create", KEY_SECRET))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            SignedBodyError::ApiKey(ApiKeyError::MissingScope(ApiKeyScope::ReadOrders))
+        ));
+        assert_eq!(status(err), StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_api_key_expired(pool: PgPool) {
+        let app = TestApp::new(pool);
+        let now = OffsetDateTime::now_utc();
+        let expired = PrimitiveDateTime::new(now.date(), now.time()) - Duration::minutes(1);
+        create_key(
+            &app,
+            "key_expired",
+            "default",
+            vec![ApiKeyScope::ReadOrders],
+            Some(expired),
+        )
+        .await;
+
+        let err = extract(&app, keyed_request("key_expired", KEY_SECRET))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            SignedBodyError::ApiKey(ApiKeyError::Inactive)
+        ));
+        assert_eq!(status(err), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_api_key_revoked(pool: PgPool) {
+        let app = TestApp::new(pool);
+        create_key(
+            &app,
+            "key_revoked",
+            "default",
+            vec![ApiKeyScope::ReadOrders],
+            None,
+        )
+        .await;
+        processor(&app)
+            .process(RevokeApiKey {
+                id: "key_revoked".to_string(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        let err = extract(&app, keyed_request("key_revoked", KEY_SECRET))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            SignedBodyError::ApiKey(ApiKeyError::Inactive)
+        ));
+        assert_eq!(status(err), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_api_key_unknown_or_foreign(pool: PgPool) {
+        let app = TestApp::new(pool);
+        create_key(
+            &app,
+            "key_other",
+            "other_shop",
+            vec![ApiKeyScope::ReadOrders],
+            None,
+        )
+        .await;
+
+        for key_id in ["key_missing", "key_other"] {
+            let err = extract(&app, keyed_request(key_id, KEY_SECRET))
+                .await
+                .err()
+                .unwrap();
+            assert!(
+                matches!(err, SignedBodyError::ApiKey(ApiKeyError::Inactive)),
+                "{key_id}: {err:?}"
+            );
+            assert_eq!(status(err), StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_api_key_wrong_secret(pool: PgPool) {
+        let app = TestApp::new(pool);
+        create_key(
+            &app,
+            "key_read",
+            "default",
+            vec![ApiKeyScope::ReadOrders],
+            None,
+        )
+        .await;
+
+        // Signed with the merchant secret, but naming the key
+        let err = extract(&app, keyed_request("key_read", MERCHANT_SECRET))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, SignedBodyError::VerificationFailed));
+        assert_eq!(status(err), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -2,5 +2,6 @@
 
 pub mod admin;
 pub mod extractors;
+mod orders;
 pub mod service;
 pub mod user;
@@ -0,0 +1,49 @@
+//! Order state changes shared by the User and Service APIs.
+
+use ocrch_core::entities::erc20_pending_deposit::Erc20PendingDeposit;
+use ocrch_core::entities::order_records::{OrderRecord, OrderStatus};
+use ocrch_core::entities::trc20_pending_deposit::Trc20PendingDeposit;
+use ocrch_core::entities::webhook_outbox::WebhookOutbox;
+use ocrch_core::events::WebhookEvent;
+use uuid::Uuid;
+
+use crate::state::{AppState, OrderStatusUpdate};
+
+/// Close a pending order with the given status (`Cancelled` or `Expired`).
+///
+/// Sets the order status, deletes all pending deposits (both ERC-20 and
+/// TRC-20), and queues a webhook in the same transaction, then wakes the
+/// webhook sender and notifies WebSocket clients. The status only changes
+/// if the order is still pending when the transaction runs, so a payment
+/// matched in the meantime is never overwritten; returns `false` in that
+/// case, with nothing changed.
+pub(crate) async fn close_order(
+    state: &AppState,
+    order_id: Uuid,
+    status: OrderStatus,
+) -> Result<bool, sqlx::Error> {
+    let webhook_event = WebhookEvent::OrderStatusChanged {
+        order_id,
+        new_status: status,
+    };
+
+    let mut tx = state.db.begin().await?;
+    if !OrderRecord::update_pending_status_tx(&mut tx, order_id, status).await? {
+        return Ok(false);
+    }
+    Erc20PendingDeposit::delete_for_order_tx(&mut tx, order_id).await?;
+    Trc20PendingDeposit::delete_for_order_tx(&mut tx, order_id).await?;
+    WebhookOutbox::enqueue_tx(&mut tx, &webhook_event).await?;
+    tx.commit().await?;
+
+    if let Err(e) = state.event_senders.webhook_event.send(webhook_event).await {
+        tracing::error!(error = %e, "Failed to emit OrderStatusChanged webhook event");
+    }
+
+    let _ = state.order_status_tx.send(OrderStatusUpdate {
+        order_id,
+        payment_detected: None,
+    });
+
+    Ok(true)
+}
@@ -1,27 +1,71 @@
 //! Service API handlers.
 //!
 //! These endpoints are called by the application backend and require
-//! a signed body verified via the `Ocrch-Signature` header.
+//! a signed body verified via the `Ocrch-Signature` header, signed either
+//! with the merchant secret or with an API key holding the endpoint's
+//! scope (`Ocrch-Key-Id` header). They are served
+//! both at the API root, for the default merchant, and under
+//! `/merchants/{merchant_id}`; orders are scoped to the signing merchant.
 //!
 //! # Endpoints
 //!
-//! - `POST /orders`       – create a new pending order
+//! - `POST /orders`        – create a new pending order
 //! - `POST /orders/status` – get the status of an existing order
+//! - `POST /orders/lookup` – get an order by its merchant order ID
+//! - `POST /orders/list`   – list orders by status and creation time
+//! - `POST /orders/cancel` – cancel or expire a pending order
 
 use axum::{Json, Router, http::StatusCode, response::IntoResponse, routing::post};
 use kanau::processor::Processor;
-use ocrch_core::entities::order_records::{CreateOrderRecord, GetOrderRecordById, OrderRecord};
+use ocrch_core::config::find_merchant;
+use ocrch_core::entities::api_key::ApiKeyScope;
+use ocrch_core::entities::order_records::{
+    CreateOrderRecord, CreatedOrderRecord, GetOrderRecordById, GetOrderRecordByMerchantOrderId,
+    ListMerchantOrderRecords, OrderRecord, OrderStatus,
+};
 use ocrch_core::framework::DatabaseProcessor;
-use ocrch_sdk::objects::{GetOrderRequest, OrderResponse, PaymentCreatingEssential};
+use ocrch_core::utils::webhook_url::WebhookUrlError;
+use ocrch_sdk::objects::admin::clamp_pagination;
+use ocrch_sdk::objects::create_payment::{
+    MAX_CUSTOMER_REFERENCE_LENGTH, MAX_DESCRIPTION_LENGTH, MAX_METADATA_SIZE,
+};
+use ocrch_sdk::objects::{
+    CancelOrderRequest, GetOrderRequest, ListOrdersRequest, LookupOrderRequest, OrderListResponse,
+    OrderResponse, PaymentCreatingEssential,
+};
 
-use crate::api::extractors::SignedBody;
+use crate::api::extractors::{ScopedBody, SignedBody};
+use crate::api::orders::close_order;
 use crate::state::AppState;
 
+impl ScopedBody for PaymentCreatingEssential {
+    const SCOPE: ApiKeyScope = ApiKeyScope::CreateOrders;
+}
+
+impl ScopedBody for GetOrderRequest {
+    const SCOPE: ApiKeyScope = ApiKeyScope::ReadOrders;
+}
+
+impl ScopedBody for LookupOrderRequest {
+    const SCOPE: ApiKeyScope = ApiKeyScope::ReadOrders;
+}
+
+impl ScopedBody for ListOrdersRequest {
+    const SCOPE: ApiKeyScope = ApiKeyScope::ReadOrders;
+}
+
+impl ScopedBody for CancelOrderRequest {
+    const SCOPE: ApiKeyScope = ApiKeyScope::CancelOrders;
+}
+
 /// Build the Service API router.
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/orders", post(create_order))
         .route("/orders/status", post(get_order_status))
+        .route("/orders/lookup", post(lookup_order))
+        .route("/orders/list", post(list_orders))
+        .route("/orders/cancel", post(cancel_order))
 }
 
 /// Convert an `OrderRecord` (DB model) into an `OrderResponse` (API model).
@@ -32,39 +76,164 @@ fn to_response(record: &OrderRecord) -> OrderResponse {
         amount: record.amount,
         status: record.status.into(),
         created_at: record.created_at.assume_utc().unix_timestamp(),
+        metadata: record.metadata.clone(),
+        description: record.description.clone(),
+        customer_reference: record.customer_reference.clone(),
+        success_url: record.success_url.clone(),
+        cancel_url: record.cancel_url.clone(),
     }
 }
 
 /// `POST /orders` — create a new pending order.
 ///
 /// Accepts a signed `PaymentCreatingEssential` body and inserts a new
-/// order record into the database with status `pending`.
+/// order record for the signing merchant into the database with status
+/// `pending`. The webhook URL, if any, must satisfy that merchant's webhook
+/// URL policy, the redirect URLs must be on one of its allowed origins, and
+/// the metadata, description and customer reference must be within their
+/// size limits.
+///
+/// Creation is idempotent per merchant order ID: a retry with the same
+/// parameters returns the existing order with `200 OK`, and a request that
+/// differs from it is rejected with `409 Conflict`.
 async fn create_order(
     state: axum::extract::State<AppState>,
-    SignedBody(payload): SignedBody<PaymentCreatingEssential>,
+    SignedBody {
+        merchant_id,
+        body: payload,
+    }: SignedBody<PaymentCreatingEssential>,
 ) -> Result<impl IntoResponse, ServiceApiError> {
     let processor = DatabaseProcessor {
         pool: state.db.clone(),
     };
 
-    let record = processor
+    check_order_details(&payload)?;
+    let (policy, allowed_origins) =
+        find_merchant(&state.config.merchants.read().await, &merchant_id)
+            .map(|merchant| {
+                (
+                    merchant.webhook_url_policy.clone(),
+                    merchant.allowed_origins.clone(),
+                )
+            })
+            .ok_or(ServiceApiError::NotFound)?;
+    for (field, url) in [
+        ("success_url", &payload.success_url),
+        ("cancel_url", &payload.cancel_url),
+    ] {
+        if let Some(url) = url {
+            check_redirect_url(field, url, &allowed_origins)?;
+        }
+    }
+    if let Some(url) = &payload.webhook_url {
+        policy
+            .check_resolved(url)
+            .await
+            .map_err(ServiceApiError::InvalidWebhookUrl)?;
+    }
+
+    let created = processor
         .process(CreateOrderRecord {
-            merchant_order_id: payload.order_id,
+            merchant_id,
+            merchant_order_id: payload.order_id.clone(),
             amount: payload.amount,
-            webhook_url: payload.webhook_url,
+            webhook_url: payload.webhook_url.clone(),
+            metadata: payload.metadata.clone(),
+            description: payload.description.clone(),
+            customer_reference: payload.customer_reference.clone(),
+            success_url: payload.success_url.clone(),
+            cancel_url: payload.cancel_url.clone(),
         })
         .await
         .map_err(ServiceApiError::Database)?;
 
-    Ok((StatusCode::CREATED, Json(to_response(&record))))
+    match created {
+        CreatedOrderRecord::Created(record) => {
+            Ok((StatusCode::CREATED, Json(to_response(&record))))
+        }
+        CreatedOrderRecord::Existing(record) if matches_request(&record, &payload) => {
+            Ok((StatusCode::OK, Json(to_response(&record))))
+        }
+        CreatedOrderRecord::Existing(record) => {
+            Err(ServiceApiError::Conflict(record.merchant_order_id))
+        }
+    }
+}
+
+/// Check the size limits of the merchant-supplied order details.
+fn check_order_details(payload: &PaymentCreatingEssential) -> Result<(), ServiceApiError> {
+    if let Some(metadata) = &payload.metadata {
+        if !metadata.is_object() {
+            return Err(ServiceApiError::InvalidRequest(
+                "metadata must be a JSON object".to_string(),
+            ));
+        }
+        if metadata.to_string().len() > MAX_METADATA_SIZE {
+            return Err(ServiceApiError::InvalidRequest(format!(
+                "metadata exceeds {MAX_METADATA_SIZE} bytes"
+            )));
+        }
+    }
+    if let Some(description) = &payload.description
+        && description.chars().count() > MAX_DESCRIPTION_LENGTH
+    {
+        return Err(ServiceApiError::InvalidRequest(format!(
+            "description exceeds {MAX_DESCRIPTION_LENGTH} characters"
+        )));
+    }
+    if let Some(reference) = &payload.customer_reference
+        && reference.chars().count() > MAX_CUSTOMER_REFERENCE_LENGTH
+    {
+        return Err(ServiceApiError::InvalidRequest(format!(
+            "customer_reference exceeds {MAX_CUSTOMER_REFERENCE_LENGTH} characters"
+        )));
+    }
+    Ok(())
+}
+
+/// Check that a redirect URL is an absolute `http(s)` URL on one of the
+/// merchant's allowed origins, so that it cannot be used as an open redirect.
+fn check_redirect_url(
+    field: &str,
+    url: &str,
+    allowed_origins: &[String],
+) -> Result<(), ServiceApiError> {
+    let parsed = url::Url::parse(url)
+        .ok()
+        .filter(|parsed| matches!(parsed.scheme(), "http" | "https"))
+        .ok_or_else(|| {
+            ServiceApiError::InvalidRequest(format!("{field} must be an absolute http(s) URL"))
+        })?;
+    let origin = parsed.origin().unicode_serialization();
+    if !allowed_origins.iter().any(|allowed| allowed == &origin) {
+        return Err(ServiceApiError::InvalidRequest(format!(
+            "{field} origin {origin} is not allowed"
+        )));
+    }
+    Ok(())
+}
+
+/// Whether an existing order was created with the same parameters.
+fn matches_request(record: &OrderRecord, payload: &PaymentCreatingEssential) -> bool {
+    record.amount == payload.amount
+        && record.webhook_url == payload.webhook_url
+        && record.metadata == payload.metadata
+        && record.description == payload.description
+        && record.customer_reference == payload.customer_reference
+        && record.success_url == payload.success_url
+        && record.cancel_url == payload.cancel_url
 }
 
 /// `POST /orders/status` — get the status of an existing order.
 ///
 /// Accepts a signed `GetOrderRequest` body containing the order UUID.
+/// Orders of other merchants are reported as not found.
 async fn get_order_status(
     state: axum::extract::State<AppState>,
-    SignedBody(payload): SignedBody<GetOrderRequest>,
+    SignedBody {
+        merchant_id,
+        body: payload,
+    }: SignedBody<GetOrderRequest>,
 ) -> Result<impl IntoResponse, ServiceApiError> {
     let processor = DatabaseProcessor {
         pool: state.db.clone(),
@@ -76,11 +245,143 @@ async fn get_order_status(
         })
         .await
         .map_err(ServiceApiError::Database)?
+        .filter(|record| record.merchant_id == merchant_id)
         .ok_or(ServiceApiError::NotFound)?;
 
     Ok(Json(to_response(&record)))
 }
 
+/// `POST /orders/lookup` — get an order by its merchant order ID.
+///
+/// Accepts a signed `LookupOrderRequest` body and looks the ID up among
+/// the signing merchant's orders.
+async fn lookup_order(
+    state: axum::extract::State<AppState>,
+    SignedBody {
+        merchant_id,
+        body: payload,
+    }: SignedBody<LookupOrderRequest>,
+) -> Result<impl IntoResponse, ServiceApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+
+    let record = processor
+        .process(GetOrderRecordByMerchantOrderId {
+            merchant_id,
+            merchant_order_id: payload.merchant_order_id,
+        })
+        .await
+        .map_err(ServiceApiError::Database)?
+        .ok_or(ServiceApiError::NotFound)?;
+
+    Ok(Json(to_response(&record)))
+}
+
+/// `POST /orders/list` — list the merchant's orders, newest first.
+///
+/// Accepts a signed `ListOrdersRequest` body with optional status and
+/// creation time filters. Pages are chained with `next_cursor`, the ID of
+/// the last order returned, which stays stable while new orders arrive. A
+/// cursor that is not one of the merchant's orders is rejected.
+async fn list_orders(
+    state: axum::extract::State<AppState>,
+    SignedBody {
+        merchant_id,
+        body: payload,
+    }: SignedBody<ListOrdersRequest>,
+) -> Result<impl IntoResponse, ServiceApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+
+    let to_datetime = |ts: i64| {
+        time::OffsetDateTime::from_unix_timestamp(ts)
+            .map(|t| time::PrimitiveDateTime::new(t.date(), t.time()))
+            .map_err(|_| ServiceApiError::InvalidRequest(format!("invalid timestamp {ts}")))
+    };
+    let (limit, _) = clamp_pagination(payload.limit, 0);
+
+    if let Some(cursor) = payload.cursor {
+        processor
+            .process(GetOrderRecordById { order_id: cursor })
+            .await
+            .map_err(ServiceApiError::Database)?
+            .filter(|record| record.merchant_id == merchant_id)
+            .ok_or_else(|| ServiceApiError::InvalidRequest(format!("unknown cursor {cursor}")))?;
+    }
+
+    // Fetch one extra order to learn whether there is a next page
+    let mut records = processor
+        .process(ListMerchantOrderRecords {
+            merchant_id,
+            status: payload.status.map(Into::into),
+            since: payload.since.map(to_datetime).transpose()?,
+            until: payload.until.map(to_datetime).transpose()?,
+            after: payload.cursor,
+            limit: limit + 1,
+        })
+        .await
+        .map_err(ServiceApiError::Database)?;
+
+    let next_cursor = if records.len() as i64 > limit {
+        records.truncate(limit as usize);
+        records.last().map(|record| record.order_id)
+    } else {
+        None
+    };
+
+    Ok(Json(OrderListResponse {
+        orders: records.iter().map(to_response).collect(),
+        next_cursor,
+    }))
+}
+
+/// `POST /orders/cancel` — cancel or expire a pending order.
+///
+/// Accepts a signed `CancelOrderRequest` body. The order is closed the same
+/// way as through the User API: its pending deposits are deleted and the
+/// webhook is queued in one transaction.
+async fn cancel_order(
+    state: axum::extract::State<AppState>,
+    SignedBody {
+        merchant_id,
+        body: payload,
+    }: SignedBody<CancelOrderRequest>,
+) -> Result<impl IntoResponse, ServiceApiError> {
+    let processor = DatabaseProcessor {
+        pool: state.db.clone(),
+    };
+    let order_id = payload.order_id;
+
+    processor
+        .process(GetOrderRecordById { order_id })
+        .await
+        .map_err(ServiceApiError::Database)?
+        .filter(|record| record.merchant_id == merchant_id)
+        .ok_or(ServiceApiError::NotFound)?;
+
+    let status = if payload.expire {
+        OrderStatus::Expired
+    } else {
+        OrderStatus::Cancelled
+    };
+    let closed = close_order(&state, order_id, status)
+        .await
+        .map_err(ServiceApiError::Database)?;
+    if !closed {
+        return Err(ServiceApiError::OrderNotPending);
+    }
+
+    let updated = processor
+        .process(GetOrderRecordById { order_id })
+        .await
+        .map_err(ServiceApiError::Database)?
+        .ok_or(ServiceApiError::NotFound)?;
+
+    Ok(Json(to_response(&updated)))
+}
+
 /// Errors that can occur in Service API handlers.
 #[derive(Debug)]
 enum ServiceApiError {
@@ -88,6 +389,14 @@ enum ServiceApiError {
     Database(sqlx::Error),
     /// The requested order was not found.
     NotFound,
+    /// The order's webhook URL was refused.
+    InvalidWebhookUrl(WebhookUrlError),
+    /// An order with this merchant order ID exists with other parameters.
+    Conflict(String),
+    /// The request body is invalid.
+    InvalidRequest(String),
+    /// The order is not in a pending state.
+    OrderNotPending,
 }
 
 impl IntoResponse for ServiceApiError {
@@ -98,6 +407,183 @@ impl IntoResponse for ServiceApiError {
                 (StatusCode::INTERNAL_SERVER_ERROR, "internal server error").into_response()
             }
             ServiceApiError::NotFound => (StatusCode::NOT_FOUND, "order not found").into_response(),
+            ServiceApiError::InvalidWebhookUrl(e) => {
+                (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+            }
+            ServiceApiError::Conflict(merchant_order_id) => (
+                StatusCode::CONFLICT,
+                format!("order {merchant_order_id:?} already exists with different parameters"),
+            )
+                .into_response(),
+            ServiceApiError::InvalidRequest(message) => {
+                (StatusCode::BAD_REQUEST, message).into_response()
+            }
+            ServiceApiError::OrderNotPending => {
+                (StatusCode::CONFLICT, "order is not pending").into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::test_util::{MERCHANT_SECRET, TestApp, signed_post};
+
+    fn create_request(order_id: &str, amount: &str) -> PaymentCreatingEssential {
+        PaymentCreatingEssential {
+            amount: amount.parse().unwrap(),
+            expecting_wallet_address: None,
+            order_id: order_id.to_string(),
+            blockchain: None,
+            stablecoin: None,
+            webhook_url: None,
+            metadata: Some(serde_json::json!({ "cart": 42 })),
+            description: Some("Test order".to_string()),
+            customer_reference: None,
+            success_url: Some("https://shop.example.com/done".to_string()),
+            cancel_url: None,
+        }
+    }
+
+    async fn create(app: &TestApp, payload: PaymentCreatingEssential) -> (StatusCode, Vec<u8>) {
+        let (status, body) = app
+            .send(signed_post(
+                "/api/v1/service/orders",
+                payload,
+                MERCHANT_SECRET,
+            ))
+            .await;
+        (status, body.to_vec())
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_create_order_replay(pool: PgPool) {
+        let app = TestApp::new(pool.clone());
+
+        let (status, body) = create(&app, create_request("order-1", "10.50")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let created: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        let (status, body) = create(&app, create_request("order-1", "10.50")).await;
+        assert_eq!(status, StatusCode::OK);
+        let replayed: OrderResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(replayed.order_id, created.order_id);
+        assert_eq!(replayed.amount, created.amount);
+        assert_eq!(replayed.created_at, created.created_at);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM order_records")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_create_order_conflict(pool: PgPool) {
+        let app = TestApp::new(pool);
+
+        let (status, body) = create(&app, create_request("order-1", "10.50")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let created: OrderResponse = serde_json::from_slice(&body).unwrap();
+
+        let (status, _) = create(&app, create_request("order-1", "20.00")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let mut other_description = create_request("order-1", "10.50");
+        other_description.description = None;
+        let (status, _) = create(&app, other_description).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        // The original order is left as it was
+        let (status, body) = create(&app, create_request("order-1", "10.50")).await;
+        assert_eq!(status, StatusCode::OK);
+        let replayed: OrderResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(replayed.order_id, created.order_id);
+    }
+
+    async fn list(app: &TestApp, cursor: Option<uuid::Uuid>) -> (StatusCode, Vec<u8>) {
+        let request = ListOrdersRequest {
+            limit: 2,
+            cursor,
+            ..Default::default()
+        };
+        let (status, body) = app
+            .send(signed_post(
+                "/api/v1/service/orders/list",
+                request,
+                MERCHANT_SECRET,
+            ))
+            .await;
+        (status, body.to_vec())
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_list_orders_pages(pool: PgPool) {
+        let app = TestApp::new(pool);
+        for i in 0..5 {
+            let (status, _) = create(&app, create_request(&format!("order-{i}"), "10.50")).await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+
+        let mut orders = Vec::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let (status, body) = list(&app, cursor).await;
+            assert_eq!(status, StatusCode::OK);
+            let page: OrderListResponse = serde_json::from_slice(&body).unwrap();
+            assert!(page.orders.len() <= 2);
+            orders.extend(page.orders);
+            pages += 1;
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(pages, 3);
+        let mut merchant_order_ids: Vec<_> = orders
+            .iter()
+            .map(|order| order.merchant_order_id.clone())
+            .collect();
+        merchant_order_ids.sort();
+        merchant_order_ids.dedup();
+        assert_eq!(merchant_order_ids.len(), 5);
+        assert!(
+            orders
+                .windows(2)
+                .all(|pair| pair[0].created_at >= pair[1].created_at)
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_list_orders_unknown_cursor(pool: PgPool) {
+        let app = TestApp::new(pool.clone());
+        let other = DatabaseProcessor { pool }
+            .process(CreateOrderRecord {
+                merchant_id: "other_shop".to_string(),
+                merchant_order_id: "order-1".to_string(),
+                amount: "10.50".parse().unwrap(),
+                webhook_url: None,
+                metadata: None,
+                description: None,
+                customer_reference: None,
+                success_url: None,
+                cancel_url: None,
+            })
+            .await
+            .unwrap();
+        let CreatedOrderRecord::Created(other) = other else {
+            panic!("order already exists");
+        };
+
+        // A random ID, and an order of another merchant
+        for cursor in [uuid::Uuid::new_v4(), other.order_id] {
+            let (status, _) = list(&app, Some(cursor)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{cursor}");
         }
     }
 }
@@ -1,4 +1,5 @@
 use axum::{Json, extract::State, response::IntoResponse};
+use ocrch_core::processors::PoolingKey;
 use ocrch_sdk::objects::ChainCoinPair;
 
 use super::UserApiError;
@@ -8,14 +9,20 @@ use crate::state::AppState;
 /// `GET /chains` — list available blockchain + stablecoin payment options.
 ///
 /// Returns every (blockchain, stablecoin, wallet_address) triple derived
-/// from the configured wallets.
+/// from the wallets available to the merchant, except for pairs whose sync
+/// is currently unhealthy (customers could pay but we would not see the
+/// transfer).
 pub(super) async fn get_chains(
     state: State<AppState>,
-    _verified: VerifiedUrl,
+    verified: VerifiedUrl,
 ) -> Result<impl IntoResponse, UserApiError> {
+    let health_config = state.config.sync_health.read().await.clone();
+    let sync_health = state.sync_health.snapshot().await;
+
     let wallets = state.config.wallets.read().await;
     let pairs: Vec<ChainCoinPair> = wallets
         .iter()
+        .filter(|w| w.is_available_to(&verified.merchant_id))
         .flat_map(|w| {
             w.enabled_coins.iter().map(move |coin| ChainCoinPair {
                 blockchain: w.blockchain,
@@ -23,6 +30,13 @@ pub(super) async fn get_chains(
                 wallet_address: w.address.clone(),
             })
         })
+        .filter(|pair| {
+            let key = PoolingKey::new(pair.blockchain.into(), pair.stablecoin.into());
+            sync_health
+                .iter()
+                .find(|(k, _)| *k == key)
+                .is_none_or(|(_, health)| health.is_healthy(&health_config))
+        })
         .collect();
     drop(wallets);
     Ok(Json(pairs))
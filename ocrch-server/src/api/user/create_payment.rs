@@ -7,15 +7,14 @@ use axum::{
 use kanau::processor::Processor;
 use ocrch_core::entities::StablecoinName;
 use ocrch_core::entities::erc20_pending_deposit::Erc20PendingDepositInsert;
-use ocrch_core::entities::order_records::{GetOrderRecordById, OrderStatus};
+use ocrch_core::entities::order_records::OrderStatus;
 use ocrch_core::entities::trc20_pending_deposit::Trc20PendingDepositInsert;
 use ocrch_core::events::PendingDepositChanged;
 use ocrch_core::framework::DatabaseProcessor;
 use ocrch_sdk::objects::blockchains::Blockchain;
 use ocrch_sdk::objects::{PaymentDetail, SelectPaymentMethod};
-use uuid::Uuid;
 
-use super::{UserApiError, blockchain_to_etherscan_chain};
+use super::{OrderPath, UserApiError, blockchain_to_etherscan_chain, merchant_order};
 use crate::api::extractors::VerifiedUrl;
 use crate::state::AppState;
 
@@ -23,22 +22,19 @@ use crate::state::AppState;
 ///
 /// Creates a new pending deposit for the given order on the selected
 /// blockchain and stablecoin, then emits a `PendingDepositChanged` event
-/// so the pooling pipeline begins watching for the payment.
+/// so the pooling pipeline begins watching for the payment. The merchant's
+/// own wallets are preferred over shared ones.
 pub(super) async fn create_payment(
     state: State<AppState>,
-    _verified: VerifiedUrl,
-    Path(order_id): Path<Uuid>,
+    verified: VerifiedUrl,
+    Path(OrderPath { order_id }): Path<OrderPath>,
     Json(body): Json<SelectPaymentMethod>,
 ) -> Result<impl IntoResponse, UserApiError> {
     let processor = DatabaseProcessor {
         pool: state.db.clone(),
     };
 
-    let record = processor
-        .process(GetOrderRecordById { order_id })
-        .await
-        .map_err(UserApiError::Database)?
-        .ok_or(UserApiError::NotFound)?;
+    let record = merchant_order(&processor, order_id, &verified.merchant_id).await?;
 
     if record.status != OrderStatus::Pending {
         return Err(UserApiError::OrderNotPending);
@@ -47,7 +43,12 @@ pub(super) async fn create_payment(
     let wallets = state.config.wallets.read().await;
     let wallet = wallets
         .iter()
-        .find(|w| w.blockchain == body.blockchain && w.enabled_coins.contains(&body.stablecoin))
+        .filter(|w| {
+            w.is_available_to(&verified.merchant_id)
+                && w.blockchain == body.blockchain
+                && w.enabled_coins.contains(&body.stablecoin)
+        })
+        .min_by_key(|w| w.merchant.is_none())
         .ok_or(UserApiError::WalletNotFound)?;
 
     let wallet_address = wallet.address.clone();
@@ -3,12 +3,9 @@ use axum::{
     extract::{Path, State},
     response::IntoResponse,
 };
-use kanau::processor::Processor;
-use ocrch_core::entities::order_records::GetOrderRecordById;
 use ocrch_core::framework::DatabaseProcessor;
-use uuid::Uuid;
 
-use super::{UserApiError, to_response};
+use super::{OrderPath, UserApiError, merchant_order, to_response};
 use crate::api::extractors::VerifiedUrl;
 use crate::state::AppState;
 
@@ -17,18 +14,14 @@ use crate::state::AppState;
 /// Returns the current status of the order.
 pub(super) async fn get_order_status(
     state: State<AppState>,
-    _verified: VerifiedUrl,
-    Path(order_id): Path<Uuid>,
+    verified: VerifiedUrl,
+    Path(OrderPath { order_id }): Path<OrderPath>,
 ) -> Result<impl IntoResponse, UserApiError> {
     let processor = DatabaseProcessor {
         pool: state.db.clone(),
     };
 
-    let record = processor
-        .process(GetOrderRecordById { order_id })
-        .await
-        .map_err(UserApiError::Database)?
-        .ok_or(UserApiError::NotFound)?;
+    let record = merchant_order(&processor, order_id, &verified.merchant_id).await?;
 
     Ok(Json(to_response(&record)))
 }
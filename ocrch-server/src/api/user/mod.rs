@@ -2,7 +2,9 @@
 //!
 //! These endpoints are called by the checkout frontend (user's browser)
 //! and require a verified signed frontend URL via the `Ocrch-Signature`
-//! and `Ocrch-Signed-Url` headers.
+//! and `Ocrch-Signed-Url` headers. They are served both at the API root,
+//! for the default merchant, and under `/merchants/{merchant_id}`; orders
+//! of other merchants than the signing one are reported as not found.
 //!
 //! # Endpoints
 //!
@@ -18,10 +20,14 @@ use axum::{
     response::IntoResponse,
     routing::{get, post},
 };
+use kanau::processor::Processor;
 use ocrch_core::entities::erc20_pending_deposit::EtherScanChain;
-use ocrch_core::entities::order_records::OrderRecord;
+use ocrch_core::entities::order_records::{GetOrderRecordById, OrderRecord};
+use ocrch_core::framework::DatabaseProcessor;
 use ocrch_sdk::objects::OrderResponse;
 use ocrch_sdk::objects::blockchains::Blockchain;
+use serde::Deserialize;
+use uuid::Uuid;
 
 use crate::state::AppState;
 
@@ -50,6 +56,13 @@ pub fn router() -> Router<AppState> {
         .route("/orders/{order_id}/ws", get(ws::order_status_ws))
 }
 
+/// Path parameters of the per-order routes. Also matches the nested
+/// `/merchants/{merchant_id}` routes, whose merchant comes from `VerifiedUrl`.
+#[derive(Debug, Deserialize)]
+struct OrderPath {
+    order_id: Uuid,
+}
+
 /// Convert an `OrderRecord` (DB model) into an `OrderResponse` (API model).
 fn to_response(record: &OrderRecord) -> OrderResponse {
     OrderResponse {
@@ -58,6 +71,11 @@ fn to_response(record: &OrderRecord) -> OrderResponse {
         amount: record.amount,
         status: record.status.into(),
         created_at: record.created_at.assume_utc().unix_timestamp(),
+        metadata: record.metadata.clone(),
+        description: record.description.clone(),
+        customer_reference: record.customer_reference.clone(),
+        success_url: record.success_url.clone(),
+        cancel_url: record.cancel_url.clone(),
     }
 }
 
@@ -107,6 +125,23 @@ impl IntoResponse for UserApiError {
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Load an order of the given merchant.
+///
+/// Returns `Err(UserApiError::NotFound)` if the order does not exist or
+/// belongs to another merchant.
+async fn merchant_order(
+    processor: &DatabaseProcessor,
+    order_id: Uuid,
+    merchant_id: &str,
+) -> Result<OrderRecord, UserApiError> {
+    processor
+        .process(GetOrderRecordById { order_id })
+        .await
+        .map_err(UserApiError::Database)?
+        .filter(|record| record.merchant_id == merchant_id)
+        .ok_or(UserApiError::NotFound)
+}
+
 /// Map an SDK `Blockchain` variant to an `EtherScanChain`.
 ///
 /// Returns `Err(UserApiError::InvalidChain)` if called with `Blockchain::Tron`.
@@ -6,29 +6,35 @@ use axum::{
     response::IntoResponse,
 };
 use kanau::processor::Processor;
-use ocrch_core::entities::order_records::{GetOrderRecordById, OrderStatus};
+use ocrch_core::config::required_confirmations_for;
+use ocrch_core::entities::order_records::{
+    GetOrderPaymentDetection, GetOrderRecordById, OrderStatus,
+};
 use ocrch_core::framework::DatabaseProcessor;
+use ocrch_sdk::objects::blockchains::Blockchain;
 use ocrch_sdk::objects::ws::{WsCloseCode, WsServerMessage};
 use uuid::Uuid;
 
-use super::to_response;
+use super::{OrderPath, to_response};
 use crate::api::extractors::VerifiedUrl;
 use crate::state::AppState;
 
 /// `GET /orders/{order_id}/ws` — WebSocket order status stream.
 ///
 /// Upgrades the HTTP connection to a WebSocket and pushes
-/// [`OrderResponse`] JSON frames whenever the order status changes.
+/// [`OrderResponse`] JSON frames whenever the order status changes,
+/// plus `payment_detected` frames while a transfer awaits confirmations.
 /// The first frame is always the current status; the connection is
 /// closed after a terminal status (`Paid`, `Expired`, `Cancelled`).
 pub(super) async fn order_status_ws(
     state: State<AppState>,
-    _url_signature_verified_before_upgrade: VerifiedUrl,
-    Path(order_id): Path<Uuid>,
+    url_signature_verified_before_upgrade: VerifiedUrl,
+    Path(OrderPath { order_id }): Path<OrderPath>,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
     let app_state = state.0.clone();
-    ws.on_upgrade(move |socket| handle_order_ws(socket, app_state, order_id))
+    let merchant_id = url_signature_verified_before_upgrade.merchant_id;
+    ws.on_upgrade(move |socket| handle_order_ws(socket, app_state, order_id, merchant_id))
 }
 
 /// Returns `true` if the given status is a terminal (final) state.
@@ -41,12 +47,18 @@ fn is_terminal(status: OrderStatus) -> bool {
 
 /// Background task that drives a single WebSocket connection.
 ///
-/// 1. Sends the current order status as the first message.
+/// 1. Sends the current order status as the first message, or closes with
+///    `ORDER_NOT_FOUND` if the order does not belong to `merchant_id`.
 /// 2. If already terminal, closes immediately.
 /// 3. Otherwise subscribes to the broadcast channel and forwards
 ///    status updates for this `order_id` until a terminal state is
 ///    reached or the client disconnects.
-async fn handle_order_ws(mut socket: WebSocket, state: AppState, order_id: Uuid) {
+async fn handle_order_ws(
+    mut socket: WebSocket,
+    state: AppState,
+    order_id: Uuid,
+    merchant_id: String,
+) {
     let processor = DatabaseProcessor {
         pool: state.db.clone(),
     };
@@ -58,8 +70,8 @@ async fn handle_order_ws(mut socket: WebSocket, state: AppState, order_id: Uuid)
 
     // --- Send current status as the first message --------------------------
     let record = match processor.process(GetOrderRecordById { order_id }).await {
-        Ok(Some(r)) => r,
-        Ok(None) => {
+        Ok(Some(r)) if r.merchant_id == merchant_id => r,
+        Ok(_) => {
             let _ = send_json(
                 &mut socket,
                 &WsServerMessage::Error {
@@ -109,6 +121,32 @@ async fn handle_order_ws(mut socket: WebSocket, state: AppState, order_id: Uuid)
         return;
     }
 
+    // Replay an already-detected payment so a reconnecting page does not
+    // fall back to the spinner
+    match processor
+        .process(GetOrderPaymentDetection { order_id })
+        .await
+    {
+        Ok(Some(detection)) => {
+            let blockchain = detection.chain.map_or(Blockchain::Tron, Blockchain::from);
+            let required_confirmations =
+                required_confirmations_for(&state.config.chains.read().await, blockchain);
+            let msg = WsServerMessage::PaymentDetected {
+                blockchain,
+                txn_hash: detection.txn_hash,
+                confirmations: detection.confirmations.max(0) as u32,
+                required_confirmations,
+            };
+            if send_json(&mut socket, &msg).await.is_err() {
+                return;
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, %order_id, "WS: failed to query payment detection");
+        }
+    }
+
     // --- Relay updates until terminal or disconnect ------------------------
 
     loop {
@@ -116,6 +154,13 @@ async fn handle_order_ws(mut socket: WebSocket, state: AppState, order_id: Uuid)
             result = broadcast_rx.recv() => {
                 match result {
                     Ok(update) if update.order_id == order_id => {
+                        if let Some(msg) = update.payment_detected {
+                            if send_json(&mut socket, &msg).await.is_err() {
+                                return;
+                            }
+                            continue;
+                        }
+
                         let record = match processor
                             .process(GetOrderRecordById { order_id })
                             .await
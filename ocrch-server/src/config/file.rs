@@ -2,6 +2,7 @@
 //!
 //! These structs directly map to the `ocrch-config.toml` file format.
 
+use ocrch_sdk::objects::WebhookEventType;
 use ocrch_sdk::objects::blockchains::{Blockchain, Stablecoin};
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
@@ -11,10 +12,22 @@ use std::net::SocketAddr;
 pub struct FileConfig {
     pub server: ServerConfig,
     pub admin: AdminConfig,
-    pub merchant: MerchantConfig,
+    /// A single merchant with ID `default`. Either this or `merchants`
+    /// must be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merchant: Option<MerchantConfig>,
+    /// Several merchants (`[[merchants]]`); the first is the default merchant.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merchants: Vec<MerchantConfig>,
     pub api_keys: ApiKeysFileConfig,
     #[serde(default)]
     pub wallets: Vec<WalletConfig>,
+    #[serde(default)]
+    pub chains: Vec<ChainConfig>,
+    #[serde(default)]
+    pub sync_health: SyncHealthFileConfig,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookFileConfig>,
 }
 
 /// API keys configuration section.
@@ -49,16 +62,75 @@ pub struct AdminConfig {
 /// Merchant configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MerchantConfig {
+    /// Unique merchant ID used in API paths. Required in `[[merchants]]`;
+    /// the `[merchant]` section is always `default`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Human-readable merchant name.
     pub name: String,
-    /// Secret key for signing API requests.
-    pub secret: String,
+    /// Secret key for signing API requests. Either this or `secrets` must
+    /// be set.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Active secrets with key IDs (`[[merchant.secrets]]`), used instead of
+    /// `secret` to rotate keys without downtime.
+    #[serde(default)]
+    pub secrets: Vec<MerchantSecretFileConfig>,
     /// List of allowed origins for CORS (frontend URLs).
     #[serde(default)]
     pub allowed_origins: Vec<String>,
     /// Optional webhook URL for unknown transfer notifications.
     #[serde(default)]
     pub unknown_transfer_webhook_url: Option<String>,
+    /// Webhook URL for order events of orders created without one.
+    #[serde(default)]
+    pub default_webhook_url: Option<String>,
+    /// Payload schema version (1 or 2) sent to the order and
+    /// unknown-transfer webhook URLs.
+    #[serde(default = "default_payload_version")]
+    pub webhook_payload_version: u8,
+    /// Hosts that order webhook URLs may point to; `*.example.com` matches
+    /// subdomains. Empty allows every public host.
+    #[serde(default)]
+    pub webhook_allowed_hosts: Vec<String>,
+    /// Allow order webhook URLs that point to private, loopback or
+    /// link-local addresses.
+    #[serde(default)]
+    pub webhook_allow_private_networks: bool,
+}
+
+/// A merchant secret with its key ID (`[[merchant.secrets]]` or
+/// `[[merchants.secrets]]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MerchantSecretFileConfig {
+    /// Key ID sent in signature headers.
+    pub id: String,
+    /// Secret key.
+    pub secret: String,
+}
+
+/// Additional webhook endpoint (`[[webhooks]]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookFileConfig {
+    /// Unique name of the subscription.
+    pub name: String,
+    /// URL the events are POSTed to.
+    pub url: String,
+    /// Event types sent to this endpoint.
+    pub events: Vec<WebhookEventType>,
+    /// Merchant whose events are sent. Defaults to every merchant.
+    #[serde(default)]
+    pub merchant: Option<String>,
+    /// Signing secret. Defaults to the merchant secrets.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Payload schema version (1 or 2).
+    #[serde(default = "default_payload_version")]
+    pub payload_version: u8,
+}
+
+fn default_payload_version() -> u8 {
+    1
 }
 
 /// Wallet configuration for receiving payments.
@@ -75,6 +147,113 @@ pub struct WalletConfig {
     /// transaction's block (ERC-20) or timestamp (TRC-20) instead of from the beginning.
     #[serde(default)]
     pub starting_tx: Option<String>,
+    /// ID of the merchant this wallet belongs to. Wallets without one are
+    /// shared by all merchants.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merchant: Option<String>,
+}
+
+/// Per-chain configuration section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainConfig {
+    /// The blockchain these settings apply to.
+    pub blockchain: Blockchain,
+    /// Optional WebSocket JSON-RPC endpoint used for real-time
+    /// `eth_subscribe("logs")` subscriptions (EVM chains only).
+    #[serde(default)]
+    pub ws_rpc_url: Option<String>,
+    /// Adaptive pooling curve for this chain. Omitted fields fall back to
+    /// the built-in defaults.
+    #[serde(default)]
+    pub pooling: Option<PoolingFileConfig>,
+    /// Confirmations a transfer needs before it settles an order.
+    /// Defaults to a per-chain value when omitted.
+    #[serde(default)]
+    pub required_confirmations: Option<u32>,
+}
+
+/// Pooling curve for a chain, in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolingFileConfig {
+    /// Interval used right after pending deposit activity.
+    #[serde(default = "default_active_interval_secs")]
+    pub active_interval_secs: u64,
+    /// Back-off steps between the active and idle intervals.
+    #[serde(default = "default_decay_steps")]
+    pub decay: Vec<PoolingDecayStepFileConfig>,
+    /// Seconds without activity after which the chain is considered idle.
+    #[serde(default = "default_idle_after_secs")]
+    pub idle_after_secs: u64,
+    /// Interval used once the chain is idle.
+    #[serde(default = "default_idle_interval_secs")]
+    pub idle_interval_secs: u64,
+    /// Interval used while the chain has no live pending deposits.
+    #[serde(default = "default_heartbeat_interval_secs")]
+    pub heartbeat_interval_secs: u64,
+}
+
+/// A single back-off step of a pooling curve.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolingDecayStepFileConfig {
+    /// Seconds without activity after which this step applies.
+    pub after_secs: u64,
+    /// Interval used while this step applies.
+    pub interval_secs: u64,
+}
+
+fn default_active_interval_secs() -> u64 {
+    2
+}
+
+fn default_decay_steps() -> Vec<PoolingDecayStepFileConfig> {
+    vec![
+        PoolingDecayStepFileConfig {
+            after_secs: 5,
+            interval_secs: 10,
+        },
+        PoolingDecayStepFileConfig {
+            after_secs: 10,
+            interval_secs: 30,
+        },
+    ]
+}
+
+fn default_idle_after_secs() -> u64 {
+    30
+}
+
+fn default_idle_interval_secs() -> u64 {
+    60
+}
+
+fn default_heartbeat_interval_secs() -> u64 {
+    900
+}
+
+/// Sync health monitoring section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncHealthFileConfig {
+    /// Consecutive sync failures after which a chain-coin pair is hidden
+    /// from the User API.
+    #[serde(default = "default_unhealthy_after_failures")]
+    pub unhealthy_after_failures: u32,
+    /// Blocks a sync may fall behind the chain head before its pair is
+    /// hidden from the User API. Unset disables the check.
+    #[serde(default)]
+    pub max_lag_blocks: Option<u32>,
+}
+
+impl Default for SyncHealthFileConfig {
+    fn default() -> Self {
+        Self {
+            unhealthy_after_failures: default_unhealthy_after_failures(),
+            max_lag_blocks: None,
+        }
+    }
+}
+
+fn default_unhealthy_after_failures() -> u32 {
+    5
 }
 
 impl FileConfig {
@@ -106,18 +285,109 @@ allowed_origins = ["https://checkout.example.com"]
 etherscan_api_key = "test-etherscan-key"
 tronscan_api_key = "test-tronscan-key"
 
+[[merchant.secrets]]
+id = "2026-10"
+secret = "rotated-secret"
+
 [[wallets]]
 blockchain = "eth"
 address = "0x1234567890abcdef"
 enabled_coins = ["USDT", "USDC"]
+
+[[chains]]
+blockchain = "eth"
+ws_rpc_url = "wss://eth.example.com"
+
+[[webhooks]]
+name = "audit"
+url = "https://audit.example.com/ocrch"
+events = ["order_status_changed", "unknown_transfer_received"]
+merchant = "default"
+secret = "audit-secret"
+payload_version = 2
+
+[[chains]]
+blockchain = "tron"
+
+[chains.pooling]
+active_interval_secs = 3
+decay = [{ after_secs = 60, interval_secs = 15 }]
+idle_after_secs = 120
 "#;
         let config: FileConfig = toml::from_str(toml_str).unwrap();
         assert_eq!(config.server.listen.port(), 3000);
-        assert_eq!(config.merchant.name, "Test Store");
+        let merchant = config.merchant.as_ref().unwrap();
+        assert_eq!(merchant.name, "Test Store");
+        assert!(merchant.id.is_none());
+        assert_eq!(merchant.secret.as_deref(), Some("secret123"));
+        assert_eq!(merchant.secrets.len(), 1);
+        assert_eq!(merchant.secrets[0].id, "2026-10");
+        assert!(config.merchants.is_empty());
         assert_eq!(config.wallets.len(), 1);
+        assert_eq!(config.chains.len(), 2);
+        assert!(config.chains[0].pooling.is_none());
+        let pooling = config.chains[1].pooling.as_ref().unwrap();
+        assert_eq!(pooling.active_interval_secs, 3);
+        assert_eq!(pooling.decay.len(), 1);
+        assert_eq!(pooling.idle_after_secs, 120);
+        assert_eq!(pooling.idle_interval_secs, 60);
+        crate::config::validate_pooling(Blockchain::Tron, pooling).unwrap();
+        assert_eq!(config.webhooks.len(), 1);
+        assert_eq!(
+            config.webhooks[0].events,
+            [
+                WebhookEventType::OrderStatusChanged,
+                WebhookEventType::UnknownTransferReceived
+            ]
+        );
+        assert_eq!(config.webhooks[0].payload_version, 2);
+        assert_eq!(config.webhooks[0].merchant.as_deref(), Some("default"));
+        assert_eq!(merchant.webhook_payload_version, 1);
+        assert!(merchant.default_webhook_url.is_none());
+        assert!(config.wallets[0].merchant.is_none());
         assert!(!config.is_admin_secret_hashed());
     }
 
+    #[test]
+    fn test_multiple_merchants_parsing() {
+        let toml_str = r#"
+[server]
+
+[admin]
+secret = "test-secret"
+
+[api_keys]
+etherscan_api_key = "test-etherscan-key"
+tronscan_api_key = "test-tronscan-key"
+
+[[merchants]]
+id = "shop-a"
+name = "Shop A"
+secret = "secret-a"
+
+[[merchants]]
+id = "shop-b"
+name = "Shop B"
+allowed_origins = ["https://b.example.com"]
+
+[[merchants.secrets]]
+id = "2026-10"
+secret = "secret-b"
+
+[[wallets]]
+blockchain = "eth"
+address = "0x1234567890abcdef"
+enabled_coins = ["USDT"]
+merchant = "shop-b"
+"#;
+        let config: FileConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.merchant.is_none());
+        assert_eq!(config.merchants.len(), 2);
+        assert_eq!(config.merchants[0].id.as_deref(), Some("shop-a"));
+        assert_eq!(config.merchants[1].secrets[0].id, "2026-10");
+        assert_eq!(config.wallets[0].merchant.as_deref(), Some("shop-b"));
+    }
+
     #[test]
     fn test_hashed_secret_detection() {
         let config = FileConfig {
@@ -127,17 +397,27 @@ enabled_coins = ["USDT", "USDC"]
             admin: AdminConfig {
                 secret: "$argon2id$v=19$m=19456,t=2,p=1$abc123".to_string(),
             },
-            merchant: MerchantConfig {
+            merchant: Some(MerchantConfig {
+                id: None,
                 name: "Test Store".to_string(),
-                secret: "secret123".to_string(),
+                secret: Some("secret123".to_string()),
+                secrets: vec![],
                 allowed_origins: vec![],
                 unknown_transfer_webhook_url: None,
-            },
+                default_webhook_url: None,
+                webhook_payload_version: 1,
+                webhook_allowed_hosts: vec![],
+                webhook_allow_private_networks: false,
+            }),
+            merchants: vec![],
             api_keys: ApiKeysFileConfig {
                 etherscan_api_key: "test-key".to_string(),
                 tronscan_api_key: "test-key".to_string(),
             },
             wallets: vec![],
+            chains: vec![],
+            sync_health: SyncHealthFileConfig::default(),
+            webhooks: vec![],
         };
         assert!(config.is_admin_secret_hashed());
     }
@@ -0,0 +1,251 @@
+//! Merchants and wallets managed through the Admin API.
+//!
+//! They are stored in the database and layered over the config file on
+//! every load, so the file stays the bootstrap source: a stored merchant or
+//! wallet replaces the file entry with the same key, a disabled wallet hides
+//! it, and everything else is added. The merged configuration is validated
+//! like the file itself.
+
+use crate::config::file::{
+    FileConfig, MerchantConfig as FileMerchantConfig, MerchantSecretFileConfig,
+    WalletConfig as FileWalletConfig,
+};
+use kanau::processor::Processor;
+use ocrch_core::config::DEFAULT_MERCHANT_ID;
+use ocrch_core::entities::managed_config::{
+    ListManagedMerchants, ListManagedWallets, ManagedMerchant, ManagedWallet,
+};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_sdk::objects::blockchains::Blockchain;
+
+/// The merchants and wallets stored in the database.
+#[derive(Debug, Clone, Default)]
+pub struct ManagedConfig {
+    pub merchants: Vec<ManagedMerchant>,
+    pub wallets: Vec<ManagedWallet>,
+}
+
+impl ManagedConfig {
+    /// Read the managed merchants and wallets.
+    pub async fn load(processor: &DatabaseProcessor) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            merchants: processor.process(ListManagedMerchants).await?,
+            wallets: processor.process(ListManagedWallets).await?,
+        })
+    }
+
+    /// Layer the managed entries over a parsed config file.
+    pub(super) fn apply(&self, config: &mut FileConfig) {
+        if !self.merchants.is_empty() && config.merchants.is_empty() {
+            // Managed merchants join the single [merchant], which keeps its
+            // place as the default merchant
+            config.merchants.extend(config.merchant.take());
+        }
+        for managed in &self.merchants {
+            let merchant = to_file_merchant(managed);
+            match config
+                .merchants
+                .iter_mut()
+                .find(|m| m.id.as_deref().unwrap_or(DEFAULT_MERCHANT_ID) == managed.id)
+            {
+                Some(existing) => *existing = merchant,
+                None => config.merchants.push(merchant),
+            }
+        }
+
+        for managed in &self.wallets {
+            let wallet = to_file_wallet(managed);
+            let existing = config.wallets.iter().position(|w| {
+                w.blockchain == wallet.blockchain
+                    && same_wallet(w.blockchain, &w.address, &wallet.address)
+            });
+            match (existing, managed.enabled) {
+                (Some(i), true) => config.wallets[i] = wallet,
+                (Some(i), false) => {
+                    config.wallets.remove(i);
+                }
+                (None, true) => config.wallets.push(wallet),
+                (None, false) => {}
+            }
+        }
+    }
+}
+
+/// Whether two addresses on `blockchain` are the same wallet. EVM addresses
+/// are compared case-insensitively.
+pub fn same_wallet(blockchain: Blockchain, a: &str, b: &str) -> bool {
+    match blockchain {
+        Blockchain::Tron => a == b,
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+fn to_file_merchant(m: &ManagedMerchant) -> FileMerchantConfig {
+    FileMerchantConfig {
+        id: Some(m.id.clone()),
+        name: m.name.clone(),
+        secret: m
+            .secrets
+            .iter()
+            .find(|s| s.key_id.is_none())
+            .map(|s| s.secret.clone()),
+        secrets: m
+            .secrets
+            .iter()
+            .filter_map(|s| {
+                Some(MerchantSecretFileConfig {
+                    id: s.key_id.clone()?,
+                    secret: s.secret.clone(),
+                })
+            })
+            .collect(),
+        allowed_origins: m.allowed_origins.clone(),
+        unknown_transfer_webhook_url: m.unknown_transfer_webhook_url.clone(),
+        default_webhook_url: m.default_webhook_url.clone(),
+        // Out-of-range versions are rejected by validation as version 0
+        webhook_payload_version: u8::try_from(m.webhook_payload_version).unwrap_or(0),
+        webhook_allowed_hosts: m.webhook_allowed_hosts.clone(),
+        webhook_allow_private_networks: m.webhook_allow_private_networks,
+    }
+}
+
+fn to_file_wallet(w: &ManagedWallet) -> FileWalletConfig {
+    FileWalletConfig {
+        blockchain: w.blockchain.into(),
+        address: w.address.clone(),
+        enabled_coins: w.enabled_coins.iter().map(|&c| c.into()).collect(),
+        starting_tx: w.starting_tx.clone(),
+        merchant: w.merchant_id.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocrch_core::entities::managed_config::ManagedMerchantSecret;
+    use ocrch_core::entities::{BlockchainName, StablecoinName};
+
+    #[test]
+    fn test_apply_layers_over_file() {
+        let mut config: FileConfig = toml::from_str(
+            r#"
+[server]
+listen = "127.0.0.1:3000"
+
+[admin]
+secret = "test-secret"
+
+[merchant]
+name = "Test Store"
+secret = "secret123"
+
+[api_keys]
+etherscan_api_key = "test-etherscan-key"
+tronscan_api_key = "test-tronscan-key"
+
+[[wallets]]
+blockchain = "eth"
+address = "0xAbCd"
+enabled_coins = ["USDT"]
+
+[[wallets]]
+blockchain = "polygon"
+address = "0x1234"
+enabled_coins = ["USDT"]
+"#,
+        )
+        .unwrap();
+        let managed = ManagedConfig {
+            merchants: vec![ManagedMerchant {
+                id: "books".to_string(),
+                name: "Books".to_string(),
+                secrets: vec![ManagedMerchantSecret {
+                    key_id: Some("2026-10".to_string()),
+                    secret: "books-secret".to_string(),
+                }],
+                allowed_origins: vec![],
+                unknown_transfer_webhook_url: None,
+                default_webhook_url: None,
+                webhook_payload_version: 2,
+                webhook_allowed_hosts: vec![],
+                webhook_allow_private_networks: false,
+            }],
+            wallets: vec![
+                ManagedWallet {
+                    blockchain: BlockchainName::Ethereum,
+                    address: "0xabcd".to_string(),
+                    enabled_coins: vec![StablecoinName::Usdc],
+                    starting_tx: None,
+                    merchant_id: Some("books".to_string()),
+                    enabled: true,
+                },
+                ManagedWallet {
+                    blockchain: BlockchainName::Polygon,
+                    address: "0x1234".to_string(),
+                    enabled_coins: vec![],
+                    starting_tx: None,
+                    merchant_id: None,
+                    enabled: false,
+                },
+            ],
+        };
+
+        managed.apply(&mut config);
+
+        assert!(config.merchant.is_none());
+        assert_eq!(config.merchants.len(), 2);
+        assert_eq!(config.merchants[0].name, "Test Store");
+        assert_eq!(config.merchants[1].id.as_deref(), Some("books"));
+        assert_eq!(config.merchants[1].secrets[0].id, "2026-10");
+        assert_eq!(config.merchants[1].webhook_payload_version, 2);
+        assert_eq!(config.wallets.len(), 1);
+        assert_eq!(config.wallets[0].address, "0xabcd");
+        assert_eq!(config.wallets[0].merchant.as_deref(), Some("books"));
+    }
+
+    #[test]
+    fn test_apply_keeps_same_address_on_other_chain() {
+        let mut config: FileConfig = toml::from_str(
+            r#"
+[server]
+listen = "127.0.0.1:3000"
+
+[admin]
+secret = "test-secret"
+
+[merchant]
+name = "Test Store"
+secret = "secret123"
+
+[api_keys]
+etherscan_api_key = "test-etherscan-key"
+tronscan_api_key = "test-tronscan-key"
+
+[[wallets]]
+blockchain = "eth"
+address = "0xAbCd"
+enabled_coins = ["USDT"]
+"#,
+        )
+        .unwrap();
+        let managed = ManagedConfig {
+            merchants: vec![],
+            wallets: vec![ManagedWallet {
+                blockchain: BlockchainName::Polygon,
+                address: "0xabcd".to_string(),
+                enabled_coins: vec![StablecoinName::Usdc],
+                starting_tx: None,
+                merchant_id: None,
+                enabled: true,
+            }],
+        };
+
+        managed.apply(&mut config);
+
+        assert_eq!(config.wallets.len(), 2);
+        assert_eq!(config.wallets[0].blockchain, Blockchain::Ethereum);
+        assert_eq!(config.wallets[0].address, "0xAbCd");
+        assert_eq!(config.wallets[1].blockchain, Blockchain::Polygon);
+        assert_eq!(config.wallets[1].address, "0xabcd");
+    }
+}
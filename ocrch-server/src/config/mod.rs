@@ -1,18 +1,25 @@
 //! Configuration module for ocrch-server.
 //!
 //! Handles loading configuration from TOML files, CLI arguments,
-//! and environment variables. Also handles admin secret hashing.
+//! environment variables and the merchants and wallets managed in the
+//! database. Also handles admin secret hashing.
 
 pub mod file;
+pub mod managed;
 pub mod runtime;
 
 use crate::config::file::{
-    FileConfig, MerchantConfig as FileMerchantConfig, WalletConfig as FileWalletConfig,
+    ChainConfig as FileChainConfig, FileConfig, MerchantConfig as FileMerchantConfig,
+    PoolingFileConfig as FilePoolingConfig, WalletConfig as FileWalletConfig, WebhookFileConfig,
 };
+use crate::config::managed::ManagedConfig;
 use crate::config::runtime::{
-    AdminConfig, ApiKeysConfig, MerchantConfig, ServerConfig, SharedConfig, WalletConfig,
+    AdminConfig, ApiKeysConfig, ChainConfig, MerchantConfig, MerchantSecret, PoolingDecayStep,
+    PoolingPolicy, ServerConfig, SharedConfig, SyncHealthConfig, WalletConfig,
+    WebhookPayloadVersion, WebhookSubscriptionConfig, WebhookUrlPolicy,
 };
-use ocrch_core::config::ConfigStore;
+use ocrch_core::config::{ConfigStore, DEFAULT_MERCHANT_ID, default_required_confirmations};
+use ocrch_sdk::objects::blockchains::Blockchain;
 use std::net::SocketAddr;
 use std::path::Path;
 use thiserror::Error;
@@ -32,6 +39,9 @@ pub enum ConfigError {
     #[error("validation error: {0}")]
     ValidationError(String),
 
+    #[error("failed to load managed merchants and wallets: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
     #[error("password hashing error: {0}")]
     HashError(String),
 
@@ -43,9 +53,12 @@ pub enum ConfigError {
 pub struct LoadedConfig {
     pub server: ServerConfig,
     pub admin: AdminConfig,
-    pub merchant: MerchantConfig,
+    pub merchants: Vec<MerchantConfig>,
     pub wallets: Vec<WalletConfig>,
     pub api_keys: ApiKeysConfig,
+    pub chains: Vec<ChainConfig>,
+    pub sync_health: SyncHealthConfig,
+    pub webhooks: Vec<WebhookSubscriptionConfig>,
 }
 
 impl LoadedConfig {
@@ -54,9 +67,12 @@ impl LoadedConfig {
         SharedConfig {
             server: ConfigStore::new(self.server),
             admin: ConfigStore::new(self.admin),
-            merchant: ConfigStore::new(self.merchant),
+            merchants: ConfigStore::new(self.merchants),
             wallets: ConfigStore::new(self.wallets),
             api_keys: ConfigStore::new(self.api_keys),
+            chains: ConfigStore::new(self.chains),
+            sync_health: ConfigStore::new(self.sync_health),
+            webhooks: ConfigStore::new(self.webhooks),
         }
     }
 }
@@ -81,10 +97,11 @@ impl ConfigLoader {
     /// This will:
     /// 1. Read the TOML file
     /// 2. Apply CLI overrides
-    /// 3. Validate the configuration
-    /// 4. Hash the admin secret if it's plaintext (and rewrite the file)
-    /// 5. Build the loaded configuration
-    pub fn load(&self) -> Result<LoadedConfig, ConfigError> {
+    /// 3. Layer the managed merchants and wallets over it
+    /// 4. Validate the configuration
+    /// 5. Hash the admin secret if it's plaintext (and rewrite the file)
+    /// 6. Build the loaded configuration
+    pub fn load(&self, managed: &ManagedConfig) -> Result<LoadedConfig, ConfigError> {
         // Read the config file
         let config_content = std::fs::read_to_string(&self.config_path)?;
         let mut file_config: FileConfig = toml::from_str(&config_content)?;
@@ -94,8 +111,13 @@ impl ConfigLoader {
             file_config.server.listen = listen;
         }
 
+        // Layer the managed entries over a copy, so that only the file's
+        // own contents are written back below
+        let mut merged_config = file_config.clone();
+        managed.apply(&mut merged_config);
+
         // Validate the configuration
-        self.validate(&file_config)?;
+        self.validate(&merged_config)?;
 
         // Hash admin secret if needed and rewrite config
         let secret_hash = if file_config.is_admin_secret_hashed() {
@@ -109,19 +131,14 @@ impl ConfigLoader {
         };
 
         // Build the config parts
-        Ok(self.build_loaded_config(file_config, secret_hash))
-    }
-
-    /// Reload the configuration (used during SIGHUP).
-    ///
-    /// Returns a LoadedConfig that can be used to update individual parts
-    /// of a SharedConfig.
-    pub fn reload(&self) -> Result<LoadedConfig, ConfigError> {
-        self.load()
+        Ok(self.build_loaded_config(merged_config, secret_hash))
     }
 
     fn validate(&self, config: &FileConfig) -> Result<(), ConfigError> {
-        // Check that wallets have at least one enabled coin
+        let merchant_ids = validate_merchants(config)?;
+
+        // Check that wallets have at least one enabled coin and belong to a
+        // configured merchant, if any
         for wallet in &config.wallets {
             if wallet.enabled_coins.is_empty() {
                 return Err(ConfigError::ValidationError(format!(
@@ -129,6 +146,66 @@ impl ConfigLoader {
                     wallet.address
                 )));
             }
+            if let Some(merchant) = &wallet.merchant
+                && !merchant_ids.contains(&merchant.as_str())
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "wallet {} belongs to unknown merchant {merchant:?}",
+                    wallet.address
+                )));
+            }
+        }
+
+        // Check that each chain is configured at most once, and that
+        // WebSocket RPC endpoints are only set for EVM chains
+        for (i, chain) in config.chains.iter().enumerate() {
+            if config.chains[..i]
+                .iter()
+                .any(|c| c.blockchain == chain.blockchain)
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "chain {:?} is configured more than once",
+                    chain.blockchain
+                )));
+            }
+            if chain.blockchain == Blockchain::Tron && chain.ws_rpc_url.is_some() {
+                return Err(ConfigError::ValidationError(
+                    "ws_rpc_url is only supported for EVM chains".to_string(),
+                ));
+            }
+            if chain.required_confirmations == Some(0) {
+                return Err(ConfigError::ValidationError(format!(
+                    "chain {:?} required_confirmations must be at least 1",
+                    chain.blockchain
+                )));
+            }
+            if let Some(pooling) = &chain.pooling {
+                validate_pooling(chain.blockchain, pooling)?;
+            }
+        }
+
+        for (i, webhook) in config.webhooks.iter().enumerate() {
+            validate_webhook(webhook)?;
+            if let Some(merchant) = &webhook.merchant
+                && !merchant_ids.contains(&merchant.as_str())
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "webhook {:?} belongs to unknown merchant {merchant:?}",
+                    webhook.name
+                )));
+            }
+            if config.webhooks[..i].iter().any(|w| w.name == webhook.name) {
+                return Err(ConfigError::ValidationError(format!(
+                    "webhook {:?} is configured more than once",
+                    webhook.name
+                )));
+            }
+        }
+
+        if config.sync_health.unhealthy_after_failures == 0 {
+            return Err(ConfigError::ValidationError(
+                "sync_health.unhealthy_after_failures must be at least 1".to_string(),
+            ));
         }
         Ok(())
     }
@@ -171,31 +248,302 @@ impl ConfigLoader {
                 listen: file_config.server.listen,
             },
             admin: AdminConfig::new(secret_hash),
-            merchant: convert_merchant(file_config.merchant),
+            merchants: file_config
+                .merchant
+                .into_iter()
+                .chain(file_config.merchants)
+                .map(convert_merchant)
+                .collect(),
             wallets,
             api_keys: ApiKeysConfig {
                 etherscan_api_key: file_config.api_keys.etherscan_api_key,
                 tronscan_api_key: file_config.api_keys.tronscan_api_key,
             },
+            chains: file_config.chains.into_iter().map(convert_chain).collect(),
+            sync_health: SyncHealthConfig {
+                unhealthy_after_failures: file_config.sync_health.unhealthy_after_failures,
+                max_lag_blocks: file_config.sync_health.max_lag_blocks,
+            },
+            webhooks: file_config
+                .webhooks
+                .into_iter()
+                .map(convert_webhook)
+                .collect(),
         }
     }
 }
 
 fn convert_merchant(m: FileMerchantConfig) -> MerchantConfig {
+    let secrets = m
+        .secret
+        .map(|secret| MerchantSecret {
+            id: None,
+            secret: secret.into_bytes().into_boxed_slice(),
+        })
+        .into_iter()
+        .chain(m.secrets.into_iter().map(|s| MerchantSecret {
+            id: Some(s.id),
+            secret: s.secret.into_bytes().into_boxed_slice(),
+        }))
+        .collect();
     MerchantConfig::new(
+        m.id.unwrap_or_else(|| DEFAULT_MERCHANT_ID.to_string()),
         m.name,
-        m.secret.into_bytes().into_boxed_slice(),
+        secrets,
         m.allowed_origins,
         m.unknown_transfer_webhook_url,
+        m.default_webhook_url,
+        convert_payload_version(m.webhook_payload_version).unwrap_or_default(),
+        WebhookUrlPolicy {
+            allowed_hosts: m
+                .webhook_allowed_hosts
+                .iter()
+                .map(|h| h.to_ascii_lowercase())
+                .collect(),
+            allow_private_networks: m.webhook_allow_private_networks,
+        },
     )
 }
 
+fn convert_webhook(w: WebhookFileConfig) -> WebhookSubscriptionConfig {
+    WebhookSubscriptionConfig {
+        name: w.name,
+        url: w.url,
+        events: w.events,
+        merchant: w.merchant,
+        secret: w.secret.map(|s| s.into_bytes().into_boxed_slice()),
+        payload_version: convert_payload_version(w.payload_version).unwrap_or_default(),
+    }
+}
+
+/// Map a configured payload version number to a [`WebhookPayloadVersion`].
+fn convert_payload_version(version: u8) -> Result<WebhookPayloadVersion, ConfigError> {
+    match version {
+        1 => Ok(WebhookPayloadVersion::V1),
+        2 => Ok(WebhookPayloadVersion::V2),
+        v => Err(ConfigError::ValidationError(format!(
+            "unsupported webhook payload version {v}"
+        ))),
+    }
+}
+
 fn convert_wallet(w: FileWalletConfig) -> WalletConfig {
     WalletConfig {
         blockchain: w.blockchain,
         address: w.address,
         enabled_coins: w.enabled_coins,
         starting_tx: w.starting_tx,
+        merchant: w.merchant,
+    }
+}
+
+fn convert_chain(c: FileChainConfig) -> ChainConfig {
+    ChainConfig {
+        blockchain: c.blockchain,
+        ws_rpc_url: c.ws_rpc_url,
+        pooling: c.pooling.map(convert_pooling).unwrap_or_default(),
+        required_confirmations: c
+            .required_confirmations
+            .unwrap_or_else(|| default_required_confirmations(c.blockchain)),
+    }
+}
+
+fn convert_pooling(p: FilePoolingConfig) -> PoolingPolicy {
+    let secs = |s: u64| time::Duration::seconds(s as i64);
+    PoolingPolicy {
+        active_interval: secs(p.active_interval_secs),
+        decay: p
+            .decay
+            .into_iter()
+            .map(|step| PoolingDecayStep {
+                after: secs(step.after_secs),
+                interval: secs(step.interval_secs),
+            })
+            .collect(),
+        idle_after: secs(p.idle_after_secs),
+        idle_interval: secs(p.idle_interval_secs),
+        heartbeat_interval: secs(p.heartbeat_interval_secs),
+    }
+}
+
+/// Check that a pooling curve has non-zero intervals and that its decay
+/// steps are strictly increasing and end before the idle threshold.
+fn validate_pooling(blockchain: Blockchain, p: &FilePoolingConfig) -> Result<(), ConfigError> {
+    let intervals = std::iter::once(p.active_interval_secs)
+        .chain(p.decay.iter().map(|step| step.interval_secs))
+        .chain([p.idle_interval_secs, p.heartbeat_interval_secs]);
+    for interval in intervals {
+        if interval == 0 || interval > i64::MAX as u64 {
+            return Err(ConfigError::ValidationError(format!(
+                "chain {blockchain:?} has an invalid pooling interval of {interval}s"
+            )));
+        }
+    }
+
+    let thresholds: Vec<u64> = p
+        .decay
+        .iter()
+        .map(|step| step.after_secs)
+        .chain(std::iter::once(p.idle_after_secs))
+        .collect();
+    if thresholds.windows(2).any(|w| w[0] >= w[1]) || p.idle_after_secs > i64::MAX as u64 {
+        return Err(ConfigError::ValidationError(format!(
+            "chain {blockchain:?} pooling decay steps must be strictly increasing and end before idle_after_secs"
+        )));
+    }
+    Ok(())
+}
+
+/// Check that exactly one of `[merchant]` and `[[merchants]]` is set, and
+/// every merchant has a unique, path-safe ID and a valid configuration.
+///
+/// Returns the merchant IDs.
+fn validate_merchants(config: &FileConfig) -> Result<Vec<&str>, ConfigError> {
+    match (&config.merchant, config.merchants.is_empty()) {
+        (Some(_), false) => {
+            return Err(ConfigError::ValidationError(
+                "set either [merchant] or [[merchants]], not both".to_string(),
+            ));
+        }
+        (None, true) => {
+            return Err(ConfigError::ValidationError(
+                "[merchant] or [[merchants]] must be set".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
+    let mut ids: Vec<&str> = Vec::new();
+    for m in config.merchant.iter().chain(&config.merchants) {
+        let id = m.id.as_deref().unwrap_or(DEFAULT_MERCHANT_ID);
+        let valid_id = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+        if !valid_id {
+            return Err(ConfigError::ValidationError(format!(
+                "invalid merchant id {id:?}: use letters, digits, '-' and '_'"
+            )));
+        }
+        if ids.contains(&id) {
+            return Err(ConfigError::ValidationError(format!(
+                "merchant {id:?} is configured more than once"
+            )));
+        }
+        ids.push(id);
+
+        validate_merchant_secrets(id, m)?;
+        let webhook_urls = m
+            .unknown_transfer_webhook_url
+            .iter()
+            .chain(&m.default_webhook_url);
+        for url in webhook_urls {
+            validate_webhook_url(url)?;
+        }
+        for host in &m.webhook_allowed_hosts {
+            validate_allowed_host(host)?;
+        }
+        convert_payload_version(m.webhook_payload_version)?;
+    }
+    Ok(ids)
+}
+
+/// Check that a merchant has either a single `secret` or a list of named
+/// `secrets`, with unique key IDs that fit in a signature header.
+fn validate_merchant_secrets(id: &str, m: &FileMerchantConfig) -> Result<(), ConfigError> {
+    match (&m.secret, m.secrets.is_empty()) {
+        (Some(_), false) => {
+            return Err(ConfigError::ValidationError(format!(
+                "merchant {id:?}: set either secret or [[secrets]], not both"
+            )));
+        }
+        (None, true) => {
+            return Err(ConfigError::ValidationError(format!(
+                "merchant {id:?}: secret or [[secrets]] must be set"
+            )));
+        }
+        (Some(secret), true) if secret.is_empty() => {
+            return Err(ConfigError::ValidationError(format!(
+                "merchant {id:?}: secret must not be empty"
+            )));
+        }
+        _ => {}
+    }
+    for (i, s) in m.secrets.iter().enumerate() {
+        let valid_id = !s.id.is_empty()
+            && s.id != "t"
+            && s.id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+        if !valid_id {
+            return Err(ConfigError::ValidationError(format!(
+                "merchant {id:?}: invalid secret id {:?}: use letters, digits, '-' and '_' (\"t\" is reserved)",
+                s.id
+            )));
+        }
+        if s.secret.is_empty() {
+            return Err(ConfigError::ValidationError(format!(
+                "merchant {id:?}: secret {:?} is empty",
+                s.id
+            )));
+        }
+        if m.secrets[..i].iter().any(|other| other.id == s.id) {
+            return Err(ConfigError::ValidationError(format!(
+                "merchant {id:?}: secret {:?} is configured more than once",
+                s.id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a `[[webhooks]]` entry has a name, a valid URL and at least
+/// one event type.
+fn validate_webhook(w: &WebhookFileConfig) -> Result<(), ConfigError> {
+    if w.name.trim().is_empty() {
+        return Err(ConfigError::ValidationError(
+            "webhook name must not be empty".to_string(),
+        ));
+    }
+    if w.events.is_empty() {
+        return Err(ConfigError::ValidationError(format!(
+            "webhook {:?} has no events",
+            w.name
+        )));
+    }
+    if w.secret.as_deref() == Some("") {
+        return Err(ConfigError::ValidationError(format!(
+            "webhook {:?} has an empty secret",
+            w.name
+        )));
+    }
+    convert_payload_version(w.payload_version)?;
+    validate_webhook_url(&w.url)
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), ConfigError> {
+    match url::Url::parse(url) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => Ok(()),
+        _ => Err(ConfigError::ValidationError(format!(
+            "invalid webhook URL {url:?}"
+        ))),
+    }
+}
+
+/// Check a `merchant.webhook_allowed_hosts` entry: a host name, an IP
+/// address, or `*.` followed by a domain.
+fn validate_allowed_host(host: &str) -> Result<(), ConfigError> {
+    let name = host.strip_prefix("*.").unwrap_or(host);
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::ValidationError(format!(
+            "invalid webhook allowed host {host:?}"
+        )))
     }
 }
 
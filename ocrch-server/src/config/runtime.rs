@@ -4,5 +4,8 @@
 //! This module re-exports them for convenience.
 
 pub use ocrch_core::config::{
-    AdminConfig, ApiKeysConfig, MerchantConfig, ServerConfig, SharedConfig, WalletConfig,
+    AdminConfig, ApiKeysConfig, ChainConfig, MerchantConfig, MerchantSecret, ServerConfig,
+    SharedConfig, SyncHealthConfig, WalletConfig, WebhookPayloadVersion, WebhookSubscriptionConfig,
 };
+pub use ocrch_core::utils::pooling_interval::{PoolingDecayStep, PoolingPolicy};
+pub use ocrch_core::utils::webhook_url::WebhookUrlPolicy;
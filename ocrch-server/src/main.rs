@@ -4,26 +4,33 @@
 
 mod api;
 mod config;
+mod reload;
+mod resync;
 mod server;
 mod shutdown;
 mod state;
+mod supervisor;
+#[cfg(test)]
+mod test_util;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use config::managed::ManagedConfig;
 use config::{ConfigLoader, get_database_url};
-use ocrch_core::config::{ConfigStore, WalletConfig};
-use ocrch_core::entities::StablecoinName;
+use ocrch_core::config::{ChainConfig, ConfigStore};
 use ocrch_core::entities::erc20_pending_deposit::EtherScanChain;
 use ocrch_core::events::{
     BlockchainTarget, EventSenders, WebhookEvent, match_tick_channel,
-    pending_deposit_changed_channel, pooling_tick_channel, webhook_event_channel,
+    pending_deposit_changed_channel, webhook_event_channel,
 };
 use ocrch_core::framework::DatabaseProcessor;
-use ocrch_core::processors::blockchain_sync::BlockchainSyncRunner;
 use ocrch_core::processors::{
-    Erc20BlockchainSync, OrderBookWatcher, PoolingKey, PoolingManager, PoolingManagerConfig,
-    Trc20BlockchainSync, WebhookSender,
+    OrderBookWatcher, PoolingManager, PoolingManagerConfig, SyncHealthRegistry, WebhookSender,
 };
-use ocrch_sdk::objects::blockchains::Blockchain;
+use ocrch_core::utils::pooling_interval::PoolingPolicy;
+use ocrch_sdk::objects::WsServerMessage;
+use ocrch_sdk::objects::admin::ResyncRequest;
+use ocrch_sdk::objects::blockchains::{Blockchain, Stablecoin};
+use reload::{ConfigReloader, spawn_managed_config_listener};
 use server::{build_router, run_server};
 use shutdown::spawn_config_reload_handler;
 use sqlx::PgPool;
@@ -32,6 +39,7 @@ use state::{AppState, OrderStatusUpdate};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
+use supervisor::SyncSupervisor;
 use tokio::sync::{broadcast, watch};
 use tokio::task::JoinHandle;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
@@ -52,6 +60,47 @@ struct Args {
     /// Run database migrations on startup
     #[arg(long, default_value = "false")]
     migrate: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Re-fetch a wallet's transfers over a historical range, then exit.
+    ///
+    /// Already-stored transfers are skipped, and the live sync cursor is not
+    /// touched. Prints the number of new transfers.
+    Resync(ResyncArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct ResyncArgs {
+    /// Blockchain identifier (e.g. eth, polygon, tron)
+    #[arg(long, value_parser = parse_identifier::<Blockchain>)]
+    blockchain: Blockchain,
+
+    /// Configured wallet address to rescan
+    #[arg(long)]
+    wallet: String,
+
+    /// Stablecoin identifier (e.g. USDT)
+    #[arg(long, value_parser = parse_identifier::<Stablecoin>)]
+    token: Stablecoin,
+
+    /// Range start: block number (EVM) or millisecond timestamp (Tron)
+    #[arg(long)]
+    from: i64,
+
+    /// Range end, inclusive: block number (EVM) or millisecond timestamp (Tron)
+    #[arg(long)]
+    to: i64,
+}
+
+/// Parse a config-style identifier (e.g. `eth`, `USDT`) using its serde name.
+fn parse_identifier<T: serde::de::DeserializeOwned>(s: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(s.to_string()))
+        .map_err(|_| format!("unknown identifier `{s}`"))
 }
 
 /// The result of setting up the event pipeline.
@@ -62,10 +111,12 @@ struct EventPipeline {
     order_status_tx: broadcast::Sender<OrderStatusUpdate>,
     /// Shutdown signal sender -- set to `true` to stop all processors.
     shutdown_tx: watch::Sender<bool>,
-    /// Join handles for all spawned processor tasks.
+    /// Join handles for all spawned processor tasks except the sync tasks.
     join_handles: Vec<JoinHandle<()>>,
-    /// Config store for PoolingManager (needed for config reload).
-    pooling_config_store: ConfigStore<PoolingManagerConfig>,
+    /// Supervisor owning the sync runners and log subscribers.
+    supervisor: SyncSupervisor,
+    /// Sync health registry shared by the sync runners and the API.
+    sync_health: SyncHealthRegistry,
 }
 
 #[tokio::main]
@@ -78,19 +129,6 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Starting ocrch-server v{}", env!("CARGO_PKG_VERSION"));
 
-    // Load configuration
-    let config_loader = Arc::new(ConfigLoader::new(&args.config, args.listen));
-    let loaded_config = config_loader.load().map_err(|e| {
-        tracing::error!("Failed to load configuration: {}", e);
-        e
-    })?;
-
-    let listen_addr = loaded_config.server.listen;
-    tracing::info!("Configuration loaded from {:?}", args.config);
-
-    // Convert to shared config with separate locks for each section
-    let shared_config = loaded_config.into_shared();
-
     // Get database URL from environment
     let database_url = get_database_url().inspect_err(|e| {
         tracing::error!("DATABASE_URL environment variable not set: {e}");
@@ -121,20 +159,73 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("Migrations completed successfully");
     }
 
+    // Load configuration: the config file with the merchants and wallets
+    // managed in the database layered over it
+    let processor = DatabaseProcessor {
+        pool: db_pool.clone(),
+    };
+    let config_loader = Arc::new(ConfigLoader::new(&args.config, args.listen));
+    let managed_config = ManagedConfig::load(&processor).await.map_err(|e| {
+        tracing::error!("Failed to load managed merchants and wallets: {}", e);
+        e
+    })?;
+    let loaded_config = config_loader.load(&managed_config).map_err(|e| {
+        tracing::error!("Failed to load configuration: {}", e);
+        e
+    })?;
+
+    let listen_addr = loaded_config.server.listen;
+    tracing::info!("Configuration loaded from {:?}", args.config);
+
+    // Convert to shared config with separate locks for each section
+    let shared_config = loaded_config.into_shared();
+
+    // One-off subcommands run against the database and exit
+    if let Some(Command::Resync(resync_args)) = &args.command {
+        let request = ResyncRequest {
+            blockchain: resync_args.blockchain,
+            wallet_address: resync_args.wallet.clone(),
+            token: resync_args.token,
+            start: resync_args.from,
+            end: resync_args.to,
+        };
+        let result = resync::resync(&db_pool, &shared_config, &request).await;
+        db_pool.close().await;
+        let inserted = result?;
+        println!("{inserted} new transfers inserted");
+        return Ok(());
+    }
+
     // Set up the event pipeline (channels + processors)
     let pipeline = setup_event_pipeline(&shared_config, &db_pool).await;
 
+    // Config reloads respawn sync tasks through the supervisor
+    let reloader = Arc::new(ConfigReloader::new(
+        config_loader,
+        shared_config.clone(),
+        processor,
+        pipeline.supervisor,
+    ));
+
     // Create application state
     let state = AppState::new(
         db_pool.clone(),
         shared_config,
         pipeline.event_senders,
         pipeline.order_status_tx,
+        pipeline.sync_health,
+        reloader.clone(),
     );
 
     // Spawn config reload handler (listens for SIGHUP)
-    let shutdown_notify =
-        spawn_config_reload_handler(state.clone(), config_loader, pipeline.pooling_config_store);
+    let shutdown_notify = spawn_config_reload_handler(reloader.clone());
+
+    // Reload when another instance changes the managed merchants or wallets
+    let listener_handle = spawn_managed_config_listener(
+        reloader.clone(),
+        db_pool.clone(),
+        pipeline.shutdown_tx.subscribe(),
+    );
 
     // Build the router
     let router = build_router(state);
@@ -152,13 +243,16 @@ async fn main() -> anyhow::Result<()> {
     // 2. Signal the config reload handler to stop
     shutdown_notify.notify_one();
 
-    // 3. Wait for all processor tasks to finish draining
+    // 3. Stop the supervised sync tasks and wait for the other processors
+    //    to finish draining
+    reloader.shutdown().await;
+    let _ = listener_handle.await;
     for handle in pipeline.join_handles {
         let _ = handle.await;
     }
     tracing::info!("All processors shut down");
 
-    // 4. Close database connections gracefully
+    // 5. Close database connections gracefully
     tracing::info!("Closing database connections...");
     db_pool.close().await;
     tracing::info!("Server shutdown complete");
@@ -177,6 +271,7 @@ async fn main() -> anyhow::Result<()> {
 /// PendingDepositChanged -> PoolingManager
 /// PoolingManager -> PoolingTick -> BlockchainSyncRunner (one per wallet+coin)
 /// BlockchainSyncRunner -> MatchTick -> OrderBookWatcher
+/// LogSubscriber (optional, one per EVM chain) -> MatchTick -> OrderBookWatcher
 /// OrderBookWatcher -> WebhookEvent -> WebhookSender
 /// ```
 async fn setup_event_pipeline(
@@ -191,54 +286,45 @@ async fn setup_event_pipeline(
     let (match_tx, match_rx) = match_tick_channel();
     let (webhook_tx, webhook_rx) = webhook_event_channel();
 
-    // -- Build per-wallet-coin PoolingTick channels & sync runners ---------
-    let wallets = config.wallets.read().await;
-    let api_keys = config.api_keys.read().await;
-
-    let mut tick_senders = Vec::new();
-    let mut join_handles: Vec<JoinHandle<()>> = Vec::new();
-
-    for wallet in wallets.iter() {
-        for coin in &wallet.enabled_coins {
-            let (tick_tx, tick_rx) = pooling_tick_channel();
-            let blockchain_target = blockchain_to_target(wallet.blockchain);
-            let token: StablecoinName = (*coin).into();
-            let key = PoolingKey::new(blockchain_target, token);
-
-            tick_senders.push((key, tick_tx));
-
-            // Spawn the appropriate BlockchainSyncRunner
-            let handle = spawn_sync_runner(
-                wallet,
-                token,
-                &api_keys.etherscan_api_key,
-                &api_keys.tronscan_api_key,
-                db_pool.clone(),
-                shutdown_rx.clone(),
-                tick_rx,
-                match_tx.clone(),
-            );
-            join_handles.push(handle);
-        }
+    // -- Sync runners & log subscribers (per wallet+coin / per EVM chain) --
+    //
+    // The supervisor hands the runners' tick senders to the PoolingManager
+    // through `pooling_config_store`, both now and on every config reload.
+    let pooling_config_store = ConfigStore::new(PoolingManagerConfig {
+        tick_senders: Vec::new(),
+        policies: Vec::new(),
+    });
+    let sync_health = SyncHealthRegistry::new();
+    let mut supervisor = SyncSupervisor::new(
+        db_pool.clone(),
+        sync_health.clone(),
+        config.chains.clone(),
+        match_tx.clone(),
+        pooling_config_store.clone(),
+    );
+    {
+        let wallets = config.wallets.read().await;
+        let api_keys = config.api_keys.read().await;
+        let chains = config.chains.read().await;
+        supervisor.reconcile(&wallets, &api_keys, &chains).await;
     }
 
-    drop(wallets);
-    drop(api_keys);
-
     tracing::info!(
-        sync_runners = join_handles.len(),
+        sync_runners = supervisor.runner_count(),
         "Spawned BlockchainSync runners"
     );
 
     // -- PoolingManager ----------------------------------------------------
-    let pooling_config = PoolingManagerConfig { tick_senders };
-    let pooling_config_store = ConfigStore::new(pooling_config);
+    let mut join_handles: Vec<JoinHandle<()>> = Vec::new();
     let config_watcher = pooling_config_store.subscribe();
 
     let pm_shutdown_rx = shutdown_rx.clone();
     let pm_config_store = pooling_config_store.clone();
+    let pm_processor = DatabaseProcessor {
+        pool: db_pool.clone(),
+    };
     let pm_handle = tokio::spawn(async move {
-        PoolingManager::new()
+        PoolingManager::new(pm_processor)
             .run(pm_shutdown_rx, pdc_rx, pm_config_store, config_watcher)
             .await;
     });
@@ -256,9 +342,11 @@ async fn setup_event_pipeline(
 
     let obw_shutdown_rx = shutdown_rx.clone();
     let obw_pool = db_pool.clone();
+    let obw_chains = config.chains.clone();
     let obw_handle = tokio::spawn(async move {
         let watcher = OrderBookWatcher {
             processor: DatabaseProcessor { pool: obw_pool },
+            chains: obw_chains,
         };
         watcher.run(obw_shutdown_rx, match_rx, obw_tx).await;
     });
@@ -269,11 +357,34 @@ async fn setup_event_pipeline(
     let fanout_webhook_tx = webhook_tx.clone();
     let fanout_handle = tokio::spawn(async move {
         while let Some(event) = obw_rx.recv().await {
-            // Broadcast order-status changes to WebSocket clients
-            if let WebhookEvent::OrderStatusChanged { order_id, .. } = &event {
-                let _ = fanout_broadcast_tx.send(OrderStatusUpdate {
-                    order_id: *order_id,
-                });
+            // Broadcast order-status changes and payment detections to
+            // WebSocket clients
+            match &event {
+                WebhookEvent::OrderStatusChanged { order_id, .. } => {
+                    let _ = fanout_broadcast_tx.send(OrderStatusUpdate {
+                        order_id: *order_id,
+                        payment_detected: None,
+                    });
+                }
+                WebhookEvent::PaymentDetected {
+                    order_id,
+                    blockchain,
+                    txn_hash,
+                    confirmations,
+                    required_confirmations,
+                    ..
+                } => {
+                    let _ = fanout_broadcast_tx.send(OrderStatusUpdate {
+                        order_id: *order_id,
+                        payment_detected: Some(WsServerMessage::PaymentDetected {
+                            blockchain: (*blockchain).into(),
+                            txn_hash: txn_hash.clone(),
+                            confirmations: *confirmations,
+                            required_confirmations: *required_confirmations,
+                        }),
+                    });
+                }
+                WebhookEvent::UnknownTransferReceived { .. } => {}
             }
             // Forward every event to the real WebhookSender
             if let Err(e) = fanout_webhook_tx.send(event).await {
@@ -306,59 +417,17 @@ async fn setup_event_pipeline(
         order_status_tx,
         shutdown_tx,
         join_handles,
-        pooling_config_store,
+        supervisor,
+        sync_health,
     }
 }
 
-/// Spawn a `BlockchainSyncRunner` for a specific wallet + coin pair.
-#[allow(clippy::too_many_arguments)]
-fn spawn_sync_runner(
-    wallet: &WalletConfig,
-    token: StablecoinName,
-    etherscan_api_key: &str,
-    tronscan_api_key: &str,
-    pool: PgPool,
-    shutdown_rx: watch::Receiver<bool>,
-    tick_rx: ocrch_core::events::PoolingTickReceiver,
-    match_tx: ocrch_core::events::MatchTickSender,
-) -> JoinHandle<()> {
-    match wallet.blockchain {
-        Blockchain::Tron => {
-            let sdk_coin: ocrch_sdk::objects::Stablecoin = token.into();
-            let contract_address = sdk_coin
-                .get_data()
-                .get_contract_address(Blockchain::Tron)
-                .expect("TRC-20 contract address must exist for enabled coin")
-                .to_string();
-
-            let sync = Trc20BlockchainSync::new(
-                token,
-                wallet.address.clone(),
-                contract_address,
-                wallet.starting_tx.clone(),
-                tronscan_api_key.to_string(),
-            );
-            let runner = BlockchainSyncRunner::new(sync, pool);
-            tokio::spawn(async move {
-                runner.run(shutdown_rx, tick_rx, match_tx).await;
-            })
-        }
-        other => {
-            // All non-Tron blockchains are ERC-20 compatible
-            let chain = blockchain_to_etherscan_chain(other);
-            let sync = Erc20BlockchainSync::new(
-                chain,
-                token,
-                wallet.address.clone(),
-                etherscan_api_key.to_string(),
-                wallet.starting_tx.clone(),
-            );
-            let runner = BlockchainSyncRunner::new(sync, pool);
-            tokio::spawn(async move {
-                runner.run(shutdown_rx, tick_rx, match_tx).await;
-            })
-        }
-    }
+/// Collect the configured per-chain pooling curves for the `PoolingManager`.
+fn pooling_policies(chains: &[ChainConfig]) -> Vec<(BlockchainTarget, PoolingPolicy)> {
+    chains
+        .iter()
+        .map(|c| (blockchain_to_target(c.blockchain), c.pooling.clone()))
+        .collect()
 }
 
 /// Map an SDK `Blockchain` variant to a `BlockchainTarget` for the event system.
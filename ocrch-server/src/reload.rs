@@ -0,0 +1,216 @@
+//! Configuration hot-reload.
+//!
+//! Shared by the SIGHUP handler, the Admin API `POST /reload` endpoint and
+//! the managed merchant and wallet endpoints. A reload re-reads the config
+//! file and the managed entries in the database, swaps every
+//! [`SharedConfig`] section and then lets the [`SyncSupervisor`] respawn
+//! exactly the sync tasks whose wallets, API keys or chain settings changed.
+//! The resulting diff is logged and kept for `GET /reload`.
+//!
+//! Changes to the managed entries are announced with Postgres `NOTIFY`, so
+//! every instance sharing the database reloads them.
+
+use crate::config::managed::ManagedConfig;
+use crate::config::{ConfigError, ConfigLoader};
+use crate::supervisor::SyncSupervisor;
+use kanau::processor::Processor;
+use ocrch_core::config::SharedConfig;
+use ocrch_core::entities::managed_config::{CONFIG_CHANGED_CHANNEL, NotifyManagedConfigChanged};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_sdk::objects::admin::ReloadReport;
+use sqlx::postgres::PgListener;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock, watch};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Delay before reconnecting a lost change listener.
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Applies configuration reloads to the running server.
+pub struct ConfigReloader {
+    config_loader: Arc<ConfigLoader>,
+    config: SharedConfig,
+    processor: DatabaseProcessor,
+    /// Sent with change notifications so this instance skips its own.
+    instance_id: Uuid,
+    /// Held for the whole reload so concurrent reloads are serialized.
+    supervisor: Mutex<SyncSupervisor>,
+    last_report: RwLock<Option<ReloadReport>>,
+}
+
+impl ConfigReloader {
+    /// Create a reloader around an already reconciled supervisor.
+    pub fn new(
+        config_loader: Arc<ConfigLoader>,
+        config: SharedConfig,
+        processor: DatabaseProcessor,
+        supervisor: SyncSupervisor,
+    ) -> Self {
+        Self {
+            config_loader,
+            config,
+            processor,
+            instance_id: Uuid::new_v4(),
+            supervisor: Mutex::new(supervisor),
+            last_report: RwLock::new(None),
+        }
+    }
+
+    /// Reload the config file and the managed entries and reconcile the
+    /// running sync tasks.
+    ///
+    /// On error the running configuration is left untouched.
+    pub async fn reload(&self) -> Result<ReloadReport, ConfigError> {
+        let mut supervisor = self.supervisor.lock().await;
+        let managed = ManagedConfig::load(&self.processor).await?;
+        let loaded_config = self.config_loader.load(&managed)?;
+
+        // Update all config sections
+        self.config.server.update(loaded_config.server).await;
+        self.config.admin.update(loaded_config.admin).await;
+        self.config.merchants.update(loaded_config.merchants).await;
+        self.config
+            .wallets
+            .update(loaded_config.wallets.clone())
+            .await;
+        self.config
+            .api_keys
+            .update(loaded_config.api_keys.clone())
+            .await;
+        self.config
+            .chains
+            .update(loaded_config.chains.clone())
+            .await;
+        self.config
+            .sync_health
+            .update(loaded_config.sync_health)
+            .await;
+        self.config.webhooks.update(loaded_config.webhooks).await;
+
+        let report = supervisor
+            .reconcile(
+                &loaded_config.wallets,
+                &loaded_config.api_keys,
+                &loaded_config.chains,
+            )
+            .await;
+        log_report(&report);
+
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Check that the config file with `managed` layered over it is valid,
+    /// without applying it.
+    pub fn check(&self, managed: &ManagedConfig) -> Result<(), ConfigError> {
+        self.config_loader.load(managed).map(|_| ())
+    }
+
+    /// Apply a change to the managed entries: tell the other instances and
+    /// reload this one.
+    pub async fn managed_changed(&self) -> Result<ReloadReport, ConfigError> {
+        if let Err(e) = self
+            .processor
+            .process(NotifyManagedConfigChanged {
+                instance_id: self.instance_id,
+            })
+            .await
+        {
+            tracing::error!(error = %e, "Failed to notify other instances of a config change");
+        }
+        self.reload().await
+    }
+
+    /// The diff applied by the most recent successful reload, if any.
+    pub async fn last_report(&self) -> Option<ReloadReport> {
+        self.last_report.read().await.clone()
+    }
+
+    /// Stop every supervised sync task (used during graceful shutdown).
+    pub async fn shutdown(&self) {
+        self.supervisor.lock().await.shutdown().await;
+    }
+}
+
+/// Spawns a task that reloads the configuration whenever another instance
+/// changes the managed merchants or wallets.
+///
+/// The task stops once `shutdown_rx` turns `true`.
+pub fn spawn_managed_config_listener(
+    reloader: Arc<ConfigReloader>,
+    pool: sqlx::PgPool,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let own_id = reloader.instance_id.to_string();
+        loop {
+            let mut listener = match listen(&pool).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to listen for config changes");
+                    tokio::select! {
+                        _ = tokio::time::sleep(LISTENER_RETRY_DELAY) => continue,
+                        _ = shutdown_rx.changed() => return,
+                    }
+                }
+            };
+            loop {
+                tokio::select! {
+                    notification = listener.try_recv() => match notification {
+                        Ok(Some(n)) if n.payload() == own_id => {}
+                        Ok(Some(_)) => {
+                            tracing::info!("Managed config changed on another instance, reloading");
+                            reload_logged(&reloader).await;
+                        }
+                        Ok(None) => {
+                            // The listener reconnects on the next call;
+                            // reload in case a change was missed meanwhile
+                            tracing::warn!("Config change listener lost its connection, reloading");
+                            reload_logged(&reloader).await;
+                        }
+                        Err(e) => {
+                            tracing::error!(error = %e, "Config change listener failed");
+                            break;
+                        }
+                    },
+                    _ = shutdown_rx.changed() => return,
+                }
+            }
+        }
+    })
+}
+
+async fn listen(pool: &sqlx::PgPool) -> Result<PgListener, sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CONFIG_CHANGED_CHANNEL).await?;
+    Ok(listener)
+}
+
+async fn reload_logged(reloader: &ConfigReloader) {
+    if let Err(e) = reloader.reload().await {
+        tracing::error!("Failed to reload configuration: {}", e);
+    }
+}
+
+fn log_report(report: &ReloadReport) {
+    for runner in &report.added {
+        tracing::info!(blockchain = ?runner.blockchain, wallet = %runner.wallet_address, token = ?runner.token, "Reload: spawned sync runner");
+    }
+    for runner in &report.removed {
+        tracing::info!(blockchain = ?runner.blockchain, wallet = %runner.wallet_address, token = ?runner.token, "Reload: stopped sync runner");
+    }
+    for runner in &report.restarted {
+        tracing::info!(blockchain = ?runner.blockchain, wallet = %runner.wallet_address, token = ?runner.token, "Reload: restarted sync runner");
+    }
+    tracing::info!(
+        added = report.added.len(),
+        removed = report.removed.len(),
+        restarted = report.restarted.len(),
+        etherscan_api_key_changed = report.etherscan_api_key_changed,
+        tronscan_api_key_changed = report.tronscan_api_key_changed,
+        log_subscribers_changed = ?report.log_subscribers_changed,
+        "Configuration reloaded successfully"
+    );
+}
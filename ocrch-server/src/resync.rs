@@ -0,0 +1,99 @@
+//! Historical transfer resync.
+//!
+//! Shared by the `ocrch-server resync` subcommand and the Admin API
+//! `POST /resync` endpoint. A resync re-fetches a configured wallet's
+//! transfers over an explicit range and stores them through the same
+//! idempotent insert path as the live sync. It never reads or moves the
+//! live sync cursor.
+
+use ocrch_core::config::SharedConfig;
+use ocrch_core::entities::StablecoinName;
+use ocrch_core::processors::{
+    BlockchainSync, Erc20BlockchainSync, ResyncRange, SyncError, Trc20BlockchainSync,
+};
+use ocrch_sdk::objects::admin::ResyncRequest;
+use ocrch_sdk::objects::blockchains::Blockchain;
+use sqlx::PgPool;
+use thiserror::Error;
+
+/// Errors that can occur during a resync.
+#[derive(Debug, Error)]
+pub enum ResyncError {
+    #[error("invalid range: start must not be after end")]
+    InvalidRange,
+
+    #[error("wallet {0} is not configured for this blockchain and token")]
+    WalletNotConfigured(String),
+
+    #[error("token is not supported on this blockchain")]
+    UnsupportedToken,
+
+    #[error("sync failed: {0}")]
+    Sync(#[from] SyncError),
+}
+
+/// Re-fetch transfers for a configured wallet over the requested range.
+///
+/// Returns the number of transfers that were not already stored.
+pub async fn resync(
+    pool: &PgPool,
+    config: &SharedConfig,
+    request: &ResyncRequest,
+) -> Result<u32, ResyncError> {
+    if request.start > request.end {
+        return Err(ResyncError::InvalidRange);
+    }
+
+    let wallet = config
+        .wallets
+        .read()
+        .await
+        .iter()
+        .find(|w| {
+            w.blockchain == request.blockchain
+                && w.address.eq_ignore_ascii_case(&request.wallet_address)
+                && w.enabled_coins.contains(&request.token)
+        })
+        .cloned()
+        .ok_or_else(|| ResyncError::WalletNotConfigured(request.wallet_address.clone()))?;
+    let api_keys = config.api_keys.read().await.clone();
+
+    let token: StablecoinName = request.token.into();
+    let range = ResyncRange {
+        start: request.start,
+        end: request.end,
+    };
+
+    let inserted = match wallet.blockchain {
+        Blockchain::Tron => {
+            let contract_address = request
+                .token
+                .get_data()
+                .get_contract_address(Blockchain::Tron)
+                .ok_or(ResyncError::UnsupportedToken)?
+                .to_string();
+            Trc20BlockchainSync::new(
+                token,
+                wallet.address,
+                contract_address,
+                wallet.starting_tx,
+                api_keys.tronscan_api_key,
+            )
+            .resync(pool, range)
+            .await?
+        }
+        other => {
+            Erc20BlockchainSync::new(
+                crate::blockchain_to_etherscan_chain(other),
+                token,
+                wallet.address,
+                api_keys.etherscan_api_key,
+                wallet.starting_tx,
+            )
+            .resync(pool, range)
+            .await?
+        }
+    };
+
+    Ok(inserted)
+}
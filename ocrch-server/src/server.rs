@@ -15,8 +15,13 @@ pub fn build_router(state: AppState) -> Router {
         .route("/health", get(health_check))
         // Service API (application backend)
         .nest("/api/v1/service", api::service::router())
+        .nest(
+            "/api/v1/service/merchants/{merchant_id}",
+            api::service::router(),
+        )
         // User API (checkout frontend)
         .nest("/api/v1/user", api::user::router())
+        .nest("/api/v1/user/merchants/{merchant_id}", api::user::router())
         // Admin API (admin dashboard)
         .nest("/api/v1/admin", api::admin::router())
         // Add state to all routes
@@ -1,11 +1,6 @@
 //! Signal handling for graceful shutdown and config reload.
 
-use crate::config::ConfigLoader;
-use crate::state::AppState;
-use ocrch_core::config::ConfigStore;
-use ocrch_core::events::pooling_tick_channel;
-use ocrch_core::processors::{PoolingKey, PoolingManagerConfig};
-use ocrch_sdk::objects::blockchains::Blockchain;
+use crate::reload::ConfigReloader;
 use std::sync::Arc;
 use tokio::signal::unix::{SignalKind, signal};
 use tokio::sync::Notify;
@@ -30,11 +25,7 @@ pub async fn shutdown_signal() {
 /// Spawns a task that listens for SIGHUP and reloads the configuration.
 ///
 /// Returns a Notify that can be used to signal when shutdown is complete.
-pub fn spawn_config_reload_handler(
-    state: AppState,
-    config_loader: Arc<ConfigLoader>,
-    pooling_config_store: ConfigStore<PoolingManagerConfig>,
-) -> Arc<Notify> {
+pub fn spawn_config_reload_handler(reloader: Arc<ConfigReloader>) -> Arc<Notify> {
     let shutdown_notify = Arc::new(Notify::new());
     let shutdown_notify_clone = shutdown_notify.clone();
 
@@ -45,41 +36,8 @@ pub fn spawn_config_reload_handler(
             tokio::select! {
                 _ = sighup.recv() => {
                     tracing::info!("Received SIGHUP, reloading configuration");
-                    match config_loader.reload() {
-                        Ok(loaded_config) => {
-                            // Update all config sections
-                            state.config.server.update(loaded_config.server).await;
-                            state.config.admin.update(loaded_config.admin).await;
-                            state.config.merchant.update(loaded_config.merchant).await;
-                            state.config.wallets.update(loaded_config.wallets.clone()).await;
-                            state.config.api_keys.update(loaded_config.api_keys).await;
-
-                            // Rebuild PoolingManagerConfig from new wallets so
-                            // PoolingManager can diff and reconcile tick loops.
-                            let new_tick_senders = loaded_config
-                                .wallets
-                                .iter()
-                                .flat_map(|w| {
-                                    w.enabled_coins.iter().map(move |coin| {
-                                        let target = blockchain_to_target(w.blockchain);
-                                        let token = (*coin).into();
-                                        let key = PoolingKey::new(target, token);
-                                        let (tx, _rx) = pooling_tick_channel();
-                                        (key, tx)
-                                    })
-                                })
-                                .collect();
-                            pooling_config_store
-                                .update(PoolingManagerConfig {
-                                    tick_senders: new_tick_senders,
-                                })
-                                .await;
-
-                            tracing::info!("Configuration reloaded successfully");
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to reload configuration: {}", e);
-                        }
+                    if let Err(e) = reloader.reload().await {
+                        tracing::error!("Failed to reload configuration: {}", e);
                     }
                 }
                 _ = shutdown_notify_clone.notified() => {
@@ -92,28 +50,3 @@ pub fn spawn_config_reload_handler(
 
     shutdown_notify
 }
-
-/// Map an SDK `Blockchain` variant to a `BlockchainTarget` for the event system.
-fn blockchain_to_target(blockchain: Blockchain) -> ocrch_core::events::BlockchainTarget {
-    match blockchain {
-        Blockchain::Tron => ocrch_core::events::BlockchainTarget::Trc20,
-        other => ocrch_core::events::BlockchainTarget::Erc20(blockchain_to_etherscan_chain(other)),
-    }
-}
-
-/// Map an SDK `Blockchain` variant to an `EtherScanChain`.
-fn blockchain_to_etherscan_chain(
-    blockchain: Blockchain,
-) -> ocrch_core::entities::erc20_pending_deposit::EtherScanChain {
-    use ocrch_core::entities::erc20_pending_deposit::EtherScanChain;
-    match blockchain {
-        Blockchain::Ethereum => EtherScanChain::Ethereum,
-        Blockchain::Polygon => EtherScanChain::Polygon,
-        Blockchain::Base => EtherScanChain::Base,
-        Blockchain::ArbitrumOne => EtherScanChain::ArbitrumOne,
-        Blockchain::Linea => EtherScanChain::Linea,
-        Blockchain::Optimism => EtherScanChain::Optimism,
-        Blockchain::AvalancheC => EtherScanChain::AvalancheC,
-        Blockchain::Tron => panic!("Tron is not an EtherScan chain"),
-    }
-}
@@ -1,8 +1,12 @@
 //! Application state shared across all request handlers.
 
+use crate::reload::ConfigReloader;
 use ocrch_core::config::SharedConfig;
 use ocrch_core::events::EventSenders;
+use ocrch_core::processors::SyncHealthRegistry;
+use ocrch_sdk::objects::WsServerMessage;
 use sqlx::PgPool;
+use std::sync::Arc;
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
@@ -13,6 +17,9 @@ use uuid::Uuid;
 #[derive(Debug, Clone)]
 pub struct OrderStatusUpdate {
     pub order_id: Uuid,
+    /// Set when an unconfirmed payment was detected; forwarded to the
+    /// client as-is instead of re-reading the order status.
+    pub payment_detected: Option<WsServerMessage>,
 }
 
 /// Application state that is shared across all request handlers.
@@ -28,6 +35,10 @@ pub struct AppState {
     pub event_senders: EventSenders,
     /// Broadcast sender for order status changes (consumed by WebSocket handlers).
     pub order_status_tx: broadcast::Sender<OrderStatusUpdate>,
+    /// Sync health per (blockchain, token), updated by the sync runners.
+    pub sync_health: SyncHealthRegistry,
+    /// Applies config reloads and keeps the last reload report.
+    pub reloader: Arc<ConfigReloader>,
 }
 
 impl AppState {
@@ -37,12 +48,16 @@ impl AppState {
         config: SharedConfig,
         event_senders: EventSenders,
         order_status_tx: broadcast::Sender<OrderStatusUpdate>,
+        sync_health: SyncHealthRegistry,
+        reloader: Arc<ConfigReloader>,
     ) -> Self {
         Self {
             db,
             config,
             event_senders,
             order_status_tx,
+            sync_health,
+            reloader,
         }
     }
 }
@@ -0,0 +1,390 @@
+//! Supervision of the blockchain sync tasks.
+//!
+//! The [`SyncSupervisor`] owns one `BlockchainSyncRunner` per configured
+//! (wallet, coin) pair and one `LogSubscriber` per EVM chain with a
+//! `ws_rpc_url`. Every task has its own shutdown channel, so a config reload
+//! only stops and respawns the tasks whose settings actually changed while
+//! the rest keep running undisturbed.
+
+use crate::{blockchain_to_etherscan_chain, blockchain_to_target, pooling_policies};
+use ocrch_core::config::{ApiKeysConfig, ChainConfig, ConfigStore, WalletConfig};
+use ocrch_core::entities::StablecoinName;
+use ocrch_core::events::{
+    BlockchainTarget, MatchTickSender, PoolingTickSender, pooling_tick_channel,
+};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_core::processors::blockchain_sync::BlockchainSyncRunner;
+use ocrch_core::processors::{
+    Erc20BlockchainSync, LogSubscriber, LogWatch, PoolingKey, PoolingManagerConfig,
+    SyncHealthRegistry, Trc20BlockchainSync,
+};
+use ocrch_sdk::objects::admin::{ReloadReport, SyncRunnerRef};
+use ocrch_sdk::objects::blockchains::{Blockchain, Stablecoin};
+use sqlx::PgPool;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Everything a sync runner is spawned with. A runner is respawned whenever
+/// any of these change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RunnerSpec {
+    blockchain: Blockchain,
+    address: String,
+    token: Stablecoin,
+    starting_tx: Option<String>,
+    api_key: String,
+}
+
+impl RunnerSpec {
+    /// Whether both specs describe the same (wallet, coin) pair.
+    fn same_runner(&self, other: &RunnerSpec) -> bool {
+        self.blockchain == other.blockchain
+            && self.address == other.address
+            && self.token == other.token
+    }
+
+    fn pooling_key(&self) -> PoolingKey {
+        PoolingKey::new(blockchain_to_target(self.blockchain), self.token.into())
+    }
+
+    fn to_ref(&self) -> SyncRunnerRef {
+        SyncRunnerRef {
+            blockchain: self.blockchain,
+            wallet_address: self.address.clone(),
+            token: self.token,
+        }
+    }
+}
+
+/// Everything a log subscriber is spawned with, apart from the tick senders
+/// of the runners on its chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SubscriberSpec {
+    blockchain: Blockchain,
+    ws_url: String,
+    watches: Vec<LogWatch>,
+}
+
+/// A spawned task together with its private shutdown channel.
+struct SupervisedTask<S> {
+    spec: S,
+    shutdown_tx: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl<S> SupervisedTask<S> {
+    /// Signal the task to stop and wait for it to finish its current work.
+    async fn stop(self) {
+        let _ = self.shutdown_tx.send(true);
+        let _ = self.handle.await;
+    }
+}
+
+/// Owns the sync runners and log subscribers and reconciles them against
+/// the configuration.
+pub struct SyncSupervisor {
+    pool: PgPool,
+    sync_health: SyncHealthRegistry,
+    chains: ConfigStore<Vec<ChainConfig>>,
+    match_tx: MatchTickSender,
+    pooling_config_store: ConfigStore<PoolingManagerConfig>,
+    runners: Vec<(SupervisedTask<RunnerSpec>, PoolingTickSender)>,
+    subscribers: Vec<SupervisedTask<SubscriberSpec>>,
+}
+
+impl SyncSupervisor {
+    /// Create a supervisor with no tasks running yet.
+    ///
+    /// Call [`reconcile()`](Self::reconcile) to spawn the configured tasks.
+    pub fn new(
+        pool: PgPool,
+        sync_health: SyncHealthRegistry,
+        chains: ConfigStore<Vec<ChainConfig>>,
+        match_tx: MatchTickSender,
+        pooling_config_store: ConfigStore<PoolingManagerConfig>,
+    ) -> Self {
+        Self {
+            pool,
+            sync_health,
+            chains,
+            match_tx,
+            pooling_config_store,
+            runners: Vec::new(),
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Number of sync runners currently running.
+    pub fn runner_count(&self) -> usize {
+        self.runners.len()
+    }
+
+    /// Bring the running tasks in line with the given configuration.
+    ///
+    /// Runners whose (wallet, coin) pair is gone are shut down gracefully,
+    /// runners whose wallet settings or API key changed are stopped and
+    /// respawned, and new pairs get a fresh runner. The `PoolingManager`
+    /// config is then updated with the live tick senders. Log subscribers
+    /// are respawned when their chain settings, watched wallets or the
+    /// runners they back-fill through changed.
+    pub async fn reconcile(
+        &mut self,
+        wallets: &[WalletConfig],
+        api_keys: &ApiKeysConfig,
+        chains: &[ChainConfig],
+    ) -> ReloadReport {
+        let desired = runner_specs(wallets, api_keys);
+        let mut report = ReloadReport {
+            reloaded_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+            added: Vec::new(),
+            removed: Vec::new(),
+            restarted: Vec::new(),
+            etherscan_api_key_changed: false,
+            tronscan_api_key_changed: false,
+            log_subscribers_changed: Vec::new(),
+        };
+
+        // 1. Stop runners that are gone or whose spec changed. The old runner
+        //    is fully stopped before its replacement starts so two runners
+        //    never advance the same sync cursor concurrently.
+        let (kept, stale): (Vec<_>, Vec<_>) = std::mem::take(&mut self.runners)
+            .into_iter()
+            .partition(|(task, _)| desired.contains(&task.spec));
+        self.runners = kept;
+        for (task, _) in stale {
+            let spec = task.spec.clone();
+            task.stop().await;
+            match desired.iter().find(|d| d.same_runner(&spec)) {
+                Some(new_spec) => {
+                    if new_spec.api_key != spec.api_key {
+                        if spec.blockchain == Blockchain::Tron {
+                            report.tronscan_api_key_changed = true;
+                        } else {
+                            report.etherscan_api_key_changed = true;
+                        }
+                    }
+                    report.restarted.push(spec.to_ref());
+                }
+                None => report.removed.push(spec.to_ref()),
+            }
+        }
+
+        // 2. Spawn runners for new or changed specs.
+        for spec in desired {
+            if self.runners.iter().any(|(task, _)| task.spec == spec) {
+                continue;
+            }
+            if !report.restarted.contains(&spec.to_ref()) {
+                report.added.push(spec.to_ref());
+            }
+            let runner = self.spawn_runner(spec);
+            self.runners.push(runner);
+        }
+
+        // 3. Forget the health of pairs that no longer have any runner.
+        for removed in &report.removed {
+            let key = PoolingKey::new(
+                blockchain_to_target(removed.blockchain),
+                removed.token.into(),
+            );
+            if !self
+                .runners
+                .iter()
+                .any(|(task, _)| task.spec.pooling_key() == key)
+            {
+                self.sync_health.remove(key).await;
+            }
+        }
+
+        // 4. Hand the live tick senders to the PoolingManager.
+        self.pooling_config_store
+            .update(PoolingManagerConfig {
+                tick_senders: self
+                    .runners
+                    .iter()
+                    .map(|(task, tx)| (task.spec.pooling_key(), tx.clone()))
+                    .collect(),
+                policies: pooling_policies(chains),
+            })
+            .await;
+
+        // 5. Reconcile log subscribers.
+        let runners_changed: Vec<Blockchain> = report
+            .added
+            .iter()
+            .chain(&report.removed)
+            .chain(&report.restarted)
+            .map(|r| r.blockchain)
+            .collect();
+        let desired = subscriber_specs(chains, wallets);
+        let (kept, stale): (Vec<_>, Vec<_>) = std::mem::take(&mut self.subscribers)
+            .into_iter()
+            .partition(|task| {
+                desired.contains(&task.spec) && !runners_changed.contains(&task.spec.blockchain)
+            });
+        self.subscribers = kept;
+        for task in stale {
+            report.log_subscribers_changed.push(task.spec.blockchain);
+            task.stop().await;
+        }
+        for spec in desired {
+            if self.subscribers.iter().any(|task| task.spec == spec) {
+                continue;
+            }
+            if !report.log_subscribers_changed.contains(&spec.blockchain) {
+                report.log_subscribers_changed.push(spec.blockchain);
+            }
+            let subscriber = self.spawn_subscriber(spec);
+            self.subscribers.push(subscriber);
+        }
+
+        report
+    }
+
+    /// Stop every supervised task and wait for them to finish.
+    pub async fn shutdown(&mut self) {
+        for task in std::mem::take(&mut self.subscribers) {
+            task.stop().await;
+        }
+        for (task, _) in std::mem::take(&mut self.runners) {
+            task.stop().await;
+        }
+    }
+
+    /// Spawn a `BlockchainSyncRunner` for a specific wallet + coin pair.
+    fn spawn_runner(&self, spec: RunnerSpec) -> (SupervisedTask<RunnerSpec>, PoolingTickSender) {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let (tick_tx, tick_rx) = pooling_tick_channel();
+        let token: StablecoinName = spec.token.into();
+        let pool = self.pool.clone();
+        let sync_health = self.sync_health.clone();
+        let chains = self.chains.clone();
+        let match_tx = self.match_tx.clone();
+
+        let handle = match spec.blockchain {
+            Blockchain::Tron => {
+                let contract_address = spec
+                    .token
+                    .get_data()
+                    .get_contract_address(Blockchain::Tron)
+                    .expect("TRC-20 contract address must exist for enabled coin")
+                    .to_string();
+
+                let sync = Trc20BlockchainSync::new(
+                    token,
+                    spec.address.clone(),
+                    contract_address,
+                    spec.starting_tx.clone(),
+                    spec.api_key.clone(),
+                );
+                let runner = BlockchainSyncRunner::new(sync, pool, sync_health, chains);
+                tokio::spawn(async move {
+                    runner.run(shutdown_rx, tick_rx, match_tx).await;
+                })
+            }
+            other => {
+                // All non-Tron blockchains are ERC-20 compatible
+                let chain = blockchain_to_etherscan_chain(other);
+                let sync = Erc20BlockchainSync::new(
+                    chain,
+                    token,
+                    spec.address.clone(),
+                    spec.api_key.clone(),
+                    spec.starting_tx.clone(),
+                );
+                let runner = BlockchainSyncRunner::new(sync, pool, sync_health, chains);
+                tokio::spawn(async move {
+                    runner.run(shutdown_rx, tick_rx, match_tx).await;
+                })
+            }
+        };
+
+        let task = SupervisedTask {
+            spec,
+            shutdown_tx,
+            handle,
+        };
+        (task, tick_tx)
+    }
+
+    /// Spawn a `LogSubscriber` for a single EVM chain.
+    fn spawn_subscriber(&self, spec: SubscriberSpec) -> SupervisedTask<SubscriberSpec> {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let chain = blockchain_to_etherscan_chain(spec.blockchain);
+
+        // Runners on this chain are ticked after a reconnect to back-fill gaps
+        let target = BlockchainTarget::Erc20(chain);
+        let backfill_txs = self
+            .runners
+            .iter()
+            .map(|(task, tx)| (task.spec.pooling_key(), tx))
+            .filter(|(key, _)| key.blockchain == target)
+            .map(|(key, tx)| (key.token, tx.clone()))
+            .collect();
+
+        let subscriber = LogSubscriber::new(
+            chain,
+            spec.ws_url.clone(),
+            spec.watches.clone(),
+            DatabaseProcessor {
+                pool: self.pool.clone(),
+            },
+        );
+        let match_tx = self.match_tx.clone();
+        let handle = tokio::spawn(async move {
+            subscriber.run(shutdown_rx, match_tx, backfill_txs).await;
+        });
+        tracing::info!(%target, "Spawned LogSubscriber");
+
+        SupervisedTask {
+            spec,
+            shutdown_tx,
+            handle,
+        }
+    }
+}
+
+/// One runner spec per (wallet, enabled coin) pair.
+fn runner_specs(wallets: &[WalletConfig], api_keys: &ApiKeysConfig) -> Vec<RunnerSpec> {
+    wallets
+        .iter()
+        .flat_map(|wallet| {
+            let api_key = match wallet.blockchain {
+                Blockchain::Tron => &api_keys.tronscan_api_key,
+                _ => &api_keys.etherscan_api_key,
+            };
+            wallet.enabled_coins.iter().map(move |coin| RunnerSpec {
+                blockchain: wallet.blockchain,
+                address: wallet.address.clone(),
+                token: *coin,
+                starting_tx: wallet.starting_tx.clone(),
+                api_key: api_key.clone(),
+            })
+        })
+        .collect()
+}
+
+/// One subscriber spec per chain with a `ws_rpc_url` and at least one wallet.
+fn subscriber_specs(chains: &[ChainConfig], wallets: &[WalletConfig]) -> Vec<SubscriberSpec> {
+    chains
+        .iter()
+        .filter_map(|chain_config| {
+            let ws_url = chain_config.ws_rpc_url.clone()?;
+            let watches: Vec<LogWatch> = wallets
+                .iter()
+                .filter(|w| w.blockchain == chain_config.blockchain)
+                .flat_map(|w| {
+                    w.enabled_coins.iter().map(|coin| LogWatch {
+                        token: (*coin).into(),
+                        wallet_address: w.address.clone(),
+                    })
+                })
+                .collect();
+            (!watches.is_empty()).then_some(SubscriberSpec {
+                blockchain: chain_config.blockchain,
+                ws_url,
+                watches,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,134 @@
+//! Fixtures for tests that drive handlers and extractors against a database.
+//!
+//! [`TestApp`] builds an [`AppState`] the way `main` does, from a config
+//! with a single merchant, but without starting any processors.
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::http::StatusCode;
+use ocrch_core::config::ConfigStore;
+use ocrch_core::events::{
+    EventSenders, MatchTickReceiver, PendingDepositChangedReceiver, WebhookEventReceiver,
+    match_tick_channel, pending_deposit_changed_channel, webhook_event_channel,
+};
+use ocrch_core::framework::DatabaseProcessor;
+use ocrch_core::processors::{PoolingManagerConfig, SyncHealthRegistry};
+use ocrch_sdk::signature::{SIGNATURE_HEADER, Signature, SignedObject};
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use tower::Service;
+use uuid::Uuid;
+
+use crate::config::ConfigLoader;
+use crate::config::managed::ManagedConfig;
+use crate::reload::ConfigReloader;
+use crate::server::build_router;
+use crate::state::AppState;
+use crate::supervisor::SyncSupervisor;
+
+/// Secret of the `default` merchant.
+pub const MERCHANT_SECRET: &str = "shop-secret";
+
+const CONFIG: &str = r#"
+[server]
+listen = "127.0.0.1:0"
+
+[admin]
+secret = "admin-secret"
+
+[merchant]
+name = "Test Shop"
+secret = "shop-secret"
+allowed_origins = ["https://shop.example.com"]
+
+[api_keys]
+etherscan_api_key = "etherscan-key"
+tronscan_api_key = "tronscan-key"
+"#;
+
+/// An [`AppState`] on a test database.
+///
+/// Holds the receiving ends of the event channels, so that handlers can
+/// emit events as they do in production.
+pub struct TestApp {
+    pub state: AppState,
+    _receivers: (
+        PendingDepositChangedReceiver,
+        MatchTickReceiver,
+        WebhookEventReceiver,
+    ),
+}
+
+impl TestApp {
+    pub fn new(pool: PgPool) -> Self {
+        // The loader reads (and rewrites) a config file
+        let path = std::env::temp_dir().join(format!("ocrch-test-{}.toml", Uuid::new_v4()));
+        std::fs::write(&path, CONFIG).unwrap();
+        let loader = Arc::new(ConfigLoader::new(&path, None));
+        let loaded = loader.load(&ManagedConfig::default());
+        std::fs::remove_file(&path).unwrap();
+        let config = loaded.unwrap().into_shared();
+
+        let (pending_deposit_changed, pdc_rx) = pending_deposit_changed_channel();
+        let (match_tick, match_rx) = match_tick_channel();
+        let (webhook_event, webhook_rx) = webhook_event_channel();
+        let (order_status_tx, _) = broadcast::channel(16);
+        let sync_health = SyncHealthRegistry::new();
+        let supervisor = SyncSupervisor::new(
+            pool.clone(),
+            sync_health.clone(),
+            config.chains.clone(),
+            match_tick.clone(),
+            ConfigStore::new(PoolingManagerConfig {
+                tick_senders: Vec::new(),
+                policies: Vec::new(),
+            }),
+        );
+        let reloader = Arc::new(ConfigReloader::new(
+            loader,
+            config.clone(),
+            DatabaseProcessor { pool: pool.clone() },
+            supervisor,
+        ));
+
+        Self {
+            state: AppState::new(
+                pool,
+                config,
+                EventSenders {
+                    pending_deposit_changed,
+                    match_tick,
+                    webhook_event,
+                },
+                order_status_tx,
+                sync_health,
+                reloader,
+            ),
+            _receivers: (pdc_rx, match_rx, webhook_rx),
+        }
+    }
+
+    /// Send a request through the full API router and collect the response.
+    pub async fn send(&self, req: axum::extract::Request) -> (StatusCode, Bytes) {
+        let response = build_router(self.state.clone()).call(req).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, body)
+    }
+}
+
+/// A `POST` request with `body` signed by `secret` in the single-signature
+/// header format.
+pub fn signed_post<T: Signature>(uri: &str, body: T, secret: &str) -> axum::extract::Request {
+    let signed = SignedObject::new(body, secret.as_bytes()).unwrap();
+    axum::extract::Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header(SIGNATURE_HEADER, signed.to_header())
+        .header("content-type", "application/json")
+        .body(axum::body::Body::from(signed.json))
+        .unwrap()
+}